@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.20;
+
+import {IRiscZeroVerifier, ISingleSignConsumer} from "./SingleSignConsumer.sol";
+
+/// Reads bundles of independent batch proofs, as `host::bundle::Bundle` packs them:
+/// a version byte and a `uint16` count, then per proof a `uint16` seal length and the
+/// seal, a `uint32` journal length and the journal, and a `uint16` digest count and the
+/// digests to consume. Lengths are big-endian and nothing is padded, so a bundle is
+/// barely longer than the proofs it holds. A new bundle version needs a matching reader.
+library SingleSignBundle {
+    /// `BUNDLE_VERSION` of the bundles this library reads
+    uint8 internal constant VERSION = 1;
+
+    error MalformedBundle();
+    error UnsupportedBundleVersion(uint8 version);
+
+    /// The number of proofs in `bundle` and the offset of the first.
+    function header(bytes calldata bundle) internal pure returns (uint256 count, uint256 at) {
+        if (bundle.length < 3) revert MalformedBundle();
+        if (uint8(bundle[0]) != VERSION) revert UnsupportedBundleVersion(uint8(bundle[0]));
+        return (uint16(bytes2(bundle[1:3])), 3);
+    }
+
+    /// The proof at offset `at` and the offset of the next one.
+    function next(bytes calldata bundle, uint256 at)
+        internal
+        pure
+        returns (bytes calldata seal, bytes calldata journal, bytes32[] memory digests, uint256)
+    {
+        uint256 length;
+        (length, at) = number(bundle, at, 2);
+        (seal, at) = slice(bundle, at, length);
+        (length, at) = number(bundle, at, 4);
+        (journal, at) = slice(bundle, at, length);
+        (length, at) = number(bundle, at, 2);
+        if (at + 32 * length > bundle.length) revert MalformedBundle();
+        digests = new bytes32[](length);
+        for (uint256 i; i < length; ++i) {
+            digests[i] = bytes32(bundle[at:at + 32]);
+            at += 32;
+        }
+        return (seal, journal, digests, at);
+    }
+
+    /// Revert unless the last proof ended the bundle at `at`.
+    function end(bytes calldata bundle, uint256 at) internal pure {
+        if (at != bundle.length) revert MalformedBundle();
+    }
+
+    function number(bytes calldata b, uint256 at, uint256 size)
+        private
+        pure
+        returns (uint256 value, uint256)
+    {
+        if (at + size > b.length) revert MalformedBundle();
+        for (uint256 i; i < size; ++i) {
+            value = value << 8 | uint8(b[at + i]);
+        }
+        return (value, at + size);
+    }
+
+    function slice(bytes calldata b, uint256 at, uint256 length)
+        private
+        pure
+        returns (bytes calldata, uint256)
+    {
+        if (at + length > b.length) revert MalformedBundle();
+        return (b[at:at + length], at + length);
+    }
+}
+
+/// Lands many independent batch proofs in one transaction, so they pay the base fee
+/// once. `verify` checks every proof of a bundle for contracts keeping their own
+/// bookkeeping; `consume` hands each proof and its digests to a `SingleSignConsumer`.
+/// Consumers then see this contract as `msg.sender`.
+contract BatchVerifier {
+    IRiscZeroVerifier public immutable verifier;
+
+    error InvalidProof(uint256 index);
+    error ConsumeFailed(uint256 index, bytes reason);
+
+    constructor(IRiscZeroVerifier _verifier) {
+        verifier = _verifier;
+    }
+
+    /// Revert unless every proof of `bundle` verifies as a run of `imageId`, naming the
+    /// first that does not. Returns the number of proofs.
+    function verify(bytes calldata bundle, bytes32 imageId) external view returns (uint256 count) {
+        uint256 at;
+        (count, at) = SingleSignBundle.header(bundle);
+        for (uint256 i; i < count; ++i) {
+            (bytes calldata seal, bytes calldata journal,, uint256 next) =
+                SingleSignBundle.next(bundle, at);
+            try verifier.verify(seal, imageId, sha256(journal)) {}
+            catch {
+                revert InvalidProof(i);
+            }
+            at = next;
+        }
+        SingleSignBundle.end(bundle, at);
+    }
+
+    /// Call `consumer.consume` with every proof of `bundle` and its digests. A failing
+    /// call reverts the whole bundle with its index and reason, so either every proof
+    /// lands or none does. Returns the number of proofs.
+    function consume(ISingleSignConsumer consumer, bytes calldata bundle)
+        external
+        returns (uint256 count)
+    {
+        uint256 at;
+        (count, at) = SingleSignBundle.header(bundle);
+        for (uint256 i; i < count; ++i) {
+            (bytes calldata seal, bytes calldata journal, bytes32[] memory digests, uint256 next) =
+                SingleSignBundle.next(bundle, at);
+            try consumer.consume(seal, journal, digests) {}
+            catch (bytes memory reason) {
+                revert ConsumeFailed(i, reason);
+            }
+            at = next;
+        }
+        SingleSignBundle.end(bundle, at);
+    }
+}
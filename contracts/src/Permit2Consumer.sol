@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.20;
+
+import {IRiscZeroVerifier, SingleSignConsumer, SingleSignJournal} from "./SingleSignConsumer.sol";
+
+/// The parts of Permit2 `Permit2Consumer` calls: its EIP-712 domain and the transfers of
+/// its allowance side (`AllowanceTransfer`).
+interface IPermit2 {
+    function DOMAIN_SEPARATOR() external view returns (bytes32);
+    function transferFrom(address from, address to, uint160 amount, address token) external;
+}
+
+/// Consumer that executes the Permit2 `PermitTransferFrom`s a batch proof attests. The
+/// owner signed the blob the permits are in rather than each permit, so Permit2 cannot
+/// check the signature itself; instead the owner approves this contract once in
+/// Permit2 (`approve(token, consumer, amount, expiration)`) and the contract holds
+/// each transfer to its proven permit before moving tokens through that allowance.
+///
+/// The batch must be proven with the extract paths `permitted.token`,
+/// `permitted.amount`, `spender`, `nonce` and `deadline`, in that order, and with a
+/// transfer per permit (`--recipient`, `--requested-amount`). Nonces are tracked here
+/// per owner, not in Permit2's bitmap, which only signature transfers use.
+contract Permit2Consumer is SingleSignConsumer {
+    /// Permit2's `TokenPermissions` and `PermitTransferFrom` type hashes
+    bytes32 private constant TOKEN_PERMISSIONS_TYPEHASH =
+        keccak256("TokenPermissions(address token,uint256 amount)");
+    bytes32 private constant PERMIT_TRANSFER_FROM_TYPEHASH = keccak256(
+        "PermitTransferFrom(TokenPermissions permitted,address spender,uint256 nonce,"
+        "uint256 deadline)TokenPermissions(address token,uint256 amount)"
+    );
+
+    /// The fields of an entry, in extract-path order
+    struct Permit {
+        address token;
+        uint256 amount;
+        address spender;
+        uint256 nonce;
+        uint256 deadline;
+    }
+
+    IPermit2 public immutable permit2;
+
+    mapping(address owner => mapping(uint256 nonce => bool)) public usedNonces;
+
+    event PermitExecuted(
+        bytes32 indexed digest, address indexed owner, address to, address token, uint256 amount
+    );
+
+    error NoTransfer(bytes32 digest);
+    error NotPermit(bytes32 digest);
+    error WrongSpender(bytes32 digest, address spender);
+    error PermitExpired(bytes32 digest, uint256 deadline);
+    error NonceUsed(address owner, uint256 nonce);
+    error AmountExceedsPermit(bytes32 digest, uint256 requested, uint256 permitted);
+
+    constructor(IRiscZeroVerifier _verifier, bytes32 _imageId, IPermit2 _permit2)
+        SingleSignConsumer(_verifier, _imageId)
+    {
+        permit2 = _permit2;
+    }
+
+    function _onConsume(SingleSignJournal.Entry memory entry) internal override {
+        if (!entry.hasTransfer) revert NoTransfer(entry.digest);
+        if (entry.fields.length != 5 * 32) revert NotPermit(entry.digest);
+        Permit memory permit = abi.decode(entry.fields, (Permit));
+        // The fields are only what the prover chose to extract; the digest they hash to
+        // under Permit2's domain is what the owner signed
+        if (permitDigest(permit) != entry.digest) revert NotPermit(entry.digest);
+        if (permit.spender != address(this)) revert WrongSpender(entry.digest, permit.spender);
+        if (block.timestamp > permit.deadline) revert PermitExpired(entry.digest, permit.deadline);
+        if (usedNonces[entry.signer][permit.nonce]) revert NonceUsed(entry.signer, permit.nonce);
+        usedNonces[entry.signer][permit.nonce] = true;
+        if (entry.requestedAmount > permit.amount || entry.requestedAmount > type(uint160).max) {
+            revert AmountExceedsPermit(entry.digest, entry.requestedAmount, permit.amount);
+        }
+        permit2.transferFrom(
+            entry.signer, entry.transferTo, uint160(entry.requestedAmount), permit.token
+        );
+        emit PermitExecuted(
+            entry.digest, entry.signer, entry.transferTo, permit.token, entry.requestedAmount
+        );
+    }
+
+    function permitDigest(Permit memory permit) private view returns (bytes32) {
+        bytes32 permitted =
+            keccak256(abi.encode(TOKEN_PERMISSIONS_TYPEHASH, permit.token, permit.amount));
+        bytes32 structHash = keccak256(
+            abi.encode(
+                PERMIT_TRANSFER_FROM_TYPEHASH,
+                permitted,
+                permit.spender,
+                permit.nonce,
+                permit.deadline
+            )
+        );
+        return keccak256(abi.encodePacked("\x19\x01", permit2.DOMAIN_SEPARATOR(), structHash));
+    }
+}
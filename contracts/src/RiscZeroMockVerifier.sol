@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.20;
+
+import {IRiscZeroVerifier} from "./SingleSignConsumer.sol";
+
+/// Verifier for local chains that accepts the seals `host::seal::encode_seal` makes of
+/// dev-mode (fake) receipts: `selector` followed by the digest of the receipt claim for
+/// a successful run of `imageId` with journal `journalDigest`. It proves nothing, so
+/// deploy it only on Anvil and similar test chains.
+contract RiscZeroMockVerifier is IRiscZeroVerifier {
+    /// `sha256("risc0.ReceiptClaim")` and `sha256("risc0.Output")`, the tags risc0
+    /// prefixes the hashes of those structs with
+    bytes32 private constant RECEIPT_CLAIM_TAG = sha256("risc0.ReceiptClaim");
+    bytes32 private constant OUTPUT_TAG = sha256("risc0.Output");
+    /// Digest of the post state of a halted run
+    bytes32 private constant SYSTEM_STATE_ZERO_DIGEST =
+        0xa3acc27117418996340b84e5a90f3ef4c49d22c79e44aad822ec9c313e1eb8e2;
+
+    bytes4 public immutable selector;
+
+    error VerificationFailed();
+
+    constructor(bytes4 _selector) {
+        selector = _selector;
+    }
+
+    function verify(bytes calldata seal, bytes32 imageId, bytes32 journalDigest)
+        external
+        view
+    {
+        if (seal.length != 36 || bytes4(seal[:4]) != selector) revert VerificationFailed();
+        if (bytes32(seal[4:]) != claimDigest(imageId, journalDigest)) {
+            revert VerificationFailed();
+        }
+    }
+
+    /// Digest of `ReceiptClaim::ok(imageId, journal)`: no input, halted with exit code
+    /// 0, and an output of the journal with no assumptions.
+    function claimDigest(bytes32 imageId, bytes32 journalDigest)
+        public
+        pure
+        returns (bytes32)
+    {
+        bytes32 output =
+            sha256(abi.encodePacked(OUTPUT_TAG, journalDigest, bytes32(0), uint16(2) << 8));
+        return sha256(
+            abi.encodePacked(
+                RECEIPT_CLAIM_TAG,
+                bytes32(0),
+                imageId,
+                SYSTEM_STATE_ZERO_DIGEST,
+                output,
+                uint32(0),
+                uint32(0),
+                uint16(4) << 8
+            )
+        );
+    }
+}
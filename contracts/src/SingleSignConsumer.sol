@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.20;
+
+/// The RISC Zero verifier (or the chain's verifier router), which reverts unless
+/// `seal` proves a run of `imageId` that committed a journal with SHA-256 `journalDigest`.
+interface IRiscZeroVerifier {
+    function verify(bytes calldata seal, bytes32 imageId, bytes32 journalDigest) external view;
+}
+
+/// The `consume` call `host::submit` sends to every chain.
+interface ISingleSignConsumer {
+    function consume(bytes calldata seal, bytes calldata journal, bytes32[] calldata digests)
+        external;
+}
+
+/// Reads what a consumer needs from the journal of the batch guest: the `Vec<Output>`
+/// it commits with risc0's serde, a stream of 32-bit little-endian words. Integers of
+/// up to 32 bits, bools and enum variants take one word, u64 two (low word first);
+/// options and sequences start with a tag or length word; byte strings, which is how
+/// addresses, hashes and U256 values serialize, are a length word followed by the
+/// bytes padded to a word. Fields are read in the order of `single_sign_types::Output`,
+/// so a new journal version needs a matching decoder.
+library SingleSignJournal {
+    struct Entry {
+        uint16 version;
+        address signer;
+        bytes32 digest;
+        /// False when the entry attests a failure rather than a signed message
+        bool ok;
+        /// keccak256 of the blob the entry was proven in
+        bytes32 blobHash;
+        /// The message fields the prover extracted with `extract_paths`, ABI-encoded as
+        /// a parameter tuple; empty when none were, and in privacy mode
+        bytes fields;
+        /// Whether the host evaluated a signing policy, which one, and its verdict
+        bool hasPolicy;
+        bytes32 policyHash;
+        bool policyPassed;
+        /// Whether an intent envelope authorized the entry, and the envelope's nonce,
+        /// deadline and the time the guest checked it against
+        bool hasIntent;
+        uint256 intentNonce;
+        uint256 intentDeadline;
+        uint64 intentTime;
+        /// Whether the prover named the Permit2 transfer the message permits, and its
+        /// recipient and requested amount. The signer did not sign them and the guest
+        /// does not check them against the message, so a consumer moving tokens must
+        /// hold them to the permit itself
+        bool hasTransfer;
+        address transferTo;
+        uint256 requestedAmount;
+    }
+
+    error MalformedJournal();
+
+    function decode(bytes calldata journal) internal pure returns (Entry[] memory entries) {
+        (uint256 count, uint256 at) = word(journal, 0);
+        entries = new Entry[](count);
+        for (uint256 i; i < count; ++i) {
+            (entries[i], at) = entry(journal, at);
+        }
+        if (at != journal.length) revert MalformedJournal();
+    }
+
+    function entry(bytes calldata j, uint256 at) private pure returns (Entry memory e, uint256) {
+        uint256 value;
+        bytes32 bytesValue;
+        (value, at) = word(j, at);
+        e.version = uint16(value);
+        (bytesValue, at) = fixedBytes(j, at);
+        e.signer = address(uint160(uint256(bytesValue)));
+        (e.digest, at) = fixedBytes(j, at);
+        // failure: Option<Failure { code, range: { start: u64, end: u64 } }>
+        (value, at) = word(j, at);
+        e.ok = value == 0;
+        if (value == 1) at += 4 * 5;
+        (e.fields, at) = readBytes(j, at);
+        // field_commitments
+        (value, at) = word(j, at);
+        for (uint256 i; i < value; ++i) {
+            at = skipBytes(j, at);
+        }
+        // scheme, public_key, credential_id_hash
+        at += 4;
+        at = skipBytes(j, at);
+        at = skipBytes(j, at);
+        // manifest: Option<ManifestCheck { all_match, first_mismatch: Option<u32> }>
+        (value, at) = word(j, at);
+        if (value == 1) {
+            (value, at) = word(j, at + 4);
+            if (value == 1) at += 4;
+        }
+        // struct_hash
+        at = skipBytes(j, at);
+        // intent: Option<IntentCommitment { nonce, deadline, current_time: u64 }>
+        (value, at) = word(j, at);
+        e.hasIntent = value == 1;
+        if (e.hasIntent) {
+            (bytesValue, at) = fixedBytes(j, at);
+            e.intentNonce = uint256(bytesValue);
+            (bytesValue, at) = fixedBytes(j, at);
+            e.intentDeadline = uint256(bytesValue);
+            (value, at) = u64(j, at);
+            e.intentTime = uint64(value);
+        }
+        // low_s_enforced, signature_chain_id: Option<u64>
+        (value, at) = word(j, at + 4);
+        if (value == 1) at += 8;
+        // anchor: Option<BlockAnchor { number: u64, hash }>
+        (value, at) = word(j, at);
+        if (value == 1) at = skipBytes(j, at + 8);
+        // policy: Option<PolicyVerdict { policy_hash, passed }>
+        (value, at) = word(j, at);
+        e.hasPolicy = value == 1;
+        if (e.hasPolicy) {
+            (e.policyHash, at) = fixedBytes(j, at);
+            (value, at) = word(j, at);
+            e.policyPassed = value == 1;
+        }
+        // manifest_hash, blob_hash
+        at = skipBytes(j, at);
+        (e.blobHash, at) = fixedBytes(j, at);
+        // message_mode: Option<MessageMode>
+        (value, at) = word(j, at);
+        if (value == 1) at += 4;
+        // transfer: Option<TransferDetails { to, requested_amount }>
+        (value, at) = word(j, at);
+        e.hasTransfer = value == 1;
+        if (e.hasTransfer) {
+            (bytesValue, at) = fixedBytes(j, at);
+            e.transferTo = address(uint160(uint256(bytesValue)));
+            (bytesValue, at) = fixedBytes(j, at);
+            e.requestedAmount = uint256(bytesValue);
+        }
+        return (e, at);
+    }
+
+    function word(bytes calldata j, uint256 at) private pure returns (uint256 value, uint256) {
+        if (at + 4 > j.length) revert MalformedJournal();
+        value = uint256(uint8(j[at])) | uint256(uint8(j[at + 1])) << 8
+            | uint256(uint8(j[at + 2])) << 16 | uint256(uint8(j[at + 3])) << 24;
+        return (value, at + 4);
+    }
+
+    function u64(bytes calldata j, uint256 at) private pure returns (uint256 value, uint256) {
+        uint256 high;
+        (value, at) = word(j, at);
+        (high, at) = word(j, at);
+        return (high << 32 | value, at);
+    }
+
+    // A byte string of at most 32 bytes, read as a big-endian number
+    function fixedBytes(bytes calldata j, uint256 at) private pure returns (bytes32, uint256) {
+        uint256 length;
+        (length, at) = word(j, at);
+        if (length > 32 || at + length > j.length) revert MalformedJournal();
+        uint256 value;
+        for (uint256 i; i < length; ++i) {
+            value = value << 8 | uint8(j[at + i]);
+        }
+        return (bytes32(value), at + padded(length));
+    }
+
+    function readBytes(bytes calldata j, uint256 at) private pure returns (bytes memory, uint256) {
+        uint256 length;
+        (length, at) = word(j, at);
+        if (at + padded(length) > j.length) revert MalformedJournal();
+        return (j[at:at + length], at + padded(length));
+    }
+
+    function skipBytes(bytes calldata j, uint256 at) private pure returns (uint256) {
+        uint256 length;
+        (length, at) = word(j, at);
+        at += padded(length);
+        if (at > j.length) revert MalformedJournal();
+        return at;
+    }
+
+    function padded(uint256 length) private pure returns (uint256) {
+        return (length + 3) & ~uint256(3);
+    }
+}
+
+/// Reference consumer of batch proofs: verifies the seal through the verifier router,
+/// decodes the journal, and consumes each proven digest at most once per signer. Inherit
+/// from it and override `_onConsume` to act on the messages, e.g. execute the permits
+/// they sign. Anyone can prove their own signature over any digest, so `_onConsume` must
+/// check the entry's signer is the party the message speaks for, such as a permit's
+/// `owner`, and should check the entry's policy and intent if it relies on them.
+contract SingleSignConsumer is ISingleSignConsumer {
+    /// `OUTPUT_VERSION` of the journals `SingleSignJournal` decodes
+    uint16 public constant JOURNAL_VERSION = 2;
+
+    IRiscZeroVerifier public immutable verifier;
+    /// Image ID of the batch guest, `SINGLE_SIGN_BATCH_ID`
+    bytes32 public immutable imageId;
+
+    /// Keyed by signer too, so a proof of someone else's signature over a digest does not
+    /// use up the digest for its owner
+    mapping(address signer => mapping(bytes32 digest => bool)) public consumed;
+
+    event Consumed(bytes32 indexed digest, address indexed signer);
+
+    error UnsupportedJournalVersion(uint16 version);
+    error NotProven(bytes32 digest);
+    error AlreadyConsumed(address signer, bytes32 digest);
+
+    constructor(IRiscZeroVerifier _verifier, bytes32 _imageId) {
+        verifier = _verifier;
+        imageId = _imageId;
+    }
+
+    /// Consume `digests`, each of which `journal` must attest as signed. Entries of the
+    /// journal that are not listed, such as those for other chains, are left alone.
+    function consume(bytes calldata seal, bytes calldata journal, bytes32[] calldata digests)
+        external
+    {
+        verifier.verify(seal, imageId, sha256(journal));
+        SingleSignJournal.Entry[] memory entries = SingleSignJournal.decode(journal);
+        for (uint256 i; i < entries.length; ++i) {
+            if (entries[i].version != JOURNAL_VERSION) {
+                revert UnsupportedJournalVersion(entries[i].version);
+            }
+        }
+        for (uint256 i; i < digests.length; ++i) {
+            SingleSignJournal.Entry memory entry = entryOf(entries, digests[i]);
+            if (consumed[entry.signer][entry.digest]) {
+                revert AlreadyConsumed(entry.signer, entry.digest);
+            }
+            consumed[entry.signer][entry.digest] = true;
+            emit Consumed(entry.digest, entry.signer);
+            _onConsume(entry);
+        }
+    }
+
+    /// Called once per newly consumed (signer, digest), after it is marked consumed, with
+    /// the journal entry attesting it.
+    function _onConsume(SingleSignJournal.Entry memory entry) internal virtual {}
+
+    function entryOf(SingleSignJournal.Entry[] memory entries, bytes32 digest)
+        private
+        pure
+        returns (SingleSignJournal.Entry memory)
+    {
+        for (uint256 i; i < entries.length; ++i) {
+            if (entries[i].ok && entries[i].digest == digest) return entries[i];
+        }
+        revert NotProven(digest);
+    }
+}
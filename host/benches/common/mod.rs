@@ -0,0 +1,42 @@
+//! Blobs of typed data shared by the benches.
+
+use serde_json::json;
+use single_sign_types::canonical::compact_typed_data;
+
+/// A canonical `Mail` typed data whose contents are about `contents_len` bytes of
+/// mostly non-ASCII text.
+pub fn mail(index: usize, contents_len: usize) -> String {
+    let mut contents = String::with_capacity(contents_len + 4);
+    for c in "gm ünïcödé 🦊 ".chars().cycle() {
+        if contents.len() >= contents_len {
+            break;
+        }
+        contents.push(c);
+    }
+    let json = json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+            ],
+            "Mail": [
+                { "name": "index", "type": "uint256" },
+                { "name": "contents", "type": "string" },
+            ],
+        },
+        "primaryType": "Mail",
+        "domain": { "name": "Bench", "chainId": 1 },
+        "message": { "index": index, "contents": contents },
+    });
+    compact_typed_data(&json.to_string()).unwrap()
+}
+
+/// `mail`s of `contents_len` bytes of contents, one per line, up to `len` bytes.
+pub fn blob(len: usize, contents_len: usize) -> String {
+    let mut blob = String::with_capacity(len + contents_len + 256);
+    while blob.len() < len {
+        blob.push_str(&mail(blob.len(), contents_len));
+        blob.push('\n');
+    }
+    blob
+}
@@ -0,0 +1,70 @@
+//! Guest cycles to attest one range, by size of the message, in the single-range
+//! guest as built (`--features fast-eip712` for its hasher). Execution only, no
+//! proving, so it runs in seconds.
+//!
+//! Cycles are deterministic, so each size is checked against `cycles.json` next to
+//! this file: more than `TOLERANCE` above its recorded count fails the run. Sizes
+//! missing from it are recorded; `CYCLE_BASELINE=update` re-records all of them,
+//! e.g. after an intended change. Run with `cargo bench --bench cycles`.
+
+mod common;
+
+use alloy_primitives::{Bytes, B256};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use anyhow::{bail, Result};
+use host::prepare::prepare_inputs;
+use methods::SINGLE_SIGN_ELF;
+use risc0_zkvm::{default_executor, ExecutorEnv};
+use std::{collections::BTreeMap, fs, path::Path};
+
+// Allowed growth over the recorded count before the run fails
+const TOLERANCE: f64 = 0.05;
+
+const CONTENTS_LENS: [usize; 5] = [64, 1024, 4 * 1024, 16 * 1024, 64 * 1024];
+
+fn user_cycles(contents_len: usize, signer: &PrivateKeySigner) -> Result<u64> {
+    let blob = Bytes::from(common::mail(0, contents_len).into_bytes());
+    let signature = signer.sign_message_sync(&blob)?;
+    let [input] = &prepare_inputs(&blob, signature, signer.address())?[..] else {
+        bail!("expected a single range");
+    };
+    let mut builder = ExecutorEnv::builder();
+    builder.write(&input.header())?;
+    builder.write_frame(&input.typed_data_concat);
+    let session = default_executor().execute(builder.build()?, SINGLE_SIGN_ELF)?;
+    Ok(session.segments.iter().map(|segment| u64::from(segment.cycles)).sum())
+}
+
+fn main() -> Result<()> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/cycles.json");
+    let update = std::env::var("CYCLE_BASELINE").is_ok_and(|value| value == "update");
+    let mut baseline: BTreeMap<String, u64> = match fs::read(&path) {
+        Ok(json) if !update => serde_json::from_slice(&json)?,
+        _ => BTreeMap::new(),
+    };
+    let signer = PrivateKeySigner::from_bytes(&B256::repeat_byte(0x42))?;
+    let mut regressions = Vec::new();
+    for contents_len in CONTENTS_LENS {
+        let cycles = user_cycles(contents_len, &signer)?;
+        let key = contents_len.to_string();
+        match baseline.get(&key) {
+            Some(&recorded) => {
+                let change = cycles as f64 / recorded as f64 - 1.0;
+                println!("{contents_len:>6} B: {cycles:>10} cycles ({:+.1}%)", change * 100.0);
+                if change > TOLERANCE {
+                    regressions.push(format!("{contents_len} B: {recorded} -> {cycles}"));
+                }
+            }
+            None => {
+                println!("{contents_len:>6} B: {cycles:>10} cycles (recorded)");
+                baseline.insert(key, cycles);
+            }
+        }
+    }
+    fs::write(&path, serde_json::to_string_pretty(&baseline)? + "\n")?;
+    if !regressions.is_empty() {
+        bail!("cycles grew by more than {}%: {}", TOLERANCE * 100.0, regressions.join(", "));
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Host-side costs that grow with the blob: finding its objects and hashing each.
+//!
+//! Compare against a saved run to catch regressions: `cargo bench --bench ranges --
+//! --save-baseline main` on the base branch, then `--baseline main` on the change.
+//! Criterion flags changes beyond the noise threshold below as regressed.
+
+mod common;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use host::prepare::find_json_ranges;
+use single_sign_types::DigestRange;
+use std::{hint::black_box, time::Duration};
+
+const MIB: usize = 1 << 20;
+
+fn find_ranges(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_json_ranges");
+    // Many small objects, and few large ones whose strings dominate the scan
+    for contents_len in [64, 16 * 1024] {
+        let blob = common::blob(MIB, contents_len);
+        group.throughput(Throughput::Bytes(blob.len() as u64));
+        group.bench_with_input(BenchmarkId::new("1MiB", contents_len), &blob, |b, blob| {
+            b.iter(|| find_json_ranges(black_box(blob.as_bytes())).unwrap())
+        });
+    }
+    group.finish();
+}
+
+// Per object, as alloy hashes it on the host and in the default guests, and as the
+// `fast-eip712` guests hash it
+fn digest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("digest");
+    for contents_len in [64, 1024, 16 * 1024] {
+        let mail = common::mail(0, contents_len);
+        let range = DigestRange { start: 0, end: mail.len() };
+        group.throughput(Throughput::Bytes(mail.len() as u64));
+        group.bench_with_input(BenchmarkId::new("alloy", contents_len), &mail, |b, mail| {
+            b.iter(|| range.digest(black_box(mail.as_bytes()), true).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("fast", contents_len), &mail, |b, mail| {
+            b.iter(|| range.eip712_hashes(black_box(mail.as_bytes()), true).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .noise_threshold(0.05)
+        .measurement_time(Duration::from_secs(10));
+    targets = find_ranges, digest
+}
+criterion_main!(benches);
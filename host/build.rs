@@ -0,0 +1,17 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Only the `grpc` feature needs the generated service, which requires `protoc`
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/prover.proto")?;
+    // Only the `consumer` feature binds the compiled contracts, which requires `forge`
+    #[cfg(feature = "consumer")]
+    {
+        println!("cargo:rerun-if-changed=../contracts/src");
+        let status = std::process::Command::new("forge")
+            .args(["build", "--root", "../contracts"])
+            .status()?;
+        if !status.success() {
+            return Err("forge build failed in contracts/".into());
+        }
+    }
+    Ok(())
+}
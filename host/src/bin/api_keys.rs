@@ -0,0 +1,91 @@
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+use host::{config::ConfigArgs, indexer::Indexer};
+
+/// Manage the API keys of the gRPC proving service (`grpc_server --require-api-key`),
+/// stored in `index_db` together with their usage.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a key and print it; only its hash is stored
+    Create {
+        /// Name identifying the key's holder in usage records
+        name: String,
+
+        /// Requests allowed per minute
+        #[arg(long)]
+        rate_limit: Option<u32>,
+
+        /// Proofs allowed in total, a batch counting as one
+        #[arg(long)]
+        quota: Option<u64>,
+    },
+    /// Revoke a key, keeping its usage records
+    Revoke { name: String },
+    /// List every key with its limits and usage
+    List {
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = args.config.load()?;
+    let index_db = config
+        .index_db
+        .as_deref()
+        .ok_or_else(|| anyhow!("API keys are stored in index_db; set it"))?;
+    let indexer = Indexer::open(index_db)?;
+
+    match args.command {
+        Command::Create {
+            name,
+            rate_limit,
+            quota,
+        } => {
+            let key = indexer.create_api_key(&name, rate_limit, quota)?;
+            println!("{key}");
+        }
+        Command::Revoke { name } => {
+            if !indexer.revoke_api_key(&name)? {
+                bail!("No API key named {name}");
+            }
+            println!("Revoked {name}");
+        }
+        Command::List { json } => {
+            let keys = indexer.api_keys()?;
+            if json {
+                let keys: Vec<_> = keys
+                    .iter()
+                    .map(|(key, usage)| serde_json::json!({ "key": key, "usage": usage }))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&keys)?);
+                return Ok(());
+            }
+            let unlimited = |limit: Option<String>| limit.unwrap_or_else(|| "unlimited".into());
+            for (key, usage) in keys {
+                let revoked = if key.revoked { " (revoked)" } else { "" };
+                println!(
+                    "{}{revoked}: {} requests, {} of {} proofs, {} cycles, rate limit {}",
+                    key.name,
+                    usage.requests,
+                    usage.proofs,
+                    unlimited(key.quota.map(|quota| quota.to_string())),
+                    usage.cycles,
+                    unlimited(key.rate_limit.map(|limit| format!("{limit}/min"))),
+                );
+            }
+        }
+    }
+    Ok(())
+}
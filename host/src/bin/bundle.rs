@@ -0,0 +1,142 @@
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use host::{
+    bundle::{Bundle, BundleEntry},
+    config::ConfigArgs,
+    logging::LogArgs,
+    prepare::find_json_ranges,
+    receipts::load_receipt,
+    signer::SignerArgs,
+    submit::{group_by_chain, submit_bundle},
+    verify::check_batch_receipt_against_blob,
+};
+#[cfg(feature = "webhooks")]
+use host::{
+    submit::Status,
+    webhooks::{self, Event},
+};
+use methods::SINGLE_SIGN_BATCH_ID;
+use std::{fs, path::PathBuf};
+
+/// Pack the batch receipts of several proving runs into one bundle for a chain, so they
+/// land in one transaction through the chain's `BatchVerifier` instead of one `consume`
+/// call each.
+#[derive(Parser)]
+struct Args {
+    /// Output directories of earlier proving runs, each holding receipt_batch.bin and
+    /// blob.json
+    #[arg(required = true)]
+    runs: Vec<PathBuf>,
+
+    /// Chain to bundle for; each run contributes the digests signed for it
+    #[arg(long)]
+    chain_id: u64,
+
+    /// Where to write the bundle
+    #[arg(long, default_value = "bundle.bin")]
+    out: PathBuf,
+
+    /// Send the bundle through the chain's `batch_verifier` endpoint
+    #[arg(long)]
+    submit: bool,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    // Account paying for the transaction
+    #[command(flatten)]
+    signer: SignerArgs,
+
+    /// Blocks to wait for before counting the submission as confirmed
+    #[arg(long, default_value_t = 1, requires = "submit")]
+    confirmations: u64,
+
+    /// Bundle fake dev-mode receipts (mock verifiers only)
+    #[arg(long)]
+    allow_dev_receipts: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    #[cfg(not(feature = "webhooks"))]
+    if !config.webhooks.is_empty() {
+        bail!("webhooks require the `webhooks` feature");
+    }
+
+    let mut bundle = Bundle::default();
+    for dir in &args.runs {
+        let blob_path = dir.join("blob.json");
+        let blob = fs::read(&blob_path)
+            .map_err(|e| anyhow!("Failed reading {}: {e}", blob_path.display()))?;
+        let ranges = find_json_ranges(&blob)?;
+        let receipt = load_receipt(&dir.join("receipt_batch.bin"))?;
+        let outputs = check_batch_receipt_against_blob(
+            &receipt,
+            &blob,
+            &ranges,
+            None,
+            SINGLE_SIGN_BATCH_ID,
+        )?;
+        let digests = group_by_chain(&blob, &ranges, &outputs)?
+            .remove(&args.chain_id)
+            .unwrap_or_default();
+        if digests.is_empty() {
+            println!("{}: nothing signed for chain {}, skipped", dir.display(), args.chain_id);
+            continue;
+        }
+        println!("{}: {} digests", dir.display(), digests.len());
+        bundle
+            .entries
+            .push(BundleEntry::new(&receipt, digests, args.allow_dev_receipts)?);
+    }
+    if bundle.entries.is_empty() {
+        bail!("No run holds objects signed for chain {}", args.chain_id);
+    }
+    let encoded = bundle.encode()?;
+    fs::write(&args.out, &encoded)
+        .map_err(|e| anyhow!("Failed writing {}: {e}", args.out.display()))?;
+    println!(
+        "Bundled {} proofs of {} digests into {} bytes at {}",
+        bundle.entries.len(),
+        bundle.digests().count(),
+        encoded.len(),
+        args.out.display()
+    );
+    if !args.submit {
+        return Ok(());
+    }
+
+    let signer = args
+        .signer
+        .signer()?
+        .ok_or_else(|| anyhow!("Submitting requires --private-key or --mnemonic"))?;
+    let endpoint = config
+        .endpoints
+        .get(&args.chain_id.to_string())
+        .ok_or_else(|| anyhow!("No endpoint configured for chain {}", args.chain_id))?;
+    let status = submit_bundle(args.chain_id, endpoint, &signer, encoded, args.confirmations)?;
+    println!("Chain {}: {status}", args.chain_id);
+    #[cfg(feature = "webhooks")]
+    if let Status::Confirmed {
+        tx_hash,
+        block_number,
+    } = &status
+    {
+        let event = Event::TransactionConfirmed {
+            chain_id: args.chain_id,
+            tx_hash: *tx_hash,
+            block_number: *block_number,
+            digests: bundle.digests().copied().collect(),
+        };
+        webhooks::notify(&config.webhooks, &event);
+    }
+    if !status.is_confirmed() {
+        bail!("The bundle did not confirm on chain {}", args.chain_id);
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use alloy_primitives::{Address, B256};
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use host::{
+    chains,
+    config::ConfigArgs,
+    contracts::{deploy_batch_verifier, deploy_consumer, deploy_local},
+    logging::LogArgs,
+    rpc,
+    seal::image_id_bytes,
+    signer::SignerArgs,
+};
+use methods::SINGLE_SIGN_BATCH_ID;
+
+/// Deploy the reference `SingleSignConsumer` to the chain at `rpc_url`, verifying
+/// batch receipts of this build's guest through the chain's verifier router, and print
+/// the `endpoints` entry that points `submit` at it. With `--local`, set up an Anvil
+/// node for end-to-end runs with dev-mode receipts instead.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    // Account paying for the deployment
+    #[command(flatten)]
+    signer: SignerArgs,
+
+    /// Verifier to check seals with instead of the chain's router
+    #[arg(long)]
+    verifier: Option<Address>,
+
+    /// Batch guest image ID to accept instead of this build's
+    #[arg(long)]
+    image_id: Option<B256>,
+
+    /// Deploy a mock verifier that accepts dev-mode receipts, make sure Permit2 is
+    /// there, and verify the consumer through the mock; for Anvil only
+    #[arg(long, conflicts_with = "verifier")]
+    local: bool,
+
+    /// RPC of a live chain to copy Permit2 from when the local node lacks it
+    #[arg(long, requires = "local")]
+    permit2_from: Option<String>,
+
+    /// Also deploy a `BatchVerifier` on the same verifier, for `bundle --submit`
+    #[arg(long)]
+    batch_verifier: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    let rpc_url = config
+        .rpc_url
+        .as_deref()
+        .ok_or_else(|| anyhow!("Deploying requires rpc_url"))?;
+    let chain_id = rpc::chain_id(rpc_url)?;
+    let image_id = args
+        .image_id
+        .unwrap_or_else(|| image_id_bytes(SINGLE_SIGN_BATCH_ID));
+    let signer = args
+        .signer
+        .signer()?
+        .ok_or_else(|| anyhow!("Provide --private-key or --mnemonic to pay for the deployment"))?;
+
+    if args.local {
+        let permit2_from = args.permit2_from.as_deref();
+        let deployment = deploy_local(rpc_url, signer.clone(), image_id, permit2_from)?;
+        println!("Mock verifier deployed at {:#x}", deployment.verifier);
+        println!("Permit2 at {:#x}", deployment.permit2);
+        println!("SingleSignConsumer deployed at {:#x}", deployment.consumer);
+        let batch_verifier = args
+            .batch_verifier
+            .then(|| deploy_batch_verifier(rpc_url, signer, deployment.verifier))
+            .transpose()?;
+        if let Some(batch_verifier) = batch_verifier {
+            println!("BatchVerifier deployed at {batch_verifier:#x}");
+        }
+        println!("Add them to the config, prove with RISC0_DEV_MODE=1 and submit with");
+        println!("--allow-dev-receipts:\n");
+        println!("verifier_address = \"{:#x}\"", deployment.verifier);
+        println!("permit2_address = \"{:#x}\"\n", deployment.permit2);
+        print_endpoint(chain_id, rpc_url, deployment.consumer, batch_verifier);
+        return Ok(());
+    }
+
+    let chain = chains::resolve(chain_id, &config)?;
+    let verifier = args
+        .verifier
+        .or(chain.router)
+        .or(chain.verifier)
+        .ok_or_else(|| anyhow!("Chain {chain_id} has no verifier; pass --verifier"))?;
+    let consumer = deploy_consumer(rpc_url, signer.clone(), verifier, image_id)?;
+    println!("SingleSignConsumer deployed at {consumer:#x} on chain {chain_id}");
+    let batch_verifier = args
+        .batch_verifier
+        .then(|| deploy_batch_verifier(rpc_url, signer, verifier))
+        .transpose()?;
+    if let Some(batch_verifier) = batch_verifier {
+        println!("BatchVerifier deployed at {batch_verifier:#x}");
+    }
+    println!("Add it to the config for `submit`:\n");
+    print_endpoint(chain_id, rpc_url, consumer, batch_verifier);
+    Ok(())
+}
+
+fn print_endpoint(
+    chain_id: u64,
+    rpc_url: &str,
+    consumer: Address,
+    batch_verifier: Option<Address>,
+) {
+    println!("[endpoints.{chain_id}]");
+    println!("rpc_url = \"{rpc_url}\"");
+    println!("consumer = \"{consumer:#x}\"");
+    if let Some(batch_verifier) = batch_verifier {
+        println!("batch_verifier = \"{batch_verifier:#x}\"");
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use host::{input::read_input, prepare::list_digests};
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// One line per object
+    Text,
+    /// An array of `{index, start, end, primary_type, digest, domain_separator}`
+    Json,
+    /// The digests as one `bytes32[]` argument for `cast send` / `cast call`
+    Cast,
+}
+
+/// Print the EIP-712 digest of every typed-data object in a blob, computed as the
+/// prover does, for signing UIs and contracts to consume.
+#[derive(Parser)]
+struct Args {
+    /// Concatenated typed-data blob, or `-` for stdin
+    file: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let objects = list_digests(&read_input(&args.file)?)?;
+    match args.format {
+        Format::Text => {
+            for object in &objects {
+                println!(
+                    "#{} {}..{}: {} {}",
+                    object.index, object.start, object.end, object.primary_type, object.digest
+                );
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&objects)?),
+        Format::Cast => {
+            let digests: Vec<String> = objects.iter().map(|o| o.digest.to_string()).collect();
+            println!("[{}]", digests.join(","));
+        }
+    }
+    Ok(())
+}
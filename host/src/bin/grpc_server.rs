@@ -0,0 +1,385 @@
+use alloy_primitives::{Address, Bytes};
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use host::{
+    config::{Config, ConfigArgs},
+    indexer::{ApiKey, Indexer},
+    logging::LogArgs,
+    prepare::{prepare_inputs, unchecked_inputs},
+    prover::{prove_framed, Proof},
+    seal::encode_seal,
+    verify::{check_batch_receipt_against_blob, check_receipt_against_blob},
+};
+use methods::{SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID, SINGLE_SIGN_ELF, SINGLE_SIGN_ID};
+use proto::{
+    input_chunk::Chunk,
+    prove_event::Event,
+    prover_server::{Prover, ProverServer},
+    Accepted, InputChunk, ProveEvent, ProveRequest, ProvingStarted, ReceiptReady,
+};
+use risc0_zkvm::InnerReceipt;
+use single_sign_types::{
+    intent::IntentPolicy,
+    signing::{parse_signature_with_chain_id, Envelope},
+    Assertion, BatchInput, DigestMode, Input,
+};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    thread,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{transport::Server, Request, Response, Status, Streaming};
+#[cfg(feature = "webhooks")]
+use {
+    alloy_primitives::B256,
+    host::webhooks::{self, Event as WebhookEvent},
+};
+
+mod proto {
+    tonic::include_proto!("single_sign.v1");
+}
+
+/// Largest blob accepted over one stream.
+const MAX_BLOB_LEN: usize = 16 << 20;
+
+/// Serve the streaming gRPC proving API described in `proto/prover.proto`.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:50051")]
+    listen: SocketAddr,
+
+    /// Serve only requests carrying an API key from `index_db` in their `x-api-key`
+    /// metadata, within the key's rate limit and proof quota; manage keys with the
+    /// `api_keys` binary
+    #[arg(long)]
+    require_api_key: bool,
+}
+
+struct ProverService {
+    config: Config,
+    /// Set when requests must carry an API key
+    keys: Option<Arc<Mutex<Indexer>>>,
+}
+
+// The key a request was admitted with and its row in `api_requests`
+struct Admitted {
+    keys: Arc<Mutex<Indexer>>,
+    key: ApiKey,
+    request_id: i64,
+}
+
+impl Admitted {
+    fn charge(&self, blob_len: usize, proofs: u64) -> Result<()> {
+        lock(&self.keys).charge_request(&self.key, self.request_id, blob_len, proofs)
+    }
+
+    fn finish(&self, delivered: &Delivered, error: Option<&str>) {
+        let finished = lock(&self.keys).finish_request(
+            self.request_id,
+            delivered.proofs,
+            delivered.cycles,
+            error,
+        );
+        if let Err(e) = finished {
+            tracing::warn!("Failed recording usage of API key {}: {e}", self.key.name);
+        }
+    }
+}
+
+/// Proofs sent to the client so far, and the cycles they took.
+#[derive(Default)]
+struct Delivered {
+    proofs: u64,
+    cycles: u64,
+}
+
+impl ProverService {
+    /// Check the request's API key and rate limit, when keys are required.
+    fn admit<T>(&self, request: &Request<T>) -> Result<Option<Admitted>, Status> {
+        let Some(keys) = &self.keys else {
+            return Ok(None);
+        };
+        let key = request
+            .metadata()
+            .get("x-api-key")
+            .and_then(|key| key.to_str().ok())
+            .ok_or_else(|| Status::unauthenticated("Missing x-api-key metadata"))?;
+        let indexer = lock(keys);
+        let key = indexer
+            .authenticate(key)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::unauthenticated("Unknown or revoked API key"))?;
+        let request_id = indexer
+            .begin_request(&key)
+            .map_err(|e| Status::resource_exhausted(e.to_string()))?;
+        Ok(Some(Admitted {
+            keys: keys.clone(),
+            key,
+            request_id,
+        }))
+    }
+}
+
+// A panicking prover thread must not lock every client out
+fn lock(keys: &Mutex<Indexer>) -> std::sync::MutexGuard<'_, Indexer> {
+    keys.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+type Events = mpsc::Sender<Result<ProveEvent, Status>>;
+
+#[tonic::async_trait]
+impl Prover for ProverService {
+    type ProveStream = ReceiverStream<Result<ProveEvent, Status>>;
+
+    async fn prove(
+        &self,
+        request: Request<Streaming<InputChunk>>,
+    ) -> Result<Response<Self::ProveStream>, Status> {
+        let admitted = self.admit(&request)?;
+        // Callers propagating W3C trace context see proving as part of their trace
+        let span = tracing::info_span!("grpc_prove");
+        host::logging::continue_trace(&span, |key| {
+            request.metadata().get(key).and_then(|value| value.to_str().ok())
+        });
+        let mut chunks = request.into_inner();
+        let request = match chunks.message().await?.and_then(|chunk| chunk.chunk) {
+            Some(Chunk::Request(request)) => request,
+            _ => return Err(Status::invalid_argument("First chunk must be a ProveRequest")),
+        };
+        let mut blob = Vec::new();
+        while let Some(chunk) = chunks.message().await? {
+            match chunk.chunk {
+                Some(Chunk::Blob(bytes)) => blob.extend_from_slice(&bytes),
+                _ => {
+                    return Err(Status::invalid_argument("Only blob chunks may follow the request"))
+                }
+            }
+            if blob.len() > MAX_BLOB_LEN {
+                return Err(Status::resource_exhausted(format!(
+                    "Blob exceeds {MAX_BLOB_LEN} bytes"
+                )));
+            }
+        }
+
+        // Reject bad ranges and signatures before accepting any proving work
+        let blob = Bytes::from(blob);
+        let inputs = tracing::info_span!(parent: &span, "parse")
+            .in_scope(|| accept(&request, &blob))
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        if let Some(admitted) = &admitted {
+            // A batch is delivered as one receipt
+            let proofs = if request.batch { 1 } else { inputs.len() as u64 };
+            admitted
+                .charge(blob.len(), proofs)
+                .map_err(|e| Status::resource_exhausted(e.to_string()))?;
+        }
+
+        let (events, receiver) = mpsc::channel(16);
+        let config = self.config.clone();
+        // Proving blocks, and Bonsai's blocking client cannot run inside tokio
+        thread::spawn(move || {
+            let _entered = span.enter();
+            let accepted = Event::Accepted(Accepted {
+                blob_len: blob.len() as u64,
+                ranges: inputs.len() as u32,
+            });
+            let mut delivered = Delivered::default();
+            let result = send(&events, accepted).and_then(|()| {
+                if request.batch {
+                    prove_batch(&config, request, blob, inputs, &events, &mut delivered)
+                } else {
+                    prove_ranges(&config, inputs, &events, &mut delivered)
+                }
+            });
+            if let Some(admitted) = &admitted {
+                let error = result.as_ref().err().map(ToString::to_string);
+                admitted.finish(&delivered, error.as_deref());
+            }
+            if let Err(e) = result {
+                let _ = events.blocking_send(Err(Status::internal(e.to_string())));
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Build the inputs for `request`, checked on the host unless failures are to be proven.
+fn accept(request: &ProveRequest, blob: &Bytes) -> Result<Vec<Input>> {
+    let signer: Address = request.signer.parse()?;
+    let (signature, signature_chain_id) = parse_signature_with_chain_id(&request.signature)?;
+    let (assert, inputs) = if request.report_validity {
+        let inputs = unchecked_inputs(blob, signature, signer, &Envelope::Personal)?;
+        (Assertion::ReportValidity, inputs)
+    } else {
+        (Assertion::MustBeValid, prepare_inputs(blob, signature, signer)?)
+    };
+    Ok(inputs
+        .into_iter()
+        .map(|input| Input {
+            assert,
+            extract_paths: request.extract_paths.clone(),
+            signature_chain_id,
+            ..input
+        })
+        .collect())
+}
+
+fn prove_ranges(
+    config: &Config,
+    inputs: Vec<Input>,
+    events: &Events,
+    delivered: &mut Delivered,
+) -> Result<()> {
+    for (index, input) in inputs.iter().enumerate() {
+        let index = index as u32;
+        send(events, Event::ProvingStarted(ProvingStarted { index }))?;
+        let proof = prove_framed(config, SINGLE_SIGN_ELF, SINGLE_SIGN_ID, input)?;
+        let output = check_receipt_against_blob(
+            &proof.receipt,
+            &input.typed_data_concat,
+            &input.digest_range,
+            input.signer,
+            SINGLE_SIGN_ID,
+        )?;
+        let journal_json = serde_json::to_string(&output)?;
+        let cycles = proof.total_cycles.unwrap_or(0);
+        #[cfg(feature = "webhooks")]
+        let journal = proof.receipt.journal.bytes.clone();
+        send(events, receipt_ready(index, journal_json, proof)?)?;
+        delivered.proofs += 1;
+        delivered.cycles += cycles;
+        #[cfg(feature = "webhooks")]
+        notify_proof(config, format!("grpc range {index}"), journal, vec![output.digest]);
+    }
+    Ok(())
+}
+
+fn prove_batch(
+    config: &Config,
+    request: ProveRequest,
+    blob: Bytes,
+    inputs: Vec<Input>,
+    events: &Events,
+    delivered: &mut Delivered,
+) -> Result<()> {
+    let Some(first) = inputs.first() else {
+        return Ok(());
+    };
+    let batch_input = BatchInput {
+        signer: request.signer.parse()?,
+        signature: first.signature,
+        typed_data_concat: blob,
+        ranges: inputs.iter().map(|input| input.digest_range.clone()).collect(),
+        require_canonical: true,
+        assert: first.assert,
+        extract_paths: request.extract_paths,
+        disclosure_salt: None,
+        envelope: Envelope::Personal,
+        key_signature: None,
+        segments: Vec::new(),
+        expected_digests: Vec::new(),
+        digest_mode: DigestMode::Eip712,
+        intent_policy: IntentPolicy::default(),
+        allow_high_s: false,
+        signature_chain_id: first.signature_chain_id,
+        anchor: None,
+        policy: None,
+        transfers: Vec::new(),
+    };
+    send(events, Event::ProvingStarted(ProvingStarted { index: 0 }))?;
+    let proof = prove_framed(config, SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID, &batch_input)?;
+    let outputs = check_batch_receipt_against_blob(
+        &proof.receipt,
+        &batch_input.typed_data_concat,
+        &batch_input.ranges,
+        Some(batch_input.signer),
+        SINGLE_SIGN_BATCH_ID,
+    )?;
+    let cycles = proof.total_cycles.unwrap_or(0);
+    #[cfg(feature = "webhooks")]
+    let journal = proof.receipt.journal.bytes.clone();
+    send(events, receipt_ready(0, serde_json::to_string(&outputs)?, proof)?)?;
+    delivered.proofs = 1;
+    delivered.cycles = cycles;
+    #[cfg(feature = "webhooks")]
+    notify_proof(
+        config,
+        "grpc batch".to_owned(),
+        journal,
+        outputs.iter().map(|output| output.digest).collect(),
+    );
+    Ok(())
+}
+
+// Tell the webhooks about a receipt streamed to the client
+#[cfg(feature = "webhooks")]
+fn notify_proof(config: &Config, source: String, journal: Vec<u8>, digests: Vec<B256>) {
+    let event = WebhookEvent::ProofFinished {
+        source,
+        journal: journal.into(),
+        receipt: None,
+        digests,
+    };
+    webhooks::notify(&config.webhooks, &event);
+}
+
+fn receipt_ready(index: u32, journal_json: String, proof: Proof) -> Result<Event> {
+    let receipt = proof.receipt;
+    let seal = match &receipt.inner {
+        InnerReceipt::Groth16(_) => encode_seal(&receipt, false)?,
+        _ => Vec::new(),
+    };
+    Ok(Event::ReceiptReady(ReceiptReady {
+        index,
+        journal_json,
+        journal: receipt.journal.bytes.clone(),
+        seal,
+        // Zero when the backend does not report cycles
+        cycles: proof.total_cycles.unwrap_or(0),
+        receipt: bincode::serialize(&receipt)?,
+    }))
+}
+
+fn send(events: &Events, event: Event) -> Result<()> {
+    events
+        .blocking_send(Ok(ProveEvent { event: Some(event) }))
+        .map_err(|_| anyhow!("Client disconnected"))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    config.prover.apply();
+    #[cfg(not(feature = "webhooks"))]
+    if !config.webhooks.is_empty() {
+        anyhow::bail!("webhooks require the `webhooks` feature");
+    }
+    let keys = if args.require_api_key {
+        let index_db = config
+            .index_db
+            .as_deref()
+            .ok_or_else(|| anyhow!("--require-api-key requires index_db"))?;
+        Some(Arc::new(Mutex::new(Indexer::open(index_db)?)))
+    } else {
+        tracing::warn!("Serving without API keys; keep the service on a trusted network");
+        None
+    };
+
+    println!("Serving the gRPC prover on {}", args.listen);
+    Server::builder()
+        .add_service(ProverServer::new(ProverService { config, keys }))
+        .serve(args.listen)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use alloy_primitives::B256;
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use host::seal::image_id_bytes;
+use methods::{SINGLE_SIGN_BATCH_ID, SINGLE_SIGN_ID, SINGLE_SIGN_SAFE_ID, SINGLE_SIGN_STEEL_ID};
+use risc0_zkvm::compute_image_id;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Rebuild the guest reproducibly in RISC Zero's pinned Docker image (via
+/// `cargo risczero build`), print its image IDs, and check them against the IDs
+/// compiled into this binary and, optionally, against a pinned on-chain ID.
+/// Exits non-zero on any mismatch.
+#[derive(Parser)]
+struct Args {
+    /// Manifest of the guest crate
+    #[arg(long, default_value = "methods/guest/Cargo.toml")]
+    manifest_path: PathBuf,
+
+    /// Only print the image IDs compiled into this binary
+    #[arg(long)]
+    no_build: bool,
+
+    /// Image ID the single-range guest must reproduce, e.g. the one trusted on-chain
+    #[arg(long)]
+    expected: Option<B256>,
+
+    /// Image ID the batch guest must reproduce
+    #[arg(long)]
+    expected_batch: Option<B256>,
+
+    /// Image ID the Safe state-proof guest must reproduce
+    #[arg(long)]
+    expected_safe: Option<B256>,
+
+    /// Image ID the Steel preconditions guest must reproduce
+    #[arg(long)]
+    expected_steel: Option<B256>,
+}
+
+/// Guest binaries and the image IDs this host was built with.
+const GUESTS: [(&str, [u32; 8]); 4] = [
+    ("single_sign", SINGLE_SIGN_ID),
+    ("single_sign_batch", SINGLE_SIGN_BATCH_ID),
+    ("single_sign_safe", SINGLE_SIGN_SAFE_ID),
+    ("single_sign_steel", SINGLE_SIGN_STEEL_ID),
+];
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.no_build {
+        for (name, image_id) in GUESTS {
+            println!("{name}: {}", image_id_bytes(image_id));
+        }
+        return Ok(());
+    }
+
+    println!("Building {} in Docker...", args.manifest_path.display());
+    let status = Command::new("cargo")
+        .args(["risczero", "build", "--manifest-path"])
+        .arg(&args.manifest_path)
+        .status()
+        .map_err(|e| anyhow!("Failed running `cargo risczero build` (is it installed?): {e}"))?;
+    if !status.success() {
+        bail!("`cargo risczero build` failed with {status}");
+    }
+
+    let target_dir = args
+        .manifest_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("target")
+        .join("riscv-guest");
+    let mut mismatches = 0;
+    let expectations = [
+        args.expected,
+        args.expected_batch,
+        args.expected_safe,
+        args.expected_steel,
+    ];
+    for ((name, compiled), expected) in GUESTS.into_iter().zip(expectations) {
+        let elf_path = find_docker_elf(&target_dir, name)?
+            .ok_or_else(|| anyhow!("No Docker build of {name} under {}", target_dir.display()))?;
+        let rebuilt = image_id_bytes(compute_image_id(&fs::read(&elf_path)?)?);
+        let compiled = image_id_bytes(compiled);
+        println!("{name}: {rebuilt} ({})", elf_path.display());
+        if rebuilt != compiled {
+            println!(
+                "  differs from the compiled-in {compiled}; build the host with \
+                 RISC0_USE_DOCKER=1 to embed the reproducible guest"
+            );
+            mismatches += 1;
+        }
+        if let Some(expected) = expected.filter(|expected| *expected != rebuilt) {
+            println!("  differs from the expected {expected}");
+            mismatches += 1;
+        }
+    }
+    if mismatches > 0 {
+        bail!("{mismatches} image ID mismatch(es)");
+    }
+    println!("Image IDs reproduced");
+    Ok(())
+}
+
+/// Search `dir` for the `<name>.bin` that the Docker build wrote.
+fn find_docker_elf(dir: &Path, name: &str) -> Result<Option<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    let file_name = format!("{name}.bin");
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if let Some(found) = find_docker_elf(&path, name)? {
+                return Ok(Some(found));
+            }
+        } else if path.file_name().is_some_and(|file| file == file_name.as_str())
+            && path.components().any(|component| component.as_os_str() == "docker")
+        {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
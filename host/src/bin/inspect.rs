@@ -0,0 +1,122 @@
+use anyhow::Result;
+use clap::Parser;
+use host::{
+    config::ConfigArgs,
+    diagnostics::ParseDiagnostic,
+    domains, ens,
+    input::read_input,
+    lint::{lint, Policy, Rule},
+    prepare::find_json_ranges,
+    tokens::{token_amounts, Tokens},
+};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// List the typed-data objects in a concatenated blob with their EIP-712 digests and
+/// the protocols they belong to, without proving anything. Blobs that do not parse
+/// are reported with the line, column and surrounding text of the offending byte.
+/// With `rpc_url` set, verifying contracts are annotated with their ENS names and
+/// token amounts are shown in their tokens' decimals and symbols.
+#[derive(Parser)]
+struct Args {
+    /// Concatenated typed-data blob, or `-` for stdin
+    file: PathBuf,
+
+    /// Also list the token amounts in each object, e.g. `1,000.5 USDC`
+    #[arg(long)]
+    amounts: bool,
+
+    /// Also warn about dangerous or ambiguous objects, see `host::lint`, and exit
+    /// with status 1 if there are any
+    #[arg(long)]
+    lint: bool,
+
+    /// Days ahead a deadline may lie before `--lint` warns about it
+    #[arg(long, default_value_t = 30)]
+    max_deadline_days: u64,
+
+    /// Lint rule not to report; may be repeated
+    #[arg(long, value_enum)]
+    allow: Vec<Rule>,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = args.config.load()?;
+    let rpc_url = config.rpc_url.as_deref();
+    let blob = read_input(&args.file)?;
+
+    let ranges = match find_json_ranges(&blob) {
+        Ok(ranges) => ranges,
+        Err(e) => match e.downcast_ref::<ParseDiagnostic>() {
+            Some(diagnostic) => {
+                eprintln!("{}", diagnostic.render(&args.file.display().to_string()));
+                process::exit(1);
+            }
+            None => return Err(e),
+        },
+    };
+    // Blobs often repeat a contract; look each one up once
+    let mut names: HashMap<_, String> = HashMap::new();
+    let mut tokens = Tokens::open_default(rpc_url, config.chain_id)?;
+    for (i, range) in ranges.iter().enumerate() {
+        let typed_data = match range.typed_data(&blob, false) {
+            Ok(typed_data) => typed_data,
+            Err(code) => {
+                println!("#{i} {range}: {code}");
+                continue;
+            }
+        };
+        let label = domains::identify(&typed_data.domain, &typed_data.primary_type)
+            .map_or("unknown protocol", |known| known.label);
+        let contract = typed_data
+            .domain
+            .verifying_contract
+            .map(|contract| {
+                let annotated = names
+                    .entry(contract)
+                    .or_insert_with(|| ens::annotate(rpc_url, contract));
+                format!(" at {annotated}")
+            })
+            .unwrap_or_default();
+        let primary_type = &typed_data.primary_type;
+        match range.digest(&blob, true) {
+            Ok(digest) => println!("#{i} {range}: {primary_type} ({label}){contract} {digest}"),
+            Err(code) => println!("#{i} {range}: {primary_type} ({label}){contract} {code}"),
+        }
+        if args.amounts {
+            let chain_id = typed_data.domain.chain_id;
+            for amount in token_amounts(&typed_data.domain, primary_type, &typed_data.message) {
+                let token = amount.token.and_then(|token| tokens.get(token, chain_id));
+                match token {
+                    Some(token) => println!("    {}: {}", amount.path, token.format(amount.amount)),
+                    None => println!("    {}: {}", amount.path, amount.amount),
+                }
+            }
+        }
+    }
+
+    if args.lint {
+        let policy = Policy {
+            now: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            max_deadline: args.max_deadline_days * 86_400,
+            chain_id: Some(config.chain_id),
+            allow: args.allow,
+        };
+        let findings = lint(&blob, &ranges, &policy, &mut tokens);
+        for finding in &findings {
+            println!("warning: {finding}");
+        }
+        if !findings.is_empty() {
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use clap::Parser;
+use host::{
+    config::ConfigArgs,
+    diagnostics::ParseDiagnostic,
+    input::read_input,
+    lint::{lint, Policy},
+    prepare::find_json_ranges,
+    preview::{render, Entry, Format, Previewer},
+    tokens::Tokens,
+};
+use std::{
+    fs,
+    path::PathBuf,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Render each typed-data object in a blob the way a wallet's signing prompt would,
+/// with token amounts in their decimals and symbol, deadlines as dates and named
+/// contracts, plus the warnings of `inspect --lint`, for signers to review before
+/// `sign_file` runs. Token symbols and names are looked up via `rpc_url` when set.
+#[derive(Parser)]
+struct Args {
+    /// Concatenated typed-data blob, or `-` for stdin
+    file: PathBuf,
+
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+
+    /// Write the preview here instead of to stdout
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Days ahead a deadline may lie before it is flagged
+    #[arg(long, default_value_t = 30)]
+    max_deadline_days: u64,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = args.config.load()?;
+    let blob = read_input(&args.file)?;
+
+    let ranges = match find_json_ranges(&blob) {
+        Ok(ranges) => ranges,
+        Err(e) => match e.downcast_ref::<ParseDiagnostic>() {
+            Some(diagnostic) => {
+                eprintln!("{}", diagnostic.render(&args.file.display().to_string()));
+                process::exit(1);
+            }
+            None => return Err(e),
+        },
+    };
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let rpc_url = config.rpc_url.as_deref();
+    let tokens = Tokens::open_default(rpc_url, config.chain_id)?;
+    let mut previewer = Previewer::new(rpc_url, tokens, now);
+    let mut previews = previewer.preview(&blob, &ranges);
+    let policy = Policy {
+        now,
+        max_deadline: args.max_deadline_days * 86_400,
+        chain_id: Some(config.chain_id),
+        allow: Vec::new(),
+    };
+    for finding in lint(&blob, &ranges, &policy, previewer.tokens()) {
+        previews[finding.object]
+            .entries
+            .push(Entry::new("warning", finding.to_string()));
+    }
+
+    let rendered = render(&previews, args.format);
+    match args.out {
+        Some(path) => fs::write(&path, rendered)?,
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,394 @@
+use alloy_primitives::{Bytes, B256};
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+use host::{
+    cache::ReceiptCache,
+    config::{Config, ConfigArgs},
+    ens::AddressOrName,
+    input::{read_input, SignatureArgs},
+    logging::LogArgs,
+    manifest::{SignedManifest, SigningMode},
+    prepare::{chain_inputs, list_digests, merkle_inputs, prepare_inputs, unchecked_inputs},
+    prover::prove_framed,
+    queue::{Job, JobQueue},
+    receipts::save_receipt,
+    verify::check_receipt_against_blob,
+};
+use risc0_zkvm::Receipt;
+use signal_hook::{consts::TERM_SIGNALS, flag};
+#[cfg(feature = "webhooks")]
+use host::{
+    journal::decode_output,
+    webhooks::{self, Event},
+};
+use methods::{SINGLE_SIGN_ELF, SINGLE_SIGN_ID};
+use single_sign_types::{
+    signing::{match_message_mode, parse_signature_with_chain_id, Envelope, MessageMode},
+    Assertion, Input,
+};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often a worker waiting on a proof checks for a shutdown request.
+const SHUTDOWN_POLL: Duration = Duration::from_millis(500);
+
+/// Enqueue prove jobs and run workers that survive crashes mid-batch.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// Job queue database
+    #[arg(long, default_value = "single-sign-queue")]
+    queue_db: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Queue one job proving every typed-data object in a signed blob
+    Enqueue {
+        /// Concatenated typed-data blob, or `-` for stdin
+        #[arg(required_unless_present = "manifest")]
+        file: Option<PathBuf>,
+
+        /// Signer address or ENS name, resolved via `rpc_url`. Without it, the proofs
+        /// attest whoever the signature recovers to
+        #[arg(long)]
+        signer: Option<AddressOrName>,
+
+        /// `.singlesign` manifest naming the blob, signer, signature and signing mode,
+        /// instead of passing them separately
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "file",
+                "signer",
+                "signature",
+                "signature_file",
+                "merkle",
+                "chain",
+            ]
+        )]
+        manifest: Option<PathBuf>,
+
+        #[command(flatten)]
+        signature: SignatureArgs,
+
+        /// Prove invalid ranges as failure journals instead of rejecting the job
+        #[arg(long)]
+        report_validity: bool,
+
+        /// Message field to commit ABI-encoded in the journal; repeatable
+        #[arg(long = "extract-path")]
+        extract_paths: Vec<String>,
+
+        /// The signature is over the Merkle root of the blob's digests (`sign_file --mode
+        /// merkle`); each range is then proven on its own object and branch
+        #[arg(long, conflicts_with = "report_validity")]
+        merkle: bool,
+
+        /// The signature is over the head of the hash chain of the blob's digests
+        /// (`sign_file --mode chain`); each range is then proven on its own object and link
+        #[arg(long, conflicts_with_all = ["report_validity", "merkle"])]
+        chain: bool,
+
+        /// Ways the wallet may have signed, tried in order: `personal` (personal_sign of
+        /// the blob), `keccak` (a signature over its bare keccak256) or `raw32`
+        /// (eth_signTypedData of a single-object blob). The proofs commit the one that
+        /// matched; comma-separated or repeatable
+        #[arg(
+            long = "message-mode",
+            value_delimiter = ',',
+            requires = "signer",
+            conflicts_with_all = ["manifest", "merkle", "chain"]
+        )]
+        message_modes: Vec<MessageMode>,
+    },
+    /// Claim and prove jobs until the queue is empty, or forever with --follow. On
+    /// SIGTERM or SIGINT the job in progress is checkpointed and requeued; a second
+    /// signal exits at once
+    Work {
+        /// Name recorded on claimed jobs; a worker restarted under the same name
+        /// resumes its own jobs without waiting for their leases
+        #[arg(long, default_value = "worker")]
+        worker: String,
+
+        /// Seconds a claim lasts before another worker may take the job over
+        #[arg(long, default_value_t = 3600)]
+        lease_secs: u64,
+
+        /// Keep polling for new jobs instead of exiting when the queue is empty
+        #[arg(long)]
+        follow: bool,
+    },
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    config.prover.apply();
+    let queue = JobQueue::open(&args.queue_db)?;
+
+    match args.command {
+        Command::Enqueue {
+            file,
+            signer,
+            signature,
+            report_validity,
+            extract_paths,
+            merkle,
+            chain,
+            manifest,
+            message_modes,
+        } => {
+            let (blob, signer, signature, mode) = match &manifest {
+                Some(path) => {
+                    let (manifest, blob) = SignedManifest::load(path)?;
+                    (blob, Some(manifest.signer), manifest.signature, manifest.mode)
+                }
+                None => {
+                    let file = file.ok_or_else(|| anyhow!("Pass a blob or --manifest"))?;
+                    let signer = signer
+                        .map(|signer| signer.resolve(config.rpc_url.as_deref()))
+                        .transpose()?;
+                    let mode = match (merkle, chain) {
+                        (true, _) => SigningMode::Merkle,
+                        (_, true) => SigningMode::Chain,
+                        _ => SigningMode::Personal,
+                    };
+                    let signature = signature.bytes(&file)?;
+                    (Bytes::from(read_input(&file)?), signer, signature, mode)
+                }
+            };
+            let (signature, signature_chain_id) = parse_signature_with_chain_id(&signature)?;
+            // Check the inputs against the recovered signer, but leave the guest to
+            // recover and commit it rather than expect it
+            let (signer, expected_signer) = match signer {
+                Some(signer) => (signer, true),
+                None => {
+                    let message = mode.message(&blob, B256::ZERO)?;
+                    let signer = Envelope::Personal.recover(&message, signature)?;
+                    println!("The signature recovers to {signer:#x}");
+                    (signer, false)
+                }
+            };
+            let (assert, inputs) = if mode == SigningMode::Merkle {
+                (Assertion::MustBeValid, merkle_inputs(&blob, signature, signer)?)
+            } else if mode == SigningMode::Chain {
+                (Assertion::MustBeValid, chain_inputs(&blob, signature, signer)?)
+            } else if report_validity {
+                let inputs = unchecked_inputs(&blob, signature, signer, &Envelope::Personal)?;
+                (Assertion::ReportValidity, inputs)
+            } else if !message_modes.is_empty() {
+                let inputs = unchecked_inputs(&blob, signature, signer, &Envelope::Personal)?;
+                for object in list_digests(&blob)? {
+                    let mode =
+                        match_message_mode(&blob, object.digest, signature, signer, &message_modes)
+                            .map_err(|e| anyhow!("Range #{}: {e}", object.index))?;
+                    println!("Range #{} is signed in {mode:?} mode", object.index);
+                }
+                (Assertion::MustBeValid, inputs)
+            } else {
+                (Assertion::MustBeValid, prepare_inputs(&blob, signature, signer)?)
+            };
+            let inputs = inputs
+                .into_iter()
+                .map(|input| Input {
+                    signer: input.signer.filter(|_| expected_signer),
+                    assert,
+                    extract_paths: extract_paths.clone(),
+                    signature_chain_id,
+                    message_modes: message_modes.clone(),
+                    ..input
+                })
+                .collect();
+            let id = queue.enqueue(inputs, config.receipt_kind)?;
+            println!("Enqueued job {id}");
+        }
+        Command::Work {
+            worker,
+            lease_secs,
+            follow,
+        } => {
+            #[cfg(not(feature = "webhooks"))]
+            if !config.webhooks.is_empty() {
+                bail!("webhooks require the `webhooks` feature");
+            }
+            let cache = ReceiptCache::open_default()?;
+            let shutdown = Arc::new(AtomicBool::new(false));
+            for &signal in TERM_SIGNALS {
+                // Registered first, so it only fires once the flag is already set
+                flag::register_conditional_shutdown(signal, 1, Arc::clone(&shutdown))?;
+                flag::register(signal, Arc::clone(&shutdown))?;
+            }
+            while !shutdown.load(Ordering::Relaxed) {
+                let Some(job) = queue.claim(&worker, Duration::from_secs(lease_secs))? else {
+                    if !follow {
+                        break;
+                    }
+                    thread::sleep(Duration::from_secs(5));
+                    continue;
+                };
+                println!("Claimed job {} ({} ranges)", job.id, job.inputs.len());
+                match run_job(&config, &cache, &queue, &job, &shutdown) {
+                    Ok(Outcome::Done) => {
+                        queue.complete(job.id)?;
+                        println!("Job {} done", job.id);
+                    }
+                    Ok(Outcome::Interrupted { in_flight }) => {
+                        queue.interrupt(job.id, in_flight)?;
+                        println!(
+                            "Job {} interrupted and requeued; range #{in_flight} will be proven \
+                             again",
+                            job.id
+                        );
+                    }
+                    Err(e) => {
+                        queue.release(job.id)?;
+                        println!("Job {} failed and was requeued: {e}", job.id);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// How `run_job` ended.
+enum Outcome {
+    Done,
+    /// Shut down while proving `in_flight`; the ranges before it are checkpointed
+    Interrupted { in_flight: usize },
+}
+
+/// Prove every range of `job` that is not checkpointed yet, reusing cached receipts so
+/// a job resumed after a crash only proves the ranges that were still missing. Stops
+/// without waiting for the range in flight once `shutdown` is set.
+fn run_job(
+    config: &Config,
+    cache: &ReceiptCache,
+    queue: &JobQueue,
+    job: &Job,
+    shutdown: &AtomicBool,
+) -> Result<Outcome> {
+    let checkpoint = queue.checkpoint(job.id)?;
+    if !checkpoint.interrupted.is_empty() {
+        println!("Job {}: retrying interrupted ranges {:?}", job.id, checkpoint.interrupted);
+    }
+    let mut pending = Vec::new();
+    for (i, input) in job.inputs.iter().enumerate() {
+        if checkpoint.proven.contains(&i) && receipt_path(config, job, i).exists() {
+            println!("Job {} range #{i}: already saved", job.id);
+            continue;
+        }
+        // Only a receipt attesting this job's range and signer stands in for a proof
+        let cached = cache
+            .get(SINGLE_SIGN_ID, input, job.receipt_kind)
+            .filter(|receipt| match check_job_receipt(receipt, input) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!("Job {} range #{i}: not reusing cached receipt: {e}", job.id);
+                    false
+                }
+            });
+        match cached {
+            Some(receipt) => {
+                println!("Job {} range #{i}: reusing cached receipt", job.id);
+                finish_range(config, queue, job, i, &receipt)?;
+            }
+            None => pending.push(i),
+        }
+    }
+
+    // Proving runs on its own thread, so a shutdown need not wait for the range in
+    // flight; the thread dies with the process
+    let (proofs, received) = mpsc::channel();
+    let prover_config = Config {
+        receipt_kind: job.receipt_kind,
+        ..config.clone()
+    };
+    let inputs: Vec<_> = pending.iter().map(|&i| (i, job.inputs[i].clone())).collect();
+    let id = job.id;
+    thread::spawn(move || {
+        for (i, input) in inputs {
+            println!("Job {id} range #{i}: proving");
+            let proof = prove_framed(&prover_config, SINGLE_SIGN_ELF, SINGLE_SIGN_ID, &input);
+            let failed = proof.is_err();
+            if proofs.send((i, proof)).is_err() || failed {
+                break;
+            }
+        }
+    });
+    for &in_flight in &pending {
+        let proof = loop {
+            match received.recv_timeout(SHUTDOWN_POLL) {
+                Ok((i, proof)) => break proof.map_err(|e| anyhow!("Range #{i}: {e}"))?,
+                Err(RecvTimeoutError::Timeout) if shutdown.load(Ordering::Relaxed) => {
+                    return Ok(Outcome::Interrupted { in_flight });
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    bail!("The prover of job {} stopped at range #{in_flight}", job.id)
+                }
+            }
+        };
+        let input = &job.inputs[in_flight];
+        check_job_receipt(&proof.receipt, input)
+            .map_err(|e| anyhow!("Range #{in_flight}: {e}"))?;
+        cache.put(SINGLE_SIGN_ID, input, job.receipt_kind, &proof.receipt)?;
+        finish_range(config, queue, job, in_flight, &proof.receipt)?;
+    }
+    Ok(Outcome::Done)
+}
+
+fn check_job_receipt(receipt: &Receipt, input: &Input) -> Result<()> {
+    let blob = &input.typed_data_concat;
+    check_receipt_against_blob(receipt, blob, &input.digest_range, input.signer, SINGLE_SIGN_ID)?;
+    Ok(())
+}
+
+fn receipt_path(config: &Config, job: &Job, index: usize) -> PathBuf {
+    config
+        .output_dir
+        .join(format!("job_{}", job.id))
+        .join(format!("receipt_{index}.bin"))
+}
+
+// Save the receipt of range `index` and checkpoint it
+fn finish_range(
+    config: &Config,
+    queue: &JobQueue,
+    job: &Job,
+    index: usize,
+    receipt: &Receipt,
+) -> Result<()> {
+    let receipt_path = receipt_path(config, job, index);
+    save_receipt(&receipt_path, receipt)?;
+    queue.mark_proven(job.id, index)?;
+    #[cfg(feature = "webhooks")]
+    webhooks::notify(
+        &config.webhooks,
+        &Event::ProofFinished {
+            source: format!("job {} range {index}", job.id),
+            journal: receipt.journal.bytes.clone().into(),
+            receipt: Some(receipt_path),
+            digests: vec![decode_output(&receipt.journal)?.digest],
+        },
+    );
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use host::{
+    cache::ReceiptCache,
+    config::{ConfigArgs, ReceiptKind},
+    images::ImageRegistry,
+    logging::LogArgs,
+    receipts::{compress_receipt, load_receipt, save_receipt},
+};
+use risc0_zkvm::sha::Digest;
+use std::{fs, path::PathBuf, time::Duration};
+
+/// Shrink receipts on disk: compress composite receipts to succinct or Groth16 ones,
+/// and keep the receipt cache of long-running provers within an age and size budget.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compress receipts to a more compact kind: the given files in place, or else
+    /// every cache entry, dropping cached receipts a more compact one duplicates
+    Compact {
+        /// Receipts written by the prover; the receipt cache when none are given
+        receipts: Vec<PathBuf>,
+
+        /// Kind to compress to
+        #[arg(long, value_enum, default_value_t = ReceiptKind::Succinct)]
+        kind: ReceiptKind,
+
+        /// The receipts were produced by the batch guest
+        #[arg(long)]
+        batch: bool,
+
+        /// Report what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Delete cache entries by age, then the least recently used ones by total size
+    Prune {
+        /// Delete entries not used for this many days
+        #[arg(long, required_unless_present = "max_size_mib")]
+        max_age_days: Option<u64>,
+
+        /// Then delete the least recently used entries until the cache fits in this
+        /// many MiB
+        #[arg(long)]
+        max_size_mib: Option<u64>,
+
+        /// List what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    config.prover.apply();
+    let cache = ReceiptCache::open_default()?;
+
+    match args.command {
+        Command::Compact {
+            receipts,
+            kind,
+            batch,
+            dry_run,
+        } if !receipts.is_empty() => {
+            let registry = ImageRegistry::new(&config.images);
+            for path in receipts {
+                let receipt = load_receipt(&path)?;
+                let (version, ids) = registry.identify(&receipt, batch)?;
+                if dry_run {
+                    println!("Would compress {} ({version})", path.display());
+                    continue;
+                }
+                let before = fs::metadata(&path)?.len();
+                let image_id = Digest::from_bytes(ids.get(batch).0);
+                save_receipt(&path, &compress_receipt(&receipt, image_id, kind)?)?;
+                let after = fs::metadata(&path)?.len();
+                println!("Compressed {} ({version}): {before} -> {after} bytes", path.display());
+            }
+        }
+        Command::Compact { kind, dry_run, .. } => {
+            let summary = cache.compact(kind, dry_run)?;
+            let verb = if dry_run { "Would compress" } else { "Compressed" };
+            println!(
+                "{verb} {} and drop {} duplicate cache entries: {} -> {} bytes",
+                summary.compressed, summary.deduplicated, summary.bytes_before, summary.bytes_after
+            );
+        }
+        Command::Prune {
+            max_age_days,
+            max_size_mib,
+            dry_run,
+        } => {
+            let max_age = max_age_days.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+            let max_bytes = max_size_mib.map(|mib| mib << 20);
+            let removed = cache.prune(max_age, max_bytes, dry_run)?;
+            for entry in &removed {
+                println!("{} ({} bytes)", entry.path.display(), entry.size);
+            }
+            let freed: u64 = removed.iter().map(|entry| entry.size).sum();
+            let verb = if dry_run { "Would delete" } else { "Deleted" };
+            println!("{verb} {} cache entries, {freed} bytes", removed.len());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use alloy_primitives::{Address, Bytes};
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use host::{
+    config::ConfigArgs, logging::LogArgs, prepare::find_json_ranges, prover::prove,
+    receipts::save_receipt, rpc,
+};
+use methods::{SINGLE_SIGN_SAFE_ELF, SINGLE_SIGN_SAFE_ID};
+use single_sign_types::{
+    signing::eip191_hash,
+    state_proof::{recover_owners, safe_message_hash, SafeStateInput, StateProofOutput},
+};
+use std::{fs, path::PathBuf};
+
+/// Prove that a Safe approved every typed-data object in a blob, from the chain's
+/// state rather than an RPC node's `isValidSignature` answer: the guest checks the
+/// owner signatures against the owners and threshold proven from the latest block.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// Concatenated typed-data blob the Safe signed as an EIP-191 personal message
+    file: PathBuf,
+
+    #[arg(long)]
+    safe: Address,
+
+    /// Owner signatures over the Safe message, packed and sorted as Safe expects them
+    #[arg(long)]
+    signatures: Bytes,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    config.prover.apply();
+    let rpc_url = config
+        .rpc_url
+        .as_deref()
+        .ok_or_else(|| anyhow!("Fetching state proofs requires rpc_url"))?;
+
+    let blob = Bytes::from(
+        fs::read(&args.file)
+            .map_err(|e| anyhow!("Failed reading {}: {e}", args.file.display()))?,
+    );
+    let chain_id = rpc::chain_id(rpc_url)?;
+    let message_hash = safe_message_hash(chain_id, args.safe, eip191_hash(&blob));
+    let owners = recover_owners(message_hash, &args.signatures, args.signatures.len() / 65)?;
+    let proofs = rpc::safe_proofs(rpc_url, args.safe, &owners)?;
+
+    for (i, digest_range) in find_json_ranges(&blob)?.into_iter().enumerate() {
+        let input = SafeStateInput {
+            account: args.safe,
+            chain_id,
+            header_rlp: proofs.header_rlp.clone(),
+            account_proof: proofs.account_proof.clone(),
+            singleton: proofs.singleton.clone(),
+            threshold: proofs.threshold.clone(),
+            owners: proofs.owners.clone(),
+            signatures: args.signatures.clone(),
+            typed_data_concat: blob.clone(),
+            digest_range,
+            require_canonical: true,
+        };
+        let digest = input
+            .digest_range
+            .digest(&blob, input.require_canonical)
+            .map_err(|code| anyhow!("Range #{i} ({}): {code}", input.digest_range))?;
+        // Fail fast on the host with the reason rather than as a guest panic
+        input.verify(digest)?;
+
+        let proof = prove(&config, SINGLE_SIGN_SAFE_ELF, SINGLE_SIGN_SAFE_ID, &input)?;
+        let receipt = proof.receipt;
+        receipt.verify(SINGLE_SIGN_SAFE_ID)?;
+        let output: StateProofOutput = receipt.journal.decode()?;
+        println!(
+            "#{i} {digest} approved by Safe {:#x} at block {} ({})",
+            output.account, output.block_number, output.block_hash
+        );
+
+        let receipt_path = config.output_dir.join(format!("receipt_safe_{i}.bin"));
+        save_receipt(&receipt_path, &receipt)?;
+        println!("Receipt saved to {}", receipt_path.display());
+    }
+    Ok(())
+}
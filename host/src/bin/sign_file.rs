@@ -0,0 +1,192 @@
+use alloy_primitives::{hex, Address, Bytes, Signature, B256};
+use alloy_signer::SignerSync;
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, ValueEnum};
+use host::{
+    bridge::{sign_in_browser, BridgeOptions, SignRequest},
+    input::read_input,
+    manifest::{self, SignedManifest, SigningMode},
+    prepare::{chain_head, merkle_root},
+    signer::SignerArgs,
+};
+use single_sign_types::{
+    signing::{verify_signature, MessageMode},
+    typed_data::verify_digest,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Labelled lines
+    Text,
+    /// Only the 0x-prefixed signature, to pass to `cast send`
+    Cast,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    /// EIP-191 personal signature over the raw file bytes (what the guests verify)
+    Personal,
+    /// EIP-712 signature over a file holding a single typed-data JSON
+    Eip712,
+    /// EIP-191 personal signature over the Merkle root of the file's EIP-712 digests
+    Merkle,
+    /// EIP-191 personal signature over the head of the hash chain of the file's EIP-712
+    /// digests, appended to --prev-head
+    Chain,
+}
+
+/// Sign a file and print the signature, checking it recovers to the signer. With
+/// `--browser` a wallet signs instead of a local key, so demos need no pasted key.
+#[derive(Parser)]
+struct Args {
+    /// File to sign, or `-` for stdin
+    file: PathBuf,
+
+    #[command(flatten)]
+    signer: SignerArgs,
+
+    #[arg(long, value_enum, default_value_t = Mode::Personal)]
+    mode: Mode,
+
+    /// Chain head an earlier signature covered, to append the file's objects to
+    #[arg(long, default_value_t = B256::ZERO)]
+    prev_head: B256,
+
+    /// Ask a browser wallet to sign, through a page served on localhost: `personal_sign`,
+    /// or `eth_signTypedData_v4` with `--mode eip712`
+    #[arg(long, conflicts_with_all = ["private_key", "mnemonic"])]
+    browser: bool,
+
+    /// Port of the signing page; a free one by default
+    #[arg(long, default_value_t = 0, requires = "browser")]
+    port: u16,
+
+    /// WalletConnect Cloud project ID, to pair a mobile wallet by QR code when the
+    /// browser has no wallet of its own
+    #[arg(long, requires = "browser")]
+    walletconnect_project_id: Option<String>,
+
+    /// Chain to open the WalletConnect session on
+    #[arg(long, default_value_t = 1, requires = "walletconnect_project_id")]
+    chain_id: u64,
+
+    /// Also write the signature as hex to this file, for `--signature-file`
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Also write a `.singlesign` manifest of the blob and signature, for `queue`,
+    /// `verify_receipt` and `submit`; beside the file by default
+    #[arg(long, num_args = 0..=1)]
+    manifest: Option<Option<PathBuf>>,
+
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let contents = read_input(&args.file)?;
+    let text = args.format == Format::Text;
+
+    // The bytes whose signature is checked, and for EIP-712 the JSON a wallet signs
+    let (message, mode, typed_data): (Bytes, MessageMode, Option<String>) = match args.mode {
+        Mode::Personal => (Bytes::from(contents), MessageMode::Personal, None),
+        Mode::Eip712 => {
+            let json = String::from_utf8(contents)
+                .map_err(|e| anyhow!("Typed data file is not valid UTF-8: {e}"))?;
+            let digest = verify_digest(&json)?;
+            if text {
+                println!("EIP-712 digest: 0x{}", hex::encode(digest));
+            }
+            (Bytes::copy_from_slice(digest.as_slice()), MessageMode::Raw32, Some(json))
+        }
+        Mode::Merkle => {
+            let root = merkle_root(&contents)?;
+            if text {
+                println!("Merkle root: 0x{}", hex::encode(root));
+            }
+            (Bytes::copy_from_slice(root.as_slice()), MessageMode::Personal, None)
+        }
+        Mode::Chain => {
+            let head = chain_head(args.prev_head, &contents)?;
+            if text {
+                println!("Chain head: 0x{}", hex::encode(head));
+            }
+            (Bytes::copy_from_slice(head.as_slice()), MessageMode::Personal, None)
+        }
+    };
+
+    let (signer, signature): (Address, Signature) = if args.browser {
+        let request = match typed_data {
+            Some(json) => SignRequest::TypedData(json),
+            None => SignRequest::Personal(message.clone()),
+        };
+        let options = BridgeOptions {
+            port: args.port,
+            walletconnect_project_id: args.walletconnect_project_id,
+            chain_id: args.chain_id,
+        };
+        sign_in_browser(&request, &options)?
+    } else {
+        let signer = args
+            .signer
+            .signer()?
+            .ok_or_else(|| anyhow!("Provide --private-key, --mnemonic or --browser"))?;
+        let signature = match mode {
+            MessageMode::Raw32 => signer.sign_hash_sync(&B256::from_slice(&message))?,
+            _ => signer.sign_message_sync(&message)?,
+        };
+        (signer.address(), signature)
+    };
+    verify_signature(&message, signature, signer, mode, true)?;
+
+    let signature = format!("0x{}", hex::encode(signature.as_bytes()));
+    if text {
+        println!("Signer: {signer:#x}");
+        println!("Signature: {signature}");
+    } else {
+        println!("{signature}");
+    }
+    if let Some(out) = &args.out {
+        fs::write(out, &signature)
+            .map_err(|e| anyhow!("Failed writing {}: {e}", out.display()))?;
+    }
+    if let Some(path) = &args.manifest {
+        let path = path.clone().unwrap_or_else(|| manifest::path_for(&args.file));
+        write_manifest(&args, &path, signer, &signature)?;
+        if text {
+            println!("Manifest: {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn write_manifest(args: &Args, path: &Path, signer: Address, signature: &str) -> Result<()> {
+    let mode = match args.mode {
+        Mode::Personal => SigningMode::Personal,
+        Mode::Merkle => SigningMode::Merkle,
+        Mode::Chain => SigningMode::Chain,
+        Mode::Eip712 => bail!("--manifest describes blobs, not a single typed-data object"),
+    };
+    if args.file == Path::new("-") {
+        bail!("--manifest needs the blob in a file, not stdin");
+    }
+    let blob = fs::read(&args.file)?;
+    // Name the blob relative to the manifest, so the pair can move together
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.canonicalize()?,
+        _ => Path::new(".").canonicalize()?,
+    };
+    let blob_path = args.file.canonicalize()?;
+    let blob_file = blob_path.strip_prefix(&dir).unwrap_or(&blob_path).to_path_buf();
+    let prev_head = match mode {
+        SigningMode::Chain => args.prev_head,
+        _ => B256::ZERO,
+    };
+    let signature = hex::decode(signature)?.into();
+    SignedManifest::new(&blob, blob_file, mode, prev_head, signer, signature)?.save(path)
+}
@@ -0,0 +1,92 @@
+use alloy_primitives::B256;
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use host::{
+    input::{read_input, SignatureArgs},
+    journal::{decode_batch_outputs, decode_output},
+    logging::LogArgs,
+    receipts::load_receipt,
+    storage::{check_blob_hash, Storage},
+};
+use std::{fs, io::Write, path::PathBuf};
+
+/// Store signed blobs by content hash, and fetch them back, for provers such as
+/// `watcher` that are handed only a blob's hash.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// A directory, http(s):// base URL (read-only), s3://bucket/prefix, ipfs://<cid>
+    /// (read-only, through a gateway) or ipfs+http://<kubo api>
+    #[arg(long)]
+    store: Storage,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Store a blob and its signature, and print the blob's hash
+    Put {
+        /// Concatenated typed-data blob, or `-` for stdin
+        file: PathBuf,
+
+        #[command(flatten)]
+        signature: SignatureArgs,
+    },
+    /// Fetch the blob with the given keccak256
+    Get {
+        blob_hash: B256,
+
+        /// Check the blob is the one this receipt's journal commits
+        #[arg(long)]
+        receipt: Option<PathBuf>,
+
+        /// The receipt was produced by the batch guest
+        #[arg(long, requires = "receipt")]
+        batch: bool,
+
+        /// Write the blob here instead of to stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+
+    match args.command {
+        Command::Put { file, signature } => {
+            let signature = signature.bytes(&file)?;
+            let blob = read_input(&file)?;
+            let blob_hash = args.store.pin(&blob, Some(&signature))?;
+            println!("{blob_hash}");
+        }
+        Command::Get {
+            blob_hash,
+            receipt,
+            batch,
+            out,
+        } => {
+            let blob = args.store.fetch_blob(blob_hash)?;
+            if let Some(path) = receipt {
+                let journal = load_receipt(&path)?.journal;
+                let outputs = if batch {
+                    decode_batch_outputs(&journal)?
+                } else {
+                    vec![decode_output(&journal)?]
+                };
+                check_blob_hash(&blob, &outputs)?;
+                eprintln!("Blob matches the journal of {}", path.display());
+            }
+            match out {
+                Some(path) => fs::write(&path, &blob)?,
+                None => std::io::stdout().write_all(&blob)?,
+            }
+        }
+    }
+    Ok(())
+}
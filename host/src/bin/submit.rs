@@ -0,0 +1,209 @@
+use alloy_primitives::Address;
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use host::{
+    config::ConfigArgs,
+    logging::LogArgs,
+    manifest::SignedManifest,
+    prepare::find_json_ranges,
+    progress::{BatchProgress, RangeStatus},
+    receipts::load_receipt,
+    seal::encode_seal,
+    signer::SignerArgs,
+    submit::{group_by_chain, submit_all, Route},
+    verify::check_batch_receipt_against_blob,
+};
+#[cfg(feature = "webhooks")]
+use host::{
+    submit::Status,
+    webhooks::{self, Event},
+};
+use methods::SINGLE_SIGN_BATCH_ID;
+use single_sign_types::user_op::ENTRY_POINT_V07;
+use std::{fs, path::PathBuf};
+
+/// Submit a batch receipt to every chain its objects were signed for: each chain's
+/// `endpoints` entry in the config gets a `consume` call with the digests meant for
+/// it, and each transaction is tracked to its own confirmation.
+#[derive(Parser)]
+struct Args {
+    /// Groth16 batch receipt of the blob
+    #[arg(required_unless_present = "from")]
+    receipt: Option<PathBuf>,
+
+    /// Concatenated typed-data blob the receipt attests
+    #[arg(required_unless_present_any = ["from", "manifest"])]
+    blob: Option<PathBuf>,
+
+    /// `.singlesign` manifest of the blob the receipt attests, instead of the blob;
+    /// the receipt must attest the manifest's signer
+    #[arg(long, conflicts_with_all = ["blob", "from"])]
+    manifest: Option<PathBuf>,
+
+    /// Output directory of an earlier (e.g. `--offline`) proving run: submit its
+    /// receipt_batch.bin for its blob.json, recording progress in its batch.json
+    #[arg(long, conflicts_with_all = ["receipt", "blob", "progress"])]
+    from: Option<PathBuf>,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    // Account paying for the transactions, or owning --bundler-account
+    #[command(flatten)]
+    signer: SignerArgs,
+
+    /// Submit as UserOperations of this smart account, owned by the signer, through
+    /// each chain's `bundler_url`; gas is sponsored where a `paymaster_url` is set
+    #[arg(long)]
+    bundler_account: Option<Address>,
+
+    /// EntryPoint the bundlers serve
+    #[arg(long, default_value_t = ENTRY_POINT_V07, requires = "bundler_account")]
+    entry_point: Address,
+
+    /// Hand each call to the chain's `relayer_url`, which pays for the gas
+    #[arg(long, conflicts_with = "bundler_account")]
+    relayer: bool,
+
+    /// Send each transaction through the chain's `private_rpc_url` (e.g. Flashbots
+    /// Protect) instead of the public mempool, after simulating it
+    #[arg(long, conflicts_with_all = ["bundler_account", "relayer"])]
+    private_tx: bool,
+
+    /// Blocks to wait for on each chain before counting a submission as confirmed
+    #[arg(long, default_value_t = 1)]
+    confirmations: u64,
+
+    /// Submit fake dev-mode receipts (mock verifiers only)
+    #[arg(long)]
+    allow_dev_receipts: bool,
+
+    /// batch.json of the run that proved the blob, to mark each range submitted, or
+    /// failed with its chain's reason
+    #[arg(long)]
+    progress: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    #[cfg(not(feature = "webhooks"))]
+    if !config.webhooks.is_empty() {
+        bail!("webhooks require the `webhooks` feature");
+    }
+    let signer = args
+        .signer
+        .signer()?
+        .ok_or_else(|| anyhow!("Submitting requires --private-key or --mnemonic"))?;
+
+    let (receipt_path, blob_path, progress_path) = match (&args.from, args.receipt, args.blob) {
+        (Some(dir), _, _) => (
+            dir.join("receipt_batch.bin"),
+            Some(dir.join("blob.json")),
+            Some(dir.join(BatchProgress::FILE_NAME)),
+        ),
+        (None, Some(receipt), blob) => (receipt, blob, args.progress),
+        _ => bail!("Pass a receipt and a blob or --manifest, or --from"),
+    };
+
+    let (blob, manifest_signer) = match (&args.manifest, &blob_path) {
+        (Some(path), _) => {
+            let (manifest, blob) = SignedManifest::load(path)?;
+            (blob.to_vec(), Some(manifest.signer))
+        }
+        (None, Some(path)) => {
+            let blob =
+                fs::read(path).map_err(|e| anyhow!("Failed reading {}: {e}", path.display()))?;
+            (blob, None)
+        }
+        (None, None) => bail!("Pass a blob or --manifest"),
+    };
+    let ranges = find_json_ranges(&blob)?;
+    let receipt = load_receipt(&receipt_path)?;
+    let outputs =
+        check_batch_receipt_against_blob(&receipt, &blob, &ranges, None, SINGLE_SIGN_BATCH_ID)?;
+    if let Some(signer) = manifest_signer {
+        let other = outputs
+            .iter()
+            .find(|output| output.is_ok() && output.signer != signer);
+        if let Some(output) = other {
+            bail!(
+                "Receipt attests {:#x} as the signer, not the manifest's {signer:#x}",
+                output.signer
+            );
+        }
+    }
+    let mut progress = progress_path
+        .as_deref()
+        .map(|path| BatchProgress::resume(path, &blob, &ranges))
+        .transpose()?;
+    let groups = group_by_chain(&blob, &ranges, &outputs)?;
+    for (chain_id, digests) in &groups {
+        println!("Chain {chain_id}: {} digests", digests.len());
+    }
+
+    let route = match (args.bundler_account, args.relayer, args.private_tx) {
+        (Some(account), _, _) => Route::Bundler {
+            account,
+            entry_point: args.entry_point,
+        },
+        (None, true, _) => Route::Relayer,
+        (None, false, true) => Route::Private,
+        (None, false, false) => Route::Direct,
+    };
+    let seal = encode_seal(&receipt, args.allow_dev_receipts)?;
+    let statuses = submit_all(
+        &config.endpoints,
+        &signer,
+        route,
+        seal.into(),
+        receipt.journal.bytes.clone().into(),
+        groups.clone(),
+        args.confirmations,
+    )?;
+    for (chain_id, status) in &statuses {
+        println!("Chain {chain_id}: {status}");
+        #[cfg(feature = "webhooks")]
+        if let Status::Confirmed {
+            tx_hash,
+            block_number,
+        } = status
+        {
+            let event = Event::TransactionConfirmed {
+                chain_id: *chain_id,
+                tx_hash: *tx_hash,
+                block_number: *block_number,
+                digests: groups.get(chain_id).cloned().unwrap_or_default(),
+            };
+            webhooks::notify(&config.webhooks, &event);
+        }
+    }
+    if let (Some(progress), Some(path)) = (&mut progress, &progress_path) {
+        for (i, output) in outputs.iter().enumerate() {
+            let Some(status) = groups
+                .iter()
+                .find(|(_, digests)| digests.contains(&output.digest))
+                .and_then(|(chain_id, _)| statuses.get(chain_id))
+            else {
+                continue;
+            };
+            let status = if status.is_confirmed() {
+                RangeStatus::Submitted
+            } else {
+                RangeStatus::Failed {
+                    reason: status.to_string(),
+                }
+            };
+            progress.set(path, i, status)?;
+        }
+    }
+    let failed = statuses.values().filter(|status| !status.is_confirmed()).count();
+    if failed > 0 {
+        bail!("{failed} of {} chains did not confirm", statuses.len());
+    }
+    Ok(())
+}
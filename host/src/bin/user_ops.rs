@@ -0,0 +1,114 @@
+use alloy_primitives::{Address, Bytes};
+use anyhow::Result;
+use clap::Parser;
+use host::{
+    config::ConfigArgs,
+    ens::AddressOrName,
+    input::{read_input, SignatureArgs},
+    journal::decode_batch_outputs,
+    logging::LogArgs,
+    prepare::{check_batch, find_json_ranges},
+    prover::prove_framed,
+    receipts::save_receipt,
+};
+use methods::{SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID};
+use single_sign_types::{
+    intent::IntentPolicy,
+    signing::{parse_signature_with_chain_id, Envelope},
+    user_op::ENTRY_POINT_V07,
+    Assertion, BatchInput, DigestMode,
+};
+use std::path::PathBuf;
+
+/// Prove a batch of ERC-4337 UserOperations signed together, committing each one's
+/// `userOpHash` so a bundler can show every intent in the batch was authorized.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// Concatenated UserOperation JSON objects, in the EntryPoint v0.7 RPC format, or
+    /// `-` for stdin
+    file: PathBuf,
+
+    /// Signer address or ENS name, resolved via `rpc_url`
+    #[arg(long)]
+    signer: AddressOrName,
+
+    #[command(flatten)]
+    signature: SignatureArgs,
+
+    /// EntryPoint the hashes are bound to; the chain is the configured `chain_id`
+    #[arg(long, default_value_t = ENTRY_POINT_V07)]
+    entry_point: Address,
+
+    /// Reject UserOperations that are not in canonical JSON form
+    #[arg(long)]
+    require_canonical: bool,
+
+    /// Prove invalid UserOperations as failure journals instead of rejecting the batch
+    #[arg(long)]
+    report_validity: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    config.prover.apply();
+
+    let signature = args.signature.bytes(&args.file)?;
+    let blob = Bytes::from(read_input(&args.file)?);
+    let signer = args.signer.resolve(config.rpc_url.as_deref())?;
+    let (signature, signature_chain_id) = parse_signature_with_chain_id(&signature)?;
+    let batch_input = BatchInput {
+        signer,
+        signature,
+        ranges: find_json_ranges(&blob)?,
+        typed_data_concat: blob,
+        require_canonical: args.require_canonical,
+        assert: if args.report_validity {
+            Assertion::ReportValidity
+        } else {
+            Assertion::MustBeValid
+        },
+        extract_paths: Vec::new(),
+        disclosure_salt: None,
+        envelope: Envelope::Personal,
+        key_signature: None,
+        segments: Vec::new(),
+        expected_digests: Vec::new(),
+        digest_mode: DigestMode::UserOperation {
+            entry_point: args.entry_point,
+            chain_id: config.chain_id,
+        },
+        intent_policy: IntentPolicy::default(),
+        allow_high_s: false,
+        signature_chain_id,
+        anchor: None,
+        policy: None,
+        transfers: Vec::new(),
+    };
+    if !args.report_validity {
+        check_batch(&batch_input)?;
+    }
+
+    println!("Proving {} UserOperations", batch_input.ranges.len());
+    let proof = prove_framed(&config, SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID, &batch_input)?;
+    let receipt = proof.receipt;
+    receipt.verify(SINGLE_SIGN_BATCH_ID)?;
+    for (i, output) in decode_batch_outputs(&receipt.journal)?.iter().enumerate() {
+        match &output.failure {
+            None => println!("#{i} userOpHash: {}", output.digest),
+            Some(failure) => println!("#{i} {}: {}", failure.range, failure.code),
+        }
+    }
+
+    let receipt_path = config.output_dir.join("receipt_user_ops.bin");
+    save_receipt(&receipt_path, &receipt)?;
+    println!("Receipt saved to {}", receipt_path.display());
+    Ok(())
+}
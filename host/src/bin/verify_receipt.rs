@@ -0,0 +1,223 @@
+use alloy_primitives::{hex, B256};
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, ValueEnum};
+use host::{
+    chains,
+    config::ConfigArgs,
+    images::ImageRegistry,
+    journal::{decode_batch_outputs, decode_output},
+    manifest::SignedManifest,
+    prepare::find_json_ranges,
+    receipts::load_receipt,
+    seal::{encode_seal, image_id_bytes, journal_digest, JournalExport},
+    verify::{check_batch_receipt_against_blob, check_receipt_against_blob},
+};
+use risc0_zkvm::{sha::Digest, InnerReceipt};
+use single_sign_types::DigestRange;
+use std::{fs, path::PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// The journal and, for Groth16 receipts, the verifier's arguments, labelled
+    Text,
+    /// Only `seal imageId journalDigest`, 0x-prefixed, to splice into
+    /// `cast call <verifier> "verify(bytes,bytes32,bytes32)"`
+    Cast,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Export {
+    /// `{journal_bytes, journal_sha256, image_id, seal}` as JSON: the journal to decode
+    /// and the arguments of the verifier's `verify`, without risc0 receipt internals
+    Journal,
+}
+
+/// Verify a saved receipt without the prover and pretty-print its journal.
+/// Exits non-zero if the receipt does not verify, the journal cannot be decoded, or
+/// it does not attest the blob given with --blob.
+#[derive(Parser)]
+struct Args {
+    /// Path to a receipt written by the prover
+    receipt: PathBuf,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    /// The receipt was produced by the batch guest
+    #[arg(long)]
+    batch: bool,
+
+    /// Image ID to verify against instead of looking the receipt up in the image
+    /// registry, e.g. for receipts from unregistered program versions
+    #[arg(long, conflicts_with = "image_version")]
+    image_id: Option<B256>,
+
+    /// Registered guest version to verify against (`current` for this binary's build);
+    /// by default every registered version is tried
+    #[arg(long)]
+    image_version: Option<String>,
+
+    /// Also print on-chain calldata for fake dev-mode receipts (mock verifier only)
+    #[arg(long)]
+    allow_dev_receipts: bool,
+
+    /// Check that the journal attests this typed-data blob: the range given by
+    /// --range, or every object in it for batch receipts
+    #[arg(long)]
+    blob: Option<PathBuf>,
+
+    /// Check that the journal attests the blob of this `.singlesign` manifest, signed
+    /// by the manifest's signer: the range given by --range, or every object in it
+    #[arg(long, conflicts_with = "blob")]
+    manifest: Option<PathBuf>,
+
+    /// Range of --blob or --manifest a single-range receipt attests, as `start..end`
+    #[arg(long, value_parser = parse_range)]
+    range: Option<DigestRange>,
+
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Once the receipt verifies, write only this export instead of the journal
+    #[arg(long, value_enum, conflicts_with = "format")]
+    export: Option<Export>,
+
+    /// File to write the export to instead of stdout
+    #[arg(long, requires = "export")]
+    out: Option<PathBuf>,
+}
+
+fn parse_range(range: &str) -> Result<DigestRange> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or_else(|| anyhow!("Expected a range as start..end"))?;
+    Ok(DigestRange {
+        start: start.parse()?,
+        end: end.parse()?,
+    })
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let config = args.config.load()?;
+    let registry = ImageRegistry::new(&config.images);
+    let receipt = load_receipt(&args.receipt)?;
+    let text = args.format == Format::Text && args.export.is_none();
+
+    let (image_id, version) = if let Some(image_id) = args.image_id {
+        (image_id, None)
+    } else if let Some(version) = &args.image_version {
+        (registry.get(version)?.get(args.batch), Some(version.as_str()))
+    } else {
+        let (version, ids) = registry.identify(&receipt, args.batch)?;
+        (ids.get(args.batch), Some(version))
+    };
+    let image_id = Digest::from_bytes(image_id.0);
+    receipt
+        .verify(image_id)
+        .map_err(|e| anyhow!("Receipt does not verify against image ID {image_id}: {e}"))?;
+    let kind = match &receipt.inner {
+        InnerReceipt::Composite(_) => "composite",
+        InnerReceipt::Succinct(_) => "succinct",
+        InnerReceipt::Groth16(_) => "groth16",
+        InnerReceipt::Fake(_) => "fake",
+        _ => "unknown",
+    };
+    match version {
+        _ if !text => {}
+        Some(version) => {
+            println!("{kind} receipt verified against image ID {image_id} ({version})")
+        }
+        None => println!("{kind} receipt verified against image ID {image_id}"),
+    }
+
+    if let Some(path) = &args.blob {
+        let blob =
+            fs::read(path).map_err(|e| anyhow!("Failed reading {}: {e}", path.display()))?;
+        if args.batch {
+            let ranges = find_json_ranges(&blob)?;
+            check_batch_receipt_against_blob(&receipt, &blob, &ranges, None, image_id)?;
+        } else {
+            let range = args
+                .range
+                .as_ref()
+                .ok_or_else(|| anyhow!("--blob needs --range for single-range receipts"))?;
+            check_receipt_against_blob(&receipt, &blob, range, None, image_id)?;
+        }
+        if text {
+            println!("Journal attests {}", path.display());
+        }
+    }
+    if let Some(path) = &args.manifest {
+        let (manifest, blob) = SignedManifest::load(path)?;
+        let outputs = if args.batch {
+            check_batch_receipt_against_blob(&receipt, &blob, &manifest.ranges, None, image_id)?
+        } else {
+            let range = args
+                .range
+                .as_ref()
+                .ok_or_else(|| anyhow!("--manifest needs --range for single-range receipts"))?;
+            vec![check_receipt_against_blob(&receipt, &blob, range, None, image_id)?]
+        };
+        if let Some(output) = outputs
+            .iter()
+            .find(|output| output.is_ok() && output.signer != manifest.signer)
+        {
+            bail!(
+                "Journal attests {:#x} as the signer, not the manifest's {:#x}",
+                output.signer,
+                manifest.signer
+            );
+        }
+        if text {
+            println!("Journal attests {} signed by {:#x}", path.display(), manifest.signer);
+        }
+    }
+
+    if let Some(Export::Journal) = args.export {
+        let export = JournalExport::new(&receipt, image_id, args.allow_dev_receipts)?;
+        let json = serde_json::to_string_pretty(&export)?;
+        match &args.out {
+            Some(out) => fs::write(out, json)
+                .map_err(|e| anyhow!("Failed writing {}: {e}", out.display()))?,
+            None => println!("{json}"),
+        }
+        return Ok(());
+    }
+
+    let journal = if args.batch {
+        serde_json::to_string_pretty(&decode_batch_outputs(&receipt.journal)?)?
+    } else {
+        serde_json::to_string_pretty(&decode_output(&receipt.journal)?)?
+    };
+    if !text {
+        if !matches!(receipt.inner, InnerReceipt::Groth16(_) | InnerReceipt::Fake(_)) {
+            bail!("Only Groth16 receipts have a seal to verify on-chain");
+        }
+        let seal = encode_seal(&receipt, args.allow_dev_receipts)?;
+        let image_id = image_id_bytes(image_id);
+        println!("0x{} {image_id} {}", hex::encode(seal), journal_digest(&receipt));
+        return Ok(());
+    }
+    println!("{journal}");
+
+    // Print the arguments of `IRiscZeroVerifier.verify(seal, imageId, journalDigest)`
+    if matches!(receipt.inner, InnerReceipt::Groth16(_) | InnerReceipt::Fake(_)) {
+        let seal = encode_seal(&receipt, args.allow_dev_receipts)?;
+        println!("Seal: 0x{}", hex::encode(seal));
+        println!("Image ID: {}", image_id_bytes(image_id));
+        println!("Journal digest: {}", journal_digest(&receipt));
+        // Older versions may need a different verifier than the chain's router
+        let chain = chains::resolve(config.chain_id, &config).ok();
+        let route = match (version, &chain) {
+            (Some(version), Some(chain)) => registry.get(version)?.verifier_route(chain),
+            (None, Some(chain)) => chain.router.or(chain.verifier),
+            (_, None) => None,
+        };
+        if let Some(verifier) = route {
+            println!("Verifier on chain {}: {verifier:#x}", config.chain_id);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use alloy_primitives::Address;
+use alloy_signer_local::PrivateKeySigner;
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use host::{
+    config::{Config, ConfigArgs},
+    logging::LogArgs,
+    receipts::save_receipt,
+    seal::encode_seal,
+    signer::SignerArgs,
+    storage::{check_blob_hash, Storage},
+    submit::{group_by_chain, submit_all, Route},
+    watcher::{prove_request, Request, Watcher},
+};
+use std::{fs, path::PathBuf, slice, thread, time::Duration};
+
+/// Prove on request: follow a contract's `ProofRequested(blobHash, start, end)` logs,
+/// fetch each requested blob and its signature from a store, prove the range with the
+/// batch guest, and submit the receipt to the consumer of the chain the typed data
+/// was signed for. Failed requests are logged and skipped.
+#[derive(Parser)]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    // Account paying for the fulfilments
+    #[command(flatten)]
+    signer: SignerArgs,
+
+    /// Contract emitting the requests, on the chain at `rpc_url`
+    #[arg(long)]
+    contract: Address,
+
+    /// Storage holding each requested blob and its signature: a directory, http(s)://
+    /// base URL, s3://bucket/prefix, ipfs://<cid> or ipfs+http://<kubo api>
+    #[arg(long)]
+    store: Storage,
+
+    /// First block to read; defaults to where --state left off, or the next block
+    #[arg(long)]
+    from_block: Option<u64>,
+
+    /// File the next block to read is kept in, so a restart resumes there
+    #[arg(long, default_value = "watcher.block")]
+    state: PathBuf,
+
+    /// Confirmations a request needs before it is proven
+    #[arg(long, default_value_t = 2)]
+    confirmations: u64,
+
+    /// Seconds between polls once caught up with the chain
+    #[arg(long, default_value_t = 12)]
+    poll_secs: u64,
+
+    /// Blocks to wait for on each chain before counting a fulfilment as confirmed
+    #[arg(long, default_value_t = 1)]
+    submit_confirmations: u64,
+
+    /// Submit fake dev-mode receipts (mock verifiers only)
+    #[arg(long)]
+    allow_dev_receipts: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let _telemetry = args.log.init();
+    let config = args.config.load()?;
+    config.prover.apply();
+    let rpc_url = config
+        .rpc_url
+        .clone()
+        .ok_or_else(|| anyhow!("Watching requires rpc_url"))?;
+    let signer = args
+        .signer
+        .signer()?
+        .ok_or_else(|| anyhow!("Fulfilling requires --private-key or --mnemonic"))?;
+
+    let from_block = match args.from_block {
+        Some(block) => Some(block),
+        None => fs::read_to_string(&args.state)
+            .ok()
+            .map(|state| state.trim().parse::<u64>())
+            .transpose()
+            .map_err(|e| anyhow!("Invalid block number in {}: {e}", args.state.display()))?,
+    };
+    let mut watcher = Watcher::new(&rpc_url, args.contract, from_block, args.confirmations)?;
+    println!(
+        "Watching {:#x} from block {} for blobs in {}",
+        args.contract,
+        watcher.next_block(),
+        args.store
+    );
+    loop {
+        let read_from = watcher.next_block();
+        let requests = watcher.poll()?;
+        for request in &requests {
+            println!(
+                "Request for {} of blob {} in {:?}",
+                request.range, request.blob_hash, request.tx_hash
+            );
+            if let Err(e) = fulfil(&config, &args, &signer, request) {
+                println!("Request for {} of blob {} failed: {e}", request.range, request.blob_hash);
+            }
+        }
+        fs::write(&args.state, watcher.next_block().to_string())
+            .map_err(|e| anyhow!("Failed writing {}: {e}", args.state.display()))?;
+        if watcher.next_block() == read_from {
+            thread::sleep(Duration::from_secs(args.poll_secs));
+        }
+    }
+}
+
+fn fulfil(
+    config: &Config,
+    args: &Args,
+    signer: &PrivateKeySigner,
+    request: &Request,
+) -> Result<()> {
+    let (blob, signature) = args.store.fetch(request.blob_hash)?;
+    let (receipt, output) =
+        prove_request(config, blob.clone(), &signature, request.range.clone())?;
+    check_blob_hash(&blob, slice::from_ref(&output))?;
+    let receipt_path = config.output_dir.join(format!(
+        "request_{:x}_{}_{}.bin",
+        request.blob_hash, request.range.start, request.range.end
+    ));
+    save_receipt(&receipt_path, &receipt)?;
+    println!("Proven, receipt saved to {}", receipt_path.display());
+
+    let groups =
+        group_by_chain(&blob, slice::from_ref(&request.range), slice::from_ref(&output))?;
+    let statuses = submit_all(
+        &config.endpoints,
+        signer,
+        Route::Direct,
+        encode_seal(&receipt, args.allow_dev_receipts)?.into(),
+        receipt.journal.bytes.clone().into(),
+        groups,
+        args.submit_confirmations,
+    )?;
+    for (chain_id, status) in &statuses {
+        println!("Chain {chain_id}: {status}");
+    }
+    Ok(())
+}
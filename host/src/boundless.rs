@@ -0,0 +1,100 @@
+//! Outsourced proving on the Boundless market. Enabled by the `boundless` feature.
+
+use crate::{config::Config, rpc::block_on};
+use alloy_primitives::utils::parse_ether;
+use alloy_signer_local::PrivateKeySigner;
+use anyhow::{anyhow, bail, Result};
+use boundless_market::{
+    request_builder::OfferParams, storage::storage_provider_from_env, Client, GuestEnv,
+};
+use risc0_zkvm::{
+    sha::{Digest, Digestible},
+    Groth16Receipt, Groth16ReceiptVerifierParameters, InnerReceipt, Receipt, ReceiptClaim,
+};
+use serde::Serialize;
+use std::{env, time::Duration};
+
+/// How often to check whether a submitted request has been fulfilled.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Submit a Groth16 proof request for `input`, followed by `frame` if any (see
+/// `host::prover::Framed`), to the Boundless market, paid for by
+/// `BOUNDLESS_PRIVATE_KEY` on the chain at `rpc_url`, and wait for a prover to
+/// fulfill it. The fulfillment's seal and journal are rebuilt into an ordinary
+/// receipt, which is verified against `image_id` before it is returned.
+pub fn prove<T: Serialize>(
+    config: &Config,
+    elf: &[u8],
+    image_id: Digest,
+    input: &T,
+    frame: Option<&[u8]>,
+) -> Result<Receipt> {
+    let rpc_url = config
+        .rpc_url
+        .as_deref()
+        .ok_or_else(|| anyhow!("The boundless prover requires rpc_url"))?;
+    let wallet: PrivateKeySigner = env::var("BOUNDLESS_PRIVATE_KEY")
+        .map_err(|_| anyhow!("The boundless prover requires BOUNDLESS_PRIVATE_KEY"))?
+        .parse()
+        .map_err(|e| anyhow!("Invalid BOUNDLESS_PRIVATE_KEY: {e}"))?;
+    let max_price = parse_ether(&config.boundless_max_price)
+        .map_err(|e| anyhow!("Invalid boundless_max_price {}: {e}", config.boundless_max_price))?;
+    let mut guest_env = GuestEnv::builder().write(input)?;
+    if let Some(frame) = frame {
+        guest_env = guest_env.write_frame(frame);
+    }
+    let stdin = guest_env.build_vec()?;
+    // Only upload the ELF when no program URL was configured
+    let storage_provider = match config.program_url {
+        Some(_) => None,
+        None => Some(storage_provider_from_env()?),
+    };
+
+    let (journal, seal) = block_on(async {
+        let client = Client::builder()
+            .with_rpc_url(rpc_url.parse()?)
+            .with_private_key(wallet)
+            .with_storage_provider(storage_provider)
+            .build()
+            .await?;
+        let request = client
+            .new_request()
+            .with_stdin(stdin)
+            .with_groth16_proof()
+            .with_offer(
+                OfferParams::builder()
+                    .max_price(max_price)
+                    .timeout(config.boundless_timeout)
+                    .lock_timeout(config.boundless_timeout / 2),
+            );
+        let request = match &config.program_url {
+            Some(url) => request.with_program_url(url.as_str())?,
+            None => request.with_program(elf.to_vec()),
+        };
+        let (request_id, expires_at) = client.submit_onchain(request).await?;
+        tracing::info!("Submitted Boundless request {request_id:#x}, waiting for fulfillment");
+        client
+            .wait_for_request_fulfillment(request_id, POLL_INTERVAL, expires_at)
+            .await
+            .map_err(anyhow::Error::from)
+    })??;
+
+    // The seal is in router form: the verifier selector, then the Groth16 proof
+    let verifier_parameters = Groth16ReceiptVerifierParameters::default().digest();
+    let Some(proof) = seal.strip_prefix(&verifier_parameters.as_bytes()[..4]) else {
+        bail!("Boundless fulfillment is not a Groth16 seal for this risc0 version");
+    };
+    let claim = ReceiptClaim::ok(image_id, journal.to_vec());
+    let receipt = Receipt::new(
+        InnerReceipt::Groth16(Groth16Receipt::new(
+            proof.to_vec(),
+            claim.into(),
+            verifier_parameters,
+        )),
+        journal.to_vec(),
+    );
+    receipt
+        .verify(image_id)
+        .map_err(|e| anyhow!("Boundless fulfillment does not verify: {e}"))?;
+    Ok(receipt)
+}
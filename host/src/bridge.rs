@@ -0,0 +1,137 @@
+use alloy_primitives::{hex, Address, Bytes, Signature};
+use alloy_signer_local::PrivateKeySigner;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+const PAGE: &str = include_str!("bridge.html");
+
+// Largest request body accepted; a signature reply is far smaller
+const MAX_BODY: usize = 64 * 1024;
+
+/// What the wallet is asked to sign.
+#[derive(Debug, Clone)]
+pub enum SignRequest {
+    /// EIP-191 `personal_sign` over these bytes
+    Personal(Bytes),
+    /// `eth_signTypedData_v4` over this typed-data JSON
+    TypedData(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct BridgeOptions {
+    /// Local port to serve the signing page on; 0 picks a free one
+    pub port: u16,
+    /// WalletConnect Cloud project ID, to pair a mobile wallet by QR code when the
+    /// browser has no injected wallet
+    pub walletconnect_project_id: Option<String>,
+    /// Chain the WalletConnect session is opened on
+    pub chain_id: u64,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    account: Address,
+    signature: Bytes,
+}
+
+/// Have a browser wallet sign `request`: serve a one-off page on localhost that asks
+/// the injected EIP-1193 provider, or a WalletConnect-paired mobile wallet, to sign and
+/// posts the signature back. Returns the account that signed and its signature; the
+/// caller checks that the signature recovers to that account.
+pub fn sign_in_browser(
+    request: &SignRequest,
+    options: &BridgeOptions,
+) -> Result<(Address, Signature)> {
+    let listener = TcpListener::bind(("127.0.0.1", options.port))?;
+    // Unguessable path, so no other local page can read the request or post a reply
+    let token = hex::encode(PrivateKeySigner::random().to_bytes());
+    let (method, message) = match request {
+        SignRequest::Personal(bytes) => ("personal_sign", bytes.to_string()),
+        SignRequest::TypedData(json) => ("eth_signTypedData_v4", json.clone()),
+    };
+    let request = json!({
+        "method": method,
+        "message": message,
+        "projectId": options.walletconnect_project_id,
+        "chainId": options.chain_id,
+        "callback": format!("/{token}/signature"),
+    });
+    // `<` only occurs inside JSON strings, where the escape keeps `</script>` out
+    let page = PAGE.replace("__REQUEST__", &request.to_string().replace('<', "\\u003c"));
+
+    eprintln!("Open http://{}/{token} in a browser to sign", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        match serve(&mut stream, &token, &page) {
+            Ok(Some(reply)) => {
+                let signature = Signature::try_from(reply.signature.as_ref())
+                    .map_err(|e| anyhow!("Wallet returned an invalid signature: {e}"))?;
+                return Ok((reply.account, signature));
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Bad request to the signing page: {e}"),
+        }
+    }
+    Err(anyhow!("Signing page stopped listening"))
+}
+
+/// Answer one HTTP request, returning the wallet's reply once it is posted.
+fn serve(stream: &mut TcpStream, token: &str, page: &str) -> Result<Option<Reply>> {
+    let (method, path, body) = read_request(stream)?;
+    let path = path.strip_prefix('/').unwrap_or_default();
+    if method == "GET" && path == token {
+        respond(stream, "200 OK", "text/html; charset=utf-8", page)?;
+        return Ok(None);
+    }
+    if method == "POST" && path.strip_suffix("/signature") == Some(token) {
+        let reply = serde_json::from_slice(&body)?;
+        respond(stream, "200 OK", "text/plain", "")?;
+        return Ok(Some(reply));
+    }
+    respond(stream, "404 Not Found", "text/plain", "")?;
+    Ok(None)
+}
+
+fn read_request(stream: &TcpStream) -> Result<(String, String, Vec<u8>)> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(anyhow!("Body of {content_length} bytes is too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok((method, path, body))
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! Bundles of independent batch proofs, landing on-chain in one transaction through
+//! `BatchVerifier` (`contracts/src/BatchVerifier.sol`), so N proofs pay the base fee
+//! once. A bundle packs each proof's seal, journal and the digests to consume without
+//! ABI padding:
+//!
+//! ```text
+//! bundle = version: u8 || count: u16 || entry * count
+//! entry  = seal_len: u16 || seal || journal_len: u32 || journal
+//!          || digest_count: u16 || digest: bytes32 * digest_count
+//! ```
+//!
+//! Lengths are big-endian. `SingleSignBundle` in the contract reads the same layout,
+//! so a new `BUNDLE_VERSION` needs a matching reader there.
+
+use crate::seal::encode_seal;
+use alloy_primitives::{Bytes, B256};
+use anyhow::{anyhow, bail, Result};
+use risc0_zkvm::Receipt;
+
+/// Version byte of the bundles `Bundle::encode` writes.
+pub const BUNDLE_VERSION: u8 = 1;
+
+/// One proof of a bundle and what to consume with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleEntry {
+    /// Seal as `encode_seal` packages it for the verifier router
+    pub seal: Bytes,
+    /// The batch journal the seal proves
+    pub journal: Bytes,
+    /// Digests the journal attests for the chain the bundle is sent to
+    pub digests: Vec<B256>,
+}
+
+impl BundleEntry {
+    /// The proof of `receipt`, consuming `digests`. Fails, like `encode_seal`, for
+    /// receipts that cannot be verified on-chain.
+    pub fn new(receipt: &Receipt, digests: Vec<B256>, allow_dev_receipts: bool) -> Result<Self> {
+        Ok(Self {
+            seal: encode_seal(receipt, allow_dev_receipts)?.into(),
+            journal: Bytes::copy_from_slice(&receipt.journal.bytes),
+            digests,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bundle {
+    pub entries: Vec<BundleEntry>,
+}
+
+impl Bundle {
+    /// The calldata `BatchVerifier` takes. Fails for bundles or proofs too large for
+    /// the format's length fields.
+    pub fn encode(&self) -> Result<Bytes> {
+        let mut out = vec![BUNDLE_VERSION];
+        out.extend(length::<u16>(self.entries.len(), "proofs")?.to_be_bytes());
+        for entry in &self.entries {
+            out.extend(length::<u16>(entry.seal.len(), "seal bytes")?.to_be_bytes());
+            out.extend_from_slice(&entry.seal);
+            out.extend(length::<u32>(entry.journal.len(), "journal bytes")?.to_be_bytes());
+            out.extend_from_slice(&entry.journal);
+            out.extend(length::<u16>(entry.digests.len(), "digests")?.to_be_bytes());
+            for digest in &entry.digests {
+                out.extend_from_slice(digest.as_slice());
+            }
+        }
+        Ok(out.into())
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader(bytes);
+        let [version] = reader.take::<1>()?;
+        if version != BUNDLE_VERSION {
+            bail!("Unsupported bundle version {version}, expected {BUNDLE_VERSION}");
+        }
+        let count = u16::from_be_bytes(reader.take()?);
+        let mut entries = Vec::with_capacity(count.into());
+        for _ in 0..count {
+            let seal_len = u16::from_be_bytes(reader.take()?);
+            let seal = Bytes::copy_from_slice(reader.slice(seal_len.into())?);
+            let journal_len = u32::from_be_bytes(reader.take()?);
+            let journal = Bytes::copy_from_slice(reader.slice(journal_len as usize)?);
+            let digest_count = u16::from_be_bytes(reader.take()?);
+            let digests = (0..digest_count)
+                .map(|_| reader.take::<32>().map(B256::from))
+                .collect::<Result<_>>()?;
+            entries.push(BundleEntry {
+                seal,
+                journal,
+                digests,
+            });
+        }
+        if !reader.0.is_empty() {
+            bail!("{} trailing bytes after the last proof of the bundle", reader.0.len());
+        }
+        Ok(Self { entries })
+    }
+
+    /// Every digest the bundle consumes.
+    pub fn digests(&self) -> impl Iterator<Item = &B256> {
+        self.entries.iter().flat_map(|entry| &entry.digests)
+    }
+}
+
+fn length<T: TryFrom<usize>>(len: usize, what: &str) -> Result<T> {
+    T::try_from(len).map_err(|_| anyhow!("{len} {what} do not fit in a bundle"))
+}
+
+// The unread rest of a bundle
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn slice(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.0.len() {
+            bail!("Bundle ends {} bytes short", len - self.0.len());
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.slice(N)?.try_into().expect("slice has N bytes"))
+    }
+}
@@ -0,0 +1,290 @@
+use crate::config::ReceiptKind;
+use crate::prover::Framed;
+use crate::receipts::{compress_receipt, load_receipt, save_receipt};
+use alloy_primitives::{hex, keccak256};
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use risc0_zkvm::{sha::Digest, Receipt};
+use std::{
+    collections::HashSet,
+    env,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Content-addressed on-disk receipt cache keyed by (image ID, keccak of the blob, keccak of
+/// the input header, receipt kind). The header holds the ranges, the signer, the signature and
+/// every other field that shapes the journal, so a receipt is only served for the input it
+/// proves. Entries are re-verified on every hit, so a stale or corrupted file is simply a miss.
+pub struct ReceiptCache {
+    dir: PathBuf,
+}
+
+impl ReceiptCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Open the cache at `$XDG_CACHE_HOME/single-sign/receipts`, falling back to
+    /// `~/.cache/single-sign/receipts`.
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::new(cache_dir()?.join("receipts")))
+    }
+
+    /// Return the cached receipt for this key if it exists and still verifies. A more
+    /// compact receipt of the same key, e.g. left by `compact`, serves too.
+    pub fn get<T: Framed>(
+        &self,
+        image_id: impl Into<Digest>,
+        input: &T,
+        kind: ReceiptKind,
+    ) -> Option<Receipt> {
+        let image_id = image_id.into();
+        ReceiptKind::value_variants()
+            .iter()
+            .filter(|cached| **cached >= kind)
+            .find_map(|cached| self.get_exact(&image_id, input, *cached))
+    }
+
+    fn get_exact<T: Framed>(
+        &self,
+        image_id: &Digest,
+        input: &T,
+        kind: ReceiptKind,
+    ) -> Option<Receipt> {
+        let path = self
+            .entry_path(image_id, input, kind)
+            .inspect_err(|e| tracing::debug!("No cache key for the input: {e}"))
+            .ok()?;
+        if !path.exists() {
+            return None;
+        }
+        match load_receipt(&path).and_then(|receipt| {
+            receipt.verify(*image_id)?;
+            Ok(receipt)
+        }) {
+            Ok(receipt) => {
+                // Hits count as use, so `prune` evicts the least recently used entries
+                let _ = File::options()
+                    .append(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()));
+                Some(receipt)
+            }
+            Err(e) => {
+                tracing::warn!("Ignoring unusable cache entry {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    pub fn put<T: Framed>(
+        &self,
+        image_id: impl Into<Digest>,
+        input: &T,
+        kind: ReceiptKind,
+        receipt: &Receipt,
+    ) -> Result<()> {
+        save_receipt(&self.entry_path(&image_id.into(), input, kind)?, receipt)
+    }
+
+    /// Every receipt file in the cache.
+    pub fn entries(&self) -> Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+        for image_dir in subdirectories(&self.dir)? {
+            let image_id = image_dir.file_name().and_then(|name| name.to_str());
+            let image_id = image_id.and_then(|name| hex::decode(name).ok());
+            let Some(image_id) = image_id.and_then(|id| Digest::try_from(id.as_slice()).ok()) else {
+                continue;
+            };
+            for blob_dir in subdirectories(&image_dir)? {
+                for file in fs::read_dir(&blob_dir)? {
+                    let path = file?.path();
+                    let Some((_, kind)) = split_entry_name(&path) else {
+                        continue;
+                    };
+                    let metadata = fs::metadata(&path)?;
+                    entries.push(CacheEntry {
+                        path,
+                        image_id,
+                        kind,
+                        size: metadata.len(),
+                        modified: metadata.modified()?,
+                    });
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Compress every cached receipt less compact than `kind` to `kind`, then delete
+    /// entries whose journal a more compact receipt of the same key already proves,
+    /// which `get` serves in their place. With `dry_run` only counts what would change.
+    pub fn compact(&self, kind: ReceiptKind, dry_run: bool) -> Result<CompactSummary> {
+        let mut entries = self.entries()?;
+        let mut summary = CompactSummary {
+            bytes_before: entries.iter().map(|entry| entry.size).sum(),
+            ..Default::default()
+        };
+        for entry in entries.iter_mut().filter(|entry| entry.kind < kind) {
+            summary.compressed += 1;
+            if dry_run {
+                continue;
+            }
+            let receipt = load_receipt(&entry.path)?;
+            let compressed = compress_receipt(&receipt, entry.image_id, kind)?;
+            let input_key = split_entry_name(&entry.path).map(|(key, _)| key).unwrap_or_default();
+            let path = entry_file(&entry.path, &input_key, kind);
+            save_receipt(&path, &compressed)?;
+            fs::remove_file(&entry.path)?;
+            *entry = CacheEntry {
+                size: fs::metadata(&path)?.len(),
+                path,
+                kind,
+                ..entry.clone()
+            };
+        }
+
+        // Keep the most compact receipt of each key and journal
+        entries.sort_by(|a, b| b.kind.cmp(&a.kind));
+        let mut kept = HashSet::new();
+        let mut removed_bytes = 0;
+        for entry in &entries {
+            let Ok(receipt) = load_receipt(&entry.path) else {
+                continue;
+            };
+            let input_key = split_entry_name(&entry.path).map(|(key, _)| key).unwrap_or_default();
+            let key = (entry.path.parent().map(Path::to_path_buf), input_key);
+            if !kept.insert((key, receipt.journal.bytes)) {
+                summary.deduplicated += 1;
+                removed_bytes += entry.size;
+                if !dry_run {
+                    fs::remove_file(&entry.path)?;
+                }
+            }
+        }
+        summary.bytes_after = entries.iter().map(|entry| entry.size).sum::<u64>() - removed_bytes;
+        Ok(summary)
+    }
+
+    /// Delete entries last used longer than `max_age` ago, then the least recently used
+    /// ones until the cache holds at most `max_bytes`. Returns the deleted entries; with
+    /// `dry_run` deletes nothing.
+    pub fn prune(
+        &self,
+        max_age: Option<Duration>,
+        max_bytes: Option<u64>,
+        dry_run: bool,
+    ) -> Result<Vec<CacheEntry>> {
+        let now = SystemTime::now();
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|entry| entry.modified);
+        let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+        let mut removed = Vec::new();
+        for entry in entries {
+            let age = now.duration_since(entry.modified).unwrap_or_default();
+            let expired = max_age.is_some_and(|max_age| age > max_age);
+            if !expired && max_bytes.map_or(true, |max_bytes| total <= max_bytes) {
+                continue;
+            }
+            if !dry_run {
+                fs::remove_file(&entry.path)?;
+            }
+            total -= entry.size;
+            removed.push(entry);
+        }
+        if !dry_run {
+            self.remove_empty_dirs()?;
+        }
+        Ok(removed)
+    }
+
+    // Directories of blobs and images with no entries left
+    fn remove_empty_dirs(&self) -> Result<()> {
+        for image_dir in subdirectories(&self.dir)? {
+            for blob_dir in subdirectories(&image_dir)? {
+                // Fails, as it should, while the directory still holds entries
+                let _ = fs::remove_dir(blob_dir);
+            }
+            let _ = fs::remove_dir(image_dir);
+        }
+        Ok(())
+    }
+
+    // Entries of a blob are told apart by a hash of everything else the guest reads
+    fn entry_path<T: Framed>(
+        &self,
+        image_id: &Digest,
+        input: &T,
+        kind: ReceiptKind,
+    ) -> Result<PathBuf> {
+        let header = bincode::serialize(&input.header())?;
+        let input_key = format!("input-{:x}", keccak256(header));
+        Ok(self
+            .dir
+            .join(image_id.to_string())
+            .join(format!("{:x}", keccak256(input.blob())))
+            .join(format!("{input_key}.{kind:?}.bin").to_lowercase()))
+    }
+}
+
+/// `$XDG_CACHE_HOME/single-sign`, falling back to `~/.cache/single-sign`.
+pub(crate) fn cache_dir() -> Result<PathBuf> {
+    let cache_home = match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".cache"))
+            .ok_or_else(|| anyhow!("Cannot locate cache directory: HOME is not set"))?,
+    };
+    Ok(cache_home.join("single-sign"))
+}
+
+/// A receipt file in the cache, as `ReceiptCache::entries` lists it.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    pub image_id: Digest,
+    pub kind: ReceiptKind,
+    pub size: u64,
+    /// When the receipt was cached or last served
+    pub modified: SystemTime,
+}
+
+/// What `ReceiptCache::compact` changed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompactSummary {
+    /// Receipts compressed to the requested kind
+    pub compressed: usize,
+    /// Receipts deleted because a more compact one proves the same journal
+    pub deduplicated: usize,
+    pub bytes_before: u64,
+    /// Not counting compression on a dry run, whose output sizes are unknown
+    pub bytes_after: u64,
+}
+
+// Subdirectories of `dir`, none if it does not exist yet
+fn subdirectories(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    Ok(dirs)
+}
+
+// `{input_key}.{kind}.bin`, as `entry_path` names entries
+fn split_entry_name(path: &Path) -> Option<(String, ReceiptKind)> {
+    let name = path.file_name()?.to_str()?.strip_suffix(".bin")?;
+    let (input_key, kind) = name.rsplit_once('.')?;
+    Some((input_key.to_owned(), ReceiptKind::from_str(kind, true).ok()?))
+}
+
+fn entry_file(path: &Path, input_key: &str, kind: ReceiptKind) -> PathBuf {
+    path.with_file_name(format!("{input_key}.{kind:?}.bin").to_lowercase())
+}
@@ -0,0 +1,82 @@
+use crate::config::Config;
+use alloy_primitives::{address, Address};
+use anyhow::{anyhow, Result};
+
+/// Contract addresses the host needs on one chain.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainContracts {
+    pub chain_id: u64,
+    pub permit2: Address,
+    pub verifier: Option<Address>,
+    pub router: Option<Address>,
+}
+
+// Permit2 is deployed with CREATE2 at the same address on every chain it supports.
+pub const PERMIT2: Address = address!("0x000000000022D473030F116dDEE9F6B43aC78BA3");
+
+/// Built-in deployments. Routers are RISC Zero `RiscZeroVerifierRouter` deployments;
+/// chains without a known router need `router_address` in the config.
+static KNOWN_CHAINS: &[ChainContracts] = &[
+    ChainContracts {
+        chain_id: 1,
+        permit2: PERMIT2,
+        verifier: None,
+        router: Some(address!("0x8EaB2D97Dfce405A1692a21b3ff3A172d593D319")),
+    },
+    ChainContracts {
+        chain_id: 10,
+        permit2: PERMIT2,
+        verifier: None,
+        router: None,
+    },
+    ChainContracts {
+        chain_id: 137,
+        permit2: PERMIT2,
+        verifier: None,
+        router: None,
+    },
+    ChainContracts {
+        chain_id: 8453,
+        permit2: PERMIT2,
+        verifier: None,
+        router: None,
+    },
+    ChainContracts {
+        chain_id: 42161,
+        permit2: PERMIT2,
+        verifier: None,
+        router: None,
+    },
+    ChainContracts {
+        chain_id: 11155111,
+        permit2: PERMIT2,
+        verifier: None,
+        router: Some(address!("0x925d8331ddc0a1F0d96E68CF073DFE1d92b69187")),
+    },
+];
+
+/// Look up the built-in entry for `chain_id`.
+pub fn lookup(chain_id: u64) -> Option<ChainContracts> {
+    KNOWN_CHAINS
+        .iter()
+        .find(|chain| chain.chain_id == chain_id)
+        .copied()
+}
+
+/// Resolve the contracts for `chain_id`, letting addresses set in `config` override
+/// the built-in entry. Unknown chains need at least `permit2_address` configured.
+pub fn resolve(chain_id: u64, config: &Config) -> Result<ChainContracts> {
+    let known = lookup(chain_id);
+    let permit2 = config
+        .permit2_address
+        .or(known.map(|chain| chain.permit2))
+        .ok_or_else(|| {
+            anyhow!("Chain {chain_id} is not in the registry; set permit2_address in the config")
+        })?;
+    Ok(ChainContracts {
+        chain_id,
+        permit2,
+        verifier: config.verifier_address.or(known.and_then(|chain| chain.verifier)),
+        router: config.router_address.or(known.and_then(|chain| chain.router)),
+    })
+}
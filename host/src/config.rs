@@ -0,0 +1,272 @@
+use crate::images::ImageIds;
+use alloy_primitives::{Address, U256};
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use figment::{
+    providers::{Env, Format, Serialized, Toml},
+    Figment,
+};
+use risc0_zkvm::ProverOpts;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env, path::PathBuf};
+
+/// Settings shared by every binary, layered as defaults < `single-sign.toml` <
+/// `SINGLE_SIGN_*` environment variables < command-line flags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub rpc_url: Option<String>,
+    pub chain_id: u64,
+    /// Overrides the `chains` registry entry for the selected chain
+    pub permit2_address: Option<Address>,
+    pub verifier_address: Option<Address>,
+    pub router_address: Option<Address>,
+    pub prover: ProverBackend,
+    pub output_dir: PathBuf,
+    pub receipt_kind: ReceiptKind,
+    /// Local proving: cycles per segment as a power of two; fewer need less memory
+    pub segment_limit_po2: Option<u32>,
+    /// Local proving: memory, in MiB, the prover may use, which caps the segment size
+    pub max_mem: Option<u64>,
+    /// Local proving: most cycles a single proof may execute before giving up
+    pub session_limit: Option<u64>,
+    /// SQLite database proven journals are recorded in (requires the `indexer` feature)
+    pub index_db: Option<PathBuf>,
+    /// Already uploaded guest program for Boundless requests; when unset the ELF is
+    /// uploaded through the storage provider configured in the environment
+    pub program_url: Option<String>,
+    /// Most a Boundless request may pay, in ether
+    pub boundless_max_price: String,
+    /// Seconds a Boundless request stays open before it expires
+    pub boundless_timeout: u32,
+    /// Recipient of the Permit2 transfers the prover commits; the signer when unset
+    pub recipient: Option<Address>,
+    /// Amount each committed Permit2 transfer requests, at most what its permit
+    /// allows; the whole permitted amount when unset
+    pub requested_amount: Option<U256>,
+    /// Image IDs of older guest builds by version label, see `host::images`
+    pub images: BTreeMap<String, ImageIds>,
+    /// Where to submit proofs on each chain, by chain ID, see `host::submit`
+    pub endpoints: BTreeMap<String, Endpoint>,
+    /// Notified of finished proofs and confirmed submissions (requires the `webhooks`
+    /// feature), see `host::webhooks`
+    pub webhooks: Vec<Webhook>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rpc_url: None,
+            chain_id: 1,
+            permit2_address: None,
+            verifier_address: None,
+            router_address: None,
+            prover: ProverBackend::Default,
+            output_dir: PathBuf::from("receipts"),
+            receipt_kind: ReceiptKind::Composite,
+            segment_limit_po2: None,
+            max_mem: None,
+            session_limit: None,
+            index_db: None,
+            program_url: None,
+            boundless_max_price: "0.001".to_owned(),
+            boundless_timeout: 3600,
+            recipient: None,
+            requested_amount: None,
+            images: BTreeMap::new(),
+            endpoints: BTreeMap::new(),
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+/// A chain's RPC endpoint and the contract that consumes proofs there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub rpc_url: String,
+    /// Implements `ISingleSignConsumer`, see `host::submit`
+    pub consumer: Address,
+    /// ERC-4337 bundler for `submit --bundler-account`
+    pub bundler_url: Option<String>,
+    /// ERC-7677 paymaster sponsoring those UserOperations
+    pub paymaster_url: Option<String>,
+    /// Relayer for `submit --relayer`
+    pub relayer_url: Option<String>,
+    /// Private transaction RPC, e.g. Flashbots Protect, for `submit --private-tx`
+    pub private_rpc_url: Option<String>,
+    /// `BatchVerifier` that `bundle --submit` sends bundles of proofs through, see
+    /// `host::bundle`
+    pub batch_verifier: Option<Address>,
+    /// Highest max fee per gas, in wei, that transactions sent from the local key bid,
+    /// including the raises replacing a transaction that is not mined; uncapped when unset
+    pub max_fee_per_gas: Option<u128>,
+}
+
+/// An HTTP endpoint `host::webhooks` posts events to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Key the payloads are signed with, as HMAC-SHA256; unsigned when unset
+    pub secret: Option<String>,
+    /// Events to deliver; every event when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A receipt was proven and verified by the queue or the gRPC service
+    ProofFinished,
+    /// A `submit` transaction confirmed on a chain
+    TransactionConfirmed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ProverBackend {
+    /// Let risc0 choose (Bonsai when BONSAI_API_KEY/BONSAI_API_URL are set, otherwise local)
+    Default,
+    Local,
+    Bonsai,
+    /// Outsource proving to the Boundless market (requires the `boundless` feature)
+    Boundless,
+}
+
+impl ProverBackend {
+    /// Point `risc0_zkvm::default_prover` at this backend. Boundless requests do not
+    /// go through `default_prover`, see `host::prover::prove`.
+    pub fn apply(self) {
+        match self {
+            ProverBackend::Default | ProverBackend::Boundless => {}
+            ProverBackend::Local => env::set_var("RISC0_PROVER", "local"),
+            ProverBackend::Bonsai => env::set_var("RISC0_PROVER", "bonsai"),
+        }
+    }
+}
+
+/// Ordered from least to most compact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptKind {
+    /// Cheapest to produce; fine for local development loops
+    Composite,
+    Succinct,
+    /// SNARK-wrapped for on-chain verification
+    Groth16,
+}
+
+impl ReceiptKind {
+    pub fn prover_opts(self) -> ProverOpts {
+        match self {
+            ReceiptKind::Composite => ProverOpts::composite(),
+            ReceiptKind::Succinct => ProverOpts::succinct(),
+            ReceiptKind::Groth16 => ProverOpts::groth16(),
+        }
+    }
+}
+
+/// Command-line layer of the configuration; flatten into each binary's `Args`.
+#[derive(clap::Args, Serialize)]
+pub struct ConfigArgs {
+    /// TOML config file; ignored if it does not exist
+    #[arg(long = "config", default_value = "single-sign.toml")]
+    #[serde(skip)]
+    pub config_path: PathBuf,
+
+    /// JSON-RPC endpoint of the target chain
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_url: Option<String>,
+
+    /// Chain ID used when no RPC endpoint is configured
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+
+    /// Permit2 contract address
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permit2_address: Option<Address>,
+
+    /// RISC Zero verifier contract address
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verifier_address: Option<Address>,
+
+    /// RISC Zero verifier router contract address
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub router_address: Option<Address>,
+
+    /// Proving backend
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prover: Option<ProverBackend>,
+
+    /// Directory receipts are written to
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Kind of receipt to produce
+    #[arg(long, value_enum)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt_kind: Option<ReceiptKind>,
+
+    /// Cycles per segment of local proving, as a power of two (risc0's default is 20);
+    /// lower it on machines short of memory
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_limit_po2: Option<u32>,
+
+    /// Memory, in MiB, local proving may use; picks the largest segment that fits
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_mem: Option<u64>,
+
+    /// Most cycles a single local proof may execute
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_limit: Option<u64>,
+
+    /// SQLite database to record proven journals in (requires the `indexer` feature)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_db: Option<PathBuf>,
+
+    /// URL of the already uploaded guest program for Boundless requests
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub program_url: Option<String>,
+
+    /// Maximum price of a Boundless request, in ether
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boundless_max_price: Option<String>,
+
+    /// Seconds before an unfulfilled Boundless request expires
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boundless_timeout: Option<u32>,
+
+    /// Recipient of the permits' transfers, instead of the signer
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<Address>,
+
+    /// Amount each permit's transfer requests, in base units, instead of all it permits
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested_amount: Option<U256>,
+}
+
+impl ConfigArgs {
+    pub fn load(&self) -> Result<Config> {
+        Figment::from(Serialized::defaults(Config::default()))
+            .merge(Toml::file(&self.config_path))
+            .merge(Env::prefixed("SINGLE_SIGN_"))
+            .merge(Serialized::defaults(self))
+            .extract()
+            .map_err(|e| anyhow!("Invalid configuration: {e}"))
+    }
+}
@@ -0,0 +1,182 @@
+//! Rust bindings of the contracts in `contracts/src`, generated from the artifacts
+//! `forge build` writes when the `consumer` feature builds the host, and deployments
+//! of them. `deploy_consumer` puts the reference consumer on a live chain; once
+//! deployed, add it to `endpoints` as the chain's `consumer` and `submit` sends it
+//! batch receipts. `deploy_permit2_consumer` deploys one that also executes the
+//! permits. `deploy_batch_verifier` adds the contract `bundle --submit` lands
+//! several proofs through at once. `deploy_local` sets up a whole Anvil chain for
+//! end-to-end runs, see `tests/e2e_permit2.rs`.
+
+use crate::{chains::PERMIT2, rpc::block_on};
+use alloy_primitives::{Address, Bytes, FixedBytes, B256};
+use alloy_provider::{
+    network::{Ethereum, EthereumWallet, Network, ReceiptResponse, TransactionBuilder},
+    Provider, ProviderBuilder,
+};
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{sol, SolConstructor};
+use anyhow::{anyhow, bail, Result};
+
+sol!(
+    SingleSignConsumer,
+    "../contracts/out/SingleSignConsumer.sol/SingleSignConsumer.json"
+);
+
+sol!(
+    BatchVerifier,
+    "../contracts/out/BatchVerifier.sol/BatchVerifier.json"
+);
+
+sol!(
+    Permit2Consumer,
+    "../contracts/out/Permit2Consumer.sol/Permit2Consumer.json"
+);
+
+sol!(MockERC20, "../contracts/out/MockERC20.sol/MockERC20.json");
+
+sol!(
+    RiscZeroMockVerifier,
+    "../contracts/out/RiscZeroMockVerifier.sol/RiscZeroMockVerifier.json"
+);
+
+/// Seal selector of dev-mode receipts, see `seal::encode_seal`
+const MOCK_SELECTOR: FixedBytes<4> = FixedBytes([0xFF; 4]);
+
+/// Addresses of a local deployment made by `deploy_local`.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalDeployment {
+    pub verifier: Address,
+    pub permit2: Address,
+    pub consumer: Address,
+}
+
+/// Deploy `SingleSignConsumer` from `signer`, verifying receipts of the batch guest
+/// `image_id` through `verifier`, usually the chain's router. Returns its address once
+/// the deployment is mined.
+pub fn deploy_consumer(
+    rpc_url: &str,
+    signer: PrivateKeySigner,
+    verifier: Address,
+    image_id: B256,
+) -> Result<Address> {
+    let constructor = SingleSignConsumer::constructorCall {
+        _verifier: verifier,
+        _imageId: image_id,
+    };
+    let code = [SingleSignConsumer::BYTECODE.as_ref(), &constructor.abi_encode()].concat();
+    deploy(rpc_url, signer, "SingleSignConsumer", code)
+}
+
+/// Deploy `Permit2Consumer` from `signer`, which consumes receipts like
+/// `deploy_consumer`'s and executes the permits they attest through `permit2`.
+pub fn deploy_permit2_consumer(
+    rpc_url: &str,
+    signer: PrivateKeySigner,
+    verifier: Address,
+    image_id: B256,
+    permit2: Address,
+) -> Result<Address> {
+    let constructor = Permit2Consumer::constructorCall {
+        _verifier: verifier,
+        _imageId: image_id,
+        _permit2: permit2,
+    };
+    let code = [Permit2Consumer::BYTECODE.as_ref(), &constructor.abi_encode()].concat();
+    deploy(rpc_url, signer, "Permit2Consumer", code)
+}
+
+/// Deploy `MockERC20`, a token anyone can mint, for local end-to-end runs.
+pub fn deploy_mock_token(rpc_url: &str, signer: PrivateKeySigner) -> Result<Address> {
+    deploy(rpc_url, signer, "MockERC20", MockERC20::BYTECODE.to_vec())
+}
+
+/// Deploy `BatchVerifier` from `signer`, checking bundled seals through `verifier`. Set
+/// it as the chain's `batch_verifier` endpoint to submit bundles, see `host::bundle`.
+pub fn deploy_batch_verifier(
+    rpc_url: &str,
+    signer: PrivateKeySigner,
+    verifier: Address,
+) -> Result<Address> {
+    let constructor = BatchVerifier::constructorCall {
+        _verifier: verifier,
+    };
+    let code = [BatchVerifier::BYTECODE.as_ref(), &constructor.abi_encode()].concat();
+    deploy(rpc_url, signer, "BatchVerifier", code)
+}
+
+/// Deploy `RiscZeroMockVerifier`, which accepts the seals of dev-mode receipts, so
+/// consumers on a local chain can be driven with `RISC0_DEV_MODE=1` proofs.
+pub fn deploy_mock_verifier(rpc_url: &str, signer: PrivateKeySigner) -> Result<Address> {
+    let constructor = RiscZeroMockVerifier::constructorCall {
+        _selector: MOCK_SELECTOR,
+    };
+    let code = [RiscZeroMockVerifier::BYTECODE.as_ref(), &constructor.abi_encode()].concat();
+    deploy(rpc_url, signer, "RiscZeroMockVerifier", code)
+}
+
+/// Make sure Permit2 exists at its canonical address on the Anvil node at `rpc_url`.
+/// A node forked from a live chain already has it; otherwise its code is copied from
+/// `source_rpc_url` with `anvil_setCode`. Permit2 rebuilds its domain separator when
+/// the chain ID differs from the one it was deployed on, so the copy signs for the
+/// local chain.
+pub fn install_permit2(rpc_url: &str, source_rpc_url: Option<&str>) -> Result<Address> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    block_on(async {
+        if !provider.get_code_at(PERMIT2).await?.is_empty() {
+            return anyhow::Ok(());
+        }
+        let Some(source_rpc_url) = source_rpc_url else {
+            bail!(
+                "Permit2 is not deployed on {rpc_url}; fork a live chain with anvil --fork-url \
+                 or give an RPC to copy its code from"
+            );
+        };
+        let source = ProviderBuilder::new().connect_http(source_rpc_url.parse()?);
+        let code: Bytes = source.get_code_at(PERMIT2).await?;
+        if code.is_empty() {
+            bail!("Permit2 is not deployed on {source_rpc_url} either");
+        }
+        provider
+            .raw_request::<_, ()>("anvil_setCode".into(), (PERMIT2, code))
+            .await?;
+        Ok(())
+    })??;
+    Ok(PERMIT2)
+}
+
+/// Set up the Anvil node at `rpc_url` for an end-to-end run: the mock verifier,
+/// Permit2 (see `install_permit2`) and a `SingleSignConsumer` for the batch guest
+/// `image_id` that verifies through the mock.
+pub fn deploy_local(
+    rpc_url: &str,
+    signer: PrivateKeySigner,
+    image_id: B256,
+    permit2_source: Option<&str>,
+) -> Result<LocalDeployment> {
+    let permit2 = install_permit2(rpc_url, permit2_source)?;
+    let verifier = deploy_mock_verifier(rpc_url, signer.clone())?;
+    let consumer = deploy_consumer(rpc_url, signer, verifier, image_id)?;
+    Ok(LocalDeployment {
+        verifier,
+        permit2,
+        consumer,
+    })
+}
+
+fn deploy(rpc_url: &str, signer: PrivateKeySigner, name: &str, code: Vec<u8>) -> Result<Address> {
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .connect_http(rpc_url.parse()?);
+    let tx = <Ethereum as Network>::TransactionRequest::default().with_deploy_code(code);
+    let receipt = block_on(async {
+        let pending = provider.send_transaction(tx).await?;
+        anyhow::Ok(pending.get_receipt().await?)
+    })?
+    .map_err(|e| anyhow!("Failed deploying {name}: {e}"))?;
+    if !receipt.status() {
+        bail!("Deployment of {name} reverted in {}", receipt.transaction_hash());
+    }
+    receipt
+        .contract_address()
+        .ok_or_else(|| anyhow!("Deployment receipt of {name} has no contract address"))
+}
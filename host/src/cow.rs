@@ -0,0 +1,94 @@
+//! CoW Protocol (GPv2) orders signed as part of a blob. A GPv2 order digest is the
+//! EIP-712 digest of its `Order` under the settlement contract's domain, which the
+//! guests already commit as `Output.digest`. Extracting `validTo` (see `UID_PATHS`)
+//! binds the remaining piece of the order UID to the proof too.
+
+use crate::{domains, seal::encode_seal};
+use alloy_primitives::{address, Address, Bytes};
+use alloy_sol_types::{sol, SolCall, SolValue};
+use anyhow::{anyhow, bail, Result};
+use risc0_zkvm::Receipt;
+use single_sign_types::{DigestRange, Output};
+
+/// Primary type of GPv2 order typed data.
+pub const ORDER: &str = "Order";
+
+/// `GPv2Settlement`, deployed at the same address on every supported chain.
+pub const SETTLEMENT: Address = address!("0x9008D19f58AAbD9eD0D60971565AA8510560ab41");
+
+/// `extract_paths` to prove CoW orders with, so `order_uid` can read `validTo`
+/// from the journal.
+pub const UID_PATHS: [&str; 1] = ["validTo"];
+
+sol! {
+    struct Order {
+        address sellToken;
+        address buyToken;
+        address receiver;
+        uint256 sellAmount;
+        uint256 buyAmount;
+        uint32 validTo;
+        bytes32 appData;
+        uint256 feeAmount;
+        string kind;
+        bool partiallyFillable;
+        string sellTokenBalance;
+        string buyTokenBalance;
+    }
+
+    interface IGPv2Settlement {
+        function setPreSignature(bytes calldata orderUid, bool signed) external;
+    }
+
+    /// Payload for a relayer that verifies the proof before pre-signing the order
+    /// on the owner's behalf.
+    struct PreSignatureProof {
+        bytes orderUid;
+        bytes seal;
+        bytes journal;
+    }
+}
+
+/// Whether the typed data `range` covers in `blob` is a GPv2 order.
+pub fn is_order(blob: &[u8], range: &DigestRange) -> bool {
+    range.typed_data(blob, false).is_ok_and(|typed_data| typed_data.primary_type == ORDER)
+        && domains::label(blob, range) == Some("CoW Protocol")
+}
+
+/// The order UID, `abi.encodePacked(orderDigest, owner, validTo)`, of the order a
+/// journal proves. The journal must come from proving with `UID_PATHS`.
+pub fn order_uid(output: &Output) -> Result<Bytes> {
+    if !output.is_ok() {
+        bail!("Journal proves a failure, not a signed order");
+    }
+    let valid_to = u32::abi_decode(&output.fields)
+        .map_err(|e| anyhow!("Journal fields are not the order's validTo: {e}"))?;
+    Ok([
+        output.digest.as_slice(),
+        output.signer.as_slice(),
+        &valid_to.to_be_bytes(),
+    ]
+    .concat()
+    .into())
+}
+
+/// Calldata for `GPv2Settlement.setPreSignature(orderUid, true)`, which the order's
+/// owner sends to make the proven order tradable.
+pub fn set_pre_signature_call(output: &Output) -> Result<Bytes> {
+    let call = IGPv2Settlement::setPreSignatureCall {
+        orderUid: order_uid(output)?,
+        signed: true,
+    };
+    Ok(call.abi_encode().into())
+}
+
+/// Package a Groth16 `receipt` proving one order, with its UID, for a solver or
+/// relayer contract that pre-signs orders once their proof verifies.
+pub fn pre_signature_proof(receipt: &Receipt, output: &Output) -> Result<Bytes> {
+    let proof = PreSignatureProof {
+        orderUid: order_uid(output)?,
+        seal: encode_seal(receipt, false)?.into(),
+        journal: receipt.journal.bytes.clone().into(),
+    };
+    Ok(proof.abi_encode().into())
+}
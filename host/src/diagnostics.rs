@@ -0,0 +1,113 @@
+use std::fmt;
+
+/// Why `prepare::find_json_ranges` rejected a blob. The discriminants are stable so
+/// tooling can match on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorCode {
+    /// A byte outside any object that is neither whitespace nor a byte order mark.
+    UnexpectedByte = 1,
+    /// Non-whitespace bytes after the last object.
+    TrailingGarbage = 2,
+    /// The blob ends inside an object.
+    UnterminatedObject = 3,
+    /// The blob holds no object at all.
+    NoObjects = 4,
+}
+
+impl ParseErrorCode {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+impl fmt::Display for ParseErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorCode::UnexpectedByte => write!(f, "unexpected byte between objects"),
+            ParseErrorCode::TrailingGarbage => write!(f, "trailing garbage after the last object"),
+            ParseErrorCode::UnterminatedObject => write!(f, "unterminated JSON object"),
+            ParseErrorCode::NoObjects => write!(f, "no JSON objects found"),
+        }
+    }
+}
+
+// Bytes of context shown on each side of the offending offset
+const CONTEXT: usize = 24;
+
+/// A parse error located in the blob: the byte `offset`, its 1-based `line` and
+/// `column` (counted in bytes), and a one-line `snippet` of the surrounding text with
+/// the offending position at char index `caret`, for large aggregated files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    pub code: ParseErrorCode,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    pub snippet: String,
+    pub caret: usize,
+    /// Further detail, e.g. the offending byte or how many bytes trail the last object.
+    pub detail: Option<String>,
+}
+
+impl ParseDiagnostic {
+    pub fn new(blob: &[u8], offset: usize, code: ParseErrorCode) -> Self {
+        let offset = offset.min(blob.len());
+        let line_start = blob[..offset]
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |newline| newline + 1);
+        let line_end = blob[offset..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(blob.len(), |newline| offset + newline);
+        let from = offset.saturating_sub(CONTEXT).max(line_start);
+        let to = (offset + CONTEXT).min(line_end);
+        let before = String::from_utf8_lossy(&blob[from..offset]);
+        Self {
+            code,
+            offset,
+            line: blob[..offset].iter().filter(|&&byte| byte == b'\n').count() + 1,
+            column: offset - line_start + 1,
+            snippet: String::from_utf8_lossy(&blob[from..to])
+                .trim_end_matches('\r')
+                .to_owned(),
+            caret: before.chars().count(),
+            detail: None,
+        }
+    }
+
+    pub fn with_detail(self, detail: impl Into<String>) -> Self {
+        Self {
+            detail: Some(detail.into()),
+            ..self
+        }
+    }
+
+    /// Multi-line report pointing at the offending position, as printed by `inspect`.
+    pub fn render(&self, source: &str) -> String {
+        format!(
+            "error[P{:03}]: {self}\n --> {source}:{}:{}\n  | {}\n  | {}^",
+            self.code.code(),
+            self.line,
+            self.column,
+            self.snippet,
+            " ".repeat(self.caret),
+        )
+    }
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {} (offset {})",
+            self.code, self.line, self.column, self.offset
+        )?;
+        if let Some(detail) = &self.detail {
+            write!(f, ": {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseDiagnostic {}
@@ -0,0 +1,99 @@
+use alloy_primitives::{address, Address};
+use alloy_sol_types::Eip712Domain;
+use single_sign_types::DigestRange;
+
+/// A protocol whose EIP-712 domain the host recognizes.
+#[derive(Debug, Clone, Copy)]
+pub struct KnownDomain {
+    pub label: &'static str,
+    pub name: Option<&'static str>,
+    pub version: Option<&'static str>,
+    /// `None` for per-account domains such as Safe's, which are matched by primary
+    /// type instead
+    pub verifying_contract: Option<Address>,
+    /// Primary types the protocol signs; empty matches any
+    pub primary_types: &'static [&'static str],
+}
+
+/// Curated protocol domains. A domain separator also commits to the chain ID, so
+/// entries match on the remaining fields and cover every chain a protocol is deployed
+/// on at the same address.
+static KNOWN_DOMAINS: &[KnownDomain] = &[
+    KnownDomain {
+        label: "Permit2",
+        name: Some("Permit2"),
+        version: None,
+        verifying_contract: Some(address!("0x000000000022D473030F116dDEE9F6B43aC78BA3")),
+        primary_types: &[],
+    },
+    KnownDomain {
+        label: "Seaport 1.5",
+        name: Some("Seaport"),
+        version: Some("1.5"),
+        verifying_contract: Some(address!("0x00000000000000ADc04C56Bf30aC9d3c0aAF14dC")),
+        primary_types: &[],
+    },
+    KnownDomain {
+        label: "Seaport 1.6",
+        name: Some("Seaport"),
+        version: Some("1.6"),
+        verifying_contract: Some(address!("0x0000000000000068F116a894984e2DB1123eB395")),
+        primary_types: &[],
+    },
+    KnownDomain {
+        label: "CoW Protocol",
+        name: Some("Gnosis Protocol"),
+        version: Some("v2"),
+        verifying_contract: Some(address!("0x9008D19f58AAbD9eD0D60971565AA8510560ab41")),
+        primary_types: &[],
+    },
+    KnownDomain {
+        label: "1inch Aggregation Router v5",
+        name: Some("1inch Aggregation Router"),
+        version: Some("5"),
+        verifying_contract: Some(address!("0x1111111254EEB25477B68fb85Ed929f73A960582")),
+        primary_types: &[],
+    },
+    KnownDomain {
+        label: "1inch Aggregation Router v6",
+        name: Some("1inch Aggregation Router"),
+        version: Some("6"),
+        verifying_contract: Some(address!("0x111111125421cA6dc452d289314280a0f8842A65")),
+        primary_types: &[],
+    },
+    KnownDomain {
+        label: "Safe",
+        name: None,
+        version: None,
+        verifying_contract: None,
+        primary_types: &["SafeTx", "SafeMessage"],
+    },
+];
+
+/// The label of the protocol whose verifying contract is `address`, to name contracts
+/// that appear in messages, such as a spender.
+pub fn contract_label(address: Address) -> Option<&'static str> {
+    KNOWN_DOMAINS
+        .iter()
+        .find(|known| known.verifying_contract == Some(address))
+        .map(|known| known.label)
+}
+
+/// The registry entry for typed data signed under `domain` with `primary_type`.
+pub fn identify(domain: &Eip712Domain, primary_type: &str) -> Option<&'static KnownDomain> {
+    KNOWN_DOMAINS.iter().find(|known| {
+        domain.name.as_deref() == known.name
+            && domain.version.as_deref() == known.version
+            && known
+                .verifying_contract
+                .map_or(true, |contract| domain.verifying_contract == Some(contract))
+            && (known.primary_types.is_empty() || known.primary_types.contains(&primary_type))
+    })
+}
+
+/// Protocol label of the typed data `range` covers in `blob`, if it parses and its
+/// domain is known.
+pub fn label(blob: &[u8], range: &DigestRange) -> Option<&'static str> {
+    let typed_data = range.typed_data(blob, false).ok()?;
+    identify(&typed_data.domain, &typed_data.primary_type).map(|known| known.label)
+}
@@ -0,0 +1,143 @@
+//! ENS names for address-heavy workflows: CLI arguments accept `vitalik.eth` where
+//! they take an address, and operator output annotates addresses with their primary
+//! names. Lookups go through the ENS registry at `rpc_url`, so it must point at a
+//! chain where ENS is deployed (mainnet or Sepolia).
+
+use crate::rpc::block_on;
+use alloy_primitives::{address, hex, keccak256, Address, B256};
+use alloy_provider::{
+    network::{Ethereum, Network, TransactionBuilder},
+    Provider, ProviderBuilder,
+};
+use alloy_sol_types::{sol, SolCall};
+use anyhow::{anyhow, bail, Result};
+use std::{fmt, str::FromStr};
+
+/// ENS registry, at the same address on mainnet and the testnets.
+pub const ENS_REGISTRY: Address = address!("0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+sol! {
+    interface IEnsRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    interface IAddrResolver {
+        function addr(bytes32 node) external view returns (address);
+    }
+
+    interface INameResolver {
+        function name(bytes32 node) external view returns (string);
+    }
+}
+
+/// EIP-137 namehash of `name`. Labels are lowercased; names with non-ASCII labels
+/// must already be ENSIP-15 normalized.
+pub fn namehash(name: &str) -> B256 {
+    name.rsplit('.')
+        .filter(|label| !label.is_empty())
+        .fold(B256::ZERO, |node, label| {
+            let label = keccak256(label.to_ascii_lowercase());
+            keccak256([node.as_slice(), label.as_slice()].concat())
+        })
+}
+
+/// The address `name` resolves to.
+pub fn resolve(rpc_url: &str, name: &str) -> Result<Address> {
+    let node = namehash(name);
+    let resolver = call(rpc_url, ENS_REGISTRY, IEnsRegistry::resolverCall { node })?;
+    if resolver.is_zero() {
+        bail!("{name} has no ENS resolver");
+    }
+    let address = call(rpc_url, resolver, IAddrResolver::addrCall { node })?;
+    if address.is_zero() {
+        bail!("{name} does not resolve to an address");
+    }
+    Ok(address)
+}
+
+/// The primary name of `address`, if it set one that also resolves back to it.
+pub fn reverse(rpc_url: &str, address: Address) -> Result<Option<String>> {
+    let node = namehash(&format!("{}.addr.reverse", hex::encode(address)));
+    let resolver = call(rpc_url, ENS_REGISTRY, IEnsRegistry::resolverCall { node })?;
+    if resolver.is_zero() {
+        return Ok(None);
+    }
+    let name = call(rpc_url, resolver, INameResolver::nameCall { node })?;
+    // Anyone can claim any name in their reverse record; only trust it if it points back
+    if name.is_empty() || resolve(rpc_url, &name).ok() != Some(address) {
+        return Ok(None);
+    }
+    Ok(Some(name))
+}
+
+/// `address` followed by its primary name in parentheses, if it has one. Lookup
+/// failures only lose the annotation.
+pub fn annotate(rpc_url: Option<&str>, address: Address) -> String {
+    let name = rpc_url.and_then(|rpc_url| {
+        reverse(rpc_url, address)
+            .inspect_err(|e| tracing::debug!("Reverse lookup of {address:#x} failed: {e}"))
+            .ok()
+            .flatten()
+    });
+    match name {
+        Some(name) => format!("{address:#x} ({name})"),
+        None => format!("{address:#x}"),
+    }
+}
+
+fn call<C: SolCall>(rpc_url: &str, to: Address, call: C) -> Result<C::Return> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let tx = <Ethereum as Network>::TransactionRequest::default()
+        .with_to(to)
+        .with_input(call.abi_encode());
+    let result = block_on(provider.call(tx))?
+        .map_err(|e| anyhow!("ENS call to {to:#x} failed: {e}"))?;
+    C::abi_decode_returns(&result)
+        .map_err(|e| anyhow!("Invalid ENS return data from {to:#x}: {e}"))
+}
+
+/// An address, or an ENS name to resolve to one, as CLI arguments accept them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressOrName {
+    Address(Address),
+    Name(String),
+}
+
+impl AddressOrName {
+    /// The address, resolving names through `rpc_url`.
+    pub fn resolve(&self, rpc_url: Option<&str>) -> Result<Address> {
+        match self {
+            AddressOrName::Address(address) => Ok(*address),
+            AddressOrName::Name(name) => {
+                let rpc_url =
+                    rpc_url.ok_or_else(|| anyhow!("Resolving {name} requires rpc_url"))?;
+                let address = resolve(rpc_url, name)?;
+                tracing::info!("Resolved {name} to {address:#x}");
+                Ok(address)
+            }
+        }
+    }
+}
+
+impl FromStr for AddressOrName {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(address) = s.parse() {
+            return Ok(AddressOrName::Address(address));
+        }
+        if !s.contains('.') || s.starts_with("0x") {
+            bail!("{s} is neither an address nor an ENS name");
+        }
+        Ok(AddressOrName::Name(s.to_owned()))
+    }
+}
+
+impl fmt::Display for AddressOrName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressOrName::Address(address) => write!(f, "{address:#x}"),
+            AddressOrName::Name(name) => f.write_str(name),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{chains::ChainContracts, seal::image_id_bytes};
+use alloy_primitives::{Address, B256};
+use anyhow::{anyhow, Result};
+use methods::{SINGLE_SIGN_BATCH_ID, SINGLE_SIGN_ID};
+use risc0_zkvm::{sha::Digest, Receipt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Label of the guest build compiled into this binary.
+pub const CURRENT: &str = "current";
+
+/// Image IDs of one released guest build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageIds {
+    pub single: B256,
+    pub batch: B256,
+    /// Verifier to send this version's proofs to instead of the chain's router, e.g.
+    /// once the router no longer routes the selector its receipts were sealed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifier: Option<Address>,
+}
+
+impl ImageIds {
+    pub fn get(&self, batch: bool) -> B256 {
+        if batch {
+            self.batch
+        } else {
+            self.single
+        }
+    }
+
+    /// Contract that verifies this version's seals on `chain`.
+    pub fn verifier_route(&self, chain: &ChainContracts) -> Option<Address> {
+        self.verifier.or(chain.router).or(chain.verifier)
+    }
+}
+
+/// Guest builds receipts may come from, by version label. The build compiled into
+/// this binary is always registered as `current`; older ones come from the `images`
+/// table of the config:
+///
+/// ```toml
+/// [images.v1]
+/// single = "0x…"
+/// batch = "0x…"
+/// ```
+#[derive(Debug, Clone)]
+pub struct ImageRegistry {
+    versions: BTreeMap<String, ImageIds>,
+}
+
+impl ImageRegistry {
+    pub fn new(configured: &BTreeMap<String, ImageIds>) -> Self {
+        let mut versions = configured.clone();
+        versions.insert(
+            CURRENT.to_owned(),
+            ImageIds {
+                single: image_id_bytes(SINGLE_SIGN_ID),
+                batch: image_id_bytes(SINGLE_SIGN_BATCH_ID),
+                verifier: configured.get(CURRENT).and_then(|ids| ids.verifier),
+            },
+        );
+        Self { versions }
+    }
+
+    pub fn get(&self, version: &str) -> Result<&ImageIds> {
+        self.versions.get(version).ok_or_else(|| {
+            let known: Vec<&str> = self.versions.keys().map(String::as_str).collect();
+            anyhow!("Unknown image version {version}; known versions: {}", known.join(", "))
+        })
+    }
+
+    /// Find the version whose image ID `receipt` verifies against, trying the
+    /// current build first.
+    pub fn identify(&self, receipt: &Receipt, batch: bool) -> Result<(&str, &ImageIds)> {
+        let current = self.versions.get_key_value(CURRENT);
+        let older = self.versions.iter().filter(|(version, _)| *version != CURRENT);
+        current
+            .into_iter()
+            .chain(older)
+            .find(|(_, ids)| receipt.verify(Digest::from_bytes(ids.get(batch).0)).is_ok())
+            .map(|(version, ids)| (version.as_str(), ids))
+            .ok_or_else(|| anyhow!("Receipt does not verify against any registered image ID"))
+    }
+}
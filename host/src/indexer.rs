@@ -0,0 +1,423 @@
+//! SQLite index of proven journals, so operators can answer "has digest X already
+//! been proven or consumed?" without rescanning receipts. Enabled by the `indexer`
+//! feature. The same database holds the API keys of the gRPC proving service and
+//! their usage.
+
+use crate::domains;
+use alloy_primitives::{hex, keccak256, Address, B256};
+use anyhow::{anyhow, bail, Result};
+use rusqlite::{params, Connection, Row};
+use serde::Serialize;
+use single_sign_types::{DigestRange, Output};
+use std::{fmt, path::Path};
+
+/// One proven journal entry. Hashes and addresses are stored as `0x` hex text.
+#[derive(Debug, Clone, Serialize)]
+pub struct Record {
+    pub digest: B256,
+    pub signer: Address,
+    /// keccak256 of the signed `typed_data_concat`
+    pub blob_hash: B256,
+    pub range: DigestRange,
+    pub chain_id: u64,
+    pub image_id: B256,
+    /// Set for failure journals
+    pub error_code: Option<u8>,
+    /// Transaction that consumed the proof on-chain, once submitted
+    pub tx_hash: Option<B256>,
+    /// Protocol the typed data belongs to, from `host::domains`
+    pub protocol: Option<String>,
+    /// The signer's ENS primary name, when it was looked up at proving time
+    pub signer_name: Option<String>,
+}
+
+impl Record {
+    pub fn new(
+        output: &Output,
+        blob: &[u8],
+        range: &DigestRange,
+        chain_id: u64,
+        image_id: B256,
+    ) -> Self {
+        Self {
+            digest: output.digest,
+            signer: output.signer,
+            blob_hash: keccak256(blob),
+            range: range.clone(),
+            chain_id,
+            image_id,
+            error_code: output.failure.as_ref().map(|failure| failure.code.code()),
+            tx_hash: None,
+            protocol: domains::label(blob, range).map(str::to_owned),
+            signer_name: None,
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let start: i64 = row.get("range_start")?;
+        let end: i64 = row.get("range_end")?;
+        let chain_id: i64 = row.get("chain_id")?;
+        Ok(Self {
+            digest: parse_column(row, "digest")?,
+            signer: parse_column(row, "signer")?,
+            blob_hash: parse_column(row, "blob_hash")?,
+            range: DigestRange {
+                start: start as usize,
+                end: end as usize,
+            },
+            chain_id: chain_id as u64,
+            image_id: parse_column(row, "image_id")?,
+            error_code: row.get("error_code")?,
+            tx_hash: row
+                .get::<_, Option<String>>("tx_hash")?
+                .map(|tx_hash| tx_hash.parse())
+                .transpose()
+                .map_err(|e| invalid_column("tx_hash", e))?,
+            protocol: row.get("protocol")?,
+            signer_name: row.get("signer_name")?,
+        })
+    }
+}
+
+pub struct Indexer {
+    conn: Connection,
+}
+
+impl Indexer {
+    /// Open (creating if needed) the index database at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .map_err(|e| anyhow!("Failed opening index {}: {e}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS journals (
+                id INTEGER PRIMARY KEY,
+                digest TEXT NOT NULL,
+                signer TEXT NOT NULL,
+                blob_hash TEXT NOT NULL,
+                range_start INTEGER NOT NULL,
+                range_end INTEGER NOT NULL,
+                chain_id INTEGER NOT NULL,
+                image_id TEXT NOT NULL,
+                error_code INTEGER,
+                tx_hash TEXT,
+                proven_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                protocol TEXT,
+                signer_name TEXT
+            );
+            CREATE INDEX IF NOT EXISTS journals_digest ON journals (digest);
+            CREATE TABLE IF NOT EXISTS api_keys (
+                name TEXT PRIMARY KEY,
+                key_hash TEXT NOT NULL UNIQUE,
+                rate_limit INTEGER,
+                quota INTEGER,
+                revoked INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+            );
+            CREATE TABLE IF NOT EXISTS api_requests (
+                id INTEGER PRIMARY KEY,
+                key_name TEXT NOT NULL,
+                started_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                blob_len INTEGER NOT NULL DEFAULT 0,
+                proofs INTEGER NOT NULL DEFAULT 0,
+                cycles INTEGER NOT NULL DEFAULT 0,
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS api_requests_key ON api_requests (key_name, started_at);",
+        )?;
+        // Indexes created before protocol labels or signer names lack the columns
+        for column in ["protocol", "signer_name"] {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM pragma_table_info('journals') WHERE name = ?1)",
+                [column],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                conn.execute(&format!("ALTER TABLE journals ADD COLUMN {column} TEXT"), [])?;
+            }
+        }
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, record: &Record) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO journals
+                (digest, signer, blob_hash, range_start, range_end, chain_id, image_id,
+                 error_code, tx_hash, protocol, signer_name)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                record.digest.to_string(),
+                record.signer.to_string(),
+                record.blob_hash.to_string(),
+                record.range.start as i64,
+                record.range.end as i64,
+                record.chain_id as i64,
+                record.image_id.to_string(),
+                record.error_code,
+                record.tx_hash.map(|tx_hash| tx_hash.to_string()),
+                record.protocol,
+                record.signer_name,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record that `digest` was consumed on `chain_id` by `tx_hash`. Returns the
+    /// number of proven entries updated.
+    pub fn mark_consumed(&self, digest: B256, chain_id: u64, tx_hash: B256) -> Result<usize> {
+        Ok(self.conn.execute(
+            "UPDATE journals SET tx_hash = ?1
+             WHERE digest = ?2 AND chain_id = ?3 AND error_code IS NULL",
+            params![tx_hash.to_string(), digest.to_string(), chain_id as i64],
+        )?)
+    }
+
+    /// Whether a successful journal for `digest` has been proven on any chain.
+    pub fn is_proven(&self, digest: B256) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM journals WHERE digest = ?1 AND error_code IS NULL)",
+            params![digest.to_string()],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Whether a proof of `digest` has been consumed on `chain_id`.
+    pub fn is_consumed(&self, digest: B256, chain_id: u64) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM journals
+             WHERE digest = ?1 AND chain_id = ?2 AND tx_hash IS NOT NULL)",
+            params![digest.to_string(), chain_id as i64],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Every entry recorded for `digest`, oldest first.
+    pub fn records(&self, digest: B256) -> Result<Vec<Record>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT * FROM journals WHERE digest = ?1 ORDER BY id")?;
+        let records = statement
+            .query_map(params![digest.to_string()], Record::from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(records)
+    }
+
+    /// Create an API key named `name`, allowed `rate_limit` requests a minute and
+    /// `quota` proofs in total (unlimited when unset). Returns the key, which is only
+    /// stored hashed and cannot be shown again.
+    pub fn create_api_key(
+        &self,
+        name: &str,
+        rate_limit: Option<u32>,
+        quota: Option<u64>,
+    ) -> Result<String> {
+        let quota = quota.map(|quota| sql_int(quota, "quota")).transpose()?;
+        let key = format!("ss_{}", hex::encode(rand::random::<[u8; 32]>()));
+        self.conn
+            .execute(
+                "INSERT INTO api_keys (name, key_hash, rate_limit, quota) VALUES (?1, ?2, ?3, ?4)",
+                params![name, keccak256(&key).to_string(), rate_limit, quota],
+            )
+            .map_err(|e| anyhow!("Failed creating API key {name}: {e}"))?;
+        Ok(key)
+    }
+
+    /// Revoke the API key named `name`. Returns whether there was one.
+    pub fn revoke_api_key(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .execute("UPDATE api_keys SET revoked = 1 WHERE name = ?1", params![name])?
+            > 0)
+    }
+
+    /// The unrevoked API key `key` is, if any.
+    pub fn authenticate(&self, key: &str) -> Result<Option<ApiKey>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT * FROM api_keys WHERE key_hash = ?1 AND revoked = 0")?;
+        let mut keys = statement.query_map(params![keccak256(key).to_string()], ApiKey::from_row)?;
+        Ok(keys.next().transpose()?)
+    }
+
+    /// Every API key, revoked ones included, with its usage.
+    pub fn api_keys(&self) -> Result<Vec<(ApiKey, ApiUsage)>> {
+        let mut statement = self.conn.prepare("SELECT * FROM api_keys ORDER BY created_at")?;
+        let keys = statement
+            .query_map([], ApiKey::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        keys.into_iter()
+            .map(|key| {
+                let usage = self.api_usage(&key.name, 0)?;
+                Ok((key, usage))
+            })
+            .collect()
+    }
+
+    /// Usage of the API key named `name` by requests started at or after `since`, in
+    /// Unix seconds.
+    pub fn api_usage(&self, name: &str, since: u64) -> Result<ApiUsage> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(proofs), 0), COALESCE(SUM(cycles), 0)
+             FROM api_requests WHERE key_name = ?1 AND started_at >= ?2",
+            params![name, since as i64],
+            |row| {
+                Ok(ApiUsage {
+                    requests: row.get::<_, i64>(0)? as u64,
+                    proofs: row.get::<_, i64>(1)? as u64,
+                    cycles: row.get::<_, i64>(2)? as u64,
+                })
+            },
+        )?)
+    }
+
+    /// Record a request made with `key`, failing if it exceeds the key's rate limit.
+    /// Rejected requests count towards the limit too. Returns the request's ID.
+    pub fn begin_request(&self, key: &ApiKey) -> Result<i64> {
+        if let Some(rate_limit) = key.rate_limit {
+            let minute_ago: i64 =
+                self.conn.query_row("SELECT strftime('%s', 'now') - 60", [], |row| row.get(0))?;
+            let recent = self.api_usage(&key.name, minute_ago as u64)?;
+            if recent.requests >= u64::from(rate_limit) {
+                bail!("API key {} is limited to {rate_limit} requests a minute", key.name);
+            }
+        }
+        self.conn
+            .execute("INSERT INTO api_requests (key_name) VALUES (?1)", params![key.name])?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Charge request `id` for `proofs` proofs of a `blob_len`-byte blob, failing if
+    /// they would exceed the quota of `key`.
+    pub fn charge_request(
+        &self,
+        key: &ApiKey,
+        id: i64,
+        blob_len: usize,
+        proofs: u64,
+    ) -> Result<()> {
+        if let Some(quota) = key.quota {
+            let used = self.api_usage(&key.name, 0)?.proofs;
+            if used.saturating_add(proofs) > quota {
+                let left = quota.saturating_sub(used);
+                bail!("API key {} has {left} of its {quota} proofs left", key.name);
+            }
+        }
+        self.conn.execute(
+            "UPDATE api_requests SET blob_len = ?1, proofs = ?2 WHERE id = ?3",
+            params![sql_int(blob_len, "blob length")?, sql_int(proofs, "proofs")?, id],
+        )?;
+        Ok(())
+    }
+
+    /// Settle request `id` at the `proofs` proofs and `cycles` cycles actually
+    /// delivered, refunding the rest of its charge, and record why it failed, if it did.
+    pub fn finish_request(
+        &self,
+        id: i64,
+        proofs: u64,
+        cycles: u64,
+        error: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE api_requests SET proofs = ?1, cycles = ?2, error = ?3 WHERE id = ?4",
+            params![sql_int(proofs, "proofs")?, sql_int(cycles, "cycles")?, error, id],
+        )?;
+        Ok(())
+    }
+}
+
+// SQLite integers are signed 64-bit
+fn sql_int<T: TryInto<i64> + Copy + fmt::Display>(value: T, what: &str) -> Result<i64> {
+    value
+        .try_into()
+        .map_err(|_| anyhow!("The {what} {value} does not fit in the index"))
+}
+
+/// An API key of the gRPC proving service, as `Indexer::create_api_key` stored it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKey {
+    pub name: String,
+    /// Requests allowed per minute; unlimited when unset
+    pub rate_limit: Option<u32>,
+    /// Proofs allowed in total; unlimited when unset
+    pub quota: Option<u64>,
+    pub revoked: bool,
+    /// Unix seconds
+    pub created_at: u64,
+}
+
+impl ApiKey {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            name: row.get("name")?,
+            rate_limit: row.get("rate_limit")?,
+            quota: row.get::<_, Option<i64>>("quota")?.map(|quota| quota as u64),
+            revoked: row.get("revoked")?,
+            created_at: row.get::<_, i64>("created_at")? as u64,
+        })
+    }
+}
+
+/// What an API key has used, summed over its requests.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ApiUsage {
+    pub requests: u64,
+    /// Proofs charged, i.e. delivered or still being proven
+    pub proofs: u64,
+    pub cycles: u64,
+}
+
+/// Policy consulted before proving a digest, e.g. to protect Permit2 nonces from
+/// being spent twice.
+pub trait ReplayGuard {
+    /// Fail if `digest` must not be proven again for `chain_id`.
+    fn check(&self, digest: B256, chain_id: u64) -> Result<()>;
+}
+
+impl<G: ReplayGuard + ?Sized> ReplayGuard for &G {
+    fn check(&self, digest: B256, chain_id: u64) -> Result<()> {
+        (**self).check(digest, chain_id)
+    }
+}
+
+/// Rejects digests whose proof was already consumed on the chain.
+impl ReplayGuard for Indexer {
+    fn check(&self, digest: B256, chain_id: u64) -> Result<()> {
+        if self.is_consumed(digest, chain_id)? {
+            bail!("Digest {digest} was already consumed on chain {chain_id}");
+        }
+        Ok(())
+    }
+}
+
+/// Lets `allowed` digests past `guard`, for deliberately re-proving them.
+pub struct AllowList<G> {
+    pub guard: G,
+    pub allowed: Vec<B256>,
+}
+
+impl<G: ReplayGuard> ReplayGuard for AllowList<G> {
+    fn check(&self, digest: B256, chain_id: u64) -> Result<()> {
+        if self.allowed.contains(&digest) {
+            return Ok(());
+        }
+        self.guard.check(digest, chain_id)
+    }
+}
+
+fn parse_column<T>(row: &Row, column: &str) -> rusqlite::Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    row.get::<_, String>(column)?
+        .parse()
+        .map_err(|e| invalid_column(column, e))
+}
+
+fn invalid_column(
+    column: &str,
+    error: impl std::error::Error + Send + Sync + 'static,
+) -> rusqlite::Error {
+    let error = anyhow!("Invalid {column} in index: {error}");
+    rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, error.into())
+}
@@ -0,0 +1,118 @@
+use alloy_primitives::{hex, Bytes};
+use anyhow::{anyhow, bail, Result};
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Read a file, or standard input when the path is `-`.
+pub fn read_input(path: &Path) -> Result<Vec<u8>> {
+    if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| anyhow!("Failed reading stdin: {e}"))?;
+        return Ok(bytes);
+    }
+    fs::read(path).map_err(|e| anyhow!("Failed reading {}: {e}", path.display()))
+}
+
+/// Signature options shared by the binaries proving a signed blob: hex inline, or a
+/// file (`-` for stdin) holding either the raw 65 bytes or their hex encoding, so
+/// wallet tooling can pipe it in without quoting. One of them is required unless the
+/// binary takes the signature from a manifest instead, which `bytes` enforces.
+#[derive(clap::Args, Debug)]
+pub struct SignatureArgs {
+    /// EIP-191 personal signature over the whole blob, hex encoded, with `v` 27/28 or
+    /// EIP-155 `chain_id * 2 + 35/36`
+    #[arg(long)]
+    pub signature: Option<Bytes>,
+
+    /// File holding the signature as raw bytes or hex, or `-` for stdin
+    #[arg(long, conflicts_with = "signature")]
+    pub signature_file: Option<PathBuf>,
+}
+
+impl SignatureArgs {
+    /// The signature bytes, refusing to read stdin when the blob comes from there too.
+    pub fn bytes(&self, blob_path: &Path) -> Result<Bytes> {
+        if self.signature_file.as_deref() == Some(Path::new("-")) && blob_path == Path::new("-") {
+            bail!("The blob and the signature cannot both come from stdin");
+        }
+        self.given()?
+            .ok_or_else(|| anyhow!("Pass --signature or --signature-file"))
+    }
+
+    /// The signature bytes, or `None` for binaries that sign themselves when neither
+    /// option is passed.
+    pub fn given(&self) -> Result<Option<Bytes>> {
+        match &self.signature_file {
+            Some(path) => {
+                decode_signature(read_input(path)?, &path.display().to_string()).map(Some)
+            }
+            None => Ok(self.signature.clone()),
+        }
+    }
+}
+
+/// A signature stored as raw bytes or hex, read from `source` (named in errors).
+pub fn decode_signature(contents: Vec<u8>, source: &str) -> Result<Bytes> {
+    // Hex first: its shortest signature is 130 characters, while a raw signature is 65
+    // bytes, or up to 72 with an EIP-155 `v`, and practically never valid hex
+    let text = std::str::from_utf8(&contents).map(str::trim);
+    if let Some(bytes) = text.ok().and_then(|text| hex::decode(text).ok()) {
+        return Ok(bytes.into());
+    }
+    if (65..=72).contains(&contents.len()) {
+        return Ok(contents.into());
+    }
+    let text = text.map_err(|_| anyhow!("{source} holds neither a raw nor a hex signature"))?;
+    Ok(hex::decode(text)
+        .map_err(|e| anyhow!("Invalid hex signature in {source}: {e}"))?
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // r and s of 0x11 and 0x22 bytes, then `v` big-endian in as few bytes as it takes
+    fn raw_signature(v: u64) -> Vec<u8> {
+        let mut bytes = [[0x11; 32], [0x22; 32]].concat();
+        let v = v.to_be_bytes();
+        let first = v.iter().position(|&byte| byte != 0).unwrap_or(7);
+        bytes.extend_from_slice(&v[first..]);
+        bytes
+    }
+
+    #[test]
+    fn decodes_raw_signatures_of_every_length() {
+        // 27, EIP-155 on Base (8453 * 2 + 35), and the largest chain ID `v` fits
+        for v in [27, 16_941, u64::MAX] {
+            let raw = raw_signature(v);
+            assert_eq!(decode_signature(raw.clone(), "test").unwrap(), raw);
+        }
+        assert_eq!(raw_signature(27).len(), 65);
+        assert_eq!(raw_signature(16_941).len(), 66);
+        assert_eq!(raw_signature(u64::MAX).len(), 72);
+    }
+
+    #[test]
+    fn decodes_hex_signatures() {
+        for v in [27, 16_941] {
+            let raw = raw_signature(v);
+            for text in [hex::encode(&raw), format!("0x{}\n", hex::encode_upper(&raw))] {
+                assert_eq!(decode_signature(text.into_bytes(), "test").unwrap(), raw);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_other_contents() {
+        let error = decode_signature(vec![0xff; 64], "sig.bin").unwrap_err();
+        assert_eq!(error.to_string(), "sig.bin holds neither a raw nor a hex signature");
+        let error = decode_signature(b"0xnot hex".to_vec(), "sig.txt").unwrap_err();
+        assert!(error.to_string().starts_with("Invalid hex signature in sig.txt"));
+    }
+}
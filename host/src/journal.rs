@@ -0,0 +1,237 @@
+use alloy_primitives::{Address, Bytes, B256};
+use anyhow::{bail, Result};
+use risc0_zkvm::Journal;
+use serde::Deserialize;
+use single_sign_types::{
+    intent::IntentCommitment,
+    policy::PolicyVerdict,
+    serde_hex,
+    signing::{MessageMode, SignatureScheme},
+    BlockAnchor, Failure, ManifestCheck, Output,
+};
+
+/// Decode the journal committed by the single-range guest, dispatching on the
+/// leading version word. A new layout gets its own match arm and the released ones
+/// keep theirs, decoding a struct of their fields into `Output`, so previously issued
+/// receipts keep decoding.
+pub fn decode_output(journal: &Journal) -> Result<Output> {
+    let version: u16 = journal.decode()?;
+    match version {
+        1 => Ok(journal.decode::<OutputV1>()?.into()),
+        2 => Ok(journal.decode()?),
+        v => bail!("Unsupported journal version {v}"),
+    }
+}
+
+/// Decode the journal committed by the batch guest: one `Output` per range, in order.
+pub fn decode_batch_outputs(journal: &Journal) -> Result<Vec<Output>> {
+    // A sequence is encoded as a length word followed by its elements, so the
+    // first element's version is the second word.
+    let version = match journal.decode::<u32>()? {
+        0 => return Ok(Vec::new()),
+        _ => journal.decode::<(u32, u16)>()?.1,
+    };
+    match version {
+        1 => Ok(journal
+            .decode::<Vec<OutputV1>>()?
+            .into_iter()
+            .map(Output::from)
+            .collect()),
+        2 => Ok(journal.decode()?),
+        v => bail!("Unsupported journal version {v}"),
+    }
+}
+
+// Version 1: `Output` up to `message_mode`, without `transfer`
+#[derive(Deserialize)]
+struct OutputV1 {
+    version: u16,
+    #[serde(with = "serde_hex::address")]
+    signer: Address,
+    #[serde(with = "serde_hex::b256")]
+    digest: B256,
+    failure: Option<Failure>,
+    #[serde(with = "serde_hex::bytes")]
+    fields: Bytes,
+    #[serde(with = "serde_hex::b256_vec")]
+    field_commitments: Vec<B256>,
+    scheme: SignatureScheme,
+    #[serde(with = "serde_hex::bytes")]
+    public_key: Bytes,
+    #[serde(with = "serde_hex::b256")]
+    credential_id_hash: B256,
+    manifest: Option<ManifestCheck>,
+    #[serde(with = "serde_hex::b256")]
+    struct_hash: B256,
+    intent: Option<IntentCommitment>,
+    low_s_enforced: bool,
+    signature_chain_id: Option<u64>,
+    anchor: Option<BlockAnchor>,
+    policy: Option<PolicyVerdict>,
+    #[serde(with = "serde_hex::b256")]
+    manifest_hash: B256,
+    #[serde(with = "serde_hex::b256")]
+    blob_hash: B256,
+    message_mode: Option<MessageMode>,
+}
+
+impl From<OutputV1> for Output {
+    fn from(v1: OutputV1) -> Self {
+        Output {
+            version: v1.version,
+            signer: v1.signer,
+            digest: v1.digest,
+            failure: v1.failure,
+            fields: v1.fields,
+            field_commitments: v1.field_commitments,
+            scheme: v1.scheme,
+            public_key: v1.public_key,
+            credential_id_hash: v1.credential_id_hash,
+            manifest: v1.manifest,
+            struct_hash: v1.struct_hash,
+            intent: v1.intent,
+            low_s_enforced: v1.low_s_enforced,
+            signature_chain_id: v1.signature_chain_id,
+            anchor: v1.anchor,
+            policy: v1.policy,
+            manifest_hash: v1.manifest_hash,
+            blob_hash: v1.blob_hash,
+            message_mode: v1.message_mode,
+            transfer: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U256;
+    use serde::Serialize;
+    use single_sign_types::{
+        journal::OUTPUT_VERSION, transfer::TransferDetails, DigestRange, ErrorCode,
+    };
+
+    fn journal(value: &impl Serialize) -> Journal {
+        Journal::new(words(value))
+    }
+
+    fn words(value: &impl Serialize) -> Vec<u8> {
+        let words = risc0_zkvm::serde::to_vec(value).unwrap();
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    // `output` in the version 1 layout: the current one up to `message_mode`
+    fn v1_words(output: &Output) -> Vec<u8> {
+        assert!(output.transfer.is_none(), "version 1 has no transfer");
+        let mut words = words(&Output { version: 1, ..output.clone() });
+        // The `transfer: None` tag
+        assert_eq!(words.split_off(words.len() - 4), [0; 4]);
+        words
+    }
+
+    // Every optional field set, and byte strings whose lengths are not word multiples
+    fn full_output() -> Output {
+        Output {
+            fields: Bytes::from_static(&[1, 2, 3, 4, 5]),
+            field_commitments: vec![B256::repeat_byte(1), B256::repeat_byte(2)],
+            scheme: SignatureScheme::WebAuthn,
+            public_key: Bytes::from(vec![4; 65]),
+            credential_id_hash: B256::repeat_byte(0xee),
+            manifest: Some(ManifestCheck {
+                all_match: false,
+                first_mismatch: Some(3),
+            }),
+            struct_hash: B256::repeat_byte(0xdd),
+            intent: Some(IntentCommitment {
+                nonce: U256::from(7),
+                deadline: U256::from(1_700_000_000u64),
+                current_time: 1_699_990_000,
+            }),
+            low_s_enforced: true,
+            signature_chain_id: Some(8453),
+            anchor: Some(BlockAnchor {
+                number: 21_000_000,
+                hash: B256::repeat_byte(0x0f),
+            }),
+            policy: Some(PolicyVerdict {
+                policy_hash: B256::repeat_byte(0x9a),
+                passed: true,
+            }),
+            manifest_hash: B256::repeat_byte(0xcc),
+            blob_hash: B256::repeat_byte(0xbb),
+            message_mode: Some(MessageMode::Personal),
+            transfer: Some(TransferDetails {
+                to: Address::repeat_byte(0x44),
+                requested_amount: U256::from(250),
+            }),
+            ..Output::ok(Address::repeat_byte(0x11), B256::repeat_byte(0xaa))
+        }
+    }
+
+    fn failure_output() -> Output {
+        let range = DigestRange { start: 10, end: 250 };
+        Output {
+            blob_hash: B256::repeat_byte(0xbb),
+            ..Output::err(Address::repeat_byte(0x11), ErrorCode::InvalidSignature, range)
+        }
+    }
+
+    fn json(output: &Output) -> serde_json::Value {
+        serde_json::to_value(output).unwrap()
+    }
+
+    #[test]
+    fn decodes_every_version_of_single_journals() {
+        assert_eq!(OUTPUT_VERSION, 2, "add the new version's layout to these tests");
+        for output in [Output::default(), full_output(), failure_output()] {
+            let v1 = Output { version: 1, transfer: None, ..output.clone() };
+            let decoded = decode_output(&Journal::new(v1_words(&v1))).unwrap();
+            assert_eq!(json(&decoded), json(&v1));
+            let v2 = Output { version: 2, ..output };
+            let decoded = decode_output(&journal(&v2)).unwrap();
+            assert_eq!(json(&decoded), json(&v2));
+        }
+    }
+
+    #[test]
+    fn decodes_every_version_of_batch_journals() {
+        let outputs = vec![full_output(), failure_output()];
+        let decoded = decode_batch_outputs(&journal(&outputs)).unwrap();
+        assert_eq!(
+            decoded.iter().map(json).collect::<Vec<_>>(),
+            outputs.iter().map(json).collect::<Vec<_>>()
+        );
+        let v1: Vec<Output> = outputs
+            .into_iter()
+            .map(|output| Output { version: 1, transfer: None, ..output })
+            .collect();
+        let mut bytes = words(&(v1.len() as u32));
+        for output in &v1 {
+            bytes.extend(v1_words(output));
+        }
+        let decoded = decode_batch_outputs(&Journal::new(bytes)).unwrap();
+        assert_eq!(
+            decoded.iter().map(json).collect::<Vec<_>>(),
+            v1.iter().map(json).collect::<Vec<_>>()
+        );
+        assert!(decode_batch_outputs(&journal(&Vec::<Output>::new())).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        for version in [0, OUTPUT_VERSION + 1] {
+            let output = Output { version, ..full_output() };
+            let error = decode_output(&journal(&output)).unwrap_err();
+            assert_eq!(error.to_string(), format!("Unsupported journal version {version}"));
+            let error = decode_batch_outputs(&journal(&vec![output])).unwrap_err();
+            assert_eq!(error.to_string(), format!("Unsupported journal version {version}"));
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_journals() {
+        let mut bytes = journal(&full_output()).bytes;
+        bytes.truncate(bytes.len() - 4);
+        assert!(decode_output(&Journal::new(bytes)).is_err());
+    }
+}
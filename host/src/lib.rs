@@ -0,0 +1,45 @@
+#[cfg(feature = "boundless")]
+pub mod boundless;
+pub mod bridge;
+pub mod bundle;
+pub mod cache;
+pub mod chains;
+pub mod config;
+#[cfg(feature = "consumer")]
+pub mod contracts;
+pub mod cow;
+pub mod diagnostics;
+pub mod domains;
+pub mod ens;
+pub mod images;
+#[cfg(feature = "indexer")]
+pub mod indexer;
+pub mod input;
+pub mod journal;
+pub mod lint;
+pub mod logging;
+pub mod manifest;
+pub mod metrics;
+pub mod prepare;
+pub mod preview;
+pub mod progress;
+pub mod prover;
+#[cfg(feature = "queue")]
+pub mod queue;
+pub mod receipts;
+pub mod rpc;
+pub mod seal;
+pub mod seaport;
+pub mod session;
+pub mod signer;
+#[cfg(feature = "steel")]
+pub mod steel;
+#[cfg(feature = "storage")]
+pub mod storage;
+pub mod submit;
+pub mod tokens;
+pub mod verify;
+#[cfg(feature = "watcher")]
+pub mod watcher;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
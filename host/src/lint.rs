@@ -0,0 +1,239 @@
+//! Warnings about typed data that is dangerous or ambiguous to sign, checked over a
+//! whole blob before anyone signs it. The rules only read the parsed JSON, field names
+//! and values, so they apply to any protocol; a `Policy` sets the thresholds and
+//! which rules are waived. Token metadata, where `Tokens` can look it up, names the
+//! token of an unlimited approval and exposes amounts in contracts that are no token.
+
+use crate::{
+    domains,
+    tokens::{token_amounts, Tokens},
+};
+use alloy_primitives::{Address, U256};
+use clap::ValueEnum;
+use serde_json::Value;
+use single_sign_types::DigestRange;
+use std::{collections::HashMap, fmt};
+
+/// Message fields holding a timestamp after which the signature stops being usable.
+pub const DEADLINE_FIELDS: &[&str] = &[
+    "deadline",
+    "sigDeadline",
+    "expiry",
+    "expiration",
+    "validTo",
+    "validUntil",
+    "endTime",
+];
+
+/// `type(uint160).max`, the amount Permit2 treats as an unlimited allowance.
+pub const UINT160_MAX: U256 = U256::from_limbs([u64::MAX, u64::MAX, u32::MAX as u64, 0]);
+
+/// A lint rule. The codes are stable so tooling can match on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum Rule {
+    /// An amount of `type(uint256).max` or `type(uint160).max` (Permit2's maximum).
+    UnlimitedApproval = 1,
+    /// A deadline further ahead than the policy allows.
+    FarFutureDeadline = 2,
+    /// The zero address as a field value or verifying contract.
+    ZeroAddress = 3,
+    /// A domain missing from the `host::domains` registry.
+    UnknownDomain = 4,
+    /// A nonce another object in the blob signs under the same domain and field.
+    DuplicateNonce = 5,
+    /// A chain ID other than the policy's, or than the blob's first object.
+    ChainIdMismatch = 6,
+    /// An amount in a contract without ERC-20 `symbol()` and `decimals()`.
+    NotAToken = 7,
+}
+
+impl Rule {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::UnlimitedApproval => write!(f, "unlimited approval"),
+            Rule::FarFutureDeadline => write!(f, "far-future deadline"),
+            Rule::ZeroAddress => write!(f, "zero address"),
+            Rule::UnknownDomain => write!(f, "unknown domain"),
+            Rule::DuplicateNonce => write!(f, "duplicate nonce"),
+            Rule::ChainIdMismatch => write!(f, "chain ID mismatch"),
+            Rule::NotAToken => write!(f, "not a token"),
+        }
+    }
+}
+
+/// What the rules check against.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// Current Unix time, in seconds
+    pub now: u64,
+    /// Furthest a deadline may lie past `now`, in seconds
+    pub max_deadline: u64,
+    /// Chain every object must be signed for; when unset, the first object's chain
+    pub chain_id: Option<u64>,
+    /// Rules not to report
+    pub allow: Vec<Rule>,
+}
+
+/// One warning: the object it concerns, by index in the blob, and where in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub object: usize,
+    pub rule: Rule,
+    /// JSON path of the offending value, e.g. `message.details.amount`
+    pub path: String,
+    pub detail: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} L{:03} {} at {}: {}",
+            self.object,
+            self.rule.code(),
+            self.rule,
+            self.path,
+            self.detail
+        )
+    }
+}
+
+/// Lint the typed data `ranges` cover in `blob`. Ranges that do not parse are
+/// skipped; `inspect` reports them on its own. `NotAToken` is only checked for
+/// objects `tokens` can look tokens up for; `Tokens::new(None, 0)` skips it.
+///
+/// Duplicate nonces are matched per verifying contract, chain, primary type and
+/// field, so nonces scoped more narrowly, such as Permit2's per-token allowance
+/// nonces, can be reported although they do not collide.
+pub fn lint(
+    blob: &[u8],
+    ranges: &[DigestRange],
+    policy: &Policy,
+    tokens: &mut Tokens,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut expected_chain = policy.chain_id.map(U256::from);
+    let mut nonces: HashMap<_, usize> = HashMap::new();
+    for (object, range) in ranges.iter().enumerate() {
+        let Ok(typed_data) = range.typed_data(blob, false) else {
+            continue;
+        };
+        let domain = &typed_data.domain;
+        let mut report = |rule, path: &str, detail: String| {
+            findings.push(Finding {
+                object,
+                rule,
+                path: path.to_owned(),
+                detail,
+            });
+        };
+
+        if domains::identify(domain, &typed_data.primary_type).is_none() {
+            let name = domain.name.as_deref().unwrap_or("unnamed");
+            report(Rule::UnknownDomain, "domain", format!("{name} is not a known protocol"));
+        }
+        if domain.verifying_contract == Some(Address::ZERO) {
+            let detail = "signatures verify against no contract".to_owned();
+            report(Rule::ZeroAddress, "domain.verifyingContract", detail);
+        }
+        if let Some(chain_id) = domain.chain_id {
+            let expected = *expected_chain.get_or_insert(chain_id);
+            if chain_id != expected {
+                let detail = format!("signed for chain {chain_id}, expected {expected}");
+                report(Rule::ChainIdMismatch, "domain.chainId", detail);
+            }
+        }
+
+        let amounts = token_amounts(domain, &typed_data.primary_type, &typed_data.message);
+        let mut checked = Vec::new();
+        for amount in &amounts {
+            let Some(token) = amount.token.filter(|token| !checked.contains(token)) else {
+                continue;
+            };
+            checked.push(token);
+            if tokens.online(domain.chain_id) && tokens.get(token, domain.chain_id).is_none() {
+                let detail = format!("{token:#x} has no ERC-20 symbol() or decimals()");
+                report(Rule::NotAToken, &amount.path, detail);
+            }
+        }
+
+        let mut leaves = Vec::new();
+        collect_leaves(&typed_data.message, "message".to_owned(), &mut leaves);
+        for (path, value) in leaves {
+            let field = path.rsplit('.').next().unwrap_or_default();
+            if value.as_str().and_then(|s| s.parse::<Address>().ok()) == Some(Address::ZERO) {
+                report(Rule::ZeroAddress, &path, "zero address".to_owned());
+                continue;
+            }
+            let Some(number) = parse_uint(value) else {
+                continue;
+            };
+            if DEADLINE_FIELDS.contains(&field) {
+                let limit = U256::from(policy.now.saturating_add(policy.max_deadline));
+                if number > limit {
+                    let days = (number - U256::from(policy.now)) / U256::from(86_400);
+                    report(Rule::FarFutureDeadline, &path, format!("{days} days from now"));
+                }
+            } else if number == U256::MAX || number == UINT160_MAX {
+                let token = amounts
+                    .iter()
+                    .find(|amount| amount.path == path)
+                    .and_then(|amount| tokens.get(amount.token?, domain.chain_id));
+                let detail = match token {
+                    Some(token) => format!("unlimited {} amount", token.symbol),
+                    None => "unlimited amount".to_owned(),
+                };
+                report(Rule::UnlimitedApproval, &path, detail);
+            } else if field.eq_ignore_ascii_case("chainId") && domain.chain_id != Some(number) {
+                let detail = format!("message signs chain {number}, its domain another");
+                report(Rule::ChainIdMismatch, &path, detail);
+            } else if field.eq_ignore_ascii_case("nonce") {
+                let key = (
+                    domain.verifying_contract,
+                    domain.chain_id,
+                    typed_data.primary_type.clone(),
+                    path.clone(),
+                    number,
+                );
+                let first = *nonces.entry(key).or_insert(object);
+                if first != object {
+                    report(Rule::DuplicateNonce, &path, format!("nonce {number} also in #{first}"));
+                }
+            }
+        }
+    }
+    findings.retain(|finding| !policy.allow.contains(&finding.rule));
+    findings
+}
+
+// Scalar values of `value` with their dotted JSON paths
+fn collect_leaves<'a>(value: &'a Value, path: String, leaves: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                collect_leaves(field, format!("{path}.{key}"), leaves);
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_leaves(item, format!("{path}[{i}]"), leaves);
+            }
+        }
+        _ => leaves.push((path, value)),
+    }
+}
+
+// Typed data carries integers as JSON numbers or as decimal or hex strings
+pub(crate) fn parse_uint(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(number) => number.as_u64().map(U256::from),
+        Value::String(s) if s.len() < 42 || !s.starts_with("0x") => s.parse().ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,131 @@
+use clap::ValueEnum;
+use tracing::Span;
+use tracing_subscriber::{
+    filter::EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, Layer,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per event, for log aggregation
+    Json,
+}
+
+/// Logging options shared by every binary; flatten into each binary's `Args`.
+#[derive(clap::Args)]
+pub struct LogArgs {
+    /// Format of log events; filter them with `RUST_LOG`
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// OTLP/HTTP collector to export spans to, e.g. `http://localhost:4318` (requires
+    /// the `otel` feature); spans pass the `RUST_LOG` filter too
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    pub otlp_endpoint: Option<String>,
+}
+
+impl LogArgs {
+    /// Install the global tracing subscriber. Inputs and signer options redact their
+    /// secrets in `Debug`, so logging them in either format is safe. Keep the returned
+    /// guard until the binary exits, so the last spans are exported.
+    pub fn init(&self) -> Telemetry {
+        let fmt_layer = match self.log_format {
+            LogFormat::Text => fmt::layer().boxed(),
+            LogFormat::Json => fmt::layer().json().boxed(),
+        };
+        let registry = tracing_subscriber::registry()
+            .with(EnvFilter::from_default_env())
+            .with(fmt_layer);
+
+        #[cfg(feature = "otel")]
+        if let Some(endpoint) = &self.otlp_endpoint {
+            match otel::tracer_provider(endpoint) {
+                Ok(provider) => {
+                    use opentelemetry::trace::TracerProvider;
+                    let tracer = provider.tracer("single-sign");
+                    registry
+                        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+                        .init();
+                    return Telemetry {
+                        provider: Some(provider),
+                    };
+                }
+                Err(e) => eprintln!("Not exporting spans to {endpoint}: {e}"),
+            }
+        }
+        #[cfg(not(feature = "otel"))]
+        if self.otlp_endpoint.is_some() {
+            eprintln!("Not exporting spans: --otlp-endpoint requires the `otel` feature");
+        }
+        registry.init();
+        Telemetry::default()
+    }
+}
+
+/// Flushes exported spans when dropped.
+#[must_use = "spans are only flushed when the guard drops"]
+#[derive(Default)]
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed flushing spans: {e}");
+            }
+        }
+    }
+}
+
+/// Make `span` part of the trace a remote caller started, from the W3C `traceparent`
+/// and `tracestate` values `header` looks up, e.g. in gRPC metadata. Callers without
+/// them, and builds without the `otel` feature, leave `span` where it is.
+pub fn continue_trace<'a>(span: &Span, header: impl Fn(&str) -> Option<&'a str>) {
+    #[cfg(feature = "otel")]
+    {
+        use opentelemetry::propagation::TextMapPropagator;
+        use std::collections::HashMap;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let headers: HashMap<String, String> = ["traceparent", "tracestate"]
+            .into_iter()
+            .filter_map(|key| Some((key.to_owned(), header(key)?.to_owned())))
+            .collect();
+        let parent =
+            opentelemetry_sdk::propagation::TraceContextPropagator::new().extract(&headers);
+        span.set_parent(parent);
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = (span, header);
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+    use std::env;
+
+    // Spans are batched on a background thread and sent with a blocking client, so
+    // binaries without a tokio runtime export too
+    pub fn tracer_provider(endpoint: &str) -> anyhow::Result<SdkTracerProvider> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+            .build()?;
+        // Named after the binary, e.g. `grpc_server`
+        let service = env::current_exe()
+            .ok()
+            .and_then(|exe| Some(exe.file_stem()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "single-sign".to_owned());
+        Ok(SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(service).build())
+            .build())
+    }
+}
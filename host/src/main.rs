@@ -1,17 +1,53 @@
 // These constants represent the RISC-V ELF and the image ID generated by risc0-build.
 // The ELF is used for proving and the ID is used for verification.
-use alloy_primitives::{address, hex, Address, Bytes, Signature, U256};
+use alloy_primitives::{hex, Address, Bytes, Signature, B256, U256};
 use alloy_signer::SignerSync;
 use alloy_signer_local::PrivateKeySigner;
 use alloy_sol_types::sol;
-use anyhow::Result;
-use methods::{SINGLE_SIGN_ELF, SINGLE_SIGN_ID};
-use risc0_zkvm::{default_prover, ExecutorEnv};
-use single_sign_types::{DigestRange, Input, Output};
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use host::cache::ReceiptCache;
+use host::chains;
+use host::config::{Config, ConfigArgs, ProverBackend, ReceiptKind};
+use host::cow;
+#[cfg(feature = "indexer")]
+use host::ens;
+#[cfg(feature = "indexer")]
+use host::indexer::{AllowList, Indexer, Record, ReplayGuard};
+use host::input::SignatureArgs;
+use host::journal::{decode_batch_outputs, decode_output};
+use host::logging::LogArgs;
+use host::metrics::{install_exporter, record_proof, record_proof_failure, set_queue_depth};
+use host::prepare::{prepare_inputs_with, transfer_details, unchecked_inputs};
+use host::progress::{BatchProgress, ProgressBars, RangeStatus};
+use host::prover::prove_framed;
+use host::receipts::{load_receipt, save_receipt};
+use host::rpc;
+#[cfg(feature = "indexer")]
+use host::seal::image_id_bytes;
+use host::session::{prove_session, SessionEntry, SessionOptions, SessionReport};
+use host::signer::SignerArgs;
+#[cfg(feature = "steel")]
+use host::steel;
+use host::verify::{check_batch_receipt_against_blob, check_receipt_against_blob};
+use methods::{SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID, SINGLE_SIGN_ELF, SINGLE_SIGN_ID};
+use single_sign_types::intent::{self, IntentDomain, IntentPolicy};
+#[cfg(feature = "steel")]
+use single_sign_types::precondition::Precondition;
+use single_sign_types::policy::SigningPolicy;
+use single_sign_types::{
+    signing::{parse_signature_with_chain_id, Envelope},
+    Assertion, BatchInput, DigestMode, DigestRange, Input, Output,
+};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use serde_json::json;
 // Bring in the digest verifier to ensure JSON shape conforms to EIP-712 expectations.
 use single_sign_types::typed_data::verify_digest;
+use single_sign_types::canonical::compact_typed_data;
 
 sol! {
     struct TokenPermissions {
@@ -27,8 +63,6 @@ sol! {
     }
 }
 
-static PERMIT2_ADDRESS: Address = address!("0x000000000022D473030F116dDEE9F6B43aC78BA3");
-
 fn address_hex(addr: Address) -> String {
     format!("{:#x}", addr)
 }
@@ -111,97 +145,709 @@ fn make_three_permit_transfers_for_token(token: Address) -> Vec<PermitTransferFr
     ]
 }
 
+fn describe_output(output: &Output) -> String {
+    let policy = match output.policy {
+        Some(verdict) if verdict.passed => format!(", policy {} passed", verdict.policy_hash),
+        Some(verdict) => format!(", policy {} violated", verdict.policy_hash),
+        None => String::new(),
+    };
+    let transfer = match output.transfer {
+        Some(transfer) => {
+            format!(", transfer of {} to {:#x}", transfer.requested_amount, transfer.to)
+        }
+        None => String::new(),
+    };
+    let description = match &output.failure {
+        None => format!(
+            "signer: {:#x}, digest: 0x{}, fields: 0x{}, field commitments: {:?}",
+            output.signer,
+            hex::encode(output.digest),
+            hex::encode(&output.fields),
+            output.field_commitments
+        ),
+        Some(failure) => format!(
+            "signer: {:#x}, failed on {:?}: {} (code {})",
+            output.signer,
+            failure.range,
+            failure.code,
+            failure.code.code()
+        ),
+    };
+    description + &policy + &transfer
+}
+
+/// Raw recovery may no longer reflect the intent of an EIP-7702 delegated EOA, so
+/// ask its delegate through ERC-1271 before proving.
+fn check_delegation(
+    config: &Config,
+    signer: Address,
+    prehash: B256,
+    signature: Signature,
+) -> Result<()> {
+    let rpc_url = config
+        .rpc_url
+        .as_deref()
+        .ok_or_else(|| anyhow!("--check-delegation requires rpc_url"))?;
+    let Some(delegate) = rpc::delegation(rpc_url, signer)? else {
+        return Ok(());
+    };
+    tracing::info!("Signer {signer:#x} delegates to {delegate:#x}, checking ERC-1271");
+    if !rpc::is_valid_signature(rpc_url, signer, prehash, signature.as_bytes().to_vec().into())? {
+        bail!("Delegated account {signer:#x} (via {delegate:#x}) rejects the signature");
+    }
+    Ok(())
+}
+
+/// Prove the sample Permit2 messages against a single signature over their concatenation.
+#[derive(Parser)]
+struct Args {
+    // Signing account; a random key is used when none is given
+    #[command(flatten)]
+    signer: SignerArgs,
+
+    // A signature over the sample permits made elsewhere, e.g. by a hardware wallet,
+    // instead of one by the signing account
+    #[command(flatten)]
+    signature: SignatureArgs,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// Always re-prove instead of reusing receipts from ~/.cache/single-sign/receipts
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Serve Prometheus metrics (proof duration, cycles, failures, queue depth) at this address
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Prove invalid ranges as failure journals instead of refusing to prove them
+    #[arg(long)]
+    report_validity: bool,
+
+    /// Message field to commit ABI-encoded in the journal (e.g. `permitted.token`); repeatable
+    #[arg(long = "extract-path")]
+    extract_paths: Vec<String>,
+
+    /// Commit salted hashes of the extracted fields instead of their values
+    #[arg(long)]
+    disclosure_salt: Option<B256>,
+
+    /// Sign as the owner of an ERC-7739 smart account whose `eip712Domain()` is in
+    /// this JSON file, instead of a plain EOA personal signature
+    #[arg(long)]
+    account_domain: Option<PathBuf>,
+
+    /// Sign an intent envelope listing the permits' digests, to be consumed by this
+    /// contract, instead of signing their concatenation. The envelope's deadline is set
+    /// when signing, so it cannot take a --signature made beforehand
+    #[arg(long, conflicts_with_all = ["account_domain", "signature", "signature_file"])]
+    intent_contract: Option<Address>,
+
+    /// Seconds until the intent envelope expires
+    #[arg(long, default_value_t = 3600)]
+    intent_ttl: u64,
+
+    /// Nonce the intent envelope signs, instead of the contract's `nonces(signer)` read
+    /// via `rpc_url`; required with --offline or without `rpc_url`
+    #[arg(long, requires = "intent_contract")]
+    intent_nonce: Option<U256>,
+
+    /// Via `rpc_url`, check whether the signer is an EIP-7702 delegated EOA and, if so,
+    /// require the delegate's ERC-1271 `isValidSignature` to accept the signature too
+    #[arg(long)]
+    check_delegation: bool,
+
+    /// Refuse to prove digests the index records as already consumed on this chain
+    #[arg(long)]
+    reject_duplicates: bool,
+
+    /// Digest to prove despite --reject-duplicates; repeatable
+    #[arg(long = "allow-duplicate")]
+    allow_duplicates: Vec<B256>,
+
+    /// Commit the latest block from `rpc_url` to the journals, so verifiers can reject
+    /// proofs made against a stale block. The anchored block is part of the cache key
+    #[arg(long)]
+    anchor_block: bool,
+
+    /// Also prove, with a Steel view call, that the signer holds at least this balance,
+    /// as `token:amount` in base units; repeatable
+    #[arg(long = "require-balance", value_parser = parse_balance)]
+    require_balances: Vec<(Address, U256)>,
+
+    /// Also prove, with a Steel view call, that each permit's Permit2 nonce is unused
+    #[arg(long)]
+    require_unused_nonces: bool,
+
+    /// Signing policy JSON file (see `single_sign_types::policy`) the guests evaluate
+    /// every permit against, committing its hash and verdict. The policy is part of the
+    /// cache key
+    #[arg(long)]
+    policy: Option<PathBuf>,
+
+    /// Continue from the output directory's batch.json, reusing the receipts of ranges
+    /// already proven instead of starting over. Needs the same signer as the first run
+    #[arg(long)]
+    resume: bool,
+
+    /// Hide the progress bars, e.g. in CI
+    #[arg(long)]
+    quiet: bool,
+
+    /// Prove locally without touching the network, ignoring `rpc_url`; submit the saved
+    /// receipts later with the `submit` binary
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "check_delegation",
+            "anchor_block",
+            "require_balances",
+            "require_unused_nonces",
+        ]
+    )]
+    offline: bool,
+
+    /// Prove these signed blobs instead of the sample permits, each as
+    /// `blob:signature-file` or `blob:signature-file:signer` with its own signer;
+    /// repeatable. Without a signer, the proofs attest whoever the signature recovers to
+    #[arg(
+        long = "file-path",
+        conflicts_with_all = [
+            "signature",
+            "signature_file",
+            "account_domain",
+            "intent_contract",
+            "check_delegation",
+            "reject_duplicates",
+            "anchor_block",
+            "require_balances",
+            "require_unused_nonces",
+            "policy",
+            "resume",
+        ]
+    )]
+    file_paths: Vec<SessionEntry>,
+
+    /// Ranges of the --file-path blobs proven at once
+    #[arg(long, default_value_t = 1, requires = "file_paths")]
+    jobs: usize,
+}
+
+fn parse_balance(balance: &str) -> Result<(Address, U256)> {
+    let (token, amount) = balance
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Expected a balance as token:amount"))?;
+    Ok((token.parse()?, amount.parse()?))
+}
+
 fn main() -> Result<()> {
+    let args = Args::parse();
+
     // Initialize tracing. In order to view logs, run `RUST_LOG=info cargo run`
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::filter::EnvFilter::from_default_env())
-        .init();
+    let _telemetry = args.log.init();
+    if let Some(addr) = args.metrics_addr {
+        install_exporter(addr)?;
+    }
+    let mut config = args.config.load()?;
+    if args.offline {
+        config.rpc_url = None;
+        config.prover = match config.prover {
+            ProverBackend::Default | ProverBackend::Local => ProverBackend::Local,
+            prover => bail!("--offline cannot prove with the {prover:?} backend"),
+        };
+    }
+    config.prover.apply();
+    // Dev mode only produces fake receipts, so wrapping them in Groth16 is wasted work
+    let dev_mode = risc0_zkvm::is_dev_mode();
+    if dev_mode {
+        tracing::warn!("RISC0_DEV_MODE is set: receipts are fake and prove nothing");
+        if config.receipt_kind == ReceiptKind::Groth16 {
+            config.receipt_kind = ReceiptKind::Composite;
+        }
+    }
+    if !args.file_paths.is_empty() {
+        return run_session(&args, &config, dev_mode);
+    }
+    // Prefer the connected node's chain ID over the configured one
+    let chain_id = match &config.rpc_url {
+        Some(rpc_url) => rpc::chain_id(rpc_url)?,
+        None => config.chain_id,
+    };
+    let chain = chains::resolve(chain_id, &config)?;
 
     // Construct a sample Input (placeholder values)
     println!("Signing message...");
-    let signer = PrivateKeySigner::random();
+    let key = args.signer.signer()?;
+    let signer = key.clone().unwrap_or_else(PrivateKeySigner::random);
     // Create 3 separate permits for the same token but different spenders
     let token = Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap(); // USDC
     let permits: Vec<PermitTransferFrom> = make_three_permit_transfers_for_token(token);
 
+    let parse_span = tracing::info_span!("parse").entered();
     // Build a JSON for each permit and compute its EIP-712 digest to validate the schema
     let permit_jsons: Vec<String> = permits
         .iter()
-        .map(|p| build_permit2_single_typed_data_json("Permit2", 1, PERMIT2_ADDRESS, p))
+        .map(|p| build_permit2_single_typed_data_json("Permit2", chain.chain_id, chain.permit2, p))
         .collect();
+    // These digests double as the manifest the batch guest checks its own against
+    let mut manifest: Vec<B256> = Vec::with_capacity(permit_jsons.len());
     for (i, j) in permit_jsons.iter().enumerate() {
         let d = verify_digest(j).expect("valid EIP-712 typed-data JSON");
         println!("Permit #{i} digest: 0x{}", hex::encode(d));
+        manifest.push(d);
     }
 
-    // Pack all JSONs into a single compact string and compute per-permit ranges
+    // Pack all JSONs into a single canonical string
     let compact_parts: Vec<String> = permit_jsons
         .iter()
-        .map(|j| j.chars().filter(|c| *c != ' ' && *c != '\n').collect())
-        .collect();
-    let mut start_offset: usize = 0;
-    let mut digest_ranges: Vec<DigestRange> = Vec::with_capacity(compact_parts.len());
-    for part in &compact_parts {
-        let end = start_offset + part.len();
-        digest_ranges.push(DigestRange { start: start_offset, end });
-        start_offset = end;
+        .map(|j| compact_typed_data(j))
+        .collect::<Result<_>>()?;
+    // `Bytes` is reference counted, so every `Input` below shares this one buffer
+    let typed_data_concat: Bytes = Bytes::from(compact_parts.concat().into_bytes());
+    let mut intent_policy = IntentPolicy::default();
+    let envelope = match (&args.account_domain, args.intent_contract) {
+        (Some(path), _) => Envelope::Erc7739PersonalSign(serde_json::from_slice(&fs::read(path)?)?),
+        (None, Some(verifying_contract)) => {
+            // Sign the contract's next nonce, and have the guest check it against the
+            // same value along with the deadline
+            let nonce = match (args.intent_nonce, &config.rpc_url) {
+                (Some(nonce), _) => nonce,
+                (None, Some(rpc_url)) => {
+                    rpc::intent_nonce(rpc_url, verifying_contract, signer.address())?
+                }
+                (None, None) => bail!("--intent-contract needs rpc_url or --intent-nonce"),
+            };
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            intent_policy = IntentPolicy {
+                current_time: now,
+                expected_nonce: nonce,
+            };
+            let objects: Vec<&str> = compact_parts.iter().map(String::as_str).collect();
+            let domain = IntentDomain {
+                chain_id: chain.chain_id,
+                verifying_contract,
+            };
+            let deadline = U256::from(now + args.intent_ttl);
+            Envelope::Intent(intent::wrap(&objects, domain, nonce, deadline)?.intent)
+        }
+        (None, None) => Envelope::Personal,
+    };
+    let prehash = envelope.signing_hash(&typed_data_concat)?;
+    // A signature passed in stands in for the account's own; without --private-key or
+    // --mnemonic, the proofs attest whoever it recovers to
+    let (signature, signature_chain_id, signer_address) = match args.signature.given()? {
+        Some(bytes) => {
+            let (signature, chain_id) = parse_signature_with_chain_id(&bytes)?;
+            let address = match &key {
+                Some(key) => key.address(),
+                None => envelope.recover(&typed_data_concat, signature)?,
+            };
+            (signature, chain_id, address)
+        }
+        None => (signer.sign_hash_sync(&prehash)?, None, signer.address()),
+    };
+    if args.check_delegation {
+        check_delegation(&config, signer_address, prehash, signature)?;
     }
-    let json_compact_all: String = compact_parts.concat();
-    let typed_data_concat: Bytes = Bytes::from(json_compact_all.clone().into_bytes());
-    let signature: Signature = signer.sign_message_sync(&typed_data_concat)?;
+
+    // Detect the per-permit ranges and, unless failures are to be proven, check
+    // every range and the signature on the host before spending any proving time
+    let (assert, inputs) = if args.report_validity {
+        let inputs = unchecked_inputs(&typed_data_concat, signature, signer_address, &envelope)?;
+        (Assertion::ReportValidity, inputs)
+    } else {
+        let inputs =
+            prepare_inputs_with(&typed_data_concat, signature, signer_address, &envelope)?;
+        (Assertion::MustBeValid, inputs)
+    };
+    let digest_ranges: Vec<DigestRange> =
+        inputs.iter().map(|input| input.digest_range.clone()).collect();
+    DigestRange::validate_set(&digest_ranges, typed_data_concat.len(), false)
+        .map_err(|code| anyhow!("Digest ranges are invalid: {code}"))?;
+    // What each permit's transfer moves, and to whom, checked against what it permits
+    let transfers = transfer_details(
+        &typed_data_concat,
+        &digest_ranges,
+        signer_address,
+        config.recipient,
+        config.requested_amount,
+    )?;
 
     println!("Digest ranges: {:?}", digest_ranges);
+    parse_span.exit();
 
-    // Prove each digest separately by providing the corresponding range
-    for (i, range) in digest_ranges.iter().enumerate() {
-        let input = Input {
-            signer: signer.address(),
-            signature,
-            typed_data_concat: typed_data_concat.clone(),
-            digest_range: range.clone(),
+    #[cfg(feature = "indexer")]
+    let indexer = config.index_db.as_deref().map(Indexer::open).transpose()?;
+    // Index the signer's ENS name alongside its address, when it has one
+    #[cfg(feature = "indexer")]
+    let signer_name = match (&indexer, &config.rpc_url) {
+        (Some(_), Some(rpc_url)) => ens::reverse(rpc_url, signer_address).unwrap_or_else(|e| {
+            tracing::debug!("Reverse lookup of the signer failed: {e}");
+            None
+        }),
+        _ => None,
+    };
+    #[cfg(feature = "indexer")]
+    if args.reject_duplicates {
+        let indexer = indexer
+            .as_ref()
+            .ok_or_else(|| anyhow!("--reject-duplicates requires index_db"))?;
+        let guard = AllowList {
+            guard: indexer,
+            allowed: args.allow_duplicates.clone(),
         };
-        println!("Input #{i}: {:?}", input);
+        for range in &digest_ranges {
+            // Ranges without a digest are proven as failure journals; nothing to replay
+            if let Ok(digest) = range.digest(&typed_data_concat, true) {
+                guard.check(digest, chain.chain_id)?;
+            }
+        }
+    }
+    #[cfg(not(feature = "indexer"))]
+    if config.index_db.is_some() || args.reject_duplicates {
+        bail!("index_db and --reject-duplicates require the `indexer` feature");
+    }
+    #[cfg(not(feature = "steel"))]
+    if !args.require_balances.is_empty() || args.require_unused_nonces {
+        bail!("--require-balance and --require-unused-nonces require the `steel` feature");
+    }
 
-        let env = ExecutorEnv::builder()
-            .write(&input)
-            .unwrap()
-            .build()
-            .unwrap();
+    let anchor = match (&config.rpc_url, args.anchor_block) {
+        (Some(rpc_url), true) => Some(rpc::latest_block(rpc_url)?),
+        (None, true) => bail!("--anchor-block requires rpc_url"),
+        (_, false) => None,
+    };
+    if let Some(anchor) = &anchor {
+        println!("Anchoring to block {} ({})", anchor.number, anchor.hash);
+    }
 
-        println!("Environment made for input #{i}");
+    let policy: Option<SigningPolicy> = match &args.policy {
+        Some(path) => Some(
+            serde_json::from_slice(&fs::read(path)?)
+                .map_err(|e| anyhow!("Invalid policy {}: {e}", path.display()))?,
+        ),
+        None => None,
+    };
+    if let Some(policy) = &policy {
+        println!("Evaluating policy {}", policy.hash());
+    }
+
+    // Keep fake receipts out of the cache so they never stand in for real ones
+    let cache = if args.no_cache || dev_mode {
+        None
+    } else {
+        Some(ReceiptCache::open_default()?)
+    };
 
-        // Obtain the default prover.
-        let prover = default_prover();
+    // Save the blob beside its receipts, so `submit --from` can send them later
+    let blob_path = config.output_dir.join("blob.json");
+    fs::create_dir_all(&config.output_dir)?;
+    fs::write(&blob_path, &typed_data_concat)
+        .map_err(|e| anyhow!("Failed writing {}: {e}", blob_path.display()))?;
 
-        println!("Proving input #{i}");
+    // Track every range in batch.json, so an interrupted run can pick up where it stopped
+    let progress_path = config.output_dir.join(BatchProgress::FILE_NAME);
+    let mut progress = if args.resume {
+        BatchProgress::resume(&progress_path, &typed_data_concat, &digest_ranges)?
+    } else {
+        BatchProgress::new(&typed_data_concat, &digest_ranges)
+    };
+    progress.save(&progress_path)?;
+    // One step per range, then the batch over the whole blob
+    let range_bytes: usize = digest_ranges.iter().map(|range| range.end - range.start).sum();
+    let mut bars = ProgressBars::new(
+        digest_ranges.len() + 1,
+        range_bytes + typed_data_concat.len(),
+        args.quiet,
+    );
 
-        // Proof information by proving the specified ELF binary.
-        // This struct contains the receipt along with statistics about execution of the guest
-        let prove_info = prover.prove(env, SINGLE_SIGN_ELF).unwrap();
+    // Prove each digest separately by providing the corresponding range
+    for (i, input) in inputs.into_iter().enumerate() {
+        set_queue_depth(digest_ranges.len() - i);
+        let input = Input {
+            assert,
+            extract_paths: args.extract_paths.clone(),
+            disclosure_salt: args.disclosure_salt,
+            intent_policy,
+            anchor,
+            policy: policy.clone(),
+            signature_chain_id,
+            transfer: Some(transfers[i]),
+            ..input
+        };
+        let range = &input.digest_range;
+        tracing::debug!(range = i, ?input, "Prepared input");
 
-        println!("Prove info #{i}: {:?}", prove_info);
+        let receipt_path = config.output_dir.join(format!("receipt_{i}.bin"));
+        let resumed = if progress.status(i).is_proven() {
+            load_receipt(&receipt_path)
+                .inspect_err(|e| tracing::warn!("Proving input #{i} again: {e}"))
+                .ok()
+        } else {
+            None
+        };
+        let cached = cache
+            .as_ref()
+            .and_then(|c| c.get(SINGLE_SIGN_ID, &input, config.receipt_kind));
+        let resuming = resumed.is_some();
+        let receipt = if let Some(receipt) = resumed {
+            bars.println(format!("Resuming with the saved receipt for input #{i}"));
+            bars.skip(range.end - range.start);
+            receipt
+        } else if let Some(receipt) = cached {
+            bars.println(format!("Reusing cached receipt for input #{i}"));
+            bars.skip(range.end - range.start);
+            receipt
+        } else {
+            bars.start(&format!("input #{i}"), range.end - range.start);
 
-        // extract the receipt.
-        let receipt = prove_info.receipt;
+            // Prove the specified ELF binary on the configured backend; local and Bonsai
+            // proving also report statistics about the guest's execution
+            let _span = tracing::info_span!("prove", range = i).entered();
+            let started = Instant::now();
+            let proof = match prove_framed(&config, SINGLE_SIGN_ELF, SINGLE_SIGN_ID, &input) {
+                Ok(proof) => proof,
+                Err(e) => {
+                    record_proof_failure();
+                    bars.abandon();
+                    let reason = e.to_string();
+                    progress.set(&progress_path, i, RangeStatus::Failed { reason })?;
+                    return Err(e);
+                }
+            };
+            if let Some(total_cycles) = proof.total_cycles {
+                record_proof(started.elapsed(), total_cycles);
+            }
+            bars.finish(proof.total_cycles, proof.segments);
 
-        println!("Receipt #{i}: {:?}", receipt);
+            if let Some(cache) = &cache {
+                cache.put(SINGLE_SIGN_ID, &input, config.receipt_kind, &proof.receipt)?;
+            }
+
+            proof.receipt
+        };
+        if !resuming {
+            save_receipt(&receipt_path, &receipt)?;
+            bars.println(format!("Receipt #{i} saved to {}", receipt_path.display()));
+            progress.set(&progress_path, i, RangeStatus::Proven)?;
+        }
+
+        bars.println(format!("Receipt #{i}: {:?}", receipt));
 
         // Decode public output committed by the guest
-        let output: Output = receipt.journal.decode().unwrap();
-        println!(
-            "Guest output #{i} -> signer: {:#x}, digest: 0x{}",
-            output.signer,
-            hex::encode(output.digest),
-        );
+        let output: Output = decode_output(&receipt.journal)?;
+        bars.println(format!("Guest output #{i} -> {}", describe_output(&output)));
+        if cow::is_order(&typed_data_concat, range) {
+            match cow::order_uid(&output) {
+                Ok(uid) => bars.println(format!("CoW order UID #{i}: {uid}")),
+                Err(e) => bars.println(format!("CoW order #{i} has no provable UID: {e}")),
+            }
+        }
+        #[cfg(feature = "indexer")]
+        if let Some(indexer) = &indexer {
+            let image_id = image_id_bytes(SINGLE_SIGN_ID);
+            indexer.record(&Record {
+                signer_name: signer_name.clone(),
+                ..Record::new(
+                    &output,
+                    &typed_data_concat,
+                    range,
+                    chain.chain_id,
+                    image_id,
+                )
+            })?;
+        }
 
-        println!("Output #{i}: {:?}", output);
+        bars.println(format!("Output #{i}: {:?}", output));
 
         // The receipt was verified at the end of proving, but the below code is an
-        // example of how someone else could verify this receipt.
-        receipt.verify(SINGLE_SIGN_ID).unwrap();
+        // example of how someone else could verify this receipt, and that it attests
+        // this range of the blob.
+        let verified = tracing::info_span!("verify", range = i).in_scope(|| {
+            check_receipt_against_blob(
+                &receipt,
+                &typed_data_concat,
+                range,
+                input.signer,
+                SINGLE_SIGN_ID,
+            )
+        });
+        if let Err(e) = &verified {
+            let reason = e.to_string();
+            progress.set(&progress_path, i, RangeStatus::Failed { reason })?;
+        }
+        verified?;
+
+        bars.println(format!("Receipt #{i} verified"));
+        // Submission is recorded by the submit binary; keep it when resuming
+        if *progress.status(i) != RangeStatus::Submitted {
+            progress.set(&progress_path, i, RangeStatus::Verified)?;
+        }
+
+        // Prove the on-chain preconditions on top of the signature receipt
+        #[cfg(feature = "steel")]
+        if !args.require_balances.is_empty() || args.require_unused_nonces {
+            let owner = signer_address;
+            let mut preconditions: Vec<Precondition> = args
+                .require_balances
+                .iter()
+                .map(|&(token, amount)| Precondition::MinBalance {
+                    token,
+                    owner,
+                    amount,
+                })
+                .collect();
+            if args.require_unused_nonces {
+                preconditions.push(Precondition::Permit2NonceUnused {
+                    permit2: chain.permit2,
+                    owner,
+                    nonce: permits[i].nonce,
+                });
+            }
+            let _span = tracing::info_span!("preconditions", range = i).entered();
+            let receipt = steel::prove_preconditions(
+                &config,
+                chain.chain_id,
+                &receipt,
+                SINGLE_SIGN_ID.into(),
+                preconditions,
+            )?;
+            let receipt_path = config.output_dir.join(format!("receipt_{i}_preconditions.bin"));
+            save_receipt(&receipt_path, &receipt)?;
+            bars.println(format!(
+                "Preconditions of #{i} proven, receipt saved to {}",
+                receipt_path.display()
+            ));
+        }
+    }
+    set_queue_depth(0);
+
+    // Prove all ranges in one session, recovering the signer only once
+    let batch_input = BatchInput {
+        signer: signer_address,
+        signature,
+        typed_data_concat: typed_data_concat.clone(),
+        ranges: digest_ranges.clone(),
+        require_canonical: true,
+        assert,
+        extract_paths: args.extract_paths.clone(),
+        disclosure_salt: args.disclosure_salt,
+        envelope,
+        key_signature: None,
+        segments: Vec::new(),
+        expected_digests: manifest,
+        digest_mode: DigestMode::Eip712,
+        intent_policy,
+        allow_high_s: false,
+        signature_chain_id,
+        anchor,
+        policy,
+        transfers,
+    };
+    let cached = cache
+        .as_ref()
+        .and_then(|c| c.get(SINGLE_SIGN_BATCH_ID, &batch_input, config.receipt_kind));
+    let receipt = if let Some(receipt) = cached {
+        bars.println("Reusing cached batch receipt");
+        bars.skip(typed_data_concat.len());
+        receipt
+    } else {
+        let label = format!("batch of {} ranges", batch_input.ranges.len());
+        bars.start(&label, typed_data_concat.len());
+        let _span = tracing::info_span!("prove", batch = batch_input.ranges.len()).entered();
+        let started = Instant::now();
+        let proof = prove_framed(&config, SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID, &batch_input)
+            .inspect_err(|_| {
+                record_proof_failure();
+                bars.abandon();
+            })?;
+        if let Some(total_cycles) = proof.total_cycles {
+            record_proof(started.elapsed(), total_cycles);
+        }
+        bars.finish(proof.total_cycles, proof.segments);
+        let receipt = proof.receipt;
+        if let Some(cache) = &cache {
+            cache.put(SINGLE_SIGN_BATCH_ID, &batch_input, config.receipt_kind, &receipt)?;
+        }
+        receipt
+    };
+    bars.finish_all();
 
-        println!("Receipt #{i} verified");
+    let outputs: Vec<Output> = decode_batch_outputs(&receipt.journal)?;
+    if let Some(manifest) = outputs.first().and_then(|output| output.manifest) {
+        match manifest.first_mismatch {
+            None => bars.println("Batch digests match the manifest"),
+            Some(index) => bars.println(format!("Batch digest #{index} differs from the manifest")),
+        }
+    }
+    for (i, output) in outputs.iter().enumerate() {
+        bars.println(format!("Batch output #{i} -> {}", describe_output(output)));
+        #[cfg(feature = "indexer")]
+        if let Some(indexer) = &indexer {
+            let image_id = image_id_bytes(SINGLE_SIGN_BATCH_ID);
+            indexer.record(&Record {
+                signer_name: signer_name.clone(),
+                ..Record::new(
+                    output,
+                    &typed_data_concat,
+                    &digest_ranges[i],
+                    chain.chain_id,
+                    image_id,
+                )
+            })?;
+        }
     }
+    tracing::info_span!("verify", batch = outputs.len()).in_scope(|| {
+        check_batch_receipt_against_blob(
+            &receipt,
+            &typed_data_concat,
+            &digest_ranges,
+            Some(signer_address),
+            SINGLE_SIGN_BATCH_ID,
+        )
+    })?;
+    bars.println("Batch receipt verified");
+
+    let receipt_path = config.output_dir.join("receipt_batch.bin");
+    save_receipt(&receipt_path, &receipt)?;
+    bars.println(format!("Batch receipt saved to {}", receipt_path.display()));
 
     Ok(())
 }
+
+/// Prove every `--file-path` blob, then save and print the session's report, failing
+/// if any blob failed.
+fn run_session(args: &Args, config: &Config, dev_mode: bool) -> Result<()> {
+    if config.recipient.is_some() || config.requested_amount.is_some() {
+        bail!("recipient and requested_amount apply to the sample permits, not --file-path");
+    }
+    let cache = if args.no_cache || dev_mode {
+        None
+    } else {
+        Some(ReceiptCache::open_default()?)
+    };
+    let options = SessionOptions {
+        assert: if args.report_validity {
+            Assertion::ReportValidity
+        } else {
+            Assertion::MustBeValid
+        },
+        extract_paths: args.extract_paths.clone(),
+        disclosure_salt: args.disclosure_salt,
+        jobs: args.jobs,
+    };
+    let report = prove_session(config, cache.as_ref(), &args.file_paths, &options);
+    fs::create_dir_all(&config.output_dir)?;
+    let report_path = config.output_dir.join(SessionReport::FILE_NAME);
+    report.save(&report_path)?;
+    println!("{report}");
+    println!("Session report saved to {}", report_path.display());
+    if report.failed() > 0 {
+        bail!("{} of {} blobs failed", report.failed(), report.blobs.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+//! The `.singlesign` manifest: a signed blob's hash, ranges, digests, signer and
+//! signature in one file beside the blob, written by `sign_file --manifest` and read
+//! by `queue`, `verify_receipt` and `submit` in place of a blob, signer and signature
+//! passed separately. Loading checks everything it claims, so a manifest from another
+//! machine can be trusted as far as the signature goes.
+
+use crate::prepare::{chain_head, find_json_ranges, list_digests, merkle_root};
+use alloy_primitives::{keccak256, Address, Bytes, B256};
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use single_sign_types::{
+    serde_hex,
+    signing::{parse_signature_with_chain_id, verify_signature, MessageMode},
+    DigestRange,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Layout version of the manifest; readers refuse versions they do not know.
+pub const MANIFEST_SCHEMA: u32 = 1;
+
+/// File extension of manifests.
+pub const EXTENSION: &str = "singlesign";
+
+/// What the wallet signed, in EIP-191 personal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningMode {
+    /// The blob itself
+    Personal,
+    /// `prepare::merkle_root` of the blob
+    Merkle,
+    /// `prepare::chain_head` of the blob, appended to `prev_head`
+    Chain,
+}
+
+impl SigningMode {
+    /// The bytes a wallet signs for `blob` in this mode; `prev_head` only applies to
+    /// `Chain`.
+    pub fn message(self, blob: &[u8], prev_head: B256) -> Result<Bytes> {
+        Ok(match self {
+            SigningMode::Personal => Bytes::copy_from_slice(blob),
+            SigningMode::Merkle => Bytes::copy_from_slice(merkle_root(blob)?.as_slice()),
+            SigningMode::Chain => Bytes::copy_from_slice(chain_head(prev_head, blob)?.as_slice()),
+        })
+    }
+}
+
+/// A signed blob, described by value. Fields serialize in declaration order and in
+/// the fixed formats of `single_sign_types::serde_hex`, so the same blob and signature
+/// always give the same file but for `created_at`, whatever the alloy release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SignedManifest {
+    pub schema: u32,
+    /// The blob's file, relative to the manifest
+    pub blob: PathBuf,
+    #[serde(with = "serde_hex::b256")]
+    pub blob_hash: B256,
+    pub mode: SigningMode,
+    /// Chain head the signature extended; zero for a new chain and other modes
+    #[serde(with = "serde_hex::b256")]
+    pub prev_head: B256,
+    pub ranges: Vec<DigestRange>,
+    /// EIP-712 digest of each range, in order
+    #[serde(with = "serde_hex::b256_vec")]
+    pub digests: Vec<B256>,
+    #[serde(with = "serde_hex::address")]
+    pub signer: Address,
+    #[serde(with = "serde_hex::bytes")]
+    pub signature: Bytes,
+    /// Unix time the manifest was written
+    pub created_at: u64,
+}
+
+impl SignedManifest {
+    /// Describe `blob`, stored as `blob_file` relative to the manifest, and check that
+    /// `signature` by `signer` covers it in `mode`.
+    pub fn new(
+        blob: &[u8],
+        blob_file: PathBuf,
+        mode: SigningMode,
+        prev_head: B256,
+        signer: Address,
+        signature: Bytes,
+    ) -> Result<Self> {
+        let digests = list_digests(blob)?;
+        let manifest = Self {
+            schema: MANIFEST_SCHEMA,
+            blob: blob_file,
+            blob_hash: keccak256(blob),
+            mode,
+            prev_head,
+            ranges: digests
+                .iter()
+                .map(|object| DigestRange {
+                    start: object.start,
+                    end: object.end,
+                })
+                .collect(),
+            digests: digests.iter().map(|object| object.digest).collect(),
+            signer,
+            signature,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+        manifest.check_signature(blob)?;
+        Ok(manifest)
+    }
+
+    /// Read the manifest at `path` and the blob it names, checking the blob's hash,
+    /// ranges and digests and the signature over it.
+    pub fn load(path: &Path) -> Result<(Self, Bytes)> {
+        let json =
+            fs::read(path).map_err(|e| anyhow!("Failed reading {}: {e}", path.display()))?;
+        let manifest: Self = serde_json::from_slice(&json)
+            .map_err(|e| anyhow!("Invalid manifest {}: {e}", path.display()))?;
+        if manifest.schema != MANIFEST_SCHEMA {
+            bail!("{} has unsupported schema {}", path.display(), manifest.schema);
+        }
+        let blob_path = path.parent().unwrap_or(Path::new("")).join(&manifest.blob);
+        let blob = fs::read(&blob_path)
+            .map_err(|e| anyhow!("Failed reading {}: {e}", blob_path.display()))?;
+        if keccak256(&blob) != manifest.blob_hash {
+            bail!("{} does not hash to the manifest's blobHash", blob_path.display());
+        }
+        if find_json_ranges(&blob)? != manifest.ranges {
+            bail!("{} lists other ranges than its blob", path.display());
+        }
+        let digests: Vec<B256> = list_digests(&blob)?
+            .into_iter()
+            .map(|object| object.digest)
+            .collect();
+        if digests != manifest.digests {
+            bail!("{} lists other digests than its blob", path.display());
+        }
+        manifest
+            .check_signature(&blob)
+            .map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        Ok((manifest, blob.into()))
+    }
+
+    /// Write the manifest as pretty JSON with a trailing newline.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        fs::write(path, json).map_err(|e| anyhow!("Failed writing {}: {e}", path.display()))
+    }
+
+    fn check_signature(&self, blob: &[u8]) -> Result<()> {
+        let message = self.mode.message(blob, self.prev_head)?;
+        let (signature, _) = parse_signature_with_chain_id(&self.signature)?;
+        if !verify_signature(&message, signature, self.signer, MessageMode::Personal, true)? {
+            bail!("The signature does not recover to {:#x}", self.signer);
+        }
+        Ok(())
+    }
+}
+
+/// Path of the manifest for the blob at `blob_path`: the same name, extension
+/// `.singlesign`.
+pub fn path_for(blob_path: &Path) -> PathBuf {
+    blob_path.with_extension(EXTENSION)
+}
@@ -0,0 +1,25 @@
+use anyhow::{anyhow, Result};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use std::{net::SocketAddr, time::Duration};
+
+/// Serve Prometheus metrics at `addr`. Until this is called the recorders below are no-ops.
+pub fn install_exporter(addr: SocketAddr) -> Result<()> {
+    PrometheusBuilder::new()
+        .with_http_listener(addr)
+        .install()
+        .map_err(|e| anyhow!("Failed starting metrics exporter on {addr}: {e}"))
+}
+
+pub fn record_proof(duration: Duration, total_cycles: u64) {
+    metrics::histogram!("single_sign_proof_duration_seconds").record(duration.as_secs_f64());
+    metrics::histogram!("single_sign_proof_cycles").record(total_cycles as f64);
+}
+
+pub fn record_proof_failure() {
+    metrics::counter!("single_sign_proof_failures_total").increment(1);
+}
+
+/// Number of ranges still waiting to be proven in the current run.
+pub fn set_queue_depth(pending: usize) {
+    metrics::gauge!("single_sign_proof_queue_depth").set(pending as f64);
+}
@@ -0,0 +1,491 @@
+use crate::diagnostics::{
+    ParseDiagnostic,
+    ParseErrorCode::{NoObjects, TrailingGarbage, UnexpectedByte, UnterminatedObject},
+};
+use alloy_primitives::{Address, Bytes, Signature, B256, U256};
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use single_sign_types::{
+    chain,
+    intent::IntentPolicy,
+    merkle,
+    signing::Envelope,
+    transfer::{permitted_amount, TransferDetails},
+    Assertion, BatchInput, DigestMode, DigestRange, ErrorCode, Input,
+};
+
+/// UTF-8 byte order mark, which editors on Windows prepend to saved files.
+pub const BOM: [u8; 3] = [0xef, 0xbb, 0xbf];
+
+/// Find the top-level JSON objects concatenated in `blob`, skipping whitespace
+/// (including Windows `\r\n` newlines) and UTF-8 byte order marks between them, so
+/// exported or concatenated files keep their exact signed bytes. Braces inside
+/// strings (including escaped quotes) are ignored. Anything else outside an object is
+/// an error, in particular trailing garbage after the last one; errors are
+/// `ParseDiagnostic`s locating the offending byte by line and column.
+pub fn find_json_ranges(blob: &[u8]) -> Result<Vec<DigestRange>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut skip = 0;
+    for (offset, &byte) in blob.iter().enumerate() {
+        if skip > 0 {
+            skip -= 1;
+            continue;
+        }
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'{' => {
+                if depth == 0 {
+                    start = offset;
+                }
+                depth += 1;
+            }
+            b'}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    ranges.push(DigestRange { start, end: offset + 1 });
+                }
+            }
+            b'"' if depth > 0 => in_string = true,
+            _ if depth == 0 && byte.is_ascii_whitespace() => {}
+            _ if depth == 0 && blob[offset..].starts_with(&BOM) => skip = BOM.len() - 1,
+            _ if depth == 0 && !blob[offset..].contains(&b'{') => {
+                let detail = format!("{} bytes starting with {byte:#04x}", blob.len() - offset);
+                let diagnostic = ParseDiagnostic::new(blob, offset, TrailingGarbage);
+                return Err(diagnostic.with_detail(detail).into());
+            }
+            _ if depth == 0 => {
+                let diagnostic = ParseDiagnostic::new(blob, offset, UnexpectedByte);
+                return Err(diagnostic.with_detail(format!("{byte:#04x}")).into());
+            }
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        let diagnostic = ParseDiagnostic::new(blob, start, UnterminatedObject);
+        return Err(diagnostic.with_detail("the object starting here never closes").into());
+    }
+    if ranges.is_empty() {
+        return Err(ParseDiagnostic::new(blob, blob.len(), NoObjects).into());
+    }
+    Ok(ranges)
+}
+
+/// Build one `Input` per typed-data object in `blob`, checking everything the guest
+/// would on the host first: ranges, canonical form, EIP-712 digests, and that the
+/// EIP-191 personal `signature` recovers to `signer`. Fails fast with the offending
+/// range instead of a guest panic deep inside the zkVM.
+pub fn prepare_inputs(blob: &Bytes, signature: Signature, signer: Address) -> Result<Vec<Input>> {
+    prepare_inputs_with(blob, signature, signer, &Envelope::Personal)
+}
+
+/// `prepare_inputs` for signatures made in `envelope`, e.g. by ERC-7739 accounts or
+/// over an intent envelope, which must also list every object's digest.
+pub fn prepare_inputs_with(
+    blob: &Bytes,
+    signature: Signature,
+    signer: Address,
+    envelope: &Envelope,
+) -> Result<Vec<Input>> {
+    let inputs = unchecked_inputs(blob, signature, signer, envelope)?;
+    for (i, input) in inputs.iter().enumerate() {
+        let range = &input.digest_range;
+        let digest = input
+            .digest_mode
+            .digest(range, blob, input.require_canonical)
+            .map_err(|code| anyhow!("Range #{i} ({range}): {code}"))?;
+        cross_check(&input.digest_mode, range, blob, input.require_canonical, digest)
+            .map_err(|e| anyhow!("Range #{i} ({range}): {e}"))?;
+        if !envelope.authorizes(digest) {
+            bail!("Range #{i} ({range}): {}", ErrorCode::UnauthorizedAction);
+        }
+    }
+    envelope
+        .verify(blob, signature, signer)
+        .map_err(|e| anyhow!("Signature over the blob is invalid: {e}"))?;
+    Ok(inputs)
+}
+
+/// Merkle root of the EIP-712 digests of the objects in `blob`, in order: the 32 bytes
+/// a wallet signs, in EIP-191 personal mode, for `merkle_inputs`.
+pub fn merkle_root(blob: &[u8]) -> Result<B256> {
+    Ok(merkle::root(&object_digests(blob)?))
+}
+
+/// Build one self-contained `Input` per typed-data object in `blob` for a wallet that
+/// signed only `merkle_root(blob)`: each carries just its object and the branch from
+/// its digest to the root, so the proofs are small and independent. Checks the
+/// digests and the signature over the root on the host first.
+pub fn merkle_inputs(blob: &Bytes, signature: Signature, signer: Address) -> Result<Vec<Input>> {
+    let digests = object_digests(blob)?;
+    object_inputs(blob, signature, signer, |i| {
+        merkle::branch(&digests, i).map(Envelope::Merkle)
+    })
+}
+
+/// Head of the hash chain after appending the objects in `blob` to `prev`, the head
+/// an earlier signature covered (zero for a new chain): the 32 bytes a wallet signs,
+/// in EIP-191 personal mode, for `chain_inputs`.
+pub fn chain_head(prev: B256, blob: &[u8]) -> Result<B256> {
+    Ok(chain::extend(prev, &object_digests(blob)?))
+}
+
+/// `merkle_inputs` for a wallet that signed the head of the hash chain over every
+/// object in `blob`, old and appended ones alike: each input links its object's
+/// digest to the head.
+pub fn chain_inputs(blob: &Bytes, signature: Signature, signer: Address) -> Result<Vec<Input>> {
+    let digests = object_digests(blob)?;
+    object_inputs(blob, signature, signer, |i| {
+        chain::link(&digests, i).map(Envelope::HashChain)
+    })
+}
+
+// One input per object holding only that object, checking the signature against the
+// first object's envelope, whose signed hash all objects share
+fn object_inputs(
+    blob: &Bytes,
+    signature: Signature,
+    signer: Address,
+    envelope: impl Fn(usize) -> Option<Envelope>,
+) -> Result<Vec<Input>> {
+    let inputs = find_json_ranges(blob)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, range)| {
+            let object = Bytes::from(blob.0.slice(range.start..range.end));
+            let envelope = envelope(i)
+                .ok_or_else(|| anyhow!("Range #{i} ({range}) is not covered by the envelope"))?;
+            Ok(Input {
+                signer: Some(signer),
+                signature,
+                digest_range: DigestRange {
+                    start: 0,
+                    end: object.len(),
+                },
+                typed_data_concat: object,
+                require_canonical: true,
+                assert: Assertion::MustBeValid,
+                extract_paths: Vec::new(),
+                disclosure_salt: None,
+                envelope,
+                key_signature: None,
+                digest_mode: DigestMode::Eip712,
+                intent_policy: IntentPolicy::default(),
+                allow_high_s: false,
+                signature_chain_id: None,
+                anchor: None,
+                policy: None,
+                message_modes: Vec::new(),
+                transfer: None,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(first) = inputs.first() {
+        first
+            .envelope
+            .verify(&first.typed_data_concat, signature, signer)
+            .map_err(|e| anyhow!("Signature over the envelope is invalid: {e}"))?;
+    }
+    Ok(inputs)
+}
+
+fn object_digests(blob: &[u8]) -> Result<Vec<B256>> {
+    Ok(list_digests(blob)?
+        .into_iter()
+        .map(|object| object.digest)
+        .collect())
+}
+
+/// A typed-data object of a blob with its EIP-712 digest, as `digests --format json`
+/// lists it for signing UIs and contracts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ObjectDigest {
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    pub primary_type: String,
+    pub digest: B256,
+    pub domain_separator: B256,
+}
+
+/// Every typed-data object in `blob` with the digest the guests compute for it, which
+/// requires canonical JSON as proving does.
+pub fn list_digests(blob: &[u8]) -> Result<Vec<ObjectDigest>> {
+    find_json_ranges(blob)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, range)| {
+            let error = |code: ErrorCode| anyhow!("Range #{index} ({range}): {code}");
+            let typed_data = range.typed_data(blob, true).map_err(error)?;
+            let digest = typed_data
+                .eip712_signing_hash()
+                .map_err(|_| error(ErrorCode::InvalidTypedData))?;
+            cross_check(&DigestMode::Eip712, &range, blob, true, digest)
+                .map_err(|e| anyhow!("Range #{index} ({range}): {e}"))?;
+            Ok(ObjectDigest {
+                index,
+                start: range.start,
+                end: range.end,
+                primary_type: typed_data.primary_type.clone(),
+                digest,
+                domain_separator: typed_data.domain.separator(),
+            })
+        })
+        .collect()
+}
+
+/// Inputs for every object in `blob` without checking them, for proving failure
+/// journals with `Assertion::ReportValidity`.
+pub fn unchecked_inputs(
+    blob: &Bytes,
+    signature: Signature,
+    signer: Address,
+    envelope: &Envelope,
+) -> Result<Vec<Input>> {
+    Ok(find_json_ranges(blob)?
+        .into_iter()
+        .map(|digest_range| Input {
+            signer: Some(signer),
+            signature,
+            typed_data_concat: blob.clone(),
+            digest_range,
+            require_canonical: true,
+            assert: Assertion::MustBeValid,
+            extract_paths: Vec::new(),
+            disclosure_salt: None,
+            envelope: envelope.clone(),
+            key_signature: None,
+            digest_mode: DigestMode::Eip712,
+            intent_policy: IntentPolicy::default(),
+            allow_high_s: false,
+            signature_chain_id: None,
+            anchor: None,
+            policy: None,
+            message_modes: Vec::new(),
+            transfer: None,
+        })
+        .collect())
+}
+
+/// Check a single-signer batch on the host before proving: the ranges are ordered and
+/// disjoint, every range has a digest in the batch's `digest_mode`, and the signature
+/// covers the whole blob.
+pub fn check_batch(batch: &BatchInput) -> Result<()> {
+    let blob = &batch.typed_data_concat;
+    DigestRange::validate_set(&batch.ranges, blob.len(), false)
+        .map_err(|code| anyhow!("Digest ranges are invalid: {code}"))?;
+    for (i, range) in batch.ranges.iter().enumerate() {
+        let digest = batch
+            .digest_mode
+            .digest(range, blob, batch.require_canonical)
+            .map_err(|code| anyhow!("Range #{i} ({range}): {code}"))?;
+        cross_check(&batch.digest_mode, range, blob, batch.require_canonical, digest)
+            .map_err(|e| anyhow!("Range #{i} ({range}): {e}"))?;
+        if !batch.envelope.authorizes(digest) {
+            bail!("Range #{i} ({range}): {}", ErrorCode::UnauthorizedAction);
+        }
+    }
+    check_transfers(blob, &batch.ranges, &batch.transfers)?;
+    batch
+        .envelope
+        .verify(blob, batch.signature, batch.signer)
+        .map_err(|e| anyhow!("Signature over the blob is invalid: {e}"))?;
+    Ok(())
+}
+
+/// Check a co-signed batch on the host before proving: segment spans are disjoint,
+/// each segment's ranges are sorted and disjoint, every range has a digest, and each
+/// co-signer's signature covers their span.
+pub fn check_segments(batch: &BatchInput) -> Result<()> {
+    batch
+        .check_segments()
+        .map_err(|code| anyhow!("Segments are invalid: {code}"))?;
+    for (i, segment) in batch.segments.iter().enumerate() {
+        DigestRange::validate_set(&segment.ranges, batch.typed_data_concat.len(), false)
+            .map_err(|code| anyhow!("Segment #{i} ranges are invalid: {code}"))?;
+        for range in &segment.ranges {
+            let blob = &batch.typed_data_concat;
+            let digest = batch
+                .digest_mode
+                .digest(range, blob, batch.require_canonical)
+                .map_err(|code| anyhow!("Segment #{i} range {range}: {code}"))?;
+            cross_check(&batch.digest_mode, range, blob, batch.require_canonical, digest)
+                .map_err(|e| anyhow!("Segment #{i} range {range}: {e}"))?;
+        }
+        segment.verify(&batch.typed_data_concat).map_err(|code| {
+            anyhow!("Segment #{i} signature by {:#x}: {code}", segment.signer)
+        })?;
+    }
+    // Journal order runs through the segments in turn
+    let ranges: Vec<DigestRange> =
+        batch.segments.iter().flat_map(|segment| segment.ranges.clone()).collect();
+    check_transfers(&batch.typed_data_concat, &ranges, &batch.transfers)
+}
+
+/// The Permit2 transfer of each of `ranges`: to `recipient`, or back to `owner` when
+/// unset, of `requested_amount`, or of the whole permitted amount when unset. Every
+/// range must be a `PermitTransferFrom` permitting at least the amount requested.
+pub fn transfer_details(
+    blob: &[u8],
+    ranges: &[DigestRange],
+    owner: Address,
+    recipient: Option<Address>,
+    requested_amount: Option<U256>,
+) -> Result<Vec<TransferDetails>> {
+    let transfers = ranges
+        .iter()
+        .enumerate()
+        .map(|(i, range)| {
+            let permitted =
+                permitted(blob, range).map_err(|e| anyhow!("Range #{i} ({range}): {e}"))?;
+            Ok(TransferDetails {
+                to: recipient.unwrap_or(owner),
+                requested_amount: requested_amount.unwrap_or(permitted),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    check_transfers(blob, ranges, &transfers)?;
+    Ok(transfers)
+}
+
+// Transfers, if any, go one per range, and none requests more than its permit allows
+fn check_transfers(
+    blob: &[u8],
+    ranges: &[DigestRange],
+    transfers: &[TransferDetails],
+) -> Result<()> {
+    if transfers.is_empty() {
+        return Ok(());
+    }
+    if transfers.len() != ranges.len() {
+        bail!("{} transfers for {} ranges", transfers.len(), ranges.len());
+    }
+    for (i, (range, transfer)) in ranges.iter().zip(transfers).enumerate() {
+        let permitted =
+            permitted(blob, range).map_err(|e| anyhow!("Range #{i} ({range}): {e}"))?;
+        if transfer.requested_amount > permitted {
+            bail!(
+                "Range #{i} ({range}) requests {} but permits only {permitted}",
+                transfer.requested_amount
+            );
+        }
+    }
+    Ok(())
+}
+
+fn permitted(blob: &[u8], range: &DigestRange) -> Result<U256> {
+    let typed_data = range
+        .typed_data(blob, false)
+        .map_err(|code| anyhow!("{code}"))?;
+    permitted_amount(&typed_data)
+        .ok_or_else(|| anyhow!("not a PermitTransferFrom with a permitted amount"))
+}
+
+// Built with `fast-eip712`, the guests hash typed data with `single_sign_types::eip712`
+// rather than alloy: refuse to prove a range the two hash differently, whose proof
+// would fail in the guest or commit a digest the wallet never saw
+fn cross_check(
+    mode: &DigestMode,
+    range: &DigestRange,
+    blob: &[u8],
+    require_canonical: bool,
+    digest: B256,
+) -> Result<()> {
+    if !cfg!(feature = "fast-eip712") || !matches!(mode, DigestMode::Eip712) {
+        return Ok(());
+    }
+    let (guest_digest, _) = range
+        .eip712_hashes(blob, require_canonical)
+        .map_err(|code| anyhow!("{code} in the guests' EIP-712 hasher"))?;
+    if guest_digest != digest {
+        bail!("the guests' EIP-712 hasher computes {guest_digest}, alloy {digest}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn permit(amount: u64) -> String {
+        json!({
+            "types": {
+                "EIP712Domain": [{ "name": "chainId", "type": "uint256" }],
+                "TokenPermissions": [
+                    { "name": "token", "type": "address" },
+                    { "name": "amount", "type": "uint256" },
+                ],
+                "PermitTransferFrom": [
+                    { "name": "permitted", "type": "TokenPermissions" },
+                    { "name": "spender", "type": "address" },
+                    { "name": "nonce", "type": "uint256" },
+                    { "name": "deadline", "type": "uint256" },
+                ],
+            },
+            "primaryType": "PermitTransferFrom",
+            "domain": { "chainId": 1 },
+            "message": {
+                "permitted": {
+                    "token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                    "amount": amount.to_string(),
+                },
+                "spender": "0x1111111111111111111111111111111111111111",
+                "nonce": "0",
+                "deadline": "1737072000",
+            },
+        })
+        .to_string()
+    }
+
+    fn transfers_of(
+        blob: &str,
+        recipient: Option<Address>,
+        requested_amount: Option<u64>,
+    ) -> Result<Vec<TransferDetails>> {
+        let ranges = find_json_ranges(blob.as_bytes())?;
+        let requested_amount = requested_amount.map(U256::from);
+        let owner = Address::repeat_byte(0x0a);
+        transfer_details(blob.as_bytes(), &ranges, owner, recipient, requested_amount)
+    }
+
+    #[test]
+    fn transfers_default_to_the_permitted_amount_for_the_owner() {
+        let blob = permit(100) + &permit(300);
+        let transfers = transfers_of(&blob, None, None).unwrap();
+        let amounts: Vec<U256> = transfers.iter().map(|t| t.requested_amount).collect();
+        assert_eq!(amounts, [U256::from(100), U256::from(300)]);
+        assert!(transfers.iter().all(|t| t.to == Address::repeat_byte(0x0a)));
+
+        let recipient = Address::repeat_byte(0x44);
+        let transfers = transfers_of(&blob, Some(recipient), Some(100)).unwrap();
+        assert!(transfers.iter().all(|t| t.to == recipient));
+        assert!(transfers.iter().all(|t| t.requested_amount == U256::from(100)));
+    }
+
+    #[test]
+    fn rejects_requests_above_the_permitted_amount() {
+        let blob = permit(300) + &permit(100);
+        let error = transfers_of(&blob, None, Some(200)).unwrap_err().to_string();
+        let second = find_json_ranges(blob.as_bytes()).unwrap()[1].clone();
+        assert_eq!(error, format!("Range #1 ({second}) requests 200 but permits only 100"));
+    }
+
+    #[test]
+    fn rejects_transfers_of_other_messages() {
+        let blob = permit(100).replace("PermitTransferFrom", "PermitSingle");
+        let error = transfers_of(&blob, None, None).unwrap_err().to_string();
+        assert!(error.ends_with("not a PermitTransferFrom with a permitted amount"), "{error}");
+    }
+}
@@ -0,0 +1,399 @@
+//! Human-readable previews of the typed data in a blob, like a wallet's signing
+//! prompt, so signers can review what they authorize before `sign_file` runs. Token
+//! amounts are shown in their token's decimals and symbol, see `host::tokens`,
+//! deadlines as UTC dates, and addresses with the protocol or ENS name behind them.
+//! Previews render as plain text, Markdown or a standalone HTML page.
+
+use crate::{
+    domains, ens,
+    lint::{parse_uint, DEADLINE_FIELDS, UINT160_MAX},
+    tokens::{is_amount_field, is_token_field, token_for, Token, Tokens},
+};
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::Eip712Domain;
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use single_sign_types::DigestRange;
+use std::collections::HashMap;
+
+/// How `render` lays previews out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Text,
+    Markdown,
+    Html,
+}
+
+/// One labelled line of a preview, with the lines nested under it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub label: String,
+    pub value: Option<String>,
+    pub children: Vec<Entry>,
+}
+
+impl Entry {
+    pub fn new(label: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            value: Some(value.into()),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// The preview of one typed-data object of a blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preview {
+    pub index: usize,
+    pub range: DigestRange,
+    /// Primary type and protocol, or why the object could not be read
+    pub heading: String,
+    pub entries: Vec<Entry>,
+}
+
+/// Builds previews, looking each token and address up once per blob.
+pub struct Previewer {
+    rpc_url: Option<String>,
+    tokens: Tokens,
+    now: u64,
+    names: HashMap<Address, String>,
+}
+
+// The object whose message is being described
+struct Context<'a> {
+    domain: &'a Eip712Domain,
+    primary_type: &'a str,
+}
+
+impl Previewer {
+    /// Previews dated relative to `now`, in Unix seconds, with addresses named via
+    /// `rpc_url` and amounts in the metadata `tokens` finds.
+    pub fn new(rpc_url: Option<&str>, tokens: Tokens, now: u64) -> Self {
+        Self {
+            rpc_url: rpc_url.map(str::to_owned),
+            tokens,
+            now,
+            names: HashMap::new(),
+        }
+    }
+
+    /// Preview the typed data `ranges` cover in `blob`; ranges that do not parse get a
+    /// heading saying why and no entries.
+    pub fn preview(&mut self, blob: &[u8], ranges: &[DigestRange]) -> Vec<Preview> {
+        ranges
+            .iter()
+            .enumerate()
+            .map(|(index, range)| {
+                let typed_data = match range.typed_data(blob, false) {
+                    Ok(typed_data) => typed_data,
+                    Err(code) => {
+                        return Preview {
+                            index,
+                            range: range.clone(),
+                            heading: code.to_string(),
+                            entries: Vec::new(),
+                        };
+                    }
+                };
+                let domain = &typed_data.domain;
+                let primary_type = &typed_data.primary_type;
+                let heading = match domains::identify(domain, primary_type) {
+                    Some(known) => format!("{primary_type} ({})", known.label),
+                    None => format!("{primary_type} (unknown protocol)"),
+                };
+                let mut entries = Vec::new();
+                if let Some(name) = &domain.name {
+                    entries.push(Entry::new("domain", name.to_string()));
+                }
+                if let Some(chain_id) = domain.chain_id {
+                    entries.push(Entry::new("chain", chain_id.to_string()));
+                }
+                if let Some(contract) = domain.verifying_contract {
+                    entries.push(Entry::new("contract", self.address(contract)));
+                }
+                if let Ok(digest) = range.digest(blob, true) {
+                    entries.push(Entry::new("digest", digest.to_string()));
+                }
+                let context = Context {
+                    domain,
+                    primary_type,
+                };
+                let message = match &typed_data.message {
+                    Value::Object(fields) => self.entries(fields, &context),
+                    other => vec![Entry::new("value", other.to_string())],
+                };
+                entries.push(Entry {
+                    label: "message".to_owned(),
+                    value: None,
+                    children: message,
+                });
+                Preview {
+                    index,
+                    range: range.clone(),
+                    heading,
+                    entries,
+                }
+            })
+            .collect()
+    }
+
+    /// The token lookups, cached across previews, e.g. to share with `lint`.
+    pub fn tokens(&mut self) -> &mut Tokens {
+        &mut self.tokens
+    }
+
+    fn entries(&mut self, fields: &Map<String, Value>, context: &Context) -> Vec<Entry> {
+        fields
+            .iter()
+            .map(|(field, value)| self.entry(field.clone(), field, value, fields, context))
+            .collect()
+    }
+
+    fn entry(
+        &mut self,
+        label: String,
+        field: &str,
+        value: &Value,
+        siblings: &Map<String, Value>,
+        context: &Context,
+    ) -> Entry {
+        let children = match value {
+            Value::Object(fields) => self.entries(fields, context),
+            Value::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| self.entry(format!("[{i}]"), field, item, &Map::new(), context))
+                .collect(),
+            _ => {
+                return Entry {
+                    label,
+                    value: Some(self.describe(field, value, siblings, context)),
+                    children: Vec::new(),
+                }
+            }
+        };
+        Entry {
+            label,
+            value: None,
+            children,
+        }
+    }
+
+    // A scalar field as a wallet would show it
+    fn describe(
+        &mut self,
+        field: &str,
+        value: &Value,
+        siblings: &Map<String, Value>,
+        context: &Context,
+    ) -> String {
+        if let Some(address) = value.as_str().filter(|s| s.len() == 42) {
+            if let Ok(address) = address.parse::<Address>() {
+                let token = if is_token_field(field) {
+                    self.token(address, context)
+                } else {
+                    None
+                };
+                return match token {
+                    Some(token) => format!("{} ({address:#x})", token.symbol),
+                    None => self.address(address),
+                };
+            }
+        }
+        let Some(number) = parse_uint(value) else {
+            return match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+        };
+        if DEADLINE_FIELDS.contains(&field) {
+            return format_deadline(number, self.now);
+        }
+        if !is_amount_field(field) {
+            return number.to_string();
+        }
+        let token = token_for(field, siblings, context.domain, context.primary_type)
+            .and_then(|token| self.token(token, context));
+        match token {
+            _ if number == U256::MAX || number == UINT160_MAX => match token {
+                Some(token) => format!("unlimited {}", token.symbol),
+                None => "unlimited".to_owned(),
+            },
+            Some(token) => token.format(number),
+            None => number.to_string(),
+        }
+    }
+
+    fn token(&mut self, token: Address, context: &Context) -> Option<Token> {
+        self.tokens.get(token, context.domain.chain_id)
+    }
+
+    // `address` with the known protocol or ENS name behind it
+    fn address(&mut self, address: Address) -> String {
+        let rpc_url = self.rpc_url.as_deref();
+        self.names
+            .entry(address)
+            .or_insert_with(|| match domains::contract_label(address) {
+                Some(label) => format!("{address:#x} ({label})"),
+                None => ens::annotate(rpc_url, address),
+            })
+            .clone()
+    }
+}
+
+/// A Unix timestamp as a UTC date and how far it is from `now`, or `no expiry` for
+/// timestamps past the year 9999, which protocols use to mean never.
+pub fn format_deadline(timestamp: U256, now: u64) -> String {
+    const YEAR_9999_END: u64 = 253_402_300_799;
+    let timestamp = match u64::try_from(timestamp) {
+        Ok(timestamp) if timestamp <= YEAR_9999_END => timestamp,
+        _ => return "no expiry".to_owned(),
+    };
+    let days = timestamp.abs_diff(now) / 86_400;
+    let relative = match (timestamp >= now, days) {
+        (true, 0) => "within a day".to_owned(),
+        (true, days) => format!("in {days} days"),
+        (false, 0) => "expired today".to_owned(),
+        (false, days) => format!("expired {days} days ago"),
+    };
+    format!("{} ({relative})", format_utc(timestamp))
+}
+
+// `YYYY-MM-DD HH:MM UTC`, converting days to a civil date as in Howard Hinnant's
+// `civil_from_days`
+fn format_utc(timestamp: u64) -> String {
+    let days = timestamp / 86_400;
+    let seconds = timestamp % 86_400;
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds / 3_600,
+        seconds % 3_600 / 60
+    )
+}
+
+/// Lay `previews` out in `format`; HTML is a standalone page.
+pub fn render(previews: &[Preview], format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Text => {
+            for preview in previews {
+                out.push_str(&format!(
+                    "#{} {}: {}\n",
+                    preview.index, preview.range, preview.heading
+                ));
+                text_entries(&mut out, &preview.entries, 1);
+            }
+        }
+        Format::Markdown => {
+            for preview in previews {
+                out.push_str(&format!(
+                    "## #{} {}\n\nBytes {}\n\n",
+                    preview.index,
+                    markdown_escape(&preview.heading),
+                    preview.range
+                ));
+                markdown_entries(&mut out, &preview.entries, 0);
+                out.push('\n');
+            }
+        }
+        Format::Html => {
+            out.push_str(concat!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+                "<title>Typed data to sign</title>\n</head>\n<body>\n"
+            ));
+            for preview in previews {
+                out.push_str(&format!(
+                    "<section>\n<h2>#{} {}</h2>\n<p>Bytes {}</p>\n",
+                    preview.index,
+                    html_escape(&preview.heading),
+                    preview.range
+                ));
+                html_entries(&mut out, &preview.entries);
+                out.push_str("</section>\n");
+            }
+            out.push_str("</body>\n</html>\n");
+        }
+    }
+    out
+}
+
+fn text_entries(out: &mut String, entries: &[Entry], depth: usize) {
+    for entry in entries {
+        let indent = "  ".repeat(depth);
+        match &entry.value {
+            Some(value) => out.push_str(&format!("{indent}{}: {value}\n", entry.label)),
+            None => out.push_str(&format!("{indent}{}:\n", entry.label)),
+        }
+        text_entries(out, &entry.children, depth + 1);
+    }
+}
+
+fn markdown_entries(out: &mut String, entries: &[Entry], depth: usize) {
+    for entry in entries {
+        let indent = "  ".repeat(depth);
+        let label = markdown_escape(&entry.label);
+        match &entry.value {
+            Some(value) => {
+                let value = markdown_escape(value);
+                out.push_str(&format!("{indent}- **{label}**: {value}\n"));
+            }
+            None => out.push_str(&format!("{indent}- **{label}**\n")),
+        }
+        markdown_entries(out, &entry.children, depth + 1);
+    }
+}
+
+fn html_entries(out: &mut String, entries: &[Entry]) {
+    if entries.is_empty() {
+        return;
+    }
+    out.push_str("<ul>\n");
+    for entry in entries {
+        out.push_str(&format!("<li><b>{}</b>", html_escape(&entry.label)));
+        if let Some(value) = &entry.value {
+            out.push_str(&format!(": {}", html_escape(value)));
+        }
+        out.push('\n');
+        html_entries(out, &entry.children);
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ul>\n");
+}
+
+// Signed strings are attacker-chosen; keep them from turning into markup
+fn markdown_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(if c == '\n' { ' ' } else { c });
+    }
+    escaped
+}
+
+fn html_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,227 @@
+use alloy_primitives::{keccak256, B256};
+use anyhow::{anyhow, bail, Result};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use single_sign_types::DigestRange;
+use std::{
+    fmt, fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Where one range of a batch stands. Ranges move forward through the variants in
+/// order; `Failed` can replace any of them and is retried on resume.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum RangeStatus {
+    Pending,
+    /// Its receipt is saved in the output directory
+    Proven,
+    /// Its saved receipt was checked against the blob
+    Verified,
+    Submitted,
+    Failed { reason: String },
+}
+
+impl RangeStatus {
+    /// Whether a receipt for the range is already on disk, so proving can be skipped.
+    pub fn is_proven(&self) -> bool {
+        matches!(self, Self::Proven | Self::Verified | Self::Submitted)
+    }
+}
+
+impl fmt::Display for RangeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Proven => write!(f, "proven"),
+            Self::Verified => write!(f, "verified"),
+            Self::Submitted => write!(f, "submitted"),
+            Self::Failed { reason } => write!(f, "failed: {reason}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeEntry {
+    #[serde(flatten)]
+    pub range: DigestRange,
+    #[serde(flatten)]
+    pub status: RangeStatus,
+}
+
+/// `batch.json`: the status of every range of a blob, rewritten after each step so an
+/// interrupted run can resume where it stopped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress {
+    /// keccak256 of the blob, so progress is never resumed against another one
+    pub blob_hash: B256,
+    pub ranges: Vec<RangeEntry>,
+}
+
+impl BatchProgress {
+    pub const FILE_NAME: &'static str = "batch.json";
+
+    /// Fresh progress with every range pending.
+    pub fn new(blob: &[u8], ranges: &[DigestRange]) -> Self {
+        Self {
+            blob_hash: keccak256(blob),
+            ranges: ranges
+                .iter()
+                .map(|range| RangeEntry {
+                    range: range.clone(),
+                    status: RangeStatus::Pending,
+                })
+                .collect(),
+        }
+    }
+
+    /// Load progress written for exactly this blob and these ranges.
+    pub fn resume(path: &Path, blob: &[u8], ranges: &[DigestRange]) -> Result<Self> {
+        let json = fs::read(path)
+            .map_err(|e| anyhow!("Failed reading {}: {e}", path.display()))?;
+        let progress: Self = serde_json::from_slice(&json)
+            .map_err(|e| anyhow!("Invalid {}: {e}", path.display()))?;
+        if progress.blob_hash != keccak256(blob) {
+            bail!("{} was written for another blob", path.display());
+        }
+        if !progress.ranges.iter().map(|entry| &entry.range).eq(ranges) {
+            bail!("{} lists other ranges than the blob", path.display());
+        }
+        Ok(progress)
+    }
+
+    pub fn status(&self, index: usize) -> &RangeStatus {
+        &self.ranges[index].status
+    }
+
+    /// Record a range's new status and rewrite the file at once.
+    pub fn set(&mut self, path: &Path, index: usize, status: RangeStatus) -> Result<()> {
+        self.ranges[index].status = status;
+        self.save(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write then rename, so an interrupted write never leaves a truncated file
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .map_err(|e| anyhow!("Failed writing {}: {e}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .map_err(|e| anyhow!("Failed writing {}: {e}", path.display()))
+    }
+}
+
+/// Progress bars for a proving run on stderr: a spinner for the proof underway and an
+/// overall bar showing the measured cycles per second and an ETA. Guest cycles grow
+/// with the bytes hashed and parsed, so the ETA extrapolates the proving time per byte
+/// so far over the bytes still to prove.
+pub struct ProgressBars {
+    bars: MultiProgress,
+    overall: ProgressBar,
+    current: Option<(ProgressBar, Instant, usize)>,
+    remaining_bytes: usize,
+    proven_bytes: usize,
+    cycles: u64,
+    proving_time: Duration,
+}
+
+impl ProgressBars {
+    /// Bars for `steps` proofs over `total_bytes` of input, drawn nowhere when `quiet`
+    /// (indicatif also stays silent when stderr is not a terminal).
+    pub fn new(steps: usize, total_bytes: usize, quiet: bool) -> Self {
+        let target = if quiet {
+            ProgressDrawTarget::hidden()
+        } else {
+            ProgressDrawTarget::stderr()
+        };
+        let bars = MultiProgress::with_draw_target(target);
+        let overall = bars.add(ProgressBar::new(steps as u64));
+        overall.set_style(
+            ProgressStyle::with_template("{bar:30} {pos}/{len} proofs {msg}")
+                .expect("valid template"),
+        );
+        Self {
+            bars,
+            overall,
+            current: None,
+            remaining_bytes: total_bytes,
+            proven_bytes: 0,
+            cycles: 0,
+            proving_time: Duration::ZERO,
+        }
+    }
+
+    /// Print a line above the bars instead of through them.
+    pub fn println(&self, line: impl AsRef<str>) {
+        if self.bars.is_hidden() {
+            println!("{}", line.as_ref());
+        } else {
+            self.bars.suspend(|| println!("{}", line.as_ref()));
+        }
+    }
+
+    /// Show a spinner while `label`, covering `bytes` of input, is proven.
+    pub fn start(&mut self, label: &str, bytes: usize) {
+        let spinner = self.bars.insert_before(&self.overall, ProgressBar::new_spinner());
+        spinner.set_style(
+            ProgressStyle::with_template("{spinner} {msg} {elapsed}").expect("valid template"),
+        );
+        spinner.set_message(format!("Proving {label} ({bytes} bytes)"));
+        spinner.enable_steady_tick(Duration::from_millis(120));
+        self.current = Some((spinner, Instant::now(), bytes));
+    }
+
+    /// Close the spinner with the proof's statistics and update the ETA.
+    pub fn finish(&mut self, cycles: Option<u64>, segments: Option<usize>) {
+        let Some((spinner, started, bytes)) = self.current.take() else {
+            return;
+        };
+        let elapsed = started.elapsed();
+        let stats = match (cycles, segments) {
+            (Some(cycles), Some(segments)) => format!("{cycles} cycles in {segments} segments"),
+            (Some(cycles), None) => format!("{cycles} cycles"),
+            _ => "proven".to_string(),
+        };
+        spinner.finish_with_message(format!("{stats}, {:.1}s", elapsed.as_secs_f64()));
+        self.cycles += cycles.unwrap_or(0);
+        self.proving_time += elapsed;
+        self.proven_bytes += bytes;
+        self.advance(bytes);
+    }
+
+    /// Count a step whose receipt was reused rather than proven.
+    pub fn skip(&mut self, bytes: usize) {
+        self.advance(bytes);
+    }
+
+    /// Remove the spinner of a proof that failed.
+    pub fn abandon(&mut self) {
+        if let Some((spinner, _, _)) = self.current.take() {
+            spinner.abandon_with_message("failed");
+        }
+    }
+
+    pub fn finish_all(&self) {
+        self.overall.finish_with_message("done");
+    }
+
+    fn advance(&mut self, bytes: usize) {
+        self.remaining_bytes = self.remaining_bytes.saturating_sub(bytes);
+        self.overall.inc(1);
+        if self.proven_bytes == 0 || self.proving_time.is_zero() {
+            return;
+        }
+        let seconds = self.proving_time.as_secs_f64();
+        let eta = self.remaining_bytes as f64 * seconds / self.proven_bytes as f64;
+        let message = match self.cycles {
+            0 => format!("ETA {eta:.0}s"),
+            cycles => format!("{:.0} cycles/s, ETA {eta:.0}s", cycles as f64 / seconds),
+        };
+        self.overall.set_message(message);
+    }
+}
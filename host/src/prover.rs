@@ -0,0 +1,237 @@
+use crate::config::{Config, ProverBackend, ReceiptKind};
+use anyhow::{anyhow, bail, Error, Result};
+use risc0_zkvm::{
+    default_prover, sha::Digest, ExecutorEnv, ExecutorEnvBuilder, ProverOpts, Receipt,
+};
+use serde::Serialize;
+use single_sign_types::{BatchInput, Input};
+
+/// A receipt from whichever backend proved it.
+#[derive(Debug)]
+pub struct Proof {
+    pub receipt: Receipt,
+    /// Reported by local and Bonsai proving; the Boundless market does not expose it
+    pub total_cycles: Option<u64>,
+    /// Segments the session was split into, where `total_cycles` is reported
+    pub segments: Option<usize>,
+}
+
+// Segment sizes the zkVM accepts, as powers of two cycles, and risc0's default
+const MIN_SEGMENT_PO2: u32 = 13;
+const MAX_SEGMENT_PO2: u32 = 22;
+const DEFAULT_SEGMENT_PO2: u32 = 20;
+
+// Rough peak memory of the local CPU prover per cycle of a segment
+const PROVER_BYTES_PER_CYCLE: u64 = 9 * 1024;
+
+/// Inputs of the signing guests, which read a serialized header and then the blob as
+/// a raw frame with `env::read_frame`. Serializing the blob would make the guest
+/// deserialize it word by word and copy it again, in cycles growing with its size.
+pub trait Framed: Serialize {
+    /// The input without its blob
+    fn header(&self) -> Self;
+    fn blob(&self) -> &[u8];
+}
+
+impl Framed for Input {
+    fn header(&self) -> Self {
+        Input::header(self)
+    }
+
+    fn blob(&self) -> &[u8] {
+        &self.typed_data_concat
+    }
+}
+
+impl Framed for BatchInput {
+    fn header(&self) -> Self {
+        BatchInput::header(self)
+    }
+
+    fn blob(&self) -> &[u8] {
+        &self.typed_data_concat
+    }
+}
+
+/// Prove `input` against `elf` on the configured backend. Boundless proofs always
+/// come back as Groth16 receipts, whatever `receipt_kind` is configured.
+pub fn prove<T: Serialize>(
+    config: &Config,
+    elf: &[u8],
+    image_id: impl Into<Digest>,
+    input: &T,
+) -> Result<Proof> {
+    prove_stdin(config, elf, image_id.into(), input, None)
+}
+
+/// `prove` for the signing guests' `Input` and `BatchInput`, sending the blob as a
+/// frame after the header.
+pub fn prove_framed<T: Framed>(
+    config: &Config,
+    elf: &[u8],
+    image_id: impl Into<Digest>,
+    input: &T,
+) -> Result<Proof> {
+    prove_stdin(config, elf, image_id.into(), &input.header(), Some(input.blob()))
+}
+
+fn prove_stdin<T: Serialize>(
+    config: &Config,
+    elf: &[u8],
+    image_id: Digest,
+    input: &T,
+    frame: Option<&[u8]>,
+) -> Result<Proof> {
+    if config.prover == ProverBackend::Boundless {
+        return prove_on_boundless(config, elf, image_id, input, frame);
+    }
+    let mut builder = ExecutorEnv::builder();
+    apply_limits(&mut builder, config)?;
+    builder.write(input)?;
+    if let Some(frame) = frame {
+        builder.write_frame(frame);
+    }
+    let env = builder.build()?;
+    let prover = default_prover();
+    // Local Groth16 proving wraps the succinct receipt in a SNARK as a separate step,
+    // so traces show the STARK and the wrap apart; remote provers do both at once
+    let wrap = config.receipt_kind == ReceiptKind::Groth16 && prover.get_name() == "local";
+    let opts = if wrap {
+        ProverOpts::succinct()
+    } else {
+        config.receipt_kind.prover_opts()
+    };
+    let prove_info = tracing::info_span!("zkvm_prove", prover = prover.get_name())
+        .in_scope(|| prover.prove_with_opts(env, elf, &opts))
+        .map_err(|e| limit_error(config, frame.map(<[u8]>::len), e))?;
+    let receipt = if wrap {
+        tracing::info_span!("snark_wrap")
+            .in_scope(|| prover.compress(&ProverOpts::groth16(), &prove_info.receipt))?
+    } else {
+        prove_info.receipt
+    };
+    Ok(Proof {
+        receipt,
+        total_cycles: Some(prove_info.stats.total_cycles),
+        segments: Some(prove_info.stats.segments),
+    })
+}
+
+/// `prove` for guests that verify `assumption` by composition, which only local and
+/// Bonsai proving resolve.
+pub fn prove_with_assumption<T: Serialize>(
+    config: &Config,
+    elf: &[u8],
+    input: &T,
+    assumption: Receipt,
+) -> Result<Proof> {
+    if config.prover == ProverBackend::Boundless {
+        bail!("The boundless prover cannot resolve composed receipts");
+    }
+    let mut builder = ExecutorEnv::builder();
+    apply_limits(&mut builder, config)?;
+    let env = builder.add_assumption(assumption).write(input)?.build()?;
+    let prove_info = default_prover()
+        .prove_with_opts(env, elf, &config.receipt_kind.prover_opts())
+        .map_err(|e| limit_error(config, None, e))?;
+    Ok(Proof {
+        receipt: prove_info.receipt,
+        total_cycles: Some(prove_info.stats.total_cycles),
+        segments: Some(prove_info.stats.segments),
+    })
+}
+
+/// Cycles per segment of local proving, as a power of two: `segment_limit_po2` if set,
+/// otherwise the largest segment up to risc0's default that proves within `max_mem`.
+/// `None` leaves risc0's default.
+pub fn segment_limit_po2(config: &Config) -> Result<Option<u32>> {
+    // Largest segment whose proving fits in `max_mem`
+    let fits = config
+        .max_mem
+        .map(|mib| (mib << 20) / PROVER_BYTES_PER_CYCLE)
+        .map(|cycles| cycles.checked_ilog2().unwrap_or(0));
+    match (config.segment_limit_po2, fits) {
+        (Some(po2), _) if !(MIN_SEGMENT_PO2..=MAX_SEGMENT_PO2).contains(&po2) => bail!(
+            "Segment limit 2^{po2} is outside the zkVM's 2^{MIN_SEGMENT_PO2} to \
+             2^{MAX_SEGMENT_PO2} cycles"
+        ),
+        (Some(po2), Some(fits)) if po2 > fits => bail!(
+            "Proving 2^{po2}-cycle segments takes about {} MiB, over the {} MiB max_mem; \
+             use a segment limit of {fits} or less",
+            prover_mib(po2),
+            config.max_mem.unwrap_or_default()
+        ),
+        (None, Some(fits)) if fits < MIN_SEGMENT_PO2 => bail!(
+            "{} MiB of max_mem cannot prove even the smallest segments, which take \
+             about {} MiB",
+            config.max_mem.unwrap_or_default(),
+            prover_mib(MIN_SEGMENT_PO2)
+        ),
+        (None, fits) => Ok(fits.map(|fits| fits.min(DEFAULT_SEGMENT_PO2))),
+        (po2, _) => Ok(po2),
+    }
+}
+
+fn prover_mib(po2: u32) -> u64 {
+    (PROVER_BYTES_PER_CYCLE << po2) >> 20
+}
+
+// Segment size and session limit of local proving; Bonsai and Boundless ignore them
+fn apply_limits(builder: &mut ExecutorEnvBuilder, config: &Config) -> Result<()> {
+    if let Some(po2) = segment_limit_po2(config)? {
+        builder.segment_limit_po2(po2);
+    }
+    builder.session_limit(config.session_limit);
+    Ok(())
+}
+
+// Name the limit a failed proof ran into and what to change, instead of the bare
+// executor error
+fn limit_error(config: &Config, blob_len: Option<usize>, e: Error) -> Error {
+    let message = e.to_string();
+    let lowercase = message.to_lowercase();
+    let blob = blob_len.map_or_else(String::new, |len| format!(" on a {len}-byte blob"));
+    if lowercase.contains("session limit") {
+        let limit = match config.session_limit {
+            Some(limit) => format!("the {limit}-cycle session_limit"),
+            None => "risc0's default session limit".to_owned(),
+        };
+        return anyhow!(
+            "{message}: the guest ran past {limit}{blob}; raise session_limit or prove \
+             fewer or smaller ranges per proof"
+        );
+    }
+    if lowercase.contains("out of memory") || lowercase.contains("memory allocation") {
+        return anyhow!(
+            "{message}: the guest ran out of memory{blob}; split the blob into smaller \
+             batches"
+        );
+    }
+    e
+}
+
+#[cfg(feature = "boundless")]
+fn prove_on_boundless<T: Serialize>(
+    config: &Config,
+    elf: &[u8],
+    image_id: Digest,
+    input: &T,
+    frame: Option<&[u8]>,
+) -> Result<Proof> {
+    Ok(Proof {
+        receipt: crate::boundless::prove(config, elf, image_id, input, frame)?,
+        total_cycles: None,
+        segments: None,
+    })
+}
+
+#[cfg(not(feature = "boundless"))]
+fn prove_on_boundless<T: Serialize>(
+    _config: &Config,
+    _elf: &[u8],
+    _image_id: Digest,
+    _input: &T,
+    _frame: Option<&[u8]>,
+) -> Result<Proof> {
+    bail!("The boundless prover requires the `boundless` feature")
+}
@@ -0,0 +1,183 @@
+//! Persistent prove-job queue backed by sled. Enabled by the `queue` feature.
+//!
+//! Workers claim jobs under a lease. A worker that crashes mid-batch simply lets
+//! its lease expire; the next worker to claim the job re-runs it, and ranges that
+//! were already proven come straight out of the `ReceiptCache`. A worker restarted
+//! under the same name takes its own claims back at once.
+//!
+//! Each saved receipt is recorded in the job's `Checkpoint`. A worker told to shut
+//! down stops mid-batch with `interrupt`, which marks the range it was proving as
+//! retryable and returns the job to the queue instead of waiting out the lease.
+
+use crate::config::ReceiptKind;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use single_sign_types::Input;
+use std::{
+    collections::BTreeSet,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    /// One input per range, all over the same blob
+    pub inputs: Vec<Input>,
+    pub receipt_kind: ReceiptKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JobState {
+    Queued,
+    /// Held by `worker` until `lease_expires` (seconds since the Unix epoch)
+    Claimed { worker: String, lease_expires: u64 },
+    Done,
+}
+
+/// Progress of a job across claims, so a resumed job skips the ranges already saved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Ranges whose receipts were saved
+    pub proven: BTreeSet<usize>,
+    /// Ranges that were proving when a worker shut down, to be proven again
+    pub interrupted: BTreeSet<usize>,
+}
+
+pub struct JobQueue {
+    db: sled::Db,
+    jobs: sled::Tree,
+    states: sled::Tree,
+    checkpoints: sled::Tree,
+}
+
+impl JobQueue {
+    /// Open (creating if needed) the queue database at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path)
+            .map_err(|e| anyhow!("Failed opening job queue {}: {e}", path.display()))?;
+        Ok(Self {
+            jobs: db.open_tree("jobs")?,
+            states: db.open_tree("states")?,
+            checkpoints: db.open_tree("checkpoints")?,
+            db,
+        })
+    }
+
+    pub fn enqueue(&self, inputs: Vec<Input>, receipt_kind: ReceiptKind) -> Result<u64> {
+        let id = self.db.generate_id()?;
+        let job = Job {
+            id,
+            inputs,
+            receipt_kind,
+        };
+        self.jobs.insert(id.to_be_bytes(), bincode::serialize(&job)?)?;
+        self.states
+            .insert(id.to_be_bytes(), bincode::serialize(&JobState::Queued)?)?;
+        self.db.flush()?;
+        Ok(id)
+    }
+
+    /// Claim the oldest job that is queued, whose lease has expired or that `worker`
+    /// itself claimed before restarting, holding it for `lease`. Claims are
+    /// compare-and-swapped, so concurrent workers never receive the same job as long
+    /// as their names differ.
+    pub fn claim(&self, worker: &str, lease: Duration) -> Result<Option<Job>> {
+        let now = unix_now();
+        for entry in self.states.iter() {
+            let (key, current) = entry?;
+            let claimable = match bincode::deserialize::<JobState>(&current)? {
+                JobState::Queued => true,
+                JobState::Claimed {
+                    worker: holder,
+                    lease_expires,
+                } => holder == worker || lease_expires <= now,
+                JobState::Done => false,
+            };
+            if !claimable {
+                continue;
+            }
+            let claimed = JobState::Claimed {
+                worker: worker.to_owned(),
+                lease_expires: now + lease.as_secs(),
+            };
+            let swapped = self.states.compare_and_swap(
+                &key,
+                Some(current),
+                Some(bincode::serialize(&claimed)?),
+            )?;
+            if swapped.is_ok() {
+                self.db.flush()?;
+                let job = self
+                    .jobs
+                    .get(&key)?
+                    .ok_or_else(|| anyhow!("Job state without a job"))?;
+                return Ok(Some(bincode::deserialize(&job)?));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn complete(&self, id: u64) -> Result<()> {
+        self.checkpoints.remove(id.to_be_bytes())?;
+        self.set_state(id, &JobState::Done)
+    }
+
+    /// Return a claimed job to the queue, e.g. after a proving error.
+    pub fn release(&self, id: u64) -> Result<()> {
+        self.set_state(id, &JobState::Queued)
+    }
+
+    /// Return a claimed job to the queue on shutdown, with `in_flight`, the range it
+    /// was proving, marked for proving again.
+    pub fn interrupt(&self, id: u64, in_flight: usize) -> Result<()> {
+        let mut checkpoint = self.checkpoint(id)?;
+        checkpoint.interrupted.insert(in_flight);
+        self.set_checkpoint(id, &checkpoint)?;
+        self.set_state(id, &JobState::Queued)
+    }
+
+    /// Record that the receipt of `range` was saved.
+    pub fn mark_proven(&self, id: u64, range: usize) -> Result<()> {
+        let mut checkpoint = self.checkpoint(id)?;
+        checkpoint.interrupted.remove(&range);
+        checkpoint.proven.insert(range);
+        self.set_checkpoint(id, &checkpoint)
+    }
+
+    /// The job's progress so far; empty for jobs never claimed.
+    pub fn checkpoint(&self, id: u64) -> Result<Checkpoint> {
+        match self.checkpoints.get(id.to_be_bytes())? {
+            Some(checkpoint) => Ok(bincode::deserialize(&checkpoint)?),
+            None => Ok(Checkpoint::default()),
+        }
+    }
+
+    pub fn state(&self, id: u64) -> Result<Option<JobState>> {
+        self.states
+            .get(id.to_be_bytes())?
+            .map(|state| bincode::deserialize(&state))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    fn set_checkpoint(&self, id: u64, checkpoint: &Checkpoint) -> Result<()> {
+        self.checkpoints
+            .insert(id.to_be_bytes(), bincode::serialize(checkpoint)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn set_state(&self, id: u64, state: &JobState) -> Result<()> {
+        self.states
+            .insert(id.to_be_bytes(), bincode::serialize(state)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
@@ -0,0 +1,35 @@
+use crate::config::ReceiptKind;
+use anyhow::{anyhow, Result};
+use risc0_zkvm::{default_prover, sha::Digest, Receipt};
+use std::{fs, path::Path};
+
+/// Serialize a receipt with bincode so it can be verified later without the prover.
+pub fn save_receipt(path: &Path, receipt: &Receipt) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let bytes = bincode::serialize(receipt)?;
+    fs::write(path, bytes)
+        .map_err(|e| anyhow!("Failed writing receipt to {}: {e}", path.display()))
+}
+
+/// Load a receipt written by `save_receipt`.
+pub fn load_receipt(path: &Path) -> Result<Receipt> {
+    let bytes = fs::read(path)
+        .map_err(|e| anyhow!("Failed reading receipt from {}: {e}", path.display()))?;
+    bincode::deserialize(&bytes).map_err(|e| anyhow!("Invalid receipt encoding: {e}"))
+}
+
+/// Compress `receipt` to `kind`, e.g. a composite receipt of many segments into one
+/// succinct or Groth16 receipt, checking the result still verifies against `image_id`.
+pub fn compress_receipt(
+    receipt: &Receipt,
+    image_id: impl Into<Digest>,
+    kind: ReceiptKind,
+) -> Result<Receipt> {
+    let compressed = default_prover().compress(&kind.prover_opts(), receipt)?;
+    compressed
+        .verify(image_id)
+        .map_err(|e| anyhow!("Compressed receipt does not verify: {e}"))?;
+    Ok(compressed)
+}
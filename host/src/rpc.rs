@@ -0,0 +1,176 @@
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_provider::{
+    network::{Ethereum, Network, TransactionBuilder},
+    Provider, ProviderBuilder,
+};
+use alloy_sol_types::{sol, SolCall};
+use anyhow::{anyhow, bail, Result};
+use single_sign_types::{
+    state_proof::{owner_slot, AccountProof, StorageProof, SINGLETON_SLOT, THRESHOLD_SLOT},
+    BlockAnchor,
+};
+
+// The prover is synchronous (Bonsai's blocking client cannot run inside a tokio
+// runtime), so RPC calls get a short-lived runtime of their own.
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> Result<F::Output> {
+    Ok(tokio::runtime::Runtime::new()?.block_on(future))
+}
+
+/// Query the chain ID of the node at `rpc_url`.
+pub fn chain_id(rpc_url: &str) -> Result<u64> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    block_on(provider.get_chain_id())?
+        .map_err(|e| anyhow!("Failed fetching chain ID from {rpc_url}: {e}"))
+}
+
+/// Number and hash of the latest block at `rpc_url`, to anchor proofs to.
+pub fn latest_block(rpc_url: &str) -> Result<BlockAnchor> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let number = block_on(provider.get_block_number())?
+        .map_err(|e| anyhow!("Failed fetching the block number from {rpc_url}: {e}"))?;
+    let block = block_on(provider.get_block_by_number(number.into()))?
+        .map_err(|e| anyhow!("Failed fetching block {number} from {rpc_url}: {e}"))?
+        .ok_or_else(|| anyhow!("Block {number} not found at {rpc_url}"))?;
+    Ok(BlockAnchor {
+        number,
+        hash: block.header.hash,
+    })
+}
+
+sol! {
+    interface IERC1271 {
+        function isValidSignature(bytes32 hash, bytes signature) external view returns (bytes4);
+    }
+
+    interface INonces {
+        function nonces(address owner) external view returns (uint256);
+    }
+
+    interface IERC20Metadata {
+        function symbol() external view returns (string);
+        function decimals() external view returns (uint8);
+    }
+}
+
+/// Prefix of an EIP-7702 delegation designator, `0xef0100 || delegate`.
+const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// The contract `account` delegates to under EIP-7702, if any.
+pub fn delegation(rpc_url: &str, account: Address) -> Result<Option<Address>> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let code = block_on(provider.get_code_at(account))?
+        .map_err(|e| anyhow!("Failed fetching code of {account:#x} from {rpc_url}: {e}"))?;
+    Ok(match code.strip_prefix(&DELEGATION_PREFIX[..]) {
+        Some(delegate) if delegate.len() == 20 => Some(Address::from_slice(delegate)),
+        _ => None,
+    })
+}
+
+/// Ask `account` whether it accepts `signature` over `hash` via ERC-1271. The magic
+/// return value is the `isValidSignature` selector; anything else is a rejection.
+pub fn is_valid_signature(
+    rpc_url: &str,
+    account: Address,
+    hash: B256,
+    signature: Bytes,
+) -> Result<bool> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let call = IERC1271::isValidSignatureCall { hash, signature };
+    let tx = <Ethereum as Network>::TransactionRequest::default()
+        .with_to(account)
+        .with_input(call.abi_encode());
+    let result = block_on(provider.call(tx))?
+        .map_err(|e| anyhow!("ERC-1271 check against {account:#x} failed: {e}"))?;
+    Ok(result.starts_with(&IERC1271::isValidSignatureCall::SELECTOR))
+}
+
+/// The next intent nonce `contract` expects from `owner`, via the `nonces(address)`
+/// getter that ERC-2612 and OpenZeppelin's `Nonces` expose.
+pub fn intent_nonce(rpc_url: &str, contract: Address, owner: Address) -> Result<U256> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let call = INonces::noncesCall { owner };
+    let tx = <Ethereum as Network>::TransactionRequest::default()
+        .with_to(contract)
+        .with_input(call.abi_encode());
+    let result = block_on(provider.call(tx))?
+        .map_err(|e| anyhow!("Fetching the nonce of {owner:#x} from {contract:#x} failed: {e}"))?;
+    INonces::noncesCall::abi_decode_returns(&result)
+        .map_err(|e| anyhow!("Invalid nonces() return data from {contract:#x}: {e}"))
+}
+
+/// Symbol and decimals of the ERC-20 `token`, for display. Tokens that return their
+/// symbol as `bytes32`, as some early ones do, are reported as errors.
+pub fn token_metadata(rpc_url: &str, token: Address) -> Result<(String, u8)> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let call = |input: Vec<u8>| {
+        let tx = <Ethereum as Network>::TransactionRequest::default()
+            .with_to(token)
+            .with_input(input);
+        block_on(provider.call(tx))?
+            .map_err(|e| anyhow!("Fetching the metadata of token {token:#x} failed: {e}"))
+    };
+    let symbol = call(IERC20Metadata::symbolCall {}.abi_encode())?;
+    let symbol = IERC20Metadata::symbolCall::abi_decode_returns(&symbol)
+        .map_err(|e| anyhow!("Invalid symbol() return data from {token:#x}: {e}"))?;
+    let decimals = call(IERC20Metadata::decimalsCall {}.abi_encode())?;
+    let decimals = IERC20Metadata::decimalsCall::abi_decode_returns(&decimals)
+        .map_err(|e| anyhow!("Invalid decimals() return data from {token:#x}: {e}"))?;
+    Ok((symbol, decimals))
+}
+
+/// Header and state proofs of a Safe at one block, for `SafeStateInput`.
+pub struct SafeProofs {
+    pub header_rlp: Bytes,
+    pub account_proof: AccountProof,
+    pub singleton: StorageProof,
+    pub threshold: StorageProof,
+    pub owners: Vec<StorageProof>,
+}
+
+/// Fetch the latest block header and `eth_getProof` proofs of `safe`'s singleton,
+/// threshold and the `owners` entries of `owners`. The node is not trusted: the guest
+/// checks every proof against the header, and verifiers check the block hash.
+pub fn safe_proofs(rpc_url: &str, safe: Address, owners: &[Address]) -> Result<SafeProofs> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let number = block_on(provider.get_block_number())?
+        .map_err(|e| anyhow!("Failed fetching the block number from {rpc_url}: {e}"))?;
+    let block = block_on(provider.get_block_by_number(number.into()))?
+        .map_err(|e| anyhow!("Failed fetching block {number} from {rpc_url}: {e}"))?
+        .ok_or_else(|| anyhow!("Block {number} not found at {rpc_url}"))?;
+    let header_rlp = Bytes::from(alloy_rlp::encode(&block.header.inner));
+    if keccak256(&header_rlp) != block.header.hash {
+        bail!("Header of block {number} does not hash to its block hash");
+    }
+
+    let slots: Vec<B256> = [SINGLETON_SLOT, THRESHOLD_SLOT]
+        .into_iter()
+        .map(|slot| B256::from(U256::from(slot)))
+        .chain(owners.iter().copied().map(owner_slot))
+        .collect();
+    let proof = block_on(provider.get_proof(safe, slots.clone()).block_id(number.into()))?
+        .map_err(|e| anyhow!("Failed fetching proofs of {safe:#x} from {rpc_url}: {e}"))?;
+    let mut storage = slots
+        .into_iter()
+        .zip(proof.storage_proof)
+        .map(|(slot, storage_proof)| StorageProof {
+            slot,
+            value: storage_proof.value,
+            proof: storage_proof.proof,
+        });
+    let (Some(singleton), Some(threshold)) = (storage.next(), storage.next()) else {
+        bail!("Node returned no storage proofs for {safe:#x}");
+    };
+    Ok(SafeProofs {
+        header_rlp,
+        account_proof: AccountProof {
+            nonce: proof.nonce,
+            balance: proof.balance,
+            storage_root: proof.storage_hash,
+            code_hash: proof.code_hash,
+            proof: proof.account_proof,
+        },
+        singleton,
+        threshold,
+        owners: storage.collect(),
+    })
+}
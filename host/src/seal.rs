@@ -0,0 +1,65 @@
+use alloy_primitives::{Bytes, B256};
+use anyhow::{bail, Result};
+use risc0_zkvm::{
+    sha::{Digest, Digestible},
+    InnerReceipt, Receipt,
+};
+use serde::Serialize;
+
+/// Package a receipt's seal in the calldata layout the RISC Zero verifier router
+/// expects: a 4-byte selector identifying the verifier, followed by the seal.
+/// Groth16 selectors come from the verifier parameters digest; fake (dev-mode)
+/// receipts use `0xFFFFFFFF` followed by the claim digest, as the mock verifier does.
+/// Fake receipts prove nothing, so they are refused unless `allow_dev_receipts` is set.
+pub fn encode_seal(receipt: &Receipt, allow_dev_receipts: bool) -> Result<Vec<u8>> {
+    match &receipt.inner {
+        InnerReceipt::Groth16(receipt) => {
+            let selector = &receipt.verifier_parameters.as_bytes()[..4];
+            Ok([selector, receipt.seal.as_slice()].concat())
+        }
+        InnerReceipt::Fake(receipt) if allow_dev_receipts => {
+            Ok([&[0xFF_u8; 4][..], receipt.claim.digest().as_bytes()].concat())
+        }
+        InnerReceipt::Fake(_) => {
+            bail!("Refusing to encode a fake dev-mode receipt without --allow-dev-receipts")
+        }
+        _ => bail!("Only Groth16 and fake receipts can be verified on-chain"),
+    }
+}
+
+/// SHA-256 of the journal bytes, the `journalDigest` argument of `IRiscZeroVerifier.verify`.
+pub fn journal_digest(receipt: &Receipt) -> B256 {
+    B256::from_slice(receipt.journal.digest().as_bytes())
+}
+
+/// Image ID in the `bytes32` form used on-chain.
+pub fn image_id_bytes(image_id: impl Into<Digest>) -> B256 {
+    B256::from_slice(image_id.into().as_bytes())
+}
+
+/// Exactly what `IRiscZeroVerifier.verify(seal, imageId, journalDigest)` needs, plus
+/// the journal itself for the consumer to decode, as `verify_receipt --export journal`
+/// writes it.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalExport {
+    pub journal_bytes: Bytes,
+    pub journal_sha256: B256,
+    pub image_id: B256,
+    pub seal: Bytes,
+}
+
+impl JournalExport {
+    /// Fails, like `encode_seal`, for receipts that cannot be verified on-chain.
+    pub fn new(
+        receipt: &Receipt,
+        image_id: impl Into<Digest>,
+        allow_dev_receipts: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            journal_bytes: Bytes::copy_from_slice(&receipt.journal.bytes),
+            journal_sha256: journal_digest(receipt),
+            image_id: image_id_bytes(image_id),
+            seal: encode_seal(receipt, allow_dev_receipts)?.into(),
+        })
+    }
+}
@@ -0,0 +1,113 @@
+//! Seaport orders signed as part of a blob. Seaport's order hash is the EIP-712
+//! struct hash of `OrderComponents`, which the guests commit as `Output.struct_hash`,
+//! so a zk-gated zone can check a proven order against `ZoneParameters.orderHash`.
+
+use crate::seal::encode_seal;
+use alloy_primitives::Bytes;
+use alloy_sol_types::{sol, SolValue};
+use anyhow::{bail, Result};
+use risc0_zkvm::Receipt;
+use single_sign_types::{DigestRange, Output};
+
+/// Primary type of Seaport order typed data.
+pub const ORDER_COMPONENTS: &str = "OrderComponents";
+
+sol! {
+    struct OfferItem {
+        uint8 itemType;
+        address token;
+        uint256 identifierOrCriteria;
+        uint256 startAmount;
+        uint256 endAmount;
+    }
+
+    struct ConsiderationItem {
+        uint8 itemType;
+        address token;
+        uint256 identifierOrCriteria;
+        uint256 startAmount;
+        uint256 endAmount;
+        address recipient;
+    }
+
+    struct OrderComponents {
+        address offerer;
+        address zone;
+        OfferItem[] offer;
+        ConsiderationItem[] consideration;
+        uint8 orderType;
+        uint256 startTime;
+        uint256 endTime;
+        bytes32 zoneHash;
+        uint256 salt;
+        bytes32 conduitKey;
+        uint256 counter;
+    }
+
+    struct SpentItem {
+        uint8 itemType;
+        address token;
+        uint256 identifier;
+        uint256 amount;
+    }
+
+    struct ReceivedItem {
+        uint8 itemType;
+        address token;
+        uint256 identifier;
+        uint256 amount;
+        address recipient;
+    }
+
+    struct ZoneParameters {
+        bytes32 orderHash;
+        address fulfiller;
+        address offerer;
+        SpentItem[] offer;
+        ReceivedItem[] consideration;
+        bytes extraData;
+        bytes32[] orderHashes;
+        uint256 startTime;
+        uint256 endTime;
+        bytes32 zoneHash;
+    }
+
+    /// Zone that only authorizes orders whose signature was proven in the zkVM.
+    interface IZkGatedZone {
+        function authorizeOrder(ZoneParameters calldata zoneParameters)
+            external
+            returns (bytes4 authorizedOrderMagicValue);
+
+        function validateOrder(ZoneParameters calldata zoneParameters)
+            external
+            returns (bytes4 validOrderMagicValue);
+    }
+
+    /// `extraData` the zone expects: the router seal and the raw journal, whose
+    /// SHA-256 is the `journalDigest` passed to `IRiscZeroVerifier.verify`.
+    struct ZoneProof {
+        bytes seal;
+        bytes journal;
+    }
+}
+
+/// Whether the typed data `range` covers in `blob` is a Seaport order.
+pub fn is_order(blob: &[u8], range: &DigestRange) -> bool {
+    range
+        .typed_data(blob, false)
+        .is_ok_and(|typed_data| typed_data.primary_type == ORDER_COMPONENTS)
+}
+
+/// Package a Groth16 `receipt` proving one Seaport order as the `extraData` a
+/// zk-gated zone decodes. `output` is the receipt's decoded journal; a failure
+/// journal proves nothing about the order and is refused.
+pub fn zone_extra_data(receipt: &Receipt, output: &Output) -> Result<Bytes> {
+    if !output.is_ok() {
+        bail!("Receipt proves a failure, not a signed order");
+    }
+    let proof = ZoneProof {
+        seal: encode_seal(receipt, false)?.into(),
+        journal: receipt.journal.bytes.clone().into(),
+    };
+    Ok(proof.abi_encode().into())
+}
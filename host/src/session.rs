@@ -0,0 +1,336 @@
+//! Sessions proving several independently signed blobs in one run, the way operators
+//! batch a day's files. Every object of every blob is scheduled on one pool of
+//! workers sharing the receipt cache, and a `SessionReport` sums up each blob. A blob
+//! that fails to prepare or prove is reported without stopping the others.
+
+use crate::{
+    cache::ReceiptCache,
+    config::Config,
+    ens::AddressOrName,
+    input::{decode_signature, read_input},
+    prepare::{prepare_inputs, unchecked_inputs},
+    prover::prove_framed,
+    receipts::save_receipt,
+    verify::check_receipt_against_blob,
+};
+use alloy_primitives::{Address, Bytes, B256};
+use anyhow::{anyhow, bail, Result};
+use methods::{SINGLE_SIGN_ELF, SINGLE_SIGN_ID};
+use serde::Serialize;
+use single_sign_types::{
+    serde_hex,
+    signing::{parse_signature_with_chain_id, Envelope},
+    Assertion, DigestRange, Failure, Input,
+};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// One signed blob of a session, given as `blob:signature-file` or
+/// `blob:signature-file:signer`. The signature file holds the raw or hex signature,
+/// as for `--signature-file`; the signer is an address or ENS name.
+#[derive(Debug, Clone)]
+pub struct SessionEntry {
+    pub file: PathBuf,
+    pub signature_file: PathBuf,
+    /// Without a signer, the proofs attest whoever the signature recovers to
+    pub signer: Option<AddressOrName>,
+}
+
+impl FromStr for SessionEntry {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.splitn(3, ':');
+        let (Some(file), Some(signature_file)) = (parts.next(), parts.next()) else {
+            bail!("Expected blob:signature-file or blob:signature-file:signer, got {s}");
+        };
+        if [file, signature_file].iter().any(|part| part.is_empty() || *part == "-") {
+            bail!("Session blobs and signatures must be files, got {s}");
+        }
+        Ok(Self {
+            file: file.into(),
+            signature_file: signature_file.into(),
+            signer: parts.next().map(str::parse).transpose()?,
+        })
+    }
+}
+
+/// How every range of a session is proven.
+#[derive(Debug, Clone)]
+pub struct SessionOptions {
+    pub assert: Assertion,
+    pub extract_paths: Vec<String>,
+    pub disclosure_salt: Option<B256>,
+    /// Ranges proven at once, across all blobs
+    pub jobs: usize,
+}
+
+/// The outcome of a session, saved as `session.json` in the output directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionReport {
+    pub blobs: Vec<BlobReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobReport {
+    pub file: PathBuf,
+    /// Where its blob and receipts are saved
+    pub dir: PathBuf,
+    #[serde(with = "serde_hex::address_option")]
+    pub signer: Option<Address>,
+    /// Why the blob could not be prepared; its ranges are then empty
+    pub error: Option<String>,
+    pub ranges: Vec<RangeReport>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RangeReport {
+    pub range: DigestRange,
+    #[serde(with = "serde_hex::b256_option")]
+    pub digest: Option<B256>,
+    pub receipt: Option<PathBuf>,
+    /// Whether the receipt came from the cache
+    pub cached: bool,
+    /// Set when the receipt attests that the range is invalid, as proving with
+    /// `Assertion::ReportValidity` may; `digest` is then `None`
+    pub attested_failure: Option<Failure>,
+    pub error: Option<String>,
+}
+
+impl BlobReport {
+    pub fn failed(&self) -> bool {
+        self.error.is_some() || self.ranges.iter().any(|range| range.error.is_some())
+    }
+}
+
+impl SessionReport {
+    pub const FILE_NAME: &'static str = "session.json";
+
+    pub fn failed(&self) -> usize {
+        self.blobs.iter().filter(|blob| blob.failed()).count()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+            .map_err(|e| anyhow!("Failed writing {}: {e}", path.display()))
+    }
+}
+
+impl fmt::Display for SessionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, blob) in self.blobs.iter().enumerate() {
+            let signer = blob.signer.map_or("unknown signer".to_owned(), |s| format!("{s:#x}"));
+            write!(f, "Blob #{i} {} ({signer}): ", blob.file.display())?;
+            if let Some(error) = &blob.error {
+                writeln!(f, "{error}")?;
+                continue;
+            }
+            let proven = blob.ranges.iter().filter(|range| range.digest.is_some()).count();
+            let attested = blob.ranges.iter().filter(|range| range.attested_failure.is_some());
+            let cached = blob.ranges.iter().filter(|range| range.cached).count();
+            writeln!(
+                f,
+                "{proven}/{} ranges proven, {} attested invalid ({cached} cached), receipts in {}",
+                blob.ranges.len(),
+                attested.count(),
+                blob.dir.display()
+            )?;
+            for (j, range) in blob.ranges.iter().enumerate() {
+                if let Some(error) = &range.error {
+                    writeln!(f, "  range #{j} {}: {error}", range.range)?;
+                } else if let Some(failure) = &range.attested_failure {
+                    let code = failure.code;
+                    writeln!(f, "  range #{j} {}: attested invalid, {code}", range.range)?;
+                }
+            }
+        }
+        write!(f, "{} of {} blobs failed", self.failed(), self.blobs.len())
+    }
+}
+
+// A prepared blob and the inputs of its ranges
+struct Prepared {
+    signer: Address,
+    inputs: Vec<Input>,
+}
+
+/// Prove every range of every blob in `entries`, saving each blob and its receipts in
+/// `blob_<i>` under the output directory. Ranges already in `cache` are not proven
+/// again.
+pub fn prove_session(
+    config: &Config,
+    cache: Option<&ReceiptCache>,
+    entries: &[SessionEntry],
+    options: &SessionOptions,
+) -> SessionReport {
+    let mut blobs: Vec<BlobReport> = Vec::with_capacity(entries.len());
+    let mut tasks = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let dir = config.output_dir.join(format!("blob_{i}"));
+        let prepared = prepare(config, entry, options, &dir);
+        let (signer, error) = match prepared {
+            Ok(prepared) => {
+                println!(
+                    "Blob #{i} {}: {} ranges signed by {:#x}",
+                    entry.file.display(),
+                    prepared.inputs.len(),
+                    prepared.signer
+                );
+                let inputs = prepared.inputs.into_iter().enumerate();
+                tasks.extend(inputs.map(|(j, input)| (i, j, input)));
+                (Some(prepared.signer), None)
+            }
+            Err(e) => {
+                println!("Blob #{i} {}: {e}", entry.file.display());
+                (None, Some(e.to_string()))
+            }
+        };
+        blobs.push(BlobReport {
+            file: entry.file.clone(),
+            dir,
+            signer,
+            error,
+            ranges: Vec::new(),
+        });
+    }
+
+    // Workers take the next range of any blob, so one long blob does not hold the
+    // others up
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, usize, RangeReport)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..options.jobs.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while let Some(&(i, j, ref input)) =
+                        tasks.get(next.fetch_add(1, Ordering::Relaxed))
+                    {
+                        let blob = &blobs[i];
+                        let range = prove_range(config, cache, &blob.dir, blob.signer, i, j, input);
+                        done.push((i, j, range));
+                    }
+                    done
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("session worker panicked"))
+            .collect()
+    });
+    results.sort_by_key(|&(i, j, _)| (i, j));
+    for (i, _, range) in results {
+        blobs[i].ranges.push(range);
+    }
+    SessionReport { blobs }
+}
+
+fn prepare(
+    config: &Config,
+    entry: &SessionEntry,
+    options: &SessionOptions,
+    dir: &Path,
+) -> Result<Prepared> {
+    let blob = Bytes::from(read_input(&entry.file)?);
+    let signature = decode_signature(
+        read_input(&entry.signature_file)?,
+        &entry.signature_file.display().to_string(),
+    )?;
+    let (signature, signature_chain_id) = parse_signature_with_chain_id(&signature)?;
+    // Check the inputs against the recovered signer, but leave the guest to recover
+    // and commit it rather than expect it
+    let (signer, expected_signer) = match &entry.signer {
+        Some(signer) => (signer.resolve(config.rpc_url.as_deref())?, true),
+        None => (Envelope::Personal.recover(&blob, signature)?, false),
+    };
+    let inputs = match options.assert {
+        Assertion::ReportValidity => {
+            unchecked_inputs(&blob, signature, signer, &Envelope::Personal)?
+        }
+        Assertion::MustBeValid => prepare_inputs(&blob, signature, signer)?,
+    };
+    // Save the blob beside its receipts, so `submit --from` can send them later
+    fs::create_dir_all(dir)?;
+    let blob_path = dir.join("blob.json");
+    fs::write(&blob_path, &blob)
+        .map_err(|e| anyhow!("Failed writing {}: {e}", blob_path.display()))?;
+    let inputs = inputs
+        .into_iter()
+        .map(|input| Input {
+            signer: input.signer.filter(|_| expected_signer),
+            assert: options.assert,
+            extract_paths: options.extract_paths.clone(),
+            disclosure_salt: options.disclosure_salt,
+            signature_chain_id,
+            ..input
+        })
+        .collect();
+    Ok(Prepared { signer, inputs })
+}
+
+// Proves `input` unless the cache holds its receipt, and checks the receipt attests
+// `signer`, whether it was given or recovered; blobs of several signers share the cache
+fn prove_range(
+    config: &Config,
+    cache: Option<&ReceiptCache>,
+    dir: &Path,
+    signer: Option<Address>,
+    blob_index: usize,
+    index: usize,
+    input: &Input,
+) -> RangeReport {
+    let blob = &input.typed_data_concat;
+    let range = &input.digest_range;
+    let mut report = RangeReport {
+        range: range.clone(),
+        digest: None,
+        receipt: None,
+        cached: false,
+        attested_failure: None,
+        error: None,
+    };
+    let kind = config.receipt_kind;
+    let cached = cache.and_then(|cache| cache.get(SINGLE_SIGN_ID, input, kind));
+    report.cached = cached.is_some();
+    let proven = match cached {
+        Some(receipt) => {
+            println!("Blob #{blob_index} range #{index}: reusing cached receipt");
+            Ok(receipt)
+        }
+        None => {
+            println!("Blob #{blob_index} range #{index}: proving");
+            let _span = tracing::info_span!("prove", blob = blob_index, range = index).entered();
+            prove_framed(config, SINGLE_SIGN_ELF, SINGLE_SIGN_ID, input).and_then(|proof| {
+                if let Some(cache) = cache {
+                    cache.put(SINGLE_SIGN_ID, input, kind, &proof.receipt)?;
+                }
+                Ok(proof.receipt)
+            })
+        }
+    };
+    let saved = proven.and_then(|receipt| {
+        let output = check_receipt_against_blob(&receipt, blob, range, signer, SINGLE_SIGN_ID)?;
+        let receipt_path = dir.join(format!("receipt_{index}.bin"));
+        save_receipt(&receipt_path, &receipt)?;
+        Ok((output, receipt_path))
+    });
+    match saved {
+        Ok((output, receipt_path)) => {
+            match output.failure {
+                Some(failure) => report.attested_failure = Some(failure),
+                None => report.digest = Some(output.digest),
+            }
+            report.receipt = Some(receipt_path);
+        }
+        Err(e) => {
+            println!("Blob #{blob_index} range #{index} failed: {e}");
+            report.error = Some(e.to_string());
+        }
+    }
+    report
+}
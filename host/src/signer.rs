@@ -0,0 +1,53 @@
+use alloy_signer_local::{coins_bip39::English, MnemonicBuilder, PrivateKeySigner};
+use anyhow::{anyhow, Result};
+use std::{fmt, str::FromStr};
+
+/// Signer selection shared by every binary: a raw private key, or an HD wallet
+/// mnemonic plus BIP-44 derivation path. Neither secret appears in `--help` (which
+/// would otherwise echo the environment values) or in `Debug` output.
+#[derive(clap::Args)]
+pub struct SignerArgs {
+    /// Hex-encoded private key of the signing EOA
+    #[arg(long, env = "PRIVATE_KEY", hide_env_values = true, conflicts_with = "mnemonic")]
+    pub private_key: Option<String>,
+
+    /// BIP-39 mnemonic phrase of an HD wallet
+    #[arg(long, env = "MNEMONIC", hide_env_values = true)]
+    pub mnemonic: Option<String>,
+
+    /// BIP-44 derivation path of the account to use with --mnemonic
+    #[arg(long, default_value = "m/44'/60'/0'/0/0", requires = "mnemonic")]
+    pub derivation_path: String,
+}
+
+impl fmt::Debug for SignerArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |secret: &Option<String>| secret.as_ref().map(|_| "<redacted>");
+        f.debug_struct("SignerArgs")
+            .field("private_key", &redacted(&self.private_key))
+            .field("mnemonic", &redacted(&self.mnemonic))
+            .field("derivation_path", &self.derivation_path)
+            .finish()
+    }
+}
+
+impl SignerArgs {
+    /// Build the selected signer, or `None` if neither a key nor a mnemonic was given.
+    pub fn signer(&self) -> Result<Option<PrivateKeySigner>> {
+        if let Some(private_key) = &self.private_key {
+            let signer = PrivateKeySigner::from_str(private_key)
+                .map_err(|e| anyhow!("Invalid private key: {e}"))?;
+            return Ok(Some(signer));
+        }
+        let Some(mnemonic) = &self.mnemonic else {
+            return Ok(None);
+        };
+        let signer = MnemonicBuilder::<English>::default()
+            .phrase(mnemonic.as_str())
+            .derivation_path(self.derivation_path.as_str())
+            .map_err(|e| anyhow!("Invalid derivation path {}: {e}", self.derivation_path))?
+            .build()
+            .map_err(|e| anyhow!("Failed deriving signer from mnemonic: {e}"))?;
+        Ok(Some(signer))
+    }
+}
@@ -0,0 +1,86 @@
+//! Steel preflight and proving for the `single_sign_steel` guest, which proves
+//! on-chain preconditions on top of a single-range receipt. Enabled by the `steel`
+//! feature.
+
+use crate::{config::Config, prover::prove_with_assumption, rpc::block_on};
+use anyhow::{anyhow, bail, Result};
+use methods::{SINGLE_SIGN_STEEL_ELF, SINGLE_SIGN_STEEL_ID};
+use risc0_steel::{
+    ethereum::{
+        EthChainSpec, EthEvmEnv, EthEvmInput, ETH_MAINNET_CHAIN_SPEC, ETH_SEPOLIA_CHAIN_SPEC,
+    },
+    Contract,
+};
+use risc0_zkvm::{sha::Digest, Receipt};
+use single_sign_types::precondition::{permit2_word_pos, IPermit2, IERC20, Precondition};
+
+/// Chains the guest has a chain spec for.
+fn chain_spec(chain_id: u64) -> Result<&'static EthChainSpec> {
+    match chain_id {
+        1 => Ok(&ETH_MAINNET_CHAIN_SPEC),
+        11155111 => Ok(&ETH_SEPOLIA_CHAIN_SPEC),
+        _ => bail!("Steel preconditions are not supported on chain {chain_id}"),
+    }
+}
+
+/// Make every precondition's view call against the latest block at `rpc_url`, failing
+/// if one does not hold, and return the EVM state the guest replays them from.
+pub fn preflight(
+    rpc_url: &str,
+    chain_id: u64,
+    preconditions: &[Precondition],
+) -> Result<EthEvmInput> {
+    let chain_spec = chain_spec(chain_id)?;
+    block_on(async {
+        let mut env = EthEvmEnv::builder()
+            .rpc(rpc_url.parse()?)
+            .chain_spec(chain_spec)
+            .build()
+            .await?;
+        for precondition in preconditions {
+            let returned = match precondition {
+                Precondition::MinBalance { token, owner, .. } => {
+                    let call = IERC20::balanceOfCall { account: *owner };
+                    let mut contract = Contract::preflight(*token, &mut env);
+                    contract.call_builder(&call).call().await?
+                }
+                Precondition::Permit2NonceUnused {
+                    permit2,
+                    owner,
+                    nonce,
+                } => {
+                    let call = IPermit2::nonceBitmapCall {
+                        owner: *owner,
+                        wordPos: permit2_word_pos(*nonce),
+                    };
+                    let mut contract = Contract::preflight(*permit2, &mut env);
+                    contract.call_builder(&call).call().await?
+                }
+            };
+            if !precondition.holds(returned) {
+                bail!("Precondition does not hold: {precondition:?}");
+            }
+        }
+        Ok(env.into_input().await?)
+    })?
+}
+
+/// Prove `preconditions` on top of `receipt`, a receipt of the guest `image_id`, so
+/// the new receipt attests both the signature and the chain state at one block.
+pub fn prove_preconditions(
+    config: &Config,
+    chain_id: u64,
+    receipt: &Receipt,
+    image_id: Digest,
+    preconditions: Vec<Precondition>,
+) -> Result<Receipt> {
+    let rpc_url = config
+        .rpc_url
+        .as_deref()
+        .ok_or_else(|| anyhow!("Proving preconditions requires rpc_url"))?;
+    let evm_input = preflight(rpc_url, chain_id, &preconditions)?;
+    let input = (image_id, receipt.journal.bytes.clone(), chain_id, preconditions, evm_input);
+    let proof = prove_with_assumption(config, SINGLE_SIGN_STEEL_ELF, &input, receipt.clone())?;
+    proof.receipt.verify(SINGLE_SIGN_STEEL_ID)?;
+    Ok(proof.receipt)
+}
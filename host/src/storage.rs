@@ -0,0 +1,266 @@
+//! Content-addressed storage of signed blobs, shared by the `store` and `watcher`
+//! binaries. A blob is stored under its keccak256 as `<hash>.json`, with its EIP-191
+//! signature beside it as `<hash>.sig` in hex, on the local filesystem, in an S3
+//! bucket, or in the MFS of an IPFS (Kubo) node, where it is also pinned. Read-only
+//! HTTP bases cover public buckets and IPFS gateways. Fetched blobs are checked
+//! against the hash they were asked for, and `check_blob_hash` matches them to the
+//! `blob_hash` a journal commits.
+
+use crate::input::decode_signature;
+#[cfg(feature = "s3")]
+use crate::rpc::block_on;
+use alloy_primitives::{hex, keccak256, Bytes, B256};
+use anyhow::{anyhow, bail, Result};
+use single_sign_types::Output;
+use std::{fmt, fs, path::PathBuf, str::FromStr};
+
+/// MFS directory blobs are written to on an IPFS node.
+const IPFS_DIR: &str = "/single-sign";
+
+/// Where blobs are stored, parsed from a directory path, `http(s)://` base URL,
+/// `s3://bucket/prefix`, `ipfs://<cid>` (a directory, read through a public gateway)
+/// or `ipfs+http(s)://` URL of a Kubo RPC API.
+#[derive(Debug, Clone)]
+pub enum Storage {
+    Dir(PathBuf),
+    /// Read-only
+    Http(String),
+    /// Credentials and region come from the AWS environment; without the `s3`
+    /// feature the bucket is read-only, through its public URL
+    S3 {
+        bucket: String,
+        prefix: String,
+    },
+    Ipfs {
+        api: String,
+    },
+}
+
+impl FromStr for Storage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(path) = s.strip_prefix("s3://") {
+            let (bucket, prefix) = path.split_once('/').unwrap_or((path, ""));
+            if bucket.is_empty() {
+                bail!("{s} names no bucket");
+            }
+            return Ok(Self::S3 {
+                bucket: bucket.to_owned(),
+                prefix: prefix.trim_matches('/').to_owned(),
+            });
+        }
+        if let Some(api) = s.strip_prefix("ipfs+") {
+            return Ok(Self::Ipfs {
+                api: api.trim_end_matches('/').to_owned(),
+            });
+        }
+        let url = if let Some(cid) = s.strip_prefix("ipfs://") {
+            format!("https://ipfs.io/ipfs/{cid}")
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            s.to_owned()
+        } else {
+            return Ok(Self::Dir(PathBuf::from(s)));
+        };
+        Ok(Self::Http(url.trim_end_matches('/').to_owned()))
+    }
+}
+
+impl fmt::Display for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dir(dir) => write!(f, "{}", dir.display()),
+            Self::Http(url) => write!(f, "{url}"),
+            Self::S3 { bucket, prefix } => write!(f, "s3://{bucket}/{prefix}"),
+            Self::Ipfs { api } => write!(f, "ipfs+{api}"),
+        }
+    }
+}
+
+impl Storage {
+    /// The blob with keccak256 `blob_hash`, refusing one that hashes to anything else.
+    pub fn fetch_blob(&self, blob_hash: B256) -> Result<Bytes> {
+        let name = format!("{blob_hash:x}.json");
+        let blob = self.get(&name)?;
+        if keccak256(&blob) != blob_hash {
+            bail!("{name} in {self} does not hash to {blob_hash}");
+        }
+        Ok(blob.into())
+    }
+
+    /// The blob with keccak256 `blob_hash` and its signature.
+    pub fn fetch(&self, blob_hash: B256) -> Result<(Bytes, Bytes)> {
+        let blob = self.fetch_blob(blob_hash)?;
+        let name = format!("{blob_hash:x}.sig");
+        let signature = decode_signature(self.get(&name)?, &name)?;
+        Ok((blob, signature))
+    }
+
+    /// Store `blob`, and its signature if given, returning the blob's hash. Storing a
+    /// blob that is already there rewrites the same bytes.
+    pub fn pin(&self, blob: &[u8], signature: Option<&[u8]>) -> Result<B256> {
+        let blob_hash = keccak256(blob);
+        self.put(&format!("{blob_hash:x}.json"), blob)?;
+        if let Some(signature) = signature {
+            let signature = hex::encode_prefixed(signature);
+            self.put(&format!("{blob_hash:x}.sig"), signature.as_bytes())?;
+        }
+        Ok(blob_hash)
+    }
+
+    fn get(&self, name: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Dir(dir) => {
+                let path = dir.join(name);
+                fs::read(&path).map_err(|e| anyhow!("Failed reading {}: {e}", path.display()))
+            }
+            Self::Http(base) => http_get(&format!("{base}/{name}")),
+            #[cfg(feature = "s3")]
+            Self::S3 { bucket, prefix } => s3_get(bucket, &s3_key(prefix, name)),
+            #[cfg(not(feature = "s3"))]
+            Self::S3 { bucket, prefix } => http_get(&format!(
+                "https://{bucket}.s3.amazonaws.com/{}",
+                s3_key(prefix, name)
+            )),
+            Self::Ipfs { api } => {
+                let path = format!("{IPFS_DIR}/{name}");
+                ipfs_call(api, "files/read", &[("arg", path.as_str())], None)
+            }
+        }
+    }
+
+    fn put(&self, name: &str, bytes: &[u8]) -> Result<()> {
+        match self {
+            Self::Dir(dir) => {
+                fs::create_dir_all(dir)?;
+                let path = dir.join(name);
+                fs::write(&path, bytes)
+                    .map_err(|e| anyhow!("Failed writing {}: {e}", path.display()))
+            }
+            Self::Http(base) => bail!("{base} is read-only"),
+            #[cfg(feature = "s3")]
+            Self::S3 { bucket, prefix } => s3_put(bucket, &s3_key(prefix, name), bytes),
+            #[cfg(not(feature = "s3"))]
+            Self::S3 { .. } => bail!("Writing to S3 requires the `s3` feature"),
+            Self::Ipfs { api } => {
+                let path = format!("{IPFS_DIR}/{name}");
+                let args = [
+                    ("arg", path.as_str()),
+                    ("create", "true"),
+                    ("parents", "true"),
+                    ("truncate", "true"),
+                ];
+                ipfs_call(api, "files/write", &args, Some(bytes.to_vec()))?;
+                // MFS keeps the file from garbage collection only while it is linked
+                // there; pin its CID so removing it from MFS does not drop it
+                let stat = ipfs_call(api, "files/stat", &[("arg", path.as_str())], None)?;
+                let stat: serde_json::Value = serde_json::from_slice(&stat)?;
+                let cid = stat["Hash"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("{api} reported no CID for {path}"))?;
+                ipfs_call(api, "pin/add", &[("arg", cid)], None)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Check that every entry of a journal proven over `blob` commits its hash, so a blob
+/// fetched from storage is the one the receipt attests.
+pub fn check_blob_hash(blob: &[u8], outputs: &[Output]) -> Result<()> {
+    let blob_hash = keccak256(blob);
+    for (i, output) in outputs.iter().enumerate() {
+        if output.blob_hash != blob_hash {
+            bail!(
+                "Entry #{i} was proven over blob {}, not {blob_hash}",
+                output.blob_hash
+            );
+        }
+    }
+    Ok(())
+}
+
+fn s3_key(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{prefix}/{name}")
+    }
+}
+
+fn http_get(url: &str) -> Result<Vec<u8>> {
+    let response = reqwest::blocking::get(url)
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| anyhow!("Failed fetching {url}: {e}"))?;
+    Ok(response
+        .bytes()
+        .map_err(|e| anyhow!("Failed reading {url}: {e}"))?
+        .to_vec())
+}
+
+// Kubo's RPC API takes every call as a POST, with file contents as multipart data
+fn ipfs_call(
+    api: &str,
+    method: &str,
+    args: &[(&str, &str)],
+    data: Option<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    let url = format!("{api}/api/v0/{method}");
+    let mut request = reqwest::blocking::Client::new().post(&url).query(args);
+    if let Some(data) = data {
+        let part = reqwest::blocking::multipart::Part::bytes(data);
+        request = request.multipart(reqwest::blocking::multipart::Form::new().part("file", part));
+    }
+    let response = request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| anyhow!("IPFS {method} failed at {api}: {e}"))?;
+    Ok(response
+        .bytes()
+        .map_err(|e| anyhow!("Failed reading IPFS {method} from {api}: {e}"))?
+        .to_vec())
+}
+
+#[cfg(feature = "s3")]
+async fn s3_client() -> aws_sdk_s3::Client {
+    let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+        .load()
+        .await;
+    aws_sdk_s3::Client::new(&config)
+}
+
+#[cfg(feature = "s3")]
+fn s3_get(bucket: &str, key: &str) -> Result<Vec<u8>> {
+    block_on(async {
+        let object = s3_client()
+            .await
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed fetching s3://{bucket}/{key}: {e}"))?;
+        let body = object
+            .body
+            .collect()
+            .await
+            .map_err(|e| anyhow!("Failed reading s3://{bucket}/{key}: {e}"))?;
+        anyhow::Ok(body.into_bytes().to_vec())
+    })?
+}
+
+#[cfg(feature = "s3")]
+fn s3_put(bucket: &str, key: &str, bytes: &[u8]) -> Result<()> {
+    block_on(async {
+        s3_client()
+            .await
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .body(aws_sdk_s3::primitives::ByteStream::from(bytes.to_vec()))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed writing s3://{bucket}/{key}: {e}"))?;
+        anyhow::Ok(())
+    })?
+}
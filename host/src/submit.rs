@@ -0,0 +1,672 @@
+//! Submit one batch receipt to several chains. A blob may hold objects signed for
+//! different chains, so `group_by_chain` splits the proven digests by the chain ID in
+//! each object's domain, and `submit_all` sends every chain its own `consume` call
+//! through the endpoint configured for it. Each transaction's confirmations are
+//! tracked independently: a chain that stalls or reverts does not hold up the others.
+//!
+//! The local key need not hold gas on every chain. Along `Route::Bundler` each call
+//! becomes a UserOperation of a smart account the key owns, sent to the chain's
+//! ERC-4337 bundler and optionally sponsored by an ERC-7677 paymaster; along
+//! `Route::Relayer` the chain's relayer sends it and pays for it.
+//!
+//! `Route::Private` keeps consume calls, which MEV searchers could front-run, out of
+//! the public mempool by sending them to a private RPC such as Flashbots Protect.
+
+use crate::{config::Endpoint, rpc::block_on};
+use alloy_primitives::{aliases::U192, hex, Address, Bytes, TxHash, B256, U256};
+use alloy_provider::{
+    network::{Ethereum, EthereumWallet, Network, ReceiptResponse, TransactionBuilder},
+    utils::Eip1559Estimation,
+    Provider, ProviderBuilder,
+};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{sol, SolCall};
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use single_sign_types::{user_op::UserOperation, DigestRange, Output};
+use std::{collections::BTreeMap, fmt, time::Duration};
+use tokio::{task::JoinSet, time::Instant};
+use tracing::Instrument;
+
+sol! {
+    interface ISingleSignConsumer {
+        /// Verify `seal` over the batch `journal` and act on `digests`, the entries
+        /// proven for this chain.
+        function consume(bytes seal, bytes journal, bytes32[] digests) external;
+    }
+
+    interface IBatchVerifier {
+        /// Call `consumer.consume` with every proof of `bundle`, see `host::bundle`.
+        function consume(address consumer, bytes bundle) external returns (uint256);
+    }
+
+    interface IEntryPoint {
+        function getNonce(address sender, uint192 key) external view returns (uint256);
+    }
+
+    interface ISimpleAccount {
+        function execute(address dest, uint256 value, bytes func) external;
+    }
+}
+
+// Between receipt polls of relayed transactions and UserOperations
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// How long a sent transaction or UserOperation may take to confirm before it is reported
+// as failed, so one stalled chain cannot hold up `submit_all`
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(600);
+
+// How long `send` waits for a transaction to be mined before replacing it at the same
+// nonce with higher fees, and how many times it raises them
+const REPLACE_AFTER: Duration = Duration::from_secs(120);
+const MAX_FEE_BUMPS: usize = 4;
+
+// Blocks Flashbots Protect keeps trying to include a private transaction
+const PRIVATE_TX_BLOCKS: u64 = 25;
+
+// Signature that recovers to some address without reverting, so bundlers can
+// simulate a UserOperation before it is signed
+const DUMMY_SIGNATURE: [u8; 65] = hex!(
+    "fffffffffffffffffffffffffffffff000000000000000000000000000000000"
+    "7aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c"
+);
+
+/// Who sends each chain's transaction and pays for its gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    /// The local key, from its own balance.
+    Direct,
+    /// `account`, a smart account the local key owns that executes calls as
+    /// `execute(dest, value, func)`, through the chain's `bundler_url`. With a
+    /// `paymaster_url` the paymaster sponsors the gas; otherwise `account` pays.
+    Bundler {
+        account: Address,
+        entry_point: Address,
+    },
+    /// The chain's `relayer_url`, which answers `relay_sendTransaction({chainId, to,
+    /// data})` with the hash of the transaction it sent.
+    Relayer,
+    /// The local key, through the chain's `private_rpc_url` (e.g. Flashbots Protect,
+    /// whose URL also selects MEV-Share hints), after simulating the call publicly.
+    Private,
+}
+
+/// Digests of the attested entries of a batch journal, by the chain ID of the typed
+/// data they cover. Failure entries are left out.
+pub fn group_by_chain(
+    blob: &[u8],
+    ranges: &[DigestRange],
+    outputs: &[Output],
+) -> Result<BTreeMap<u64, Vec<B256>>> {
+    let mut groups: BTreeMap<u64, Vec<B256>> = BTreeMap::new();
+    for (range, output) in ranges.iter().zip(outputs) {
+        if !output.is_ok() {
+            continue;
+        }
+        let typed_data = range
+            .typed_data(blob, false)
+            .map_err(|code| anyhow!("Range {range}: {code}"))?;
+        let chain_id = typed_data
+            .domain
+            .chain_id
+            .ok_or_else(|| anyhow!("Range {range} has no chainId to submit to"))?;
+        let chain_id =
+            u64::try_from(chain_id).map_err(|_| anyhow!("Range {range} has chainId {chain_id}"))?;
+        groups.entry(chain_id).or_default().push(output.digest);
+    }
+    Ok(groups)
+}
+
+/// How one chain's submission ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Confirmed {
+        tx_hash: TxHash,
+        block_number: Option<u64>,
+    },
+    Reverted {
+        tx_hash: TxHash,
+    },
+    /// No transaction was sent, or it could not be tracked to a receipt.
+    Failed(String),
+}
+
+impl Status {
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self, Status::Confirmed { .. })
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Confirmed {
+                tx_hash,
+                block_number: Some(block_number),
+            } => write!(f, "confirmed {tx_hash} in block {block_number}"),
+            Status::Confirmed { tx_hash, .. } => write!(f, "confirmed {tx_hash}"),
+            Status::Reverted { tx_hash } => write!(f, "reverted {tx_hash}"),
+            Status::Failed(e) => write!(f, "failed: {e}"),
+        }
+    }
+}
+
+/// Send every chain in `groups` its `consume` call along `route`, concurrently, and
+/// wait for `confirmations` blocks on each. Chains without an endpoint fail without
+/// a transaction.
+pub fn submit_all(
+    endpoints: &BTreeMap<String, Endpoint>,
+    signer: &PrivateKeySigner,
+    route: Route,
+    seal: Bytes,
+    journal: Bytes,
+    groups: BTreeMap<u64, Vec<B256>>,
+    confirmations: u64,
+) -> Result<BTreeMap<u64, Status>> {
+    block_on(async {
+        let mut tasks = JoinSet::new();
+        for (chain_id, digests) in groups {
+            let endpoint = endpoints.get(&chain_id.to_string()).cloned();
+            let call = ISingleSignConsumer::consumeCall {
+                seal: seal.clone(),
+                journal: journal.clone(),
+                digests,
+            };
+            let signer = signer.clone();
+            // Each chain's submission is its own span, under the caller's
+            let span = tracing::info_span!("submit", chain_id);
+            let task = async move {
+                let status = match endpoint {
+                    Some(endpoint) => submit(chain_id, endpoint, signer, route, call, confirmations)
+                        .await
+                        .unwrap_or_else(|e| Status::Failed(e.to_string())),
+                    None => Status::Failed(format!("no endpoint configured for chain {chain_id}")),
+                };
+                (chain_id, status)
+            };
+            tasks.spawn(task.instrument(span));
+        }
+        let mut statuses = BTreeMap::new();
+        while let Some(joined) = tasks.join_next().await {
+            let (chain_id, status) = joined?;
+            tracing::info!(chain_id, "Submission {status}");
+            statuses.insert(chain_id, status);
+        }
+        Ok(statuses)
+    })?
+}
+
+async fn submit(
+    chain_id: u64,
+    endpoint: Endpoint,
+    signer: PrivateKeySigner,
+    route: Route,
+    call: ISingleSignConsumer::consumeCall,
+    confirmations: u64,
+) -> Result<Status> {
+    let provider = ProviderBuilder::new().connect_http(endpoint.rpc_url.parse()?);
+    // A misconfigured endpoint would otherwise send the call to the wrong chain
+    let connected = provider.get_chain_id().await?;
+    if connected != chain_id {
+        bail!("Endpoint for chain {chain_id} serves chain {connected}");
+    }
+    match route {
+        Route::Direct => {
+            send(&endpoint, signer, endpoint.consumer, call.abi_encode(), confirmations).await
+        }
+        Route::Relayer => relay(chain_id, &endpoint, &provider, call, confirmations).await,
+        Route::Private => send_private(&endpoint, &provider, signer, call, confirmations).await,
+        Route::Bundler {
+            account,
+            entry_point,
+        } => {
+            let user_op = UserOpSubmission {
+                chain_id,
+                account,
+                entry_point,
+            };
+            user_op
+                .submit(&endpoint, &provider, &signer, call, confirmations)
+                .await
+        }
+    }
+}
+
+/// Send `bundle` (see `host::bundle`) from the local key to the chain's
+/// `batch_verifier`, which hands every proof in it to the chain's consumer in one
+/// transaction.
+pub fn submit_bundle(
+    chain_id: u64,
+    endpoint: &Endpoint,
+    signer: &PrivateKeySigner,
+    bundle: Bytes,
+    confirmations: u64,
+) -> Result<Status> {
+    let batch_verifier = endpoint
+        .batch_verifier
+        .ok_or_else(|| anyhow!("No batch_verifier configured for chain {chain_id}"))?;
+    let call = IBatchVerifier::consumeCall {
+        consumer: endpoint.consumer,
+        bundle,
+    };
+    block_on(async {
+        let provider = ProviderBuilder::new().connect_http(endpoint.rpc_url.parse()?);
+        let connected = provider.get_chain_id().await?;
+        if connected != chain_id {
+            bail!("Endpoint for chain {chain_id} serves chain {connected}");
+        }
+        let input = call.abi_encode();
+        send(endpoint, signer.clone(), batch_verifier, input, confirmations).await
+    })?
+}
+
+// Send from the local key, with the gas limit and nonce fixed up front and the fees
+// capped at the endpoint's `max_fee_per_gas`. A transaction refused as underpriced, or
+// not mined within `REPLACE_AFTER`, is replaced at the same nonce with higher fees, up to
+// `MAX_FEE_BUMPS` times.
+async fn send(
+    endpoint: &Endpoint,
+    signer: PrivateKeySigner,
+    to: Address,
+    input: Vec<u8>,
+    confirmations: u64,
+) -> Result<Status> {
+    let from = signer.address();
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .connect_http(endpoint.rpc_url.parse()?);
+    let tx = <Ethereum as Network>::TransactionRequest::default()
+        .with_from(from)
+        .with_to(to)
+        .with_input(input);
+    let gas_limit = provider
+        .estimate_gas(tx.clone())
+        .await
+        .map_err(|e| anyhow!("Gas estimation failed, not sending: {e}"))?;
+    let nonce = provider.get_transaction_count(from).pending().await?;
+    let tx = tx.with_gas_limit(gas_limit).with_nonce(nonce);
+    let mut fees = capped(provider.estimate_eip1559_fees().await?, endpoint.max_fee_per_gas);
+
+    let mut sent = Vec::new();
+    for _ in 0..=MAX_FEE_BUMPS {
+        let request = tx
+            .clone()
+            .with_max_fee_per_gas(fees.max_fee_per_gas)
+            .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+        match provider.send_transaction(request).await {
+            Ok(pending) => {
+                let tx_hash = *pending.tx_hash();
+                tracing::info!(
+                    %tx_hash,
+                    max_fee_per_gas = fees.max_fee_per_gas,
+                    "Submitted, waiting for {confirmations} confirmations"
+                );
+                sent.push(tx_hash);
+                if let Some(status) =
+                    wait_for_any(&provider, &sent, confirmations, REPLACE_AFTER).await?
+                {
+                    return Ok(status);
+                }
+            }
+            Err(e) if is_underpriced(&e.to_string()) => {
+                tracing::warn!("Refused as underpriced: {e}");
+            }
+            // One of the transactions already sent at this nonce was mined meanwhile
+            Err(e) if !sent.is_empty() && e.to_string().contains("nonce too low") => {
+                let status = wait_for_any(&provider, &sent, confirmations, REPLACE_AFTER).await?;
+                return Ok(status.unwrap_or_else(|| {
+                    Status::Failed(format!("nonce {nonce} used by a transaction not sent here"))
+                }));
+            }
+            Err(e) => return Err(e.into()),
+        }
+        match bump(fees, endpoint.max_fee_per_gas) {
+            Some(bumped) => {
+                tracing::warn!(max_fee_per_gas = bumped.max_fee_per_gas, "Raising fees");
+                fees = bumped;
+            }
+            None => break,
+        }
+    }
+    Ok(Status::Failed(format!(
+        "not mined at nonce {nonce} with fees up to {} wei per gas",
+        fees.max_fee_per_gas
+    )))
+}
+
+// The estimate, held to `cap`
+fn capped(fees: Eip1559Estimation, cap: Option<u128>) -> Eip1559Estimation {
+    let cap = cap.unwrap_or(u128::MAX);
+    Eip1559Estimation {
+        max_fee_per_gas: fees.max_fee_per_gas.min(cap),
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas.min(cap),
+    }
+}
+
+// Both fees raised by a fifth, past the tenth nodes require of a replacement, or None
+// once the max fee is at `cap`
+fn bump(fees: Eip1559Estimation, cap: Option<u128>) -> Option<Eip1559Estimation> {
+    if cap.is_some_and(|cap| fees.max_fee_per_gas >= cap) {
+        return None;
+    }
+    let raise = |fee: u128| fee.saturating_add((fee / 5).max(1));
+    Some(capped(
+        Eip1559Estimation {
+            max_fee_per_gas: raise(fees.max_fee_per_gas),
+            max_priority_fee_per_gas: raise(fees.max_priority_fee_per_gas),
+        },
+        cap,
+    ))
+}
+
+// Node errors for fees too low to enter the pool or to replace a pending transaction
+fn is_underpriced(error: &str) -> bool {
+    let error = error.to_lowercase();
+    error.contains("underpriced") || error.contains("fee too low") || error.contains("base fee")
+}
+
+async fn send_private(
+    endpoint: &Endpoint,
+    provider: &impl Provider,
+    signer: PrivateKeySigner,
+    call: ISingleSignConsumer::consumeCall,
+    confirmations: u64,
+) -> Result<Status> {
+    let private_rpc_url = endpoint
+        .private_rpc_url
+        .as_deref()
+        .ok_or_else(|| anyhow!("No private_rpc_url configured for the endpoint"))?;
+    let tx = <Ethereum as Network>::TransactionRequest::default()
+        .with_to(endpoint.consumer)
+        .with_input(call.abi_encode());
+    // A private transaction that reverts once included still pays for its gas
+    provider
+        .call(tx.clone().with_from(signer.address()))
+        .await
+        .map_err(|e| anyhow!("Simulation failed, not sending: {e}"))?;
+
+    // The private RPC fills the nonce and fees and receives the raw transaction, so it
+    // never reaches the public mempool
+    let private = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer))
+        .connect_http(private_rpc_url.parse()?);
+    let pending = private.send_transaction(tx).await?;
+    let tx_hash = *pending.tx_hash();
+    let sent_at = provider.get_block_number().await?;
+    tracing::info!(%tx_hash, "Sent privately, waiting for inclusion");
+    loop {
+        if provider.get_transaction_receipt(tx_hash).await?.is_some() {
+            return wait_for(provider, tx_hash, confirmations).await;
+        }
+        let latest = provider.get_block_number().await?;
+        if latest > sent_at + PRIVATE_TX_BLOCKS {
+            return Ok(Status::Failed(format!(
+                "{tx_hash} not included within {PRIVATE_TX_BLOCKS} blocks"
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn relay(
+    chain_id: u64,
+    endpoint: &Endpoint,
+    provider: &impl Provider,
+    call: ISingleSignConsumer::consumeCall,
+    confirmations: u64,
+) -> Result<Status> {
+    let relayer_url = endpoint
+        .relayer_url
+        .as_deref()
+        .ok_or_else(|| anyhow!("No relayer_url configured for chain {chain_id}"))?;
+    let relayer = ProviderBuilder::new().connect_http(relayer_url.parse()?);
+    let request = json!({
+        "chainId": chain_id,
+        "to": endpoint.consumer,
+        "data": Bytes::from(call.abi_encode()),
+    });
+    let tx_hash: TxHash = relayer
+        .raw_request("relay_sendTransaction".into(), (request,))
+        .await?;
+    tracing::info!(%tx_hash, "Relayed, waiting for {confirmations} confirmations");
+    wait_for(provider, tx_hash, confirmations).await
+}
+
+/// A `consume` call on its way through an ERC-4337 bundler.
+struct UserOpSubmission {
+    chain_id: u64,
+    account: Address,
+    entry_point: Address,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GasEstimate {
+    pre_verification_gas: U256,
+    verification_gas_limit: U256,
+    call_gas_limit: U256,
+    paymaster_verification_gas_limit: Option<U256>,
+    paymaster_post_op_gas_limit: Option<U256>,
+}
+
+// ERC-7677 `pm_getPaymasterStubData` and `pm_getPaymasterData` results
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PaymasterData {
+    paymaster: Address,
+    paymaster_data: Bytes,
+    paymaster_verification_gas_limit: Option<U256>,
+    paymaster_post_op_gas_limit: Option<U256>,
+}
+
+#[derive(Deserialize)]
+struct UserOperationReceipt {
+    success: bool,
+    receipt: BundleReceipt,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleReceipt {
+    transaction_hash: TxHash,
+}
+
+impl UserOpSubmission {
+    async fn submit(
+        &self,
+        endpoint: &Endpoint,
+        provider: &impl Provider,
+        signer: &PrivateKeySigner,
+        call: ISingleSignConsumer::consumeCall,
+        confirmations: u64,
+    ) -> Result<Status> {
+        let bundler_url = endpoint
+            .bundler_url
+            .as_deref()
+            .ok_or_else(|| anyhow!("No bundler_url configured for chain {}", self.chain_id))?;
+        let bundler = ProviderBuilder::new().connect_http(bundler_url.parse()?);
+
+        // The seal rides in the account's `execute` call to the consumer
+        let execute = ISimpleAccount::executeCall {
+            dest: endpoint.consumer,
+            value: U256::ZERO,
+            func: call.abi_encode().into(),
+        };
+        let get_nonce = IEntryPoint::getNonceCall {
+            sender: self.account,
+            key: U192::ZERO,
+        };
+        let tx = <Ethereum as Network>::TransactionRequest::default()
+            .with_to(self.entry_point)
+            .with_input(get_nonce.abi_encode());
+        let nonce = IEntryPoint::getNonceCall::abi_decode_returns(&provider.call(tx).await?)?;
+        let fees = provider.estimate_eip1559_fees().await?;
+        let mut user_op = UserOperation {
+            sender: self.account,
+            nonce,
+            call_data: execute.abi_encode().into(),
+            max_fee_per_gas: U256::from(fees.max_fee_per_gas),
+            max_priority_fee_per_gas: U256::from(fees.max_priority_fee_per_gas),
+            ..Default::default()
+        };
+
+        // Estimate with the paymaster's stub in place, then have it sign the final values
+        if let Some(paymaster_url) = &endpoint.paymaster_url {
+            self.sponsor(paymaster_url, "pm_getPaymasterStubData", &mut user_op)
+                .await?;
+        }
+        let estimate: GasEstimate = bundler
+            .raw_request(
+                "eth_estimateUserOperationGas".into(),
+                (request(&user_op, &DUMMY_SIGNATURE)?, self.entry_point),
+            )
+            .await?;
+        user_op.pre_verification_gas = estimate.pre_verification_gas;
+        user_op.verification_gas_limit = estimate.verification_gas_limit;
+        user_op.call_gas_limit = estimate.call_gas_limit;
+        if user_op.paymaster.is_some() {
+            user_op.paymaster_verification_gas_limit = estimate
+                .paymaster_verification_gas_limit
+                .or(user_op.paymaster_verification_gas_limit);
+            user_op.paymaster_post_op_gas_limit = estimate
+                .paymaster_post_op_gas_limit
+                .or(user_op.paymaster_post_op_gas_limit);
+        }
+        if let Some(paymaster_url) = &endpoint.paymaster_url {
+            self.sponsor(paymaster_url, "pm_getPaymasterData", &mut user_op)
+                .await?;
+        }
+
+        // Accounts in the `SimpleAccount` mould check an EIP-191 signature of the hash
+        let user_op_hash = user_op.hash(self.entry_point, self.chain_id)?;
+        let signature = signer.sign_message_sync(user_op_hash.as_slice())?;
+        let sent: B256 = bundler
+            .raw_request(
+                "eth_sendUserOperation".into(),
+                (request(&user_op, &signature.as_bytes())?, self.entry_point),
+            )
+            .await?;
+        if sent != user_op_hash {
+            bail!("Bundler returned userOpHash {sent}, expected {user_op_hash}");
+        }
+        tracing::info!(%user_op_hash, "Sent UserOperation, waiting for its bundle");
+
+        let deadline = Instant::now() + CONFIRMATION_TIMEOUT;
+        let receipt = loop {
+            let receipt: Option<UserOperationReceipt> = bundler
+                .raw_request("eth_getUserOperationReceipt".into(), (user_op_hash,))
+                .await?;
+            if let Some(receipt) = receipt {
+                break receipt;
+            }
+            if Instant::now() >= deadline {
+                return Ok(Status::Failed(format!(
+                    "UserOperation {user_op_hash} not bundled within {}s",
+                    CONFIRMATION_TIMEOUT.as_secs()
+                )));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        };
+        let tx_hash = receipt.receipt.transaction_hash;
+        // The bundle can succeed while the operation inside it reverts
+        if !receipt.success {
+            return Ok(Status::Reverted { tx_hash });
+        }
+        wait_for(provider, tx_hash, confirmations).await
+    }
+
+    async fn sponsor(
+        &self,
+        paymaster_url: &str,
+        method: &'static str,
+        user_op: &mut UserOperation,
+    ) -> Result<()> {
+        let paymaster = ProviderBuilder::new().connect_http(paymaster_url.parse()?);
+        let params = (
+            request(user_op, &DUMMY_SIGNATURE)?,
+            self.entry_point,
+            format!("{:#x}", self.chain_id),
+            json!({}),
+        );
+        let data: PaymasterData = paymaster.raw_request(method.into(), params).await?;
+        user_op.paymaster = Some(data.paymaster);
+        user_op.paymaster_data = Some(data.paymaster_data);
+        if data.paymaster_verification_gas_limit.is_some() {
+            user_op.paymaster_verification_gas_limit = data.paymaster_verification_gas_limit;
+        }
+        if data.paymaster_post_op_gas_limit.is_some() {
+            user_op.paymaster_post_op_gas_limit = data.paymaster_post_op_gas_limit;
+        }
+        Ok(())
+    }
+}
+
+// `user_op` in the RPC format, with `signature` and without unset optional fields
+fn request(user_op: &UserOperation, signature: &[u8]) -> Result<Value> {
+    let mut request = serde_json::to_value(user_op)?;
+    let fields = request
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("UserOperation did not serialize to an object"))?;
+    fields.retain(|_, value| !value.is_null());
+    fields.insert("signature".to_owned(), hex::encode_prefixed(signature).into());
+    Ok(request)
+}
+
+// Poll until `tx_hash` is mined and `confirmations` blocks deep, failing after
+// `CONFIRMATION_TIMEOUT`
+async fn wait_for(provider: &impl Provider, tx_hash: TxHash, confirmations: u64) -> Result<Status> {
+    let status = wait_for_any(provider, &[tx_hash], confirmations, CONFIRMATION_TIMEOUT).await?;
+    Ok(status.unwrap_or_else(|| {
+        Status::Failed(format!(
+            "{tx_hash} not mined within {}s",
+            CONFIRMATION_TIMEOUT.as_secs()
+        ))
+    }))
+}
+
+// Poll until one of `sent`, transactions replacing each other at one nonce, is mined and
+// `confirmations` blocks deep. None when none is mined within `timeout`; once one is, it
+// has `CONFIRMATION_TIMEOUT` more to get deep enough before it is reported as failed.
+async fn wait_for_any(
+    provider: &impl Provider,
+    sent: &[TxHash],
+    confirmations: u64,
+    timeout: Duration,
+) -> Result<Option<Status>> {
+    let mut deadline = Instant::now() + timeout;
+    let mut mined = None;
+    loop {
+        for &tx_hash in sent {
+            let Some(receipt) = provider.get_transaction_receipt(tx_hash).await? else {
+                continue;
+            };
+            if mined.is_none() {
+                mined = Some(tx_hash);
+                deadline = Instant::now() + CONFIRMATION_TIMEOUT;
+            }
+            let block_number = receipt.block_number.unwrap_or_default();
+            let latest = provider.get_block_number().await?;
+            if latest + 1 >= block_number + confirmations {
+                return Ok(Some(if receipt.status() {
+                    Status::Confirmed {
+                        tx_hash,
+                        block_number: receipt.block_number,
+                    }
+                } else {
+                    Status::Reverted { tx_hash }
+                }));
+            }
+        }
+        if Instant::now() >= deadline {
+            return Ok(mined.map(|tx_hash| {
+                Status::Failed(format!(
+                    "{tx_hash} not {confirmations} blocks deep within {}s of being mined",
+                    CONFIRMATION_TIMEOUT.as_secs()
+                ))
+            }));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
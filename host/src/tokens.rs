@@ -0,0 +1,229 @@
+//! ERC-20 metadata of the tokens typed data moves, so amounts read as `1,000.5 USDC`
+//! instead of `1000500000`. Which field holds an amount and which token it is in comes
+//! from the field names of known message schemas (Permit2's `token`/`amount`, CoW's
+//! `sellToken`/`sellAmount`, ERC-2612's `value`); symbols and decimals come from the
+//! token contracts at `rpc_url`. Metadata is cached per chain on disk, as tokens do
+//! not change it, so repeated previews of a blob need no RPC calls.
+
+use crate::{
+    cache::cache_dir,
+    lint::{parse_uint, DEADLINE_FIELDS},
+    rpc,
+};
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::Eip712Domain;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+/// An ERC-20 token as it is displayed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl Token {
+    /// `amount` of this token in its decimals and symbol, e.g. `1,000.5 USDC`.
+    pub fn format(&self, amount: U256) -> String {
+        format!("{} {}", format_amount(amount, self.decimals), self.symbol)
+    }
+}
+
+/// An amount in a message and the token it is denominated in, when the schema says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenAmount {
+    /// Dotted JSON path of the amount, e.g. `message.details.amount`
+    pub path: String,
+    pub token: Option<Address>,
+    pub amount: U256,
+}
+
+/// Looks token metadata up at `rpc_url`, once per token.
+pub struct Tokens {
+    rpc_url: Option<String>,
+    chain_id: u64,
+    tokens: HashMap<Address, Option<Token>>,
+    // Where successful lookups persist, if anywhere
+    path: Option<PathBuf>,
+}
+
+impl Tokens {
+    /// Tokens on `chain_id`, the chain `rpc_url` serves, cached in memory only.
+    /// Without `rpc_url` every lookup misses.
+    pub fn new(rpc_url: Option<&str>, chain_id: u64) -> Self {
+        Self {
+            rpc_url: rpc_url.map(str::to_owned),
+            chain_id,
+            tokens: HashMap::new(),
+            path: None,
+        }
+    }
+
+    /// Like `new`, but also cached in `$XDG_CACHE_HOME/single-sign/tokens/<chain_id>.json`.
+    /// An unreadable cache file counts as empty.
+    pub fn open_default(rpc_url: Option<&str>, chain_id: u64) -> Result<Self> {
+        let path = cache_dir()?.join("tokens").join(format!("{chain_id}.json"));
+        let cached: HashMap<Address, Token> = fs::read(&path)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default();
+        Ok(Self {
+            tokens: cached.into_iter().map(|(address, token)| (address, Some(token))).collect(),
+            path: Some(path),
+            ..Self::new(rpc_url, chain_id)
+        })
+    }
+
+    /// Metadata of `token` for an object signed for `chain_id` (the domain's, if it
+    /// has one). Tokens on other chains than `rpc_url` serves, and contracts without
+    /// `symbol()` and `decimals()`, have none.
+    pub fn get(&mut self, token: Address, chain_id: Option<U256>) -> Option<Token> {
+        if chain_id.is_some_and(|chain_id| chain_id != U256::from(self.chain_id)) {
+            return None;
+        }
+        if let Some(cached) = self.tokens.get(&token) {
+            return cached.clone();
+        }
+        let rpc_url = self.rpc_url.as_deref()?;
+        let fetched = rpc::token_metadata(rpc_url, token)
+            .inspect_err(|e| tracing::debug!("No token metadata for {token:#x}: {e}"))
+            .ok()
+            .map(|(symbol, decimals)| Token { symbol, decimals });
+        self.tokens.insert(token, fetched.clone());
+        if fetched.is_some() {
+            if let Err(e) = self.save() {
+                tracing::debug!("Not caching token metadata: {e}");
+            }
+        }
+        fetched
+    }
+
+    /// Whether lookups can succeed for objects signed for `chain_id`.
+    pub fn online(&self, chain_id: Option<U256>) -> bool {
+        self.rpc_url.is_some()
+            && chain_id.is_none_or(|chain_id| chain_id == U256::from(self.chain_id))
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let known: HashMap<_, _> = self
+            .tokens
+            .iter()
+            .filter_map(|(address, token)| Some((address, token.as_ref()?)))
+            .collect();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(&known)?)?;
+        Ok(())
+    }
+}
+
+/// Every amount in `message` with the token it is in, for an object under `domain`
+/// with `primary_type`. Fields of type `uint` named like amounts count, deadlines
+/// and nonces do not.
+pub fn token_amounts(
+    domain: &Eip712Domain,
+    primary_type: &str,
+    message: &Value,
+) -> Vec<TokenAmount> {
+    let mut amounts = Vec::new();
+    if let Value::Object(fields) = message {
+        collect_amounts(fields, "message", domain, primary_type, &mut amounts);
+    }
+    amounts
+}
+
+fn collect_amounts(
+    fields: &Map<String, Value>,
+    path: &str,
+    domain: &Eip712Domain,
+    primary_type: &str,
+    amounts: &mut Vec<TokenAmount>,
+) {
+    for (field, value) in fields {
+        let path = format!("{path}.{field}");
+        match value {
+            Value::Object(nested) => collect_amounts(nested, &path, domain, primary_type, amounts),
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if let Value::Object(nested) = item {
+                        let path = format!("{path}[{i}]");
+                        collect_amounts(nested, &path, domain, primary_type, amounts);
+                    }
+                }
+            }
+            _ if !is_amount_field(field) || DEADLINE_FIELDS.contains(&field.as_str()) => {}
+            _ => {
+                if let Some(amount) = parse_uint(value) {
+                    amounts.push(TokenAmount {
+                        path,
+                        token: token_for(field, fields, domain, primary_type),
+                        amount,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Whether `field` holds a token address, e.g. `token` or `sellToken`.
+pub fn is_token_field(field: &str) -> bool {
+    field.to_ascii_lowercase().ends_with("token")
+}
+
+/// Whether `field` holds a token amount, e.g. `amount`, `sellAmount` or `value`.
+pub fn is_amount_field(field: &str) -> bool {
+    let field = field.to_ascii_lowercase();
+    field.contains("amount") || field == "value" || field == "allowance"
+}
+
+/// The token the amount in `field` is denominated in: the `sellToken` beside a
+/// `sellAmount`, else a `token` beside it, else for ERC-2612 permits the token
+/// contract itself.
+pub fn token_for(
+    field: &str,
+    siblings: &Map<String, Value>,
+    domain: &Eip712Domain,
+    primary_type: &str,
+) -> Option<Address> {
+    let prefix = field
+        .strip_suffix("Amount")
+        .or_else(|| field.strip_suffix("amount"))
+        .filter(|prefix| !prefix.is_empty());
+    let sibling = |name: &str| siblings.get(name)?.as_str()?.parse::<Address>().ok();
+    prefix
+        .and_then(|prefix| sibling(&format!("{prefix}Token")))
+        .or_else(|| sibling("token"))
+        .or_else(|| {
+            let permit = primary_type == "Permit" && field == "value";
+            domain.verifying_contract.filter(|_| permit)
+        })
+}
+
+/// `amount` of a token with `decimals` decimals, with thousands separators and without
+/// trailing zeros, e.g. `1,000.5`.
+pub fn format_amount(amount: U256, decimals: u8) -> String {
+    if decimals > 77 {
+        return amount.to_string();
+    }
+    let unit = U256::from(10).pow(U256::from(decimals));
+    let whole = (amount / unit).to_string();
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let fraction = amount % unit;
+    if fraction.is_zero() {
+        return grouped;
+    }
+    let fraction = format!("{:0>width$}", fraction.to_string(), width = usize::from(decimals));
+    format!("{grouped}.{}", fraction.trim_end_matches('0'))
+}
@@ -0,0 +1,177 @@
+use crate::journal::{decode_batch_outputs, decode_output};
+use alloy_primitives::{keccak256, Address, B256};
+use risc0_zkvm::{sha::Digest, Receipt};
+use single_sign_types::{DigestRange, ErrorCode, Output};
+use std::fmt;
+
+/// Why a receipt does not attest what the blob says it should.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The receipt does not verify against the image ID.
+    Receipt(String),
+    /// The journal does not decode.
+    Journal(String),
+    /// The blob's range has no EIP-712 digest to compare against.
+    Range { range: DigestRange, code: ErrorCode },
+    /// The journal commits a different digest than the blob's range has.
+    Digest {
+        range: DigestRange,
+        expected: B256,
+        committed: B256,
+    },
+    /// The journal attests a failure for a different range.
+    FailureRange {
+        range: DigestRange,
+        committed: DigestRange,
+    },
+    /// A batch journal has a different number of entries than there are ranges.
+    Count { expected: usize, committed: usize },
+    /// The journal attests a different signer than the caller expects.
+    Signer {
+        range: DigestRange,
+        expected: Address,
+        committed: Address,
+    },
+    /// The journal was proven over a different blob.
+    BlobHash { expected: B256, committed: B256 },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Receipt(e) => write!(f, "receipt does not verify: {e}"),
+            Mismatch::Journal(e) => write!(f, "journal does not decode: {e}"),
+            Mismatch::Range { range, code } => write!(f, "range {range} of the blob: {code}"),
+            Mismatch::Digest {
+                range,
+                expected,
+                committed,
+            } => write!(
+                f,
+                "range {range} has digest {expected} but the journal commits {committed}"
+            ),
+            Mismatch::FailureRange { range, committed } => write!(
+                f,
+                "expected an attestation for range {range}, the journal reports a failure \
+                 for {committed}"
+            ),
+            Mismatch::Count {
+                expected,
+                committed,
+            } => write!(f, "expected {expected} journal entries, found {committed}"),
+            Mismatch::Signer {
+                range,
+                expected,
+                committed,
+            } => write!(
+                f,
+                "range {range} should be signed by {expected:#x} but the journal attests \
+                 {committed:#x}"
+            ),
+            Mismatch::BlobHash {
+                expected,
+                committed,
+            } => write!(
+                f,
+                "the blob hashes to {expected} but the journal was proven over {committed}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// Verify `receipt` against `image_id` and check that its journal attests `range` of
+/// `blob`: the journal was proven over this blob, the committed digest is the one
+/// recomputed from it or the committed failure names this range, and, when `signer`
+/// is given, the journal attests that signer. Pass `None` for receipts that prove
+/// whoever the signature recovers to, and for non-Ethereum keys, whose journals
+/// commit a zero signer. Returns the decoded output for callers to inspect further.
+/// Covers EIP-712 ranges; UserOperation digests are not recomputed.
+pub fn check_receipt_against_blob(
+    receipt: &Receipt,
+    blob: &[u8],
+    range: &DigestRange,
+    signer: Option<Address>,
+    image_id: impl Into<Digest>,
+) -> Result<Output, Mismatch> {
+    receipt
+        .verify(image_id)
+        .map_err(|e| Mismatch::Receipt(e.to_string()))?;
+    let output = decode_output(&receipt.journal).map_err(|e| Mismatch::Journal(e.to_string()))?;
+    check_output(&output, blob, range, signer)?;
+    Ok(output)
+}
+
+/// `check_receipt_against_blob` for a single-signer batch receipt, whose journal holds
+/// one entry per range in `ranges` order.
+pub fn check_batch_receipt_against_blob(
+    receipt: &Receipt,
+    blob: &[u8],
+    ranges: &[DigestRange],
+    signer: Option<Address>,
+    image_id: impl Into<Digest>,
+) -> Result<Vec<Output>, Mismatch> {
+    receipt
+        .verify(image_id)
+        .map_err(|e| Mismatch::Receipt(e.to_string()))?;
+    let outputs =
+        decode_batch_outputs(&receipt.journal).map_err(|e| Mismatch::Journal(e.to_string()))?;
+    if outputs.len() != ranges.len() {
+        return Err(Mismatch::Count {
+            expected: ranges.len(),
+            committed: outputs.len(),
+        });
+    }
+    for (output, range) in outputs.iter().zip(ranges) {
+        check_output(output, blob, range, signer)?;
+    }
+    Ok(outputs)
+}
+
+fn check_output(
+    output: &Output,
+    blob: &[u8],
+    range: &DigestRange,
+    signer: Option<Address>,
+) -> Result<(), Mismatch> {
+    let blob_hash = keccak256(blob);
+    if output.blob_hash != blob_hash {
+        return Err(Mismatch::BlobHash {
+            expected: blob_hash,
+            committed: output.blob_hash,
+        });
+    }
+    // Failures commit the expected signer, or zero when the input named none and the
+    // signature never recovered
+    let committed_none = output.failure.is_some() && output.signer.is_zero();
+    let expected = signer.filter(|expected| *expected != output.signer && !committed_none);
+    if let Some(expected) = expected {
+        return Err(Mismatch::Signer {
+            range: range.clone(),
+            expected,
+            committed: output.signer,
+        });
+    }
+    if let Some(failure) = &output.failure {
+        if failure.range != *range {
+            return Err(Mismatch::FailureRange {
+                range: range.clone(),
+                committed: failure.range.clone(),
+            });
+        }
+        return Ok(());
+    }
+    let expected = range.digest(blob, false).map_err(|code| Mismatch::Range {
+        range: range.clone(),
+        code,
+    })?;
+    if expected != output.digest {
+        return Err(Mismatch::Digest {
+            range: range.clone(),
+            expected,
+            committed: output.digest,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! Proving on request: `Watcher` polls a contract for `ProofRequested` logs, the
+//! signed blob each one names is fetched from `storage::Storage`, and `prove_request`
+//! proves the requested range with the batch guest, ready for `submit::submit_all` to
+//! fulfil.
+
+use crate::{
+    config::Config,
+    prepare::{check_batch, find_json_ranges},
+    prover::prove_framed,
+    rpc::block_on,
+    verify::check_batch_receipt_against_blob,
+};
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_provider::{Provider, ProviderBuilder};
+use alloy_rpc_types_eth::Filter;
+use alloy_sol_types::{sol, SolEvent};
+use anyhow::{anyhow, bail, Result};
+use methods::{SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID};
+use risc0_zkvm::Receipt;
+use single_sign_types::{
+    intent::IntentPolicy,
+    signing::{parse_signature_with_chain_id, Envelope},
+    Assertion, BatchInput, DigestMode, DigestRange, Output,
+};
+
+sol! {
+    /// Asks for a proof of the typed data at `start..end` of the blob with keccak256
+    /// `blobHash`.
+    event ProofRequested(bytes32 indexed blobHash, uint64 start, uint64 end);
+}
+
+/// Most blocks one `eth_getLogs` call covers, which public nodes cap.
+const MAX_BLOCK_SPAN: u64 = 2_000;
+
+/// One `ProofRequested` log.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub blob_hash: B256,
+    pub range: DigestRange,
+    pub block_number: Option<u64>,
+    pub tx_hash: Option<B256>,
+}
+
+/// Follows the `ProofRequested` logs of one contract, block range by block range.
+pub struct Watcher {
+    rpc_url: String,
+    contract: Address,
+    confirmations: u64,
+    next_block: u64,
+}
+
+impl Watcher {
+    /// Watch `contract` from `from_block`, or from the next block when `None`, only
+    /// reading blocks with at least `confirmations` confirmations so reorged requests
+    /// are not proven.
+    pub fn new(
+        rpc_url: &str,
+        contract: Address,
+        from_block: Option<u64>,
+        confirmations: u64,
+    ) -> Result<Self> {
+        let mut watcher = Self {
+            rpc_url: rpc_url.to_owned(),
+            contract,
+            confirmations: confirmations.max(1),
+            next_block: 0,
+        };
+        watcher.next_block = match from_block {
+            Some(block) => block,
+            None => watcher.safe_head()? + 1,
+        };
+        Ok(watcher)
+    }
+
+    /// The first block not yet read; persist it to resume after a restart.
+    pub fn next_block(&self) -> u64 {
+        self.next_block
+    }
+
+    /// Requests logged since the last call, oldest first. Returns nothing once caught up.
+    pub fn poll(&mut self) -> Result<Vec<Request>> {
+        let head = self.safe_head()?;
+        if self.next_block > head {
+            return Ok(Vec::new());
+        }
+        let to_block = head.min(self.next_block + MAX_BLOCK_SPAN - 1);
+        let filter = Filter::new()
+            .address(self.contract)
+            .event_signature(ProofRequested::SIGNATURE_HASH)
+            .from_block(self.next_block)
+            .to_block(to_block);
+        let provider = ProviderBuilder::new().connect_http(self.rpc_url.parse()?);
+        let logs = block_on(provider.get_logs(&filter))?.map_err(|e| {
+            anyhow!(
+                "Failed fetching logs of {:#x} in blocks {}..={to_block}: {e}",
+                self.contract,
+                self.next_block
+            )
+        })?;
+        let requests = logs
+            .iter()
+            .map(|log| {
+                let event = log
+                    .log_decode::<ProofRequested>()
+                    .map_err(|e| anyhow!("Malformed ProofRequested log: {e}"))?
+                    .inner
+                    .data;
+                Ok(Request {
+                    blob_hash: event.blobHash,
+                    range: DigestRange {
+                        start: usize::try_from(event.start)?,
+                        end: usize::try_from(event.end)?,
+                    },
+                    block_number: log.block_number,
+                    tx_hash: log.transaction_hash,
+                })
+            })
+            .collect::<Result<_>>()?;
+        self.next_block = to_block + 1;
+        Ok(requests)
+    }
+
+    fn safe_head(&self) -> Result<u64> {
+        let provider = ProviderBuilder::new().connect_http(self.rpc_url.parse()?);
+        let latest = block_on(provider.get_block_number())?
+            .map_err(|e| anyhow!("Failed fetching the block number from {}: {e}", self.rpc_url))?;
+        Ok(latest.saturating_sub(self.confirmations - 1))
+    }
+}
+
+/// Prove `range` of `blob`, signed as a whole with the EIP-191 `signature`, with the
+/// batch guest. The range must be one of the blob's typed-data objects, and the signer
+/// is whoever the signature recovers to; the caller decides whether to act for them.
+pub fn prove_request(
+    config: &Config,
+    blob: Bytes,
+    signature: &[u8],
+    range: DigestRange,
+) -> Result<(Receipt, Output)> {
+    if !find_json_ranges(&blob)?.contains(&range) {
+        bail!("Range {range} is not a typed-data object of the blob");
+    }
+    let (signature, signature_chain_id) = parse_signature_with_chain_id(signature)?;
+    let signer = signature
+        .recover_address_from_msg(&blob)
+        .map_err(|e| anyhow!("Failed recovering the signer of the blob: {e}"))?;
+    let batch_input = BatchInput {
+        signer,
+        signature,
+        typed_data_concat: blob,
+        ranges: vec![range],
+        require_canonical: true,
+        assert: Assertion::MustBeValid,
+        extract_paths: Vec::new(),
+        disclosure_salt: None,
+        envelope: Envelope::Personal,
+        key_signature: None,
+        segments: Vec::new(),
+        expected_digests: Vec::new(),
+        digest_mode: DigestMode::Eip712,
+        intent_policy: IntentPolicy::default(),
+        allow_high_s: false,
+        signature_chain_id,
+        anchor: None,
+        policy: None,
+        transfers: Vec::new(),
+    };
+    check_batch(&batch_input)?;
+    let receipt =
+        prove_framed(config, SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID, &batch_input)?.receipt;
+    let mut outputs = check_batch_receipt_against_blob(
+        &receipt,
+        &batch_input.typed_data_concat,
+        &batch_input.ranges,
+        Some(batch_input.signer),
+        SINGLE_SIGN_BATCH_ID,
+    )?;
+    let output = outputs
+        .pop()
+        .ok_or_else(|| anyhow!("Batch journal has no output"))?;
+    Ok((receipt, output))
+}
@@ -0,0 +1,121 @@
+//! Callbacks to integrators, so they learn about finished proofs and confirmed
+//! submissions without polling. Each configured `Webhook` gets a JSON `Event` POSTed
+//! to its URL. With a `secret`, the request carries
+//! `X-SingleSign-Signature: t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`;
+//! receivers recompute it and reject stale timestamps to stop replays. Enabled by
+//! the `webhooks` feature.
+//!
+//! Delivery is best effort: a failed POST is retried a few times and then logged,
+//! never failing the proof or submission it reports.
+
+use crate::config::{Webhook, WebhookEvent};
+use alloy_primitives::{hex, Bytes, B256};
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use single_sign_types::serde_hex;
+use std::{
+    path::PathBuf,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Attempts per webhook and event, with doubling pauses from one second.
+const ATTEMPTS: u32 = 3;
+
+/// What happened, as delivered in the body.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A receipt was proven and verified against its blob.
+    ProofFinished {
+        /// What was proven, e.g. `job 7 range 2` or `grpc request 12 range 0`
+        source: String,
+        /// The receipt's journal, as consumers pass it on-chain
+        #[serde(with = "serde_hex::bytes")]
+        journal: Bytes,
+        /// Where the receipt was saved; unset when it was only streamed to a client
+        receipt: Option<PathBuf>,
+        #[serde(with = "serde_hex::b256_vec")]
+        digests: Vec<B256>,
+    },
+    /// A `consume` transaction confirmed.
+    TransactionConfirmed {
+        chain_id: u64,
+        #[serde(with = "serde_hex::b256")]
+        tx_hash: B256,
+        block_number: Option<u64>,
+        #[serde(with = "serde_hex::b256_vec")]
+        digests: Vec<B256>,
+    },
+}
+
+impl Event {
+    pub fn kind(&self) -> WebhookEvent {
+        match self {
+            Event::ProofFinished { .. } => WebhookEvent::ProofFinished,
+            Event::TransactionConfirmed { .. } => WebhookEvent::TransactionConfirmed,
+        }
+    }
+}
+
+/// Deliver `event` to every webhook subscribed to it, blocking until each succeeded
+/// or ran out of attempts.
+pub fn notify(webhooks: &[Webhook], event: &Event) {
+    let kind = event.kind();
+    let body = match serde_json::to_vec(event) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!("Failed encoding webhook event: {e}");
+            return;
+        }
+    };
+    let subscribed = webhooks
+        .iter()
+        .filter(|webhook| webhook.events.is_empty() || webhook.events.contains(&kind));
+    for webhook in subscribed {
+        let mut pause = Duration::from_secs(1);
+        for attempt in 1..=ATTEMPTS {
+            match post(webhook, kind, &body) {
+                Ok(()) => break,
+                Err(e) if attempt == ATTEMPTS => {
+                    tracing::warn!("Giving up on webhook {}: {e}", webhook.url);
+                }
+                Err(e) => {
+                    tracing::debug!("Webhook {} attempt {attempt} failed: {e}", webhook.url);
+                    thread::sleep(pause);
+                    pause *= 2;
+                }
+            }
+        }
+    }
+}
+
+/// The `X-SingleSign-Signature` value of `body` sent at `timestamp`.
+pub fn signature(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(format!("{timestamp}.").as_bytes());
+    mac.update(body);
+    format!("t={timestamp},v1={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn post(webhook: &Webhook, kind: WebhookEvent, body: &[u8]) -> Result<()> {
+    let event = serde_json::to_value(kind)?;
+    let mut request = reqwest::blocking::Client::new()
+        .post(&webhook.url)
+        .timeout(Duration::from_secs(10))
+        .header("Content-Type", "application/json")
+        .header("X-SingleSign-Event", event.as_str().unwrap_or_default())
+        .body(body.to_vec());
+    if let Some(secret) = &webhook.secret {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        request = request.header("X-SingleSign-Signature", signature(secret, timestamp, body));
+    }
+    request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| anyhow!("POST to {} failed: {e}", webhook.url))?;
+    Ok(())
+}
@@ -0,0 +1,295 @@
+//! End to end on a local chain: prove a batch of Permit2 permits in dev mode, consume it
+//! on `Permit2Consumer` through the mock verifier, and check the permits moved tokens
+//! and cannot be replayed. Needs `anvil` and `forge` on the path, Permit2's code (from
+//! a live chain's RPC in `PERMIT2_SOURCE_RPC_URL`, or a forked node in `ANVIL_FORK_URL`)
+//! and dev-mode proving:
+//!
+//! RISC0_DEV_MODE=1 PERMIT2_SOURCE_RPC_URL=https://… \
+//!     cargo test -p host --features consumer --test e2e_permit2 -- --ignored
+
+use alloy_primitives::{address, aliases::U160, Address, Bytes, U256};
+use alloy_provider::{
+    network::{Ethereum, EthereumWallet, Network, ReceiptResponse, TransactionBuilder},
+    Provider, ProviderBuilder,
+};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{sol, SolCall, SolError};
+use anyhow::{anyhow, bail, Result};
+use host::{
+    config::{Config, Endpoint},
+    contracts::{
+        deploy_mock_token, deploy_mock_verifier, deploy_permit2_consumer, install_permit2,
+    },
+    journal::decode_batch_outputs,
+    prepare::{check_batch, find_json_ranges, transfer_details},
+    prover::prove_framed,
+    rpc,
+    seal::{encode_seal, image_id_bytes},
+    submit::{group_by_chain, submit_all, Route},
+};
+use methods::{SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID};
+use serde_json::json;
+use single_sign_types::{
+    canonical::compact_typed_data, intent::IntentPolicy, signing::Envelope, Assertion,
+    BatchInput, DigestMode,
+};
+use std::{
+    collections::BTreeMap,
+    env,
+    future::Future,
+    net::TcpListener,
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+sol! {
+    interface IMockERC20 {
+        function mint(address to, uint256 amount);
+        function approve(address spender, uint256 amount) returns (bool);
+        function balanceOf(address owner) view returns (uint256);
+    }
+
+    interface IAllowanceTransfer {
+        function approve(address token, address spender, uint160 amount, uint48 expiration);
+    }
+
+    interface IConsumer {
+        function consume(bytes seal, bytes journal, bytes32[] digests);
+        error AlreadyConsumed(address signer, bytes32 digest);
+    }
+}
+
+type TransactionRequest = <Ethereum as Network>::TransactionRequest;
+
+// Anvil's first two funded accounts
+const DEPLOYER_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const OWNER_KEY: &str = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+const RECIPIENT: Address = address!("0x4444444444444444444444444444444444444444");
+const ANVIL_CHAIN_ID: u64 = 31337;
+
+// The fields `Permit2Consumer` decodes, in its order
+const EXTRACT_PATHS: [&str; 5] =
+    ["permitted.token", "permitted.amount", "spender", "nonce", "deadline"];
+
+// Kills the node when the test ends, passing or not
+struct Anvil {
+    child: Child,
+    url: String,
+}
+
+impl Drop for Anvil {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn anvil() -> Result<Anvil> {
+    let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    let mut command = Command::new("anvil");
+    command.args(["--port", &port.to_string()]).stdout(Stdio::null());
+    if let Ok(fork_url) = env::var("ANVIL_FORK_URL") {
+        command.args(["--fork-url", &fork_url, "--chain-id", &ANVIL_CHAIN_ID.to_string()]);
+    }
+    let anvil = Anvil {
+        child: command.spawn().map_err(|e| anyhow!("Failed starting anvil: {e}"))?,
+        url: format!("http://127.0.0.1:{port}"),
+    };
+    for _ in 0..100 {
+        if rpc::chain_id(&anvil.url).is_ok() {
+            return Ok(anvil);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    bail!("anvil did not come up on {}", anvil.url)
+}
+
+fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    Ok(tokio::runtime::Runtime::new()?.block_on(future))
+}
+
+// Send `call` to `to` from `signer` and wait for it to succeed
+fn send(rpc_url: &str, signer: &PrivateKeySigner, to: Address, call: impl SolCall) -> Result<()> {
+    let provider = ProviderBuilder::new()
+        .wallet(EthereumWallet::from(signer.clone()))
+        .connect_http(rpc_url.parse()?);
+    let tx = TransactionRequest::default().with_to(to).with_input(call.abi_encode());
+    let receipt = block_on(async {
+        let pending = provider.send_transaction(tx).await?;
+        anyhow::Ok(pending.get_receipt().await?)
+    })??;
+    if !receipt.status() {
+        bail!("Call to {to} reverted in {}", receipt.transaction_hash());
+    }
+    Ok(())
+}
+
+fn balance(rpc_url: &str, token: Address, owner: Address) -> Result<U256> {
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let tx = TransactionRequest::default()
+        .with_to(token)
+        .with_input(IMockERC20::balanceOfCall { owner }.abi_encode());
+    let output = block_on(async { provider.call(tx).await })??;
+    Ok(IMockERC20::balanceOfCall::abi_decode_returns(&output)?)
+}
+
+fn permit(token: Address, amount: u64, spender: Address, nonce: u64, deadline: u64) -> String {
+    json!({
+        "domain": {
+            "name": "Permit2",
+            "chainId": ANVIL_CHAIN_ID,
+            "verifyingContract": host::chains::PERMIT2,
+        },
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" },
+            ],
+            "TokenPermissions": [
+                { "name": "token", "type": "address" },
+                { "name": "amount", "type": "uint256" },
+            ],
+            "PermitTransferFrom": [
+                { "name": "permitted", "type": "TokenPermissions" },
+                { "name": "spender", "type": "address" },
+                { "name": "nonce", "type": "uint256" },
+                { "name": "deadline", "type": "uint256" },
+            ],
+        },
+        "primaryType": "PermitTransferFrom",
+        "message": {
+            "permitted": { "token": token, "amount": amount.to_string() },
+            "spender": spender,
+            "nonce": nonce.to_string(),
+            "deadline": deadline.to_string(),
+        },
+    })
+    .to_string()
+}
+
+#[test]
+#[ignore = "needs anvil, forge, Permit2's code and RISC0_DEV_MODE=1"]
+fn consumes_a_dev_mode_batch_and_executes_its_permits() -> Result<()> {
+    assert!(risc0_zkvm::is_dev_mode(), "set RISC0_DEV_MODE=1 to prove in seconds");
+    let anvil = anvil()?;
+    let rpc_url = anvil.url.as_str();
+    let deployer: PrivateKeySigner = DEPLOYER_KEY.parse()?;
+    let owner: PrivateKeySigner = OWNER_KEY.parse()?;
+
+    // The mock verifier, Permit2, the consumer and a token the owner holds and lets the
+    // consumer spend through Permit2
+    let permit2_source = env::var("PERMIT2_SOURCE_RPC_URL").ok();
+    let permit2 = install_permit2(rpc_url, permit2_source.as_deref())?;
+    let verifier = deploy_mock_verifier(rpc_url, deployer.clone())?;
+    let image_id = image_id_bytes(SINGLE_SIGN_BATCH_ID);
+    let consumer =
+        deploy_permit2_consumer(rpc_url, deployer.clone(), verifier, image_id, permit2)?;
+    let token = deploy_mock_token(rpc_url, deployer.clone())?;
+    let minted = U256::from(1000);
+    let mint = IMockERC20::mintCall {
+        to: owner.address(),
+        amount: minted,
+    };
+    send(rpc_url, &deployer, token, mint)?;
+    let approval = IMockERC20::approveCall {
+        spender: permit2,
+        amount: U256::MAX,
+    };
+    send(rpc_url, &owner, token, approval)?;
+    let deadline = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 3600;
+    let allowance = IAllowanceTransfer::approveCall {
+        token,
+        spender: consumer,
+        amount: U160::MAX,
+        expiration: deadline,
+    };
+    send(rpc_url, &owner, permit2, allowance)?;
+
+    // Two permits for the consumer, signed at once as a blob
+    let parts = [
+        permit(token, 100, consumer, 0, deadline),
+        permit(token, 200, consumer, 1, deadline),
+    ];
+    let compact: Vec<String> =
+        parts.iter().map(|part| compact_typed_data(part)).collect::<Result<_>>()?;
+    let blob = Bytes::from(compact.concat().into_bytes());
+    let signature = owner.sign_hash_sync(&Envelope::Personal.signing_hash(&blob)?)?;
+    let ranges = find_json_ranges(&blob)?;
+    let requested = U256::from(60);
+    let transfers =
+        transfer_details(&blob, &ranges, owner.address(), Some(RECIPIENT), Some(requested))?;
+    let batch = BatchInput {
+        signer: owner.address(),
+        signature,
+        typed_data_concat: blob.clone(),
+        ranges: ranges.clone(),
+        require_canonical: true,
+        assert: Assertion::MustBeValid,
+        extract_paths: EXTRACT_PATHS.map(String::from).to_vec(),
+        disclosure_salt: None,
+        envelope: Envelope::Personal,
+        key_signature: None,
+        segments: Vec::new(),
+        expected_digests: Vec::new(),
+        digest_mode: DigestMode::Eip712,
+        intent_policy: IntentPolicy::default(),
+        allow_high_s: false,
+        signature_chain_id: None,
+        anchor: None,
+        policy: None,
+        transfers,
+    };
+    check_batch(&batch)?;
+    let config = Config::default();
+    let proof = prove_framed(&config, SINGLE_SIGN_BATCH_ELF, SINGLE_SIGN_BATCH_ID, &batch)?;
+    let outputs = decode_batch_outputs(&proof.receipt.journal)?;
+    assert!(outputs.iter().all(|output| output.is_ok()));
+
+    // Consume both permits in one `consume` call, as `submit` does
+    let seal = Bytes::from(encode_seal(&proof.receipt, true)?);
+    let journal = Bytes::from(proof.receipt.journal.bytes.clone());
+    let groups = group_by_chain(&blob, &ranges, &outputs)?;
+    let digests = groups[&ANVIL_CHAIN_ID].clone();
+    let endpoint = Endpoint {
+        rpc_url: rpc_url.to_owned(),
+        consumer,
+        bundler_url: None,
+        paymaster_url: None,
+        relayer_url: None,
+        private_rpc_url: None,
+        batch_verifier: None,
+        max_fee_per_gas: None,
+    };
+    let endpoints = BTreeMap::from([(ANVIL_CHAIN_ID.to_string(), endpoint)]);
+    let route = Route::Direct;
+    let statuses =
+        submit_all(&endpoints, &deployer, route, seal.clone(), journal.clone(), groups, 1)?;
+    assert!(statuses[&ANVIL_CHAIN_ID].is_confirmed(), "{}", statuses[&ANVIL_CHAIN_ID]);
+    assert_eq!(balance(rpc_url, token, RECIPIENT)?, requested * U256::from(2));
+    assert_eq!(balance(rpc_url, token, owner.address())?, minted - requested * U256::from(2));
+
+    // The same proof again is refused before any token moves
+    let replay = IConsumer::consumeCall {
+        seal,
+        journal,
+        digests: digests.clone(),
+    };
+    let provider = ProviderBuilder::new().connect_http(rpc_url.parse()?);
+    let tx = TransactionRequest::default()
+        .with_from(deployer.address())
+        .with_to(consumer)
+        .with_input(replay.abi_encode());
+    let error = block_on(async { provider.call(tx).await })?.expect_err("replay should revert");
+    let data = error
+        .as_error_resp()
+        .and_then(|payload| payload.as_revert_data())
+        .ok_or_else(|| anyhow!("Replay failed without revert data: {error}"))?;
+    let reverted = IConsumer::AlreadyConsumed::abi_decode(&data)?;
+    assert_eq!(reverted.signer, owner.address());
+    assert_eq!(reverted.digest, digests[0]);
+    assert_eq!(balance(rpc_url, token, RECIPIENT)?, requested * U256::from(2));
+    Ok(())
+}
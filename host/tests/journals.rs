@@ -0,0 +1,179 @@
+//! Golden journals in `testdata/journals`, one file per layout and case, which must keep
+//! decoding to the same `Output`s: receipts already issued commit these bytes. The
+//! files are written from the layout rather than generated from the current types, so
+//! an accidental change to a committed field, its serde format or its order fails here.
+//! A new layout adds files for its version and leaves the existing ones alone.
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use host::journal::{decode_batch_outputs, decode_output};
+use risc0_zkvm::Journal;
+use single_sign_types::{
+    intent::IntentCommitment,
+    policy::PolicyVerdict,
+    signing::{MessageMode, SignatureScheme},
+    transfer::TransferDetails,
+    BlockAnchor, DigestRange, ErrorCode, ManifestCheck, Output,
+};
+use std::{fs, path::Path};
+
+// The file's bytes, skipping `#` comments and whitespace
+fn golden(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/journals").join(name);
+    let text = fs::read_to_string(&path).unwrap();
+    let hex: String = text
+        .lines()
+        .map(|line| line.split('#').next().unwrap())
+        .flat_map(str::split_whitespace)
+        .collect();
+    alloy_primitives::hex::decode(hex).unwrap()
+}
+
+// The bytes the guests would commit for `value`
+fn encode(value: &impl serde::Serialize) -> Vec<u8> {
+    let words = risc0_zkvm::serde::to_vec(value).unwrap();
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+fn json(output: &Output) -> serde_json::Value {
+    serde_json::to_value(output).unwrap()
+}
+
+// Outputs of the current layout, as version 1 journals committed them
+fn ok() -> Output {
+    Output {
+        version: 1,
+        struct_hash: B256::repeat_byte(0xdd),
+        low_s_enforced: true,
+        manifest_hash: B256::repeat_byte(0xcc),
+        blob_hash: B256::repeat_byte(0xbb),
+        message_mode: Some(MessageMode::Personal),
+        ..Output::ok(Address::repeat_byte(0x11), B256::repeat_byte(0xaa))
+    }
+}
+
+fn failure() -> Output {
+    let range = DigestRange { start: 10, end: 250 };
+    Output {
+        version: 1,
+        blob_hash: B256::repeat_byte(0xbb),
+        ..Output::err(Address::repeat_byte(0x11), ErrorCode::InvalidSignature, range)
+    }
+}
+
+fn full() -> Output {
+    Output {
+        fields: Bytes::from_static(&[1, 2, 3, 4, 5]),
+        field_commitments: vec![B256::repeat_byte(1), B256::repeat_byte(2)],
+        scheme: SignatureScheme::WebAuthn,
+        public_key: Bytes::from(vec![4; 65]),
+        credential_id_hash: B256::repeat_byte(0xee),
+        manifest: Some(ManifestCheck {
+            all_match: false,
+            first_mismatch: Some(3),
+        }),
+        intent: Some(IntentCommitment {
+            nonce: U256::from(7),
+            deadline: U256::from(1_700_000_000u64),
+            current_time: 1_699_990_000,
+        }),
+        signature_chain_id: Some(8453),
+        anchor: Some(BlockAnchor {
+            number: 21_000_000,
+            hash: B256::repeat_byte(0x0f),
+        }),
+        policy: Some(PolicyVerdict {
+            policy_hash: B256::repeat_byte(0x9a),
+            passed: true,
+        }),
+        ..ok()
+    }
+}
+
+fn transfer() -> Option<TransferDetails> {
+    Some(TransferDetails {
+        to: Address::repeat_byte(0x44),
+        requested_amount: U256::from(250),
+    })
+}
+
+// Version 2 appended `transfer`
+fn v2(output: Output, transfer: Option<TransferDetails>) -> Output {
+    Output {
+        version: 2,
+        transfer,
+        ..output
+    }
+}
+
+// The bytes of `output` in the version 1 layout: the current encoding without the
+// trailing `transfer: None` tag
+fn encode_v1(output: &Output) -> Vec<u8> {
+    let mut bytes = encode(output);
+    assert_eq!(bytes.split_off(bytes.len() - 4), [0; 4], "version 1 has no transfer");
+    bytes
+}
+
+fn assert_single(name: &str, expected: Output) {
+    let bytes = golden(name);
+    let decoded = decode_output(&Journal::new(bytes.clone())).unwrap();
+    assert_eq!(json(&decoded), json(&expected), "{name}");
+    let encoded = match decoded.version {
+        1 => encode_v1(&decoded),
+        _ => encode(&decoded),
+    };
+    assert_eq!(encoded, bytes, "{name} re-encodes differently");
+}
+
+#[test]
+fn decodes_version_1_journals() {
+    assert_single("v1_ok.hex", ok());
+    assert_single("v1_failure.hex", failure());
+    assert_single("v1_full.hex", full());
+}
+
+#[test]
+fn decodes_version_1_batch_journals() {
+    let bytes = golden("v1_batch.hex");
+    let decoded = decode_batch_outputs(&Journal::new(bytes.clone())).unwrap();
+    let expected = [ok(), failure()];
+    assert_eq!(decoded.iter().map(json).collect::<Vec<_>>(), expected.map(|o| json(&o)));
+    let mut encoded = encode(&(decoded.len() as u32));
+    for output in &decoded {
+        encoded.extend(encode_v1(output));
+    }
+    assert_eq!(encoded, bytes, "v1_batch.hex re-encodes differently");
+}
+
+#[test]
+fn decodes_version_2_journals() {
+    assert_single("v2_ok.hex", v2(ok(), None));
+    assert_single("v2_failure.hex", v2(failure(), None));
+    assert_single("v2_full.hex", v2(full(), transfer()));
+}
+
+#[test]
+fn decodes_version_2_batch_journals() {
+    let bytes = golden("v2_batch.hex");
+    let decoded = decode_batch_outputs(&Journal::new(bytes.clone())).unwrap();
+    let expected = [v2(ok(), transfer()), v2(failure(), None)];
+    assert_eq!(decoded.iter().map(json).collect::<Vec<_>>(), expected.map(|o| json(&o)));
+    assert_eq!(encode(&decoded), bytes, "v2_batch.hex re-encodes differently");
+}
+
+#[test]
+fn rejects_undefined_versions() {
+    let journal = Journal::new(golden("v3_unsupported.hex"));
+    let error = decode_output(&journal).unwrap_err();
+    assert_eq!(error.to_string(), "Unsupported journal version 3");
+}
+
+// Enums are committed by variant index, not by the discriminant `code()` reports to
+// contracts: reordering or removing a variant changes journals even if every
+// discriminant is kept
+#[test]
+fn enums_are_committed_by_variant_index() {
+    assert_eq!(encode(&ErrorCode::InvalidSignature), 5u32.to_le_bytes());
+    assert_eq!(ErrorCode::InvalidSignature.code(), 6);
+    assert_eq!(encode(&SignatureScheme::WebAuthn), 3u32.to_le_bytes());
+    assert_eq!(encode(&MessageMode::Personal), 2u32.to_le_bytes());
+}
@@ -0,0 +1,188 @@
+//! Properties of the range finder and range checks over generated blobs: concatenated
+//! JSON objects with escapes, nesting, non-ASCII text and the separators exported
+//! files contain, and mutations of them.
+
+use alloy_primitives::Address;
+use host::prepare::{find_json_ranges, list_digests, BOM};
+use proptest::{prelude::*, sample::Index};
+use serde_json::{json, Value};
+use single_sign_types::{canonical::compact_typed_data, DigestRange, ErrorCode};
+
+// Strings heavy in the characters the range finder must skip over inside strings:
+// braces, quotes and backslashes, which serde_json escapes, control characters, and
+// any printable character, multi-byte ones included
+fn text() -> impl Strategy<Value = String> {
+    "([{}\"\\\\]|\\p{Cc}|\\PC){0,12}"
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        text().prop_map(Value::from),
+    ];
+    leaf.prop_recursive(4, 48, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::from),
+            object(inner),
+        ]
+    })
+}
+
+fn object(values: impl Strategy<Value = Value>) -> impl Strategy<Value = Value> {
+    prop::collection::btree_map(text(), values, 0..6)
+        .prop_map(|entries| Value::Object(entries.into_iter().collect()))
+}
+
+// Whitespace, Windows newlines and byte order marks, which may appear between objects
+fn separator() -> impl Strategy<Value = String> {
+    let bom = String::from_utf8(BOM.to_vec()).unwrap();
+    let part = prop_oneof![Just(" "), Just("\t"), Just("\n"), Just("\r\n")].prop_map(String::from);
+    prop::collection::vec(prop_oneof![part, Just(bom)], 0..4).prop_map(|parts| parts.concat())
+}
+
+// Objects, each compact or pretty-printed and followed by a separator, after a leading
+// separator, with the range each object was written to
+fn blob() -> impl Strategy<Value = (String, Vec<DigestRange>)> {
+    let objects = prop::collection::vec((object(value()), any::<bool>(), separator()), 1..5);
+    (separator(), objects).prop_map(|(mut blob, objects)| {
+        let mut ranges = Vec::new();
+        for (object, pretty, separator) in objects {
+            let text = match pretty {
+                true => serde_json::to_string_pretty(&object).unwrap(),
+                false => serde_json::to_string(&object).unwrap(),
+            };
+            ranges.push(DigestRange { start: blob.len(), end: blob.len() + text.len() });
+            blob.push_str(&text);
+            blob.push_str(&separator);
+        }
+        (blob, ranges)
+    })
+}
+
+// A canonical `Mail` typed data with a nested struct and non-ASCII text throughout
+fn mail() -> impl Strategy<Value = String> {
+    let strings = ("\\PC{0,16}", "\\PC{0,16}", "\\PC{0,32}");
+    (strings, any::<[u8; 20]>(), any::<u64>()).prop_map(
+        |((domain, name, contents), wallet, chain_id)| {
+            let json = json!({
+                "types": {
+                    "EIP712Domain": [
+                        { "name": "name", "type": "string" },
+                        { "name": "chainId", "type": "uint256" },
+                    ],
+                    "Person": [
+                        { "name": "name", "type": "string" },
+                        { "name": "wallet", "type": "address" },
+                    ],
+                    "Mail": [
+                        { "name": "from", "type": "Person" },
+                        { "name": "contents", "type": "string" },
+                    ],
+                },
+                "primaryType": "Mail",
+                "domain": { "name": domain, "chainId": chain_id },
+                "message": {
+                    "from": { "name": name, "wallet": Address::from(wallet).to_string() },
+                    "contents": contents,
+                },
+            });
+            compact_typed_data(&json.to_string()).unwrap()
+        },
+    )
+}
+
+// Arbitrary ranges, or mostly valid manifests made of consecutive pairs of sorted cuts
+fn ranges() -> impl Strategy<Value = Vec<DigestRange>> {
+    let arbitrary = prop::collection::vec((0usize..64, 0usize..64), 0..6);
+    let sorted = prop::collection::btree_set(0usize..64, 0..12).prop_map(|cuts| {
+        let cuts: Vec<_> = cuts.into_iter().collect();
+        cuts.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect::<Vec<_>>()
+    });
+    prop_oneof![arbitrary, sorted].prop_map(|bounds| {
+        bounds
+            .into_iter()
+            .map(|(start, end)| DigestRange { start, end })
+            .collect()
+    })
+}
+
+proptest! {
+    #[test]
+    fn finds_exactly_the_objects_written((blob, expected) in blob()) {
+        let ranges = find_json_ranges(blob.as_bytes()).unwrap();
+        prop_assert_eq!(&ranges, &expected);
+        prop_assert_eq!(DigestRange::validate_set(&ranges, blob.len(), false), Ok(()));
+        for range in &ranges {
+            let text = range.slice_checked(blob.as_bytes()).unwrap();
+            prop_assert!(serde_json::from_str::<Value>(text).unwrap().is_object());
+        }
+    }
+
+    #[test]
+    fn mutated_blobs_never_yield_bad_ranges(
+        (blob, _) in blob(),
+        edits in prop::collection::vec((any::<Index>(), any::<u8>()), 0..4),
+        truncate in any::<Index>(),
+    ) {
+        let mut blob = blob.into_bytes();
+        for (index, byte) in edits {
+            let index = index.index(blob.len());
+            blob[index] = byte;
+        }
+        blob.truncate(truncate.index(blob.len() + 1));
+        if let Ok(ranges) = find_json_ranges(&blob) {
+            prop_assert!(!ranges.is_empty());
+            prop_assert_eq!(DigestRange::validate_set(&ranges, blob.len(), false), Ok(()));
+            for range in &ranges {
+                prop_assert_eq!(blob[range.start], b'{');
+                prop_assert_eq!(blob[range.end - 1], b'}');
+            }
+        }
+    }
+
+    #[test]
+    fn validate_set_accepts_exactly_sorted_disjoint_ranges(
+        ranges in ranges(),
+        blob_len in 0usize..64,
+        full_coverage in any::<bool>(),
+    ) {
+        let sorted = ranges.windows(2).all(|pair| pair[0].end <= pair[1].start);
+        let in_bounds = ranges.iter().all(|range| range.start < range.end && range.end <= blob_len);
+        // Sorted ranges in bounds cover the blob exactly when their lengths add up to it
+        let covered: usize = ranges.iter().map(|range| range.end.saturating_sub(range.start)).sum();
+        let result = DigestRange::validate_set(&ranges, blob_len, full_coverage);
+        if sorted {
+            let valid = in_bounds && (!full_coverage || covered == blob_len);
+            prop_assert_eq!(result.is_ok(), valid);
+        } else {
+            prop_assert_eq!(result, Err(ErrorCode::OverlappingRanges));
+        }
+    }
+
+    // The default guest hashes a range as `DigestRange::digest` does and the
+    // `fast-eip712` guest as `eip712_hashes` does: both must agree with the host's
+    // listing and with each object hashed on its own, for every range the finder returns
+    #[test]
+    fn guest_and_host_digests_agree(
+        mails in prop::collection::vec((mail(), separator()), 1..4),
+        mut blob in separator(),
+    ) {
+        for (mail, separator) in &mails {
+            blob.push_str(mail);
+            blob.push_str(separator);
+        }
+        let ranges = find_json_ranges(blob.as_bytes()).unwrap();
+        let listed = list_digests(blob.as_bytes()).unwrap();
+        prop_assert_eq!(ranges.len(), mails.len());
+        for ((range, (mail, _)), object) in ranges.iter().zip(&mails).zip(&listed) {
+            let alone = DigestRange { start: 0, end: mail.len() };
+            let digest = alone.digest(mail.as_bytes(), true).unwrap();
+            prop_assert_eq!(range.digest(blob.as_bytes(), true), Ok(digest));
+            let (fast_digest, _) = range.eip712_hashes(blob.as_bytes(), true).unwrap();
+            prop_assert_eq!(fast_digest, digest);
+            prop_assert_eq!(object.digest, digest);
+        }
+    }
+}
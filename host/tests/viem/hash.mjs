@@ -0,0 +1,41 @@
+// Prints viem's EIP-712 digest of each typed-data file named on the command line, as a
+// JSON array in argument order of { "digest" } or, where viem refuses the file,
+// { "error" }. Driven by tests/viem_diff.rs.
+import { readFileSync } from "node:fs";
+import { hashTypedData } from "viem";
+
+// JSON has no bigints, which viem wants for integer members: convert them by type
+function normalize(types, type, value) {
+  const array = type.match(/^(.*)\[(\d*)\]$/);
+  if (array) return value.map((item) => normalize(types, array[1], item));
+  if (types[type]) {
+    return Object.fromEntries(
+      types[type].map((field) => [field.name, normalize(types, field.type, value[field.name])]),
+    );
+  }
+  if (/^u?int\d*$/.test(type)) return BigInt(value);
+  return value;
+}
+
+function digest(path) {
+  const typed = JSON.parse(readFileSync(path, "utf8"));
+  // Of the domain's fields only chainId is an integer. viem derives the domain type
+  // from the fields present when the types leave it out, as alloy does
+  const domain = { ...typed.domain };
+  if (domain.chainId !== undefined) domain.chainId = BigInt(domain.chainId);
+  return hashTypedData({
+    domain,
+    types: typed.types,
+    primaryType: typed.primaryType,
+    message: normalize(typed.types, typed.primaryType, typed.message),
+  });
+}
+
+const results = process.argv.slice(2).map((path) => {
+  try {
+    return { digest: digest(path) };
+  } catch (e) {
+    return { error: e.shortMessage ?? e.message };
+  }
+});
+console.log(JSON.stringify(results));
@@ -0,0 +1,112 @@
+//! EIP-712 digests of the typed-data corpus in `testdata/typed_data` against viem's
+//! `hashTypedData`, the hasher most wallets and dapps sign with. The corpus covers what
+//! alloy-dyn-abi is likeliest to get wrong: arrays of atoms, structs and arrays, fixed
+//! arrays, domains with some fields missing, no domain type or an empty one, and
+//! referenced types out of order. Files in `invalid/` must be refused by both. Needs
+//! node and viem, pinned in `tests/viem/package.json`:
+//!
+//! (cd host/tests/viem && npm install) && cargo test -p host --features viem-diff
+
+use alloy_primitives::B256;
+use host::prepare::{find_json_ranges, list_digests};
+use serde::Deserialize;
+use single_sign_types::{canonical::compact_typed_data, typed_data::verify_digest};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Viem {
+    Digest(B256),
+    Error(String),
+}
+
+// The JSON files directly in `dir`, by name
+fn corpus(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/typed_data").join(dir);
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no typed data in {}", dir.display());
+    paths
+}
+
+// What viem makes of each file, in order
+fn viem(paths: &[PathBuf]) -> Vec<Viem> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/viem");
+    assert!(
+        dir.join("node_modules/viem").exists(),
+        "viem is not installed; run npm install in {}",
+        dir.display()
+    );
+    let output = Command::new("node")
+        .arg(dir.join("hash.mjs"))
+        .args(paths)
+        .output()
+        .expect("node runs");
+    assert!(
+        output.status.success(),
+        "hash.mjs failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results: Vec<Viem> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(results.len(), paths.len());
+    results
+}
+
+fn name(path: &Path) -> String {
+    path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+#[test]
+fn digests_match_viem() {
+    let paths = corpus("");
+    for (path, viem) in paths.iter().zip(viem(&paths)) {
+        let name = name(path);
+        let expected = match viem {
+            Viem::Digest(digest) => digest,
+            Viem::Error(e) => panic!("{name}: viem refused it: {e}"),
+        };
+        let json = fs::read_to_string(path).unwrap();
+        assert_eq!(verify_digest(&json).unwrap(), expected, "{name}: verify_digest");
+        // As proven: the canonical object as the only range of a blob
+        let blob = compact_typed_data(&json).unwrap().into_bytes();
+        let ranges = find_json_ranges(&blob).unwrap();
+        assert_eq!(ranges.len(), 1, "{name}");
+        assert_eq!(ranges[0].digest(&blob, true), Ok(expected), "{name}: DigestRange::digest");
+    }
+}
+
+#[test]
+fn listed_digests_of_the_whole_corpus_match_viem() {
+    let paths = corpus("");
+    let blob: String = paths
+        .iter()
+        .map(|path| compact_typed_data(&fs::read_to_string(path).unwrap()).unwrap())
+        .collect();
+    let listed = list_digests(blob.as_bytes()).unwrap();
+    assert_eq!(listed.len(), paths.len());
+    for ((path, viem), object) in paths.iter().zip(viem(&paths)).zip(&listed) {
+        match viem {
+            Viem::Digest(digest) => assert_eq!(object.digest, digest, "{}", name(path)),
+            Viem::Error(e) => panic!("{}: viem refused it: {e}", name(path)),
+        }
+    }
+}
+
+#[test]
+fn both_refuse_invalid_typed_data() {
+    let paths = corpus("invalid");
+    for (path, viem) in paths.iter().zip(viem(&paths)) {
+        let name = name(path);
+        assert!(matches!(viem, Viem::Error(_)), "{name}: viem hashed it: {viem:?}");
+        let json = fs::read_to_string(path).unwrap();
+        assert!(verify_digest(&json).is_err(), "{name}: verify_digest hashed it");
+    }
+}
@@ -1,3 +1,15 @@
+use risc0_build::{embed_methods_with_options, GuestOptions};
+use std::collections::HashMap;
+
 fn main() {
-    risc0_build::embed_methods();
+    // Cargo features of this crate are not passed to the guests on their own
+    let mut features = Vec::new();
+    if std::env::var_os("CARGO_FEATURE_FAST_EIP712").is_some() {
+        features.push("fast-eip712".to_string());
+    }
+    let options = GuestOptions {
+        features,
+        ..Default::default()
+    };
+    embed_methods_with_options(HashMap::from([("single_sign", options)]));
 }
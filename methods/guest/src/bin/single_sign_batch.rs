@@ -0,0 +1,191 @@
+use alloy_primitives::{Address, B256};
+use risc0_zkvm::guest::env;
+use single_sign_types::{
+    hash::keccak256,
+    manifest_hash,
+    typed_data::{disclose, signing_hash, SeparatorCache, TypeHashCache},
+    Assertion, BatchInput, DigestMode, DigestRange, ErrorCode, ManifestCheck, Output,
+};
+
+fn main() {
+    // Read the batch from the host: the header, then the blob as a raw frame
+    let mut input: BatchInput = env::read();
+    input.typed_data_concat = env::read_frame().into();
+
+    // Pair every range with its signer and whether that signer's signature holds.
+    // A single signer's signature over the full concatenation is verified once for
+    // the whole batch; co-signers' signatures once per segment
+    let mut invalid_set = None;
+    let signed_ranges: Vec<(Address, bool, &DigestRange)> = if input.segments.is_empty() {
+        // When reporting validity, an unordered or overlapping manifest fails every
+        // range of it; ranges that are merely invalid on their own are attested per
+        // range
+        match DigestRange::validate_set(&input.ranges, input.typed_data_concat.len(), false) {
+            Err(code) if input.assert == Assertion::MustBeValid => panic!("Invalid ranges: {code}"),
+            Err(code @ ErrorCode::OverlappingRanges) => invalid_set = Some(code),
+            _ => {}
+        }
+        let signature = if input.allow_high_s {
+            input.signature.normalized_s()
+        } else {
+            input.signature
+        };
+        let signature_valid = match &input.key_signature {
+            None => input
+                .envelope
+                .verify(&input.typed_data_concat, signature, input.signer),
+            Some(key_signature) => key_signature.verify(&input.typed_data_concat),
+        }
+        .is_ok();
+        input
+            .ranges
+            .iter()
+            .map(|range| (input.signer, signature_valid, range))
+            .collect()
+    } else {
+        if let Err(code) = input.check_segments() {
+            panic!("Invalid segments: {code}");
+        }
+        input
+            .segments
+            .iter()
+            .flat_map(|segment| {
+                let signature_valid = segment.verify(&input.typed_data_concat).is_ok();
+                segment
+                    .ranges
+                    .iter()
+                    .map(move |range| (segment.signer, signature_valid, range))
+            })
+            .collect()
+    };
+
+    // Co-signers always sign with Ethereum keys, in EIP-191 personal mode, and must
+    // be low-s
+    let intent = input.envelope.intent().filter(|_| input.segments.is_empty());
+    let key_signature = input
+        .key_signature
+        .as_ref()
+        .filter(|_| input.segments.is_empty());
+    let allow_high_s = input.allow_high_s && input.segments.is_empty();
+    let low_s_enforced = key_signature.is_none() && !allow_high_s;
+    let signature_chain_id = input
+        .signature_chain_id
+        .filter(|_| input.segments.is_empty() && key_signature.is_none());
+    // Pin the exact ranges, in journal order, and the manifest they were checked against
+    let manifest_hash = manifest_hash(
+        signed_ranges.iter().map(|(_, _, range)| *range),
+        &input.expected_digests,
+    );
+    let blob_hash = keccak256(&input.typed_data_concat);
+    // Transfers go with ranges by journal position, so a count off by one would pair
+    // every later range with its neighbour's
+    let range_count = signed_ranges.len();
+    if !input.transfers.is_empty() && input.transfers.len() != range_count {
+        panic!("Invalid transfers: {} for {range_count} ranges", input.transfers.len());
+    }
+
+    // The blob's signature was checked once above; per range, only the message is
+    // hashed, once, and each distinct domain separator and struct type hash once per
+    // batch
+    let mut separators = SeparatorCache::default();
+    let mut type_hashes = TypeHashCache::default();
+    // Built with `fast-eip712`, messages with no fields to disclose and no policy to
+    // judge are hashed without parsing them into alloy's `TypedData`
+    let fast_eip712 =
+        cfg!(feature = "fast-eip712") && input.extract_paths.is_empty() && input.policy.is_none();
+
+    // Commit one journal entry per range, in segment order for co-signed batches;
+    // failures either panic or are attested
+    let mut outputs: Vec<Output> = signed_ranges
+        .into_iter()
+        .enumerate()
+        .map(|(i, (signer, signature_valid, range))| {
+            let attested = match input.digest_mode {
+                DigestMode::Eip712 if fast_eip712 => range
+                    .eip712_hashes(&input.typed_data_concat, input.require_canonical)
+                    .map(|(digest, struct_hash)| Output {
+                        struct_hash,
+                        ..Output::ok(signer, digest)
+                    }),
+                DigestMode::Eip712 => range
+                    .typed_data(&input.typed_data_concat, input.require_canonical)
+                    .and_then(|typed_data| {
+                        let struct_hash = type_hashes
+                            .hash_struct(&typed_data)
+                            .map_err(|_| ErrorCode::InvalidTypedData)?;
+                        let separator = separators.separator(&typed_data.domain);
+                        let digest = signing_hash(separator, struct_hash);
+                        let (fields, field_commitments) =
+                            disclose(&typed_data, &input.extract_paths, input.disclosure_salt)
+                                .map_err(|_| ErrorCode::InvalidFieldPath)?;
+                        let policy = input
+                            .policy
+                            .as_ref()
+                            .map(|policy| policy.evaluate(Some(&typed_data)));
+                        Ok(Output {
+                            fields,
+                            field_commitments,
+                            struct_hash,
+                            policy,
+                            ..Output::ok(signer, digest)
+                        })
+                    }),
+                // UserOperations have no EIP-712 message to extract fields from
+                mode @ DigestMode::UserOperation { .. } if input.extract_paths.is_empty() => mode
+                    .digest(range, &input.typed_data_concat, input.require_canonical)
+                    .map(|user_op_hash| Output {
+                        policy: input.policy.as_ref().map(|policy| policy.evaluate(None)),
+                        ..Output::ok(signer, user_op_hash)
+                    }),
+                DigestMode::UserOperation { .. } => Err(ErrorCode::InvalidFieldPath),
+            }
+            .and_then(|output| {
+                if !signature_valid {
+                    return Err(ErrorCode::InvalidSignature);
+                }
+                // Envelopes that do not sign the blob only authorize the digests they cover
+                if input.segments.is_empty() && !input.envelope.authorizes(output.digest) {
+                    return Err(ErrorCode::UnauthorizedAction);
+                }
+                // ... and only before its deadline, under the nonce the verifier expects
+                let intent = intent
+                    .map(|intent| intent.enforce(&input.intent_policy))
+                    .transpose()?;
+                let transfer = input.transfers.get(i).copied();
+                Ok(Output {
+                    intent,
+                    transfer,
+                    ..output
+                })
+            });
+            // A bad manifest is reported as such, whatever else is wrong with the range
+            let attested = invalid_set.map_or(attested, Err);
+            let output = match attested {
+                Ok(output) => output,
+                Err(code) if input.assert == Assertion::MustBeValid => {
+                    panic!("Invalid input: {code}")
+                }
+                Err(code) => Output::err(signer, code, range.clone()),
+            }
+            .signed_with(key_signature);
+            Output {
+                low_s_enforced,
+                signature_chain_id,
+                anchor: input.anchor,
+                manifest_hash,
+                blob_hash,
+                ..output
+            }
+        })
+        .collect();
+
+    // Attest whether the host's manifest of digests is honest
+    if !input.expected_digests.is_empty() {
+        let computed: Vec<B256> = outputs.iter().map(|output| output.digest).collect();
+        let manifest = ManifestCheck::compare(&input.expected_digests, &computed);
+        for output in &mut outputs {
+            output.manifest = Some(manifest);
+        }
+    }
+    env::commit(&outputs);
+}
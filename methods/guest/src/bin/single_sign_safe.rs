@@ -0,0 +1,18 @@
+use risc0_zkvm::guest::env;
+use single_sign_types::state_proof::{SafeStateInput, StateProofOutput};
+
+fn main() {
+    // Read the blob and the Safe's state proofs from the host
+    let input: SafeStateInput = env::read();
+
+    // Compute the EIP-712 digest of the range, then check from the block header down
+    // to the owner signatures that the Safe approved the blob holding it
+    let digest = input
+        .digest_range
+        .digest(&input.typed_data_concat, input.require_canonical)
+        .unwrap_or_else(|code| panic!("Invalid range: {code}"));
+    let output: StateProofOutput = input
+        .verify(digest)
+        .unwrap_or_else(|e| panic!("Invalid Safe state proof: {e}"));
+    env::commit(&output);
+}
@@ -0,0 +1,61 @@
+use alloy_primitives::{Bytes, B256};
+use alloy_sol_types::SolValue;
+use risc0_steel::{
+    ethereum::{EthChainSpec, EthEvmInput, ETH_MAINNET_CHAIN_SPEC, ETH_SEPOLIA_CHAIN_SPEC},
+    Contract,
+};
+use risc0_zkvm::{guest::env, sha::Digest};
+use single_sign_types::precondition::{
+    permit2_word_pos, IPermit2, IERC20, Precondition, PreconditionOutput,
+};
+
+fn main() {
+    // Read the single-range journal to build on and the EVM state for the view calls
+    let (image_id, journal, chain_id, preconditions, evm_input): (
+        Digest,
+        Vec<u8>,
+        u64,
+        Vec<Precondition>,
+        EthEvmInput,
+    ) = env::read();
+
+    // The signature was attested by another receipt; this one only holds if it does
+    env::verify(image_id, &journal).expect("No receipt attests the journal");
+
+    let chain_spec: &EthChainSpec = match chain_id {
+        1 => &ETH_MAINNET_CHAIN_SPEC,
+        11155111 => &ETH_SEPOLIA_CHAIN_SPEC,
+        _ => panic!("Unsupported chain {chain_id}"),
+    };
+    let evm_env = evm_input.into_env(chain_spec);
+    for precondition in &preconditions {
+        let returned = match precondition {
+            Precondition::MinBalance { token, owner, .. } => {
+                let call = IERC20::balanceOfCall { account: *owner };
+                Contract::new(*token, &evm_env).call_builder(&call).call()
+            }
+            Precondition::Permit2NonceUnused {
+                permit2,
+                owner,
+                nonce,
+            } => {
+                let call = IPermit2::nonceBitmapCall {
+                    owner: *owner,
+                    wordPos: permit2_word_pos(*nonce),
+                };
+                Contract::new(*permit2, &evm_env).call_builder(&call).call()
+            }
+        };
+        if !precondition.holds(returned) {
+            panic!("Precondition does not hold: {precondition:?}");
+        }
+    }
+
+    env::commit(&PreconditionOutput {
+        image_id: B256::from_slice(image_id.as_bytes()),
+        journal: Bytes::from(journal),
+        chain_id,
+        commitment: evm_env.commitment().abi_encode().into(),
+        preconditions,
+    });
+}
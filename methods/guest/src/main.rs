@@ -1,33 +1,134 @@
+use alloy_primitives::{Address, Bytes, B256};
 use risc0_zkvm::guest::env;
 use single_sign_types::{
-    signing::{verify_signature, MessageMode},
-    typed_data::verify_digest,
-    Input, Output,
+    hash::keccak256,
+    manifest_hash,
+    signing::{match_message_mode, Envelope},
+    typed_data::{disclose, signing_hash},
+    Assertion, DigestMode, ErrorCode, Input, Output,
 };
+use std::slice;
 
 fn main() {
-    // Read input from the host
-    let input: Input = env::read();
+    // Read input from the host: the header, then the blob as a raw frame
+    let mut input: Input = env::read();
+    input.typed_data_concat = env::read_frame().into();
 
-    // Compute EIP-712 digest inside the guest from the JSON bytes
-    let typed_data_slice =
-        &input.typed_data_concat[input.digest_range.start..input.digest_range.end];
-    let typed_data_digest = verify_digest(&String::from_utf8(typed_data_slice.to_vec()).unwrap())
-        .expect("Invalid typed data");
-
-    // Verify the signature against the same raw bytes using EIP-191 personal mode
-    let verified = verify_signature(
-        input.typed_data_concat,
-        input.signature,
-        input.signer,
-        MessageMode::Personal,
-    )
-    .expect("Invalid signature");
-
-    // Groundwork only: commit (signer, digest) as the public output
+    // Either refuse to produce a receipt for invalid inputs, or commit a failure
+    // journal attesting why they were rejected
+    let output = match attest(&input) {
+        Ok(output) => output,
+        Err(code) if input.assert == Assertion::MustBeValid => panic!("Invalid input: {code}"),
+        Err(code) => {
+            let signer = input.signer.unwrap_or_default();
+            Output::err(signer, code, input.digest_range.clone())
+        }
+    }
+    .signed_with(input.key_signature.as_ref());
     let output = Output {
-        signer: input.signer,
-        digest: typed_data_digest,
+        low_s_enforced: input.key_signature.is_none() && !input.allow_high_s,
+        signature_chain_id: input
+            .signature_chain_id
+            .filter(|_| input.key_signature.is_none()),
+        anchor: input.anchor,
+        manifest_hash: manifest_hash(slice::from_ref(&input.digest_range), &[]),
+        blob_hash: keccak256(&input.typed_data_concat),
+        ..output
     };
     env::commit(&output);
 }
+
+fn attest(input: &Input) -> Result<Output, ErrorCode> {
+    let blob = &input.typed_data_concat;
+    // Built with `fast-eip712`, messages with no fields to disclose and no policy to
+    // judge are hashed without parsing them into alloy's `TypedData`
+    let fast_eip712 =
+        cfg!(feature = "fast-eip712") && input.extract_paths.is_empty() && input.policy.is_none();
+    let (digest, struct_hash, fields, field_commitments, policy) = match input.digest_mode {
+        DigestMode::Eip712 if fast_eip712 => {
+            let (digest, struct_hash) =
+                input.digest_range.eip712_hashes(blob, input.require_canonical)?;
+            (digest, struct_hash, Bytes::new(), Vec::new(), None)
+        }
+        DigestMode::Eip712 => {
+            // Compute EIP-712 digest inside the guest from the JSON bytes
+            let typed_data = input.digest_range.typed_data(blob, input.require_canonical)?;
+            // Hash the message once; it is both committed and part of the digest
+            let struct_hash = typed_data
+                .hash_struct()
+                .map_err(|_| ErrorCode::InvalidTypedData)?;
+            let typed_data_digest = signing_hash(typed_data.domain.separator(), struct_hash);
+            let (fields, field_commitments) =
+                disclose(&typed_data, &input.extract_paths, input.disclosure_salt)
+                    .map_err(|_| ErrorCode::InvalidFieldPath)?;
+            // Judge the message against the host's policy; violations are attested
+            let policy = input
+                .policy
+                .as_ref()
+                .map(|policy| policy.evaluate(Some(&typed_data)));
+            (typed_data_digest, struct_hash, fields, field_commitments, policy)
+        }
+        // UserOperations have no EIP-712 message to extract fields from
+        mode @ DigestMode::UserOperation { .. } => {
+            if !input.extract_paths.is_empty() {
+                return Err(ErrorCode::InvalidFieldPath);
+            }
+            let user_op_hash = mode.digest(&input.digest_range, blob, input.require_canonical)?;
+            let policy = input.policy.as_ref().map(|policy| policy.evaluate(None));
+            (user_op_hash, B256::ZERO, Bytes::new(), Vec::new(), policy)
+        }
+    };
+
+    // Verify the signature against the same raw bytes, in EIP-191 personal mode
+    // or the smart account's ERC-7739 envelope, or under a non-Ethereum key; or
+    // against the intent envelope the wallet signed instead. High-s signatures are
+    // rejected unless the host opted into normalizing them. Without an expected
+    // signer, whoever the signature recovers to is attested; with candidate message
+    // modes, the first the expected signer signed in is
+    let signature = if input.allow_high_s {
+        input.signature.normalized_s()
+    } else {
+        input.signature
+    };
+    let (signer, message_mode) = match (&input.key_signature, input.signer) {
+        (None, signer) if !input.message_modes.is_empty() => {
+            // Modes replace the personal envelope, and need a signer to match
+            let expected = signer
+                .filter(|_| matches!(input.envelope, Envelope::Personal))
+                .ok_or(ErrorCode::InvalidSignature)?;
+            match_message_mode(blob, digest, signature, expected, &input.message_modes)
+                .map(|mode| (expected, Some(mode)))
+        }
+        (None, Some(expected)) => input
+            .envelope
+            .verify(blob, signature, expected)
+            .map(|_| (expected, None)),
+        (None, None) => input
+            .envelope
+            .recover(blob, signature)
+            .map(|signer| (signer, None)),
+        (Some(key_signature), _) => key_signature.verify(blob).map(|_| (Address::ZERO, None)),
+    }
+    .map_err(|_| ErrorCode::InvalidSignature)?;
+    // Envelopes that do not sign the blob only authorize the digests they cover
+    if !input.envelope.authorizes(digest) {
+        return Err(ErrorCode::UnauthorizedAction);
+    }
+    // ... and only before its deadline, under the nonce the verifier expects next
+    let intent = input
+        .envelope
+        .intent()
+        .map(|intent| intent.enforce(&input.intent_policy))
+        .transpose()?;
+
+    Ok(Output {
+        fields,
+        field_commitments,
+        struct_hash,
+        intent,
+        policy,
+        message_mode,
+        transfer: input.transfer,
+        ..Output::ok(signer, digest)
+    })
+}
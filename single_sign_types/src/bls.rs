@@ -0,0 +1,61 @@
+//! BLS12-381 aggregate signatures, so many approvers of one blob are proven with a
+//! single pairing check.
+//!
+//! Follows the Ethereum consensus ciphersuite: public keys in G1 (48 bytes
+//! compressed), signatures in G2 (96 bytes compressed), messages hashed to G2 with
+//! the proof-of-possession DST. Every approver signs `keccak256(blob)`. Summing
+//! public keys is only safe against rogue-key attacks when each key's proof of
+//! possession was checked when it was registered.
+
+use alloy_primitives::Bytes;
+use anyhow::{anyhow, bail, Result};
+use bls12_381::{
+    hash_to_curve::{ExpandMsgXmd, HashToCurve},
+    multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt,
+};
+use sha2_v0_9::Sha256;
+
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Sum the compressed G1 `public_keys` into the aggregate public key.
+pub fn aggregate_public_keys(public_keys: &[Bytes]) -> Result<G1Affine> {
+    if public_keys.is_empty() {
+        bail!("No BLS public keys to aggregate");
+    }
+    public_keys
+        .iter()
+        .try_fold(G1Projective::identity(), |aggregate, public_key| {
+            let public_key: &[u8; 48] = public_key
+                .as_ref()
+                .try_into()
+                .map_err(|_| anyhow!("BLS public key must be 48 bytes"))?;
+            let public_key = Option::<G1Affine>::from(G1Affine::from_compressed(public_key))
+                .filter(|key| !bool::from(key.is_identity()))
+                .ok_or_else(|| anyhow!("Invalid BLS public key"))?;
+            Ok(aggregate + public_key)
+        })
+        .map(G1Affine::from)
+}
+
+/// Verify that `signature` aggregates signatures over `message` by every key in
+/// `public_keys`: `e(aggregateKey, H(message)) == e(g1, signature)`.
+pub fn verify_aggregate(public_keys: &[Bytes], message: &[u8], signature: &[u8]) -> Result<()> {
+    let aggregate = aggregate_public_keys(public_keys)?;
+    let signature: &[u8; 96] = signature
+        .try_into()
+        .map_err(|_| anyhow!("BLS signature must be 96 bytes"))?;
+    let signature = Option::<G2Affine>::from(G2Affine::from_compressed(signature))
+        .ok_or_else(|| anyhow!("Invalid BLS signature"))?;
+    let hashed = <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(message, DST);
+
+    // Both pairings share one Miller loop and final exponentiation
+    let check = multi_miller_loop(&[
+        (&aggregate, &G2Prepared::from(G2Affine::from(hashed))),
+        (&-G1Affine::generator(), &G2Prepared::from(signature)),
+    ])
+    .final_exponentiation();
+    if check != Gt::identity() {
+        bail!("BLS aggregate signature does not verify");
+    }
+    Ok(())
+}
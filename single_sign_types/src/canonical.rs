@@ -0,0 +1,38 @@
+use alloc::{string::String, vec::Vec};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+/// Re-serialize an EIP-712 typed-data JSON into its canonical form:
+/// object keys sorted lexicographically and no insignificant whitespace.
+/// Semantically identical payloads always compact to the same bytes, so they
+/// hash to the same signed `typed_data_concat`.
+pub fn compact_typed_data(typed_data_json: &str) -> Result<String> {
+    let value: Value = serde_json::from_str(typed_data_json)
+        .map_err(|e| anyhow!("Invalid typed data JSON: {e}"))?;
+    serde_json::to_string(&sort_keys(value))
+        .map_err(|e| anyhow!("Failed serializing canonical typed data: {e}"))
+}
+
+/// Returns `true` if `typed_data_json` is already byte-for-byte in canonical form.
+pub fn is_canonical(typed_data_json: &str) -> Result<bool> {
+    Ok(compact_typed_data(typed_data_json)? == typed_data_json)
+}
+
+// Rebuild objects from sorted entries so the output order does not depend on
+// whether serde_json's `preserve_order` feature is enabled somewhere in the graph.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
@@ -0,0 +1,59 @@
+//! Hash-chained envelopes: batches that grow without re-signing old content.
+//!
+//! The commitment starts at zero and absorbs each object's EIP-712 digest in turn,
+//! `c_i = keccak256(c_{i-1} || d_i)`. Appending objects to a signed blob only needs
+//! a signature over the new head `H(prev_commitment || new_object_digest)`; the
+//! guest proves an object by recomputing its digest and folding it, and the digests
+//! after it, from the commitment before it up to the signed head.
+
+use alloc::vec::Vec;
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+
+use crate::{hash::keccak256, serde_hex};
+
+/// The signed head and what links one object's digest to it, as carried by
+/// `signing::Envelope::HashChain`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChainLink {
+    #[serde(with = "serde_hex::b256")]
+    pub head: B256,
+    /// Commitment before the object.
+    #[serde(with = "serde_hex::b256")]
+    pub prev: B256,
+    /// Digests of the objects appended after it, in order.
+    #[serde(with = "serde_hex::b256_vec")]
+    pub later: Vec<B256>,
+}
+
+impl ChainLink {
+    /// Whether appending `digest` to `prev`, then `later`, reaches `head`.
+    pub fn contains(&self, digest: B256) -> bool {
+        extend(append(self.prev, digest), &self.later) == self.head
+    }
+}
+
+/// The commitment after appending `digest` to `prev`.
+pub fn append(prev: B256, digest: B256) -> B256 {
+    keccak256([prev.as_slice(), digest.as_slice()].concat())
+}
+
+/// The commitment after appending `digests` to `prev`, in order.
+pub fn extend(prev: B256, digests: &[B256]) -> B256 {
+    digests.iter().fold(prev, |prev, digest| append(prev, *digest))
+}
+
+/// Head of the chain over `digests`, starting from the zero commitment.
+pub fn head(digests: &[B256]) -> B256 {
+    extend(B256::ZERO, digests)
+}
+
+/// Link for `digests[index]`; `None` when `index` is out of bounds.
+pub fn link(digests: &[B256], index: usize) -> Option<ChainLink> {
+    let later = digests.get(index + 1..)?;
+    Some(ChainLink {
+        head: head(digests),
+        prev: head(&digests[..index]),
+        later: later.to_vec(),
+    })
+}
@@ -0,0 +1,531 @@
+//! A minimal EIP-712 hasher for the guests: parses the typed-data JSON into borrowed
+//! slices and hashes `encodeData` as it walks the message, instead of deserializing
+//! through `serde_json` and coercing into `DynSolValue`s with `alloy-dyn-abi`. It only
+//! computes the digest and struct hash, so messages whose fields are extracted or
+//! judged by a policy still go through `typed_data`. The host cross-checks it against
+//! alloy before proving, see the `fast-eip712` feature.
+
+use alloc::{borrow::Cow, string::String, vec, vec::Vec};
+use alloy_primitives::{hex, B256, U256};
+use anyhow::{anyhow, bail, Result};
+use core::{
+    cell::RefCell,
+    str::{self, Chars},
+};
+
+use crate::{
+    hash::{keccak256, Keccak256},
+    typed_data::signing_hash,
+};
+
+// Deepest nesting accepted, well past any real message, so hostile input cannot
+// exhaust the guest's stack
+const MAX_DEPTH: usize = 64;
+
+/// The EIP-712 digest and struct hash of a typed-data JSON object, as
+/// `TypedData::eip712_signing_hash` and `TypedData::hash_struct` compute them.
+pub fn hash_typed_data(json: &[u8]) -> Result<(B256, B256)> {
+    let root = Parser { json, pos: 0 }.document()?;
+    let member = |key: &'static str| root.get(key).ok_or_else(|| anyhow!("Missing `{key}`"));
+    let types = Types::new(member("types")?)?;
+    let primary_type = member("primaryType")?.as_str()?;
+    let struct_hash = types.hash_struct(primary_type, member("message")?)?;
+    let separator = domain_separator(member("domain")?)?;
+    Ok((signing_hash(separator, struct_hash), struct_hash))
+}
+
+/// `EIP712Domain` hashed over the fields present, in the order the EIP lists them.
+fn domain_separator(domain: &Json) -> Result<B256> {
+    let mut encoded_type = String::from("EIP712Domain(");
+    let mut words = Vec::with_capacity(5);
+    let fields: [(&str, &str, fn(&Json) -> Result<B256>); 5] = [
+        ("name", "string", string_word),
+        ("version", "string", string_word),
+        ("chainId", "uint256", |value| uint_word(value, 256)),
+        ("verifyingContract", "address", address_word),
+        ("salt", "bytes32", |value| fixed_bytes_word(value, 32)),
+    ];
+    for (name, ty, word) in fields {
+        let Some(value) = domain.get(name).filter(|value| !matches!(value, Json::Null)) else {
+            continue;
+        };
+        if !words.is_empty() {
+            encoded_type.push(',');
+        }
+        encoded_type.push_str(ty);
+        encoded_type.push(' ');
+        encoded_type.push_str(name);
+        words.push(word(value).map_err(|e| anyhow!("Domain field `{name}`: {e}"))?);
+    }
+    encoded_type.push(')');
+    let mut hasher = Keccak256::new();
+    hasher.update(keccak256(encoded_type));
+    for word in words {
+        hasher.update(word);
+    }
+    Ok(hasher.finalize())
+}
+
+/// The struct definitions of `types`, each a list of `(name, type)` members, with
+/// their type hashes once computed.
+struct Types<'j> {
+    structs: Vec<(&'j str, Vec<(&'j str, &'j str)>)>,
+    type_hashes: RefCell<Vec<Option<B256>>>,
+}
+
+impl<'j> Types<'j> {
+    fn new(types: &'j Json) -> Result<Self> {
+        let Json::Object(definitions) = types else {
+            bail!("`types` is not an object");
+        };
+        let structs: Vec<_> = definitions
+            .iter()
+            .map(|(name, members)| {
+                let Json::Array(members) = members else {
+                    bail!("Type `{name}` is not an array");
+                };
+                let members = members
+                    .iter()
+                    .map(|member| {
+                        let field = |key| {
+                            member
+                                .get(key)
+                                .ok_or_else(|| anyhow!("Member of `{name}` has no `{key}`"))?
+                                .as_str()
+                        };
+                        Ok((field("name")?, field("type")?))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok((&**name, members))
+            })
+            .collect::<Result<_>>()?;
+        let type_hashes = RefCell::new(vec![None; structs.len()]);
+        Ok(Self {
+            structs,
+            type_hashes,
+        })
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.structs.iter().position(|(known, _)| *known == name)
+    }
+
+    fn members(&self, name: &str) -> Option<&[(&'j str, &'j str)]> {
+        self.index(name).map(|index| self.structs[index].1.as_slice())
+    }
+
+    fn type_hash(&self, index: usize) -> B256 {
+        if let Some(type_hash) = self.type_hashes.borrow()[index] {
+            return type_hash;
+        }
+        let type_hash = keccak256(self.encode_type(self.structs[index].0));
+        self.type_hashes.borrow_mut()[index] = Some(type_hash);
+        type_hash
+    }
+
+    /// `encodeType`: the struct, then the structs it references, sorted by name.
+    fn encode_type(&self, name: &str) -> String {
+        let mut dependencies = Vec::new();
+        self.collect_dependencies(name, &mut dependencies);
+        dependencies.sort_unstable();
+        let mut encoded = String::new();
+        let others = dependencies.into_iter().filter(|dependency| *dependency != name);
+        for dependency in core::iter::once(name).chain(others) {
+            encoded.push_str(dependency);
+            encoded.push('(');
+            let members = self.members(dependency).unwrap_or_default();
+            for (i, (member, ty)) in members.iter().enumerate() {
+                if i > 0 {
+                    encoded.push(',');
+                }
+                encoded.push_str(ty);
+                encoded.push(' ');
+                encoded.push_str(member);
+            }
+            encoded.push(')');
+        }
+        encoded
+    }
+
+    fn collect_dependencies<'n>(&'n self, name: &'n str, dependencies: &mut Vec<&'n str>) {
+        if dependencies.contains(&name) {
+            return;
+        }
+        let Some(members) = self.members(name) else {
+            return;
+        };
+        dependencies.push(name);
+        for (_, ty) in members {
+            let base = ty.split_once('[').map_or(*ty, |(base, _)| base);
+            self.collect_dependencies(base, dependencies);
+        }
+    }
+
+    /// `hashStruct`: keccak256 of the type hash followed by every member's data word.
+    fn hash_struct(&self, name: &str, value: &Json) -> Result<B256> {
+        let index = self
+            .index(name)
+            .ok_or_else(|| anyhow!("Unknown type `{name}`"))?;
+        if !matches!(value, Json::Object(_)) {
+            bail!("Value of `{name}` is not an object");
+        }
+        let mut hasher = Keccak256::new();
+        hasher.update(self.type_hash(index));
+        for (member, ty) in &self.structs[index].1 {
+            let value = value
+                .get(member)
+                .ok_or_else(|| anyhow!("`{name}` has no `{member}`"))?;
+            hasher.update(self.data_word(ty, value)?);
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// The 32-byte `encodeData` word of a value of type `ty`.
+    fn data_word(&self, ty: &str, value: &Json) -> Result<B256> {
+        if let Some(element) = ty.strip_suffix(']') {
+            let (element, length) = element
+                .rsplit_once('[')
+                .ok_or_else(|| anyhow!("Malformed array type `{ty}`"))?;
+            let Json::Array(values) = value else {
+                bail!("Value of `{ty}` is not an array");
+            };
+            if !length.is_empty() && length.parse::<usize>().ok() != Some(values.len()) {
+                bail!("Value of `{ty}` has {} elements", values.len());
+            }
+            let mut hasher = Keccak256::new();
+            for value in values {
+                hasher.update(self.data_word(element, value)?);
+            }
+            return Ok(hasher.finalize());
+        }
+        if self.members(ty).is_some() {
+            return self.hash_struct(ty, value);
+        }
+        match ty {
+            "string" => string_word(value),
+            "bytes" => Ok(keccak256(hex_bytes(value)?)),
+            "address" => address_word(value),
+            "bool" => match value {
+                Json::Bool(flag) => Ok(B256::with_last_byte(*flag as u8)),
+                Json::String(flag) if flag == "true" || flag == "false" => {
+                    Ok(B256::with_last_byte((flag == "true") as u8))
+                }
+                _ => bail!("Value of `bool` is not a boolean"),
+            },
+            _ => {
+                if let Some(size) = ty.strip_prefix("bytes") {
+                    let size = size.parse().map_err(|_| anyhow!("Invalid type `{ty}`"))?;
+                    return fixed_bytes_word(value, size);
+                }
+                if let Some(bits) = ty.strip_prefix("uint") {
+                    return uint_word(value, bit_width(bits)?);
+                }
+                if let Some(bits) = ty.strip_prefix("int") {
+                    return int_word(value, bit_width(bits)?);
+                }
+                bail!("Unsupported type `{ty}`")
+            }
+        }
+    }
+}
+
+fn bit_width(bits: &str) -> Result<usize> {
+    let bits = match bits {
+        "" => 256,
+        bits => bits.parse().map_err(|_| anyhow!("Invalid integer width `{bits}`"))?,
+    };
+    if bits == 0 || bits > 256 || bits % 8 != 0 {
+        bail!("Invalid integer width {bits}");
+    }
+    Ok(bits)
+}
+
+fn string_word(value: &Json) -> Result<B256> {
+    Ok(keccak256(value.as_str()?))
+}
+
+fn hex_bytes(value: &Json) -> Result<Vec<u8>> {
+    hex::decode(value.as_str()?).map_err(|e| anyhow!("Invalid hex: {e}"))
+}
+
+fn fixed_bytes_word(value: &Json, size: usize) -> Result<B256> {
+    let bytes = hex_bytes(value)?;
+    if size == 0 || size > 32 || bytes.len() != size {
+        bail!("Expected {size} bytes, got {}", bytes.len());
+    }
+    Ok(B256::right_padding_from(&bytes))
+}
+
+fn address_word(value: &Json) -> Result<B256> {
+    let bytes = hex_bytes(value)?;
+    if bytes.len() != 20 {
+        bail!("Expected a 20-byte address, got {} bytes", bytes.len());
+    }
+    Ok(B256::left_padding_from(&bytes))
+}
+
+// Integers are JSON numbers or strings, in decimal or 0x-prefixed hex
+fn magnitude(digits: &str) -> Result<U256> {
+    match digits.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16),
+        None => U256::from_str_radix(digits, 10),
+    }
+    .map_err(|e| anyhow!("Invalid integer `{digits}`: {e}"))
+}
+
+fn integer_text<'v>(value: &'v Json) -> Result<&'v str> {
+    match value {
+        Json::Number(number) => Ok(*number),
+        Json::String(number) => Ok(number.as_ref()),
+        _ => bail!("Value is not an integer"),
+    }
+}
+
+fn uint_word(value: &Json, bits: usize) -> Result<B256> {
+    let value = magnitude(integer_text(value)?)?;
+    if bits < 256 && value >> bits != U256::ZERO {
+        bail!("Value does not fit uint{bits}");
+    }
+    Ok(value.into())
+}
+
+/// Signed integers are sign-extended to 256 bits, in two's complement.
+fn int_word(value: &Json, bits: usize) -> Result<B256> {
+    let text = integer_text(value)?;
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = magnitude(digits)?;
+    let limit = U256::from(1) << (bits - 1);
+    if negative {
+        if value > limit {
+            bail!("Value does not fit int{bits}");
+        }
+        Ok(value.wrapping_neg().into())
+    } else {
+        if value >= limit {
+            bail!("Value does not fit int{bits}");
+        }
+        Ok(value.into())
+    }
+}
+
+/// A JSON value borrowing from the input: numbers keep their text, and strings are
+/// only copied when they hold escapes.
+enum Json<'a> {
+    Null,
+    Bool(bool),
+    Number(&'a str),
+    String(Cow<'a, str>),
+    Array(Vec<Json<'a>>),
+    Object(Vec<(Cow<'a, str>, Json<'a>)>),
+}
+
+impl<'a> Json<'a> {
+    fn get(&self, key: &str) -> Option<&Json<'a>> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Result<&str> {
+        match self {
+            Json::String(string) => Ok(string.as_ref()),
+            _ => bail!("Value is not a string"),
+        }
+    }
+}
+
+struct Parser<'a> {
+    json: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn document(mut self) -> Result<Json<'a>> {
+        let value = self.value(0)?;
+        self.skip_whitespace();
+        if self.pos != self.json.len() {
+            bail!("Trailing bytes at {}", self.pos);
+        }
+        Ok(value)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            bail!("Expected `{}` at {}", byte as char, self.pos);
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json<'a>> {
+        if depth > MAX_DEPTH {
+            bail!("Nested deeper than {MAX_DEPTH} levels");
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                let mut members = Vec::new();
+                self.sequence(b'}', |parser| {
+                    parser.skip_whitespace();
+                    let name = parser.string()?;
+                    parser.expect(b':')?;
+                    members.push((name, parser.value(depth + 1)?));
+                    Ok(())
+                })?;
+                Ok(Json::Object(members))
+            }
+            Some(b'[') => {
+                let mut values = Vec::new();
+                self.sequence(b']', |parser| {
+                    values.push(parser.value(depth + 1)?);
+                    Ok(())
+                })?;
+                Ok(Json::Array(values))
+            }
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+                    self.pos += 1;
+                }
+                // Digits are ASCII, so the slice is valid UTF-8
+                let number = str::from_utf8(&self.json[start..self.pos]).unwrap_or_default();
+                Ok(Json::Number(number))
+            }
+            _ => bail!("Unexpected byte at {}", self.pos),
+        }
+    }
+
+    // The comma-separated elements of an array or object, after its opening bracket
+    fn sequence(
+        &mut self,
+        close: u8,
+        mut element: impl FnMut(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            element(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(byte) if byte == close => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => bail!("Expected `,` or `{}` at {}", close as char, self.pos),
+            }
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json<'a>) -> Result<Json<'a>> {
+        if !self.json[self.pos..].starts_with(word.as_bytes()) {
+            bail!("Unexpected byte at {}", self.pos);
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<Cow<'a, str>> {
+        if self.peek() != Some(b'"') {
+            bail!("Expected a string at {}", self.pos);
+        }
+        self.pos += 1;
+        let start = self.pos;
+        let mut escaped = false;
+        loop {
+            match self.peek() {
+                None => bail!("Unterminated string at {start}"),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    escaped = true;
+                    self.pos += 2;
+                }
+                Some(0..=0x1f) => bail!("Control character in string at {}", self.pos),
+                Some(_) => self.pos += 1,
+            }
+        }
+        let raw = str::from_utf8(&self.json[start..self.pos])
+            .map_err(|_| anyhow!("Invalid UTF-8 in string at {start}"))?;
+        self.pos += 1;
+        Ok(if escaped {
+            Cow::Owned(unescape(raw)?)
+        } else {
+            Cow::Borrowed(raw)
+        })
+    }
+}
+
+fn unescape(raw: &str) -> Result<String> {
+    let mut unescaped = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let c = match chars.next() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                let mut code = hex_escape(&mut chars)?;
+                // Characters outside the BMP are escaped as a UTF-16 surrogate pair
+                if (0xd800..0xdc00).contains(&code) {
+                    if chars.next() != Some('\\') || chars.next() != Some('u') {
+                        bail!("Lone surrogate in string");
+                    }
+                    let low = hex_escape(&mut chars)?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        bail!("Lone surrogate in string");
+                    }
+                    code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                }
+                char::from_u32(code).ok_or_else(|| anyhow!("Lone surrogate in string"))?
+            }
+            _ => bail!("Invalid escape in string"),
+        };
+        unescaped.push(c);
+    }
+    Ok(unescaped)
+}
+
+fn hex_escape(chars: &mut Chars) -> Result<u32> {
+    (0..4).try_fold(0, |code, _| {
+        let digit = chars
+            .next()
+            .and_then(|c| c.to_digit(16))
+            .ok_or_else(|| anyhow!("Invalid \\u escape in string"))?;
+        Ok(code * 16 + digit)
+    })
+}
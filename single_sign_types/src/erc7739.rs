@@ -0,0 +1,94 @@
+//! ERC-7739 defensive rehashing for smart accounts.
+//!
+//! ERC-7739 wallets never sign an app's hash directly. They wrap it in an envelope
+//! bound to the account's own EIP-712 domain, so a signature for one account cannot
+//! be replayed against another account sharing the same owner key:
+//! - `PersonalSign(bytes prefixed)` for `personal_sign` messages
+//! - `TypedDataSign(Contents contents,string name,string version,uint256 chainId,
+//!   address verifyingContract,bytes32 salt)` for typed data
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+};
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::Eip712Domain;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{hash::keccak256, serde_hex, signing::eip191_hash, typed_data::verify_digest};
+
+/// The smart account's `eip712Domain()`, in the JSON shape wallets report it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountDomain {
+    pub name: String,
+    pub version: String,
+    pub chain_id: u64,
+    #[serde(with = "serde_hex::address")]
+    pub verifying_contract: Address,
+    /// Part of the domain separator only when set
+    #[serde(with = "serde_hex::b256_option")]
+    pub salt: Option<B256>,
+}
+
+impl AccountDomain {
+    pub fn eip712_domain(&self) -> Eip712Domain {
+        Eip712Domain::new(
+            Some(self.name.clone().into()),
+            Some(self.version.clone().into()),
+            Some(U256::from(self.chain_id)),
+            Some(self.verifying_contract),
+            self.salt,
+        )
+    }
+}
+
+/// Hash an account signs for the `personal_sign` `message`:
+/// `keccak256("\x19\x01" || accountDomainSeparator || hashStruct(PersonalSign))`.
+pub fn personal_sign_hash(message: &[u8], account: &AccountDomain) -> B256 {
+    // `bytes` members are hashed, and keccak256(prefixed) is the EIP-191 hash
+    let type_hash = keccak256("PersonalSign(bytes prefixed)");
+    let struct_hash = keccak256([type_hash.as_slice(), eip191_hash(message).as_slice()].concat());
+    let separator = account.eip712_domain().separator();
+    keccak256([&[0x19, 0x01][..], separator.as_slice(), struct_hash.as_slice()].concat())
+}
+
+/// Hash an account signs for the app typed data `typed_data_json`: the app's
+/// message becomes the `contents` of a `TypedDataSign` struct, still hashed under
+/// the app's domain, as produced by `eth_signTypedData_v4` for the wrapped request.
+pub fn typed_data_sign_hash(typed_data_json: &str, account: &AccountDomain) -> Result<B256> {
+    let mut typed: Value = serde_json::from_str(typed_data_json)
+        .map_err(|e| anyhow!("Invalid EIP-712 typed data JSON: {e}"))?;
+    let contents = typed["primaryType"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Typed data has no primaryType"))?
+        .to_owned();
+    let types = typed
+        .get_mut("types")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| anyhow!("Typed data has no types"))?;
+    types.insert(
+        "TypedDataSign".to_owned(),
+        json!([
+            { "name": "contents", "type": contents },
+            { "name": "name", "type": "string" },
+            { "name": "version", "type": "string" },
+            { "name": "chainId", "type": "uint256" },
+            { "name": "verifyingContract", "type": "address" },
+            { "name": "salt", "type": "bytes32" },
+        ]),
+    );
+    let message = typed["message"].take();
+    typed["message"] = json!({
+        "contents": message,
+        "name": account.name,
+        "version": account.version,
+        "chainId": account.chain_id,
+        "verifyingContract": account.verifying_contract,
+        "salt": account.salt.unwrap_or_default(),
+    });
+    typed["primaryType"] = json!("TypedDataSign");
+    verify_digest(&typed.to_string())
+}
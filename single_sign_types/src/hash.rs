@@ -0,0 +1,63 @@
+//! Keccak-256 for everything this crate hashes, with the backend chosen by features
+//! rather than by the caller. With `zkvm-keccak` (the guests) hashing goes through
+//! `tiny-keccak`, which the guest manifest patches to RISC Zero's keccak accelerator;
+//! otherwise (the host and other native builds) it is alloy's software keccak, or
+//! `keccak-asm` when alloy's `asm-keccak` is on. Both compute the same digests.
+
+use alloy_primitives::B256;
+
+/// keccak256 of `data`.
+pub fn keccak256(data: impl AsRef<[u8]>) -> B256 {
+    #[cfg(feature = "zkvm-keccak")]
+    {
+        let mut hasher = Keccak256::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+    #[cfg(not(feature = "zkvm-keccak"))]
+    alloy_primitives::keccak256(data)
+}
+
+/// Incremental keccak256, for preimages hashed piece by piece.
+#[derive(Clone)]
+pub struct Keccak256 {
+    #[cfg(feature = "zkvm-keccak")]
+    inner: tiny_keccak::Keccak,
+    #[cfg(not(feature = "zkvm-keccak"))]
+    inner: alloy_primitives::Keccak256,
+}
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keccak256 {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "zkvm-keccak")]
+            inner: tiny_keccak::Keccak::v256(),
+            #[cfg(not(feature = "zkvm-keccak"))]
+            inner: alloy_primitives::Keccak256::new(),
+        }
+    }
+
+    pub fn update(&mut self, data: impl AsRef<[u8]>) {
+        #[cfg(feature = "zkvm-keccak")]
+        tiny_keccak::Hasher::update(&mut self.inner, data.as_ref());
+        #[cfg(not(feature = "zkvm-keccak"))]
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> B256 {
+        #[cfg(feature = "zkvm-keccak")]
+        {
+            let mut digest = B256::ZERO;
+            tiny_keccak::Hasher::finalize(self.inner, &mut digest.0);
+            digest
+        }
+        #[cfg(not(feature = "zkvm-keccak"))]
+        self.inner.finalize()
+    }
+}
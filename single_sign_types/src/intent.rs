@@ -0,0 +1,251 @@
+//! Intent envelopes: one EIP-712 signature authorizing N heterogeneous actions.
+//!
+//! Instead of signing the raw concatenation, the wallet signs a `SingleSignEnvelope`
+//! listing the EIP-712 digest of every object in the blob, as a proper typed-data
+//! request it can render. The guests recompute each object's digest from the raw
+//! bytes and only attest those the envelope lists, so the signature never has to
+//! cover the blob itself.
+
+use alloc::{string::String, vec::Vec};
+use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_sol_types::{sol, Eip712Domain, SolStruct};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    canonical::compact_typed_data, serde_hex, typed_data::verify_digest, DigestRange, ErrorCode,
+};
+
+/// `name` of the domain envelopes are signed under.
+pub const DOMAIN_NAME: &str = "SingleSign";
+/// `version` of the domain envelopes are signed under.
+pub const DOMAIN_VERSION: &str = "1";
+
+sol! {
+    /// The typed data the wallet signs: the digests of the actions it authorizes,
+    /// in blob order.
+    #[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    struct SingleSignEnvelope {
+        bytes32[] actions;
+        uint256 nonce;
+        uint256 deadline;
+    }
+}
+
+/// Where an envelope is meant to be consumed; binds its signature to one contract on
+/// one chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntentDomain {
+    pub chain_id: u64,
+    #[serde(with = "serde_hex::address")]
+    pub verifying_contract: Address,
+}
+
+impl IntentDomain {
+    pub fn eip712_domain(&self) -> Eip712Domain {
+        Eip712Domain::new(
+            Some(DOMAIN_NAME.into()),
+            Some(DOMAIN_VERSION.into()),
+            Some(U256::from(self.chain_id)),
+            Some(self.verifying_contract),
+            None,
+        )
+    }
+}
+
+/// An envelope together with the domain it was signed under, as carried by
+/// `signing::Envelope::Intent`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignedIntent {
+    pub domain: IntentDomain,
+    #[serde(with = "pinned_envelope")]
+    pub envelope: SingleSignEnvelope,
+}
+
+// `SingleSignEnvelope` in the `serde_hex` formats, which fields inside `sol!` cannot
+// opt into
+#[derive(Serialize, Deserialize)]
+struct PinnedEnvelope {
+    #[serde(with = "serde_hex::b256_vec")]
+    actions: Vec<B256>,
+    #[serde(with = "serde_hex::u256")]
+    nonce: U256,
+    #[serde(with = "serde_hex::u256")]
+    deadline: U256,
+}
+
+mod pinned_envelope {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        envelope: &SingleSignEnvelope,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        PinnedEnvelope {
+            actions: envelope.actions.clone(),
+            nonce: envelope.nonce,
+            deadline: envelope.deadline,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SingleSignEnvelope, D::Error> {
+        let envelope = PinnedEnvelope::deserialize(deserializer)?;
+        Ok(SingleSignEnvelope {
+            actions: envelope.actions,
+            nonce: envelope.nonce,
+            deadline: envelope.deadline,
+        })
+    }
+}
+
+impl SignedIntent {
+    /// The EIP-712 hash the wallet signs.
+    pub fn signing_hash(&self) -> B256 {
+        self.envelope
+            .eip712_signing_hash(&self.domain.eip712_domain())
+    }
+
+    /// Whether `digest` is one of the actions the envelope authorizes.
+    pub fn authorizes(&self, digest: B256) -> bool {
+        self.envelope.actions.contains(&digest)
+    }
+
+    /// Check the envelope's deadline and nonce against `policy`, returning what the
+    /// guest commits about them.
+    pub fn enforce(&self, policy: &IntentPolicy) -> Result<IntentCommitment, ErrorCode> {
+        if self.envelope.deadline < U256::from(policy.current_time) {
+            return Err(ErrorCode::IntentExpired);
+        }
+        if self.envelope.nonce != policy.expected_nonce {
+            return Err(ErrorCode::NonceMismatch);
+        }
+        Ok(IntentCommitment {
+            nonce: self.envelope.nonce,
+            deadline: self.envelope.deadline,
+            current_time: policy.current_time,
+        })
+    }
+}
+
+/// What the prover asserts about the chain when proving an intent. The guest checks
+/// the envelope against it and commits both, so a verifier contract only compares
+/// `current_time` with its own clock and `nonce` with its own counter before
+/// consuming the nonce. Ignored for other envelopes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntentPolicy {
+    /// Unix time in seconds; the envelope's `deadline` must not be before it.
+    pub current_time: u64,
+    /// The signer's next nonce at the consuming contract, e.g. from `rpc::intent_nonce`.
+    #[serde(with = "serde_hex::u256")]
+    pub expected_nonce: U256,
+}
+
+/// Committed in `Output.intent` for ranges an intent envelope authorized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntentCommitment {
+    #[serde(with = "serde_hex::u256")]
+    pub nonce: U256,
+    #[serde(with = "serde_hex::u256")]
+    pub deadline: U256,
+    pub current_time: u64,
+}
+
+/// Typed-data objects wrapped into an envelope, ready to sign and prove.
+#[derive(Debug, Clone)]
+pub struct Wrapped {
+    /// The canonical objects, concatenated.
+    pub blob: Bytes,
+    pub ranges: Vec<DigestRange>,
+    pub intent: SignedIntent,
+}
+
+/// Wrap arbitrary typed-data objects into one envelope: canonicalize and concatenate
+/// them, and list their EIP-712 digests as the envelope's actions.
+pub fn wrap(
+    objects: &[&str],
+    domain: IntentDomain,
+    nonce: U256,
+    deadline: U256,
+) -> Result<Wrapped> {
+    let mut blob = String::new();
+    let mut ranges = Vec::with_capacity(objects.len());
+    let mut actions = Vec::with_capacity(objects.len());
+    for object in objects {
+        let compact = compact_typed_data(object)?;
+        actions.push(verify_digest(&compact)?);
+        let start = blob.len();
+        blob.push_str(&compact);
+        ranges.push(DigestRange {
+            start,
+            end: blob.len(),
+        });
+    }
+    Ok(Wrapped {
+        blob: Bytes::from(blob.into_bytes()),
+        ranges,
+        intent: SignedIntent {
+            domain,
+            envelope: SingleSignEnvelope {
+                actions,
+                nonce,
+                deadline,
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn intent() -> SignedIntent {
+        SignedIntent {
+            domain: IntentDomain {
+                chain_id: 8453,
+                verifying_contract: Address::repeat_byte(0xcc),
+            },
+            envelope: SingleSignEnvelope {
+                actions: vec![B256::repeat_byte(1), B256::repeat_byte(2)],
+                nonce: U256::from(7),
+                deadline: U256::from(1_700_000_000u64),
+            },
+        }
+    }
+
+    #[test]
+    fn intents_round_trip_binary_in_alloys_layout() {
+        let intent = intent();
+        let encoded = bincode::serialize(&intent).unwrap();
+        let decoded: SignedIntent = bincode::deserialize(&encoded).unwrap();
+        assert_eq!((decoded.domain, decoded.envelope), (intent.domain, intent.envelope.clone()));
+        let domain = (intent.domain.chain_id, intent.domain.verifying_contract);
+        let envelope = &intent.envelope;
+        let alloy = (domain, (&envelope.actions, envelope.nonce, envelope.deadline));
+        assert_eq!(encoded, bincode::serialize(&alloy).unwrap());
+    }
+
+    #[test]
+    fn intents_round_trip_json() {
+        let json = serde_json::to_string(&intent()).unwrap();
+        let decoded: SignedIntent = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.envelope, intent().envelope);
+    }
+
+    #[test]
+    fn policies_round_trip_binary_in_alloys_layout() {
+        let policy = IntentPolicy {
+            current_time: 1_699_990_000,
+            expected_nonce: U256::from(7),
+        };
+        let encoded = bincode::serialize(&policy).unwrap();
+        assert_eq!(bincode::deserialize::<IntentPolicy>(&encoded).unwrap(), policy);
+        let alloy = (policy.current_time, policy.expected_nonce);
+        assert_eq!(encoded, bincode::serialize(&alloy).unwrap());
+    }
+}
@@ -0,0 +1,136 @@
+//! Journal versioning policy.
+//!
+//! Every `Output` starts with a `version` field so decoders can dispatch on it
+//! before reading anything else:
+//! - risc0 serde: `version` is the first word of the journal.
+//! - ABI: `version` is the first element of the encoded tuple.
+//!
+//! Fields are only ever appended. A change to the layout of a released guest bumps
+//! `OUTPUT_VERSION`, and decoders keep a branch for every released version so
+//! receipts already consumed by verifier contracts and indexers stay readable.
+//! Fields added between releases share one version. Each released layout has golden
+//! journals in `host/testdata/journals`, which `host/tests/journals.rs` decodes.
+//!
+//! Versions:
+//! - 1: every field of `Output` up to `message_mode`, in order. Earlier guests
+//!   committed a bare `(signer, digest)` without a version word, which is not decoded.
+//! - 2: `transfer` appended.
+
+use alloc::{vec, vec::Vec};
+use alloy_dyn_abi::DynSolValue;
+use alloy_primitives::{Address, B256, U256};
+
+use crate::{signing::KeySignature, DigestRange, ErrorCode, Failure, Output};
+
+/// Layout version committed by the current guests.
+pub const OUTPUT_VERSION: u16 = 2;
+
+impl Output {
+    /// Journal attesting that `digest` was signed by `signer`. Optional fields are
+    /// left empty; set them with struct update syntax.
+    pub fn ok(signer: Address, digest: B256) -> Self {
+        Self {
+            version: OUTPUT_VERSION,
+            signer,
+            digest,
+            ..Default::default()
+        }
+    }
+
+    /// Journal attesting that `range` could not be verified for `signer`.
+    pub fn err(signer: Address, code: ErrorCode, range: DigestRange) -> Self {
+        Self {
+            version: OUTPUT_VERSION,
+            signer,
+            failure: Some(Failure { code, range }),
+            ..Default::default()
+        }
+    }
+
+    /// Attribute the journal to a non-Ethereum key instead of an address, if any.
+    pub fn signed_with(self, key_signature: Option<&KeySignature>) -> Self {
+        match key_signature {
+            None => self,
+            Some(key_signature) => Self {
+                signer: Address::ZERO,
+                scheme: key_signature.scheme(),
+                public_key: key_signature.public_key(),
+                credential_id_hash: key_signature.credential_id_hash(),
+                ..self
+            },
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// ABI-encode as `(uint16 version, address signer, bytes32 digest, uint8 errorCode,
+    /// bytes fields, bytes32[] fieldCommitments, uint8 scheme, bytes publicKey,
+    /// bytes32 credentialIdHash, bool manifestChecked, bool manifestMatches,
+    /// uint32 firstMismatch, bytes32 structHash, bool intentChecked, uint256 nonce,
+    /// uint256 deadline, uint64 currentTime, bool lowSEnforced, uint64 signatureChainId,
+    /// bool anchored, uint64 anchorBlockNumber, bytes32 anchorBlockHash,
+    /// bytes32 policyHash, uint8 policyResult, bytes32 manifestHash, bytes32 blobHash,
+    /// uint8 messageMode, bool transferGiven, address transferTo, uint256 requestedAmount)`
+    /// for on-chain consumers. `errorCode` is zero on success;
+    /// `firstMismatch` is `type(uint32).max` unless a manifest entry differs; the intent
+    /// values are zero unless `intentChecked`; `signatureChainId` is zero unless `v`
+    /// embedded one; the anchor values are zero unless `anchored`; `policyResult` is 0
+    /// without a policy, 1 if the message passed it and 2 if not; `messageMode` is 0
+    /// unless modes were listed, and otherwise the matched mode's code plus one; the
+    /// transfer values are zero unless `transferGiven`.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let code = self.failure.as_ref().map_or(0, |failure| failure.code.code());
+        let first_mismatch = self
+            .manifest
+            .and_then(|manifest| manifest.first_mismatch)
+            .unwrap_or(u32::MAX);
+        let intent = self.intent.unwrap_or_default();
+        let anchor = self.anchor.unwrap_or_default();
+        let policy = self.policy.unwrap_or_default();
+        let message_mode = self.message_mode.map_or(0, |mode| mode.code() + 1);
+        let transfer = self.transfer.unwrap_or_default();
+        let policy_result: u8 = match self.policy {
+            None => 0,
+            Some(verdict) if verdict.passed => 1,
+            Some(_) => 2,
+        };
+        // `SolValue` tuples stop at 24 elements, so encode through `DynSolValue`
+        let word = |word: B256| DynSolValue::FixedBytes(word, 32);
+        let uint = |value: U256, bits: usize| DynSolValue::Uint(value, bits);
+        DynSolValue::Tuple(vec![
+            uint(U256::from(self.version), 16),
+            DynSolValue::Address(self.signer),
+            word(self.digest),
+            uint(U256::from(code), 8),
+            DynSolValue::Bytes(self.fields.to_vec()),
+            DynSolValue::Array(self.field_commitments.iter().copied().map(word).collect()),
+            uint(U256::from(self.scheme.code()), 8),
+            DynSolValue::Bytes(self.public_key.to_vec()),
+            word(self.credential_id_hash),
+            DynSolValue::Bool(self.manifest.is_some()),
+            DynSolValue::Bool(self.manifest.is_some_and(|manifest| manifest.all_match)),
+            uint(U256::from(first_mismatch), 32),
+            word(self.struct_hash),
+            DynSolValue::Bool(self.intent.is_some()),
+            uint(intent.nonce, 256),
+            uint(intent.deadline, 256),
+            uint(U256::from(intent.current_time), 64),
+            DynSolValue::Bool(self.low_s_enforced),
+            uint(U256::from(self.signature_chain_id.unwrap_or_default()), 64),
+            DynSolValue::Bool(self.anchor.is_some()),
+            uint(U256::from(anchor.number), 64),
+            word(anchor.hash),
+            word(policy.policy_hash),
+            uint(U256::from(policy_result), 8),
+            word(self.manifest_hash),
+            word(self.blob_hash),
+            uint(U256::from(message_mode), 8),
+            DynSolValue::Bool(self.transfer.is_some()),
+            DynSolValue::Address(transfer.to),
+            uint(transfer.requested_amount, 256),
+        ])
+        .abi_encode()
+    }
+}
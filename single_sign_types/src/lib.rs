@@ -1,25 +1,772 @@
+//! Types and verification logic shared by the host and the guests. Builds without
+//! the standard library unless the `std` feature is enabled, so the guests and other
+//! embedded verifiers only pay for `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod bls;
+pub mod canonical;
+pub mod chain;
+pub mod eip712;
+pub mod erc7739;
+pub mod hash;
+pub mod intent;
+pub mod journal;
+pub mod merkle;
+pub mod policy;
+pub mod precondition;
+pub mod serde_hex;
 pub mod signing;
+pub mod state_proof;
+pub mod transfer;
 pub mod typed_data;
+pub mod user_op;
+pub mod webauthn;
 
+use alloc::{string::String, vec::Vec};
+use alloy_dyn_abi::TypedData;
 use alloy_primitives::{Address, Bytes, Signature, B256};
+use alloy_sol_types::SolValue;
+use hash::keccak256;
 use serde::{Deserialize, Serialize};
+use signing::{Envelope, KeySignature, MessageMode, SignatureScheme};
+use core::fmt;
+use intent::{IntentCommitment, IntentPolicy};
+use policy::{PolicyVerdict, SigningPolicy};
+use transfer::TransferDetails;
+use user_op::UserOperation;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DigestRange {
     pub start: usize,
     pub end: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl fmt::Display for DigestRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl DigestRange {
+    /// Check that the range is non-empty, lies within `blob`, and covers a valid
+    /// UTF-8 string (which also rules out ranges that split a multi-byte character).
+    pub fn validate(&self, blob: &[u8]) -> Result<(), ErrorCode> {
+        self.slice_checked(blob).map(|_| ())
+    }
+
+    /// Check a whole manifest of ranges over a blob of `blob_len` bytes: sorted by
+    /// start, pairwise disjoint, each non-empty and in bounds, and, with
+    /// `full_coverage`, leaving no byte of the blob outside a range. Ordering is
+    /// checked first, so a bad manifest is reported as such even if its ranges are
+    /// also invalid on their own.
+    pub fn validate_set(
+        ranges: &[DigestRange],
+        blob_len: usize,
+        full_coverage: bool,
+    ) -> Result<(), ErrorCode> {
+        if ranges
+            .windows(2)
+            .any(|pair| pair[0].end > pair[1].start || pair[0].start > pair[1].start)
+        {
+            return Err(ErrorCode::OverlappingRanges);
+        }
+        for range in ranges {
+            if range.start >= range.end {
+                return Err(ErrorCode::EmptyRange);
+            }
+            if range.end > blob_len {
+                return Err(ErrorCode::RangeOutOfBounds);
+            }
+        }
+        if full_coverage {
+            let mut covered = 0;
+            for range in ranges {
+                if range.start != covered {
+                    return Err(ErrorCode::UncoveredBytes);
+                }
+                covered = range.end;
+            }
+            if covered != blob_len {
+                return Err(ErrorCode::UncoveredBytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Borrow the range of `blob` as `&str` without copying it. Unlike indexing,
+    /// never panics: corrupted ranges (reversed, out of bounds, or splitting a
+    /// multi-byte character) are reported as an `ErrorCode`.
+    pub fn slice_checked<'a>(&self, blob: &'a [u8]) -> Result<&'a str, ErrorCode> {
+        if self.start >= self.end {
+            return Err(ErrorCode::EmptyRange);
+        }
+        if self.end > blob.len() {
+            return Err(ErrorCode::RangeOutOfBounds);
+        }
+        core::str::from_utf8(&blob[self.start..self.end]).map_err(|_| ErrorCode::InvalidUtf8)
+    }
+
+    /// Parse the typed data this range covers in `blob`.
+    pub fn typed_data(
+        &self,
+        blob: &[u8],
+        require_canonical: bool,
+    ) -> Result<TypedData, ErrorCode> {
+        let typed_data_json =
+            self.json_checked(blob, require_canonical, ErrorCode::InvalidTypedData)?;
+        typed_data::parse_typed_data(typed_data_json).map_err(|_| ErrorCode::InvalidTypedData)
+    }
+
+    /// Parse the ERC-4337 UserOperation this range covers in `blob`.
+    pub fn user_operation(
+        &self,
+        blob: &[u8],
+        require_canonical: bool,
+    ) -> Result<UserOperation, ErrorCode> {
+        let user_op_json =
+            self.json_checked(blob, require_canonical, ErrorCode::InvalidUserOperation)?;
+        UserOperation::from_json(user_op_json).map_err(|_| ErrorCode::InvalidUserOperation)
+    }
+
+    // Borrow the range as JSON, reporting `invalid` if canonical form is required but
+    // the slice does not parse.
+    fn json_checked<'a>(
+        &self,
+        blob: &'a [u8],
+        require_canonical: bool,
+        invalid: ErrorCode,
+    ) -> Result<&'a str, ErrorCode> {
+        let json = self.slice_checked(blob)?;
+        if require_canonical && !canonical::is_canonical(json).map_err(|_| invalid)? {
+            return Err(ErrorCode::NotCanonical);
+        }
+        Ok(json)
+    }
+
+    /// Compute the EIP-712 digest of the typed data this range covers in `blob`.
+    pub fn digest(&self, blob: &[u8], require_canonical: bool) -> Result<B256, ErrorCode> {
+        self.typed_data(blob, require_canonical)?
+            .eip712_signing_hash()
+            .map_err(|_| ErrorCode::InvalidTypedData)
+    }
+
+    /// The EIP-712 digest and struct hash of the typed data this range covers in `blob`,
+    /// computed by the lighter `eip712` hasher rather than parsed into `TypedData`.
+    pub fn eip712_hashes(
+        &self,
+        blob: &[u8],
+        require_canonical: bool,
+    ) -> Result<(B256, B256), ErrorCode> {
+        let json = self.json_checked(blob, require_canonical, ErrorCode::InvalidTypedData)?;
+        eip712::hash_typed_data(json.as_bytes()).map_err(|_| ErrorCode::InvalidTypedData)
+    }
+}
+
+/// Why a range could not be attested. Committed in failure journals by variant index
+/// and ABI-encoded by discriminant, so variants must never be reordered, removed or
+/// reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    EmptyRange = 1,
+    RangeOutOfBounds = 2,
+    InvalidUtf8 = 3,
+    NotCanonical = 4,
+    InvalidTypedData = 5,
+    InvalidSignature = 6,
+    InvalidFieldPath = 7,
+    OverlappingSegments = 8,
+    InvalidUserOperation = 9,
+    UnauthorizedAction = 10,
+    IntentExpired = 11,
+    NonceMismatch = 12,
+    OverlappingRanges = 13,
+    UncoveredBytes = 14,
+}
+
+impl ErrorCode {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCode::EmptyRange => write!(f, "digest range must satisfy start < end"),
+            ErrorCode::RangeOutOfBounds => write!(f, "digest range exceeds typed data length"),
+            ErrorCode::InvalidUtf8 => {
+                write!(f, "digest range does not cover a valid UTF-8 string or splits a character")
+            }
+            ErrorCode::NotCanonical => write!(f, "typed data slice is not in canonical form"),
+            ErrorCode::InvalidTypedData => write!(f, "typed data slice is not valid EIP-712 JSON"),
+            ErrorCode::InvalidSignature => {
+                write!(f, "signature does not recover to the expected signer")
+            }
+            ErrorCode::InvalidFieldPath => {
+                write!(f, "extract path does not name a message field")
+            }
+            ErrorCode::OverlappingSegments => {
+                write!(f, "signed segments are empty or their spans overlap")
+            }
+            ErrorCode::InvalidUserOperation => {
+                write!(f, "slice is not a valid ERC-4337 UserOperation")
+            }
+            ErrorCode::UnauthorizedAction => {
+                write!(f, "digest is not among the signed envelope's actions")
+            }
+            ErrorCode::IntentExpired => write!(f, "intent envelope's deadline has passed"),
+            ErrorCode::NonceMismatch => {
+                write!(f, "intent envelope's nonce is not the expected nonce")
+            }
+            ErrorCode::OverlappingRanges => write!(f, "digest ranges are out of order or overlap"),
+            ErrorCode::UncoveredBytes => {
+                write!(f, "digest ranges leave bytes of the blob uncovered")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ErrorCode {}
+
+/// What each range of the blob holds, and so which digest the guest commits for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DigestMode {
+    /// EIP-712 typed data; the digest is its signing hash.
+    #[default]
+    Eip712,
+    /// ERC-4337 UserOperations for the EntryPoint at `entry_point` on `chain_id`; the
+    /// digest is the `userOpHash`, and no struct hash or fields are committed.
+    UserOperation {
+        #[serde(with = "serde_hex::address")]
+        entry_point: Address,
+        chain_id: u64,
+    },
+}
+
+impl DigestMode {
+    /// Compute the digest the guest commits for `range` of `blob`.
+    pub fn digest(
+        &self,
+        range: &DigestRange,
+        blob: &[u8],
+        require_canonical: bool,
+    ) -> Result<B256, ErrorCode> {
+        match *self {
+            DigestMode::Eip712 => range.digest(blob, require_canonical),
+            DigestMode::UserOperation {
+                entry_point,
+                chain_id,
+            } => range
+                .user_operation(blob, require_canonical)?
+                .hash(entry_point, chain_id)
+                .map_err(|_| ErrorCode::InvalidUserOperation),
+        }
+    }
+}
+
+/// What the guest should do when a range cannot be attested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Assertion {
+    /// Panic, so no receipt exists unless every range is valid.
+    MustBeValid,
+    /// Commit a failure journal, producing a receipt that proves the failure
+    /// (e.g. that a signature does not recover to the claimed signer).
+    ReportValidity,
+}
+
+/// `Debug` redacts the signatures and the disclosure salt and only reports the
+/// blob's length, so inputs can be logged safely.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Input {
-    pub signer: Address,
+    /// Expected signer. When `None` the guest commits whichever address the signature
+    /// recovers to instead of rejecting a mismatch, attesting who signed the range;
+    /// failure journals then carry the zero address. Ignored with `key_signature`.
+    #[serde(with = "serde_hex::address_option")]
+    pub signer: Option<Address>,
+    #[serde(with = "serde_hex::signature")]
     pub signature: Signature,
+    #[serde(with = "serde_hex::bytes")]
     pub typed_data_concat: Bytes,
     pub digest_range: DigestRange,
+    /// When set, the guest rejects slices that are not already in the canonical
+    /// form produced by `canonical::compact_typed_data`.
+    pub require_canonical: bool,
+    pub assert: Assertion,
+    /// Message fields to ABI-encode into `Output.fields`, see `typed_data::extract_fields`.
+    pub extract_paths: Vec<String>,
+    /// Privacy mode: when set, commit salted hashes of the extracted fields in
+    /// `Output.field_commitments` instead of their values.
+    #[serde(with = "serde_hex::b256_option")]
+    pub disclosure_salt: Option<B256>,
+    /// How `signature` was produced over `typed_data_concat`.
+    pub envelope: Envelope,
+    /// Set for non-Ethereum signers; replaces `signer`, `signature` and `envelope`.
+    pub key_signature: Option<KeySignature>,
+    /// Whether the range holds typed data or a UserOperation.
+    pub digest_mode: DigestMode,
+    /// Deadline and nonce checks for `Envelope::Intent`.
+    pub intent_policy: IntentPolicy,
+    /// Accept a high-s `signature` by normalizing it, for contexts where two
+    /// signatures over the same message are harmless. Off by default: the guest
+    /// rejects malleable signatures and commits that it did in `Output.low_s_enforced`.
+    pub allow_high_s: bool,
+    /// Chain ID the signature's EIP-155 `v` value carried, as returned by
+    /// `signing::parse_signature_with_chain_id`. Committed as-is in
+    /// `Output.signature_chain_id`: `v` is not itself signed, so it binds how the
+    /// signature was presented rather than the signed bytes.
+    pub signature_chain_id: Option<u64>,
+    /// Recent block the host fetched, echoed into `Output.anchor` unchecked so
+    /// verifiers can require proofs made against a recent block.
+    pub anchor: Option<BlockAnchor>,
+    /// Policy to evaluate the message against; its verdict lands in `Output.policy`.
+    pub policy: Option<SigningPolicy>,
+    /// Ways the wallet may have signed, tried in order in place of `Envelope::Personal`
+    /// for wallets that differ in whether they `personal_sign` the blob or
+    /// `eth_signTypedData` the range, see `signing::match_message_mode`. The first that
+    /// recovers to `signer`, which must be set, lands in `Output.message_mode`. Empty
+    /// to verify through `envelope` alone.
+    pub message_modes: Vec<MessageMode>,
+    /// Recipient and amount of the Permit2 transfer the message permits, echoed into
+    /// `Output.transfer`; see `transfer` for who checks them.
+    pub transfer: Option<TransferDetails>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Input {
+    /// Validate the digest range against `typed_data_concat`. Shared by the host,
+    /// which checks before proving, and the guest, which commits a failure journal.
+    pub fn validate(&self) -> Result<(), ErrorCode> {
+        self.digest_range.validate(&self.typed_data_concat)
+    }
+
+    /// This input without its blob. The host serializes the header alone and sends
+    /// the blob after it as a raw frame, which the guest reads with `env::read_frame`
+    /// instead of deserializing it word by word.
+    pub fn header(&self) -> Self {
+        Self {
+            typed_data_concat: Bytes::new(),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Input")
+            .field("signer", &self.signer)
+            .field("signature", &Redacted)
+            .field("typed_data_concat", &BlobLen(self.typed_data_concat.len()))
+            .field("digest_range", &self.digest_range)
+            .field("require_canonical", &self.require_canonical)
+            .field("assert", &self.assert)
+            .field("extract_paths", &self.extract_paths)
+            .field("disclosure_salt", &self.disclosure_salt.map(|_| Redacted))
+            .field("envelope", &self.envelope)
+            .field("key_signature", &self.key_signature.as_ref().map(KeySignature::scheme))
+            .field("digest_mode", &self.digest_mode)
+            .field("intent_policy", &self.intent_policy)
+            .field("allow_high_s", &self.allow_high_s)
+            .field("signature_chain_id", &self.signature_chain_id)
+            .field("anchor", &self.anchor)
+            .field("policy", &self.policy)
+            .field("message_modes", &self.message_modes)
+            .field("transfer", &self.transfer)
+            .finish()
+    }
+}
+
+/// Several digest ranges over one signed `typed_data_concat`, proven in a single
+/// session so the signature is recovered once rather than once per range. `Debug`
+/// redacts like `Input`'s.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BatchInput {
+    #[serde(with = "serde_hex::address")]
+    pub signer: Address,
+    #[serde(with = "serde_hex::signature")]
+    pub signature: Signature,
+    #[serde(with = "serde_hex::bytes")]
+    pub typed_data_concat: Bytes,
+    pub ranges: Vec<DigestRange>,
+    pub require_canonical: bool,
+    pub assert: Assertion,
+    /// Extracted from every range.
+    pub extract_paths: Vec<String>,
+    #[serde(with = "serde_hex::b256_option")]
+    pub disclosure_salt: Option<B256>,
+    pub envelope: Envelope,
+    pub key_signature: Option<KeySignature>,
+    /// Co-signed batch: when non-empty, each segment's signer signed its own ranges,
+    /// and the segments replace `signer`, `signature`, `ranges`, `envelope` and
+    /// `key_signature`.
+    pub segments: Vec<SignedSegment>,
+    /// Digests the host claims the ranges have, in journal order; when non-empty the
+    /// guest commits whether they match, see `ManifestCheck`.
+    #[serde(with = "serde_hex::b256_vec")]
+    pub expected_digests: Vec<B256>,
+    /// Applies to every range.
+    pub digest_mode: DigestMode,
+    pub intent_policy: IntentPolicy,
+    /// Applies to `signature` only; co-signers' segment signatures must be low-s.
+    pub allow_high_s: bool,
+    /// Applies to `signature` only; co-signers' segment signatures carry no chain ID.
+    pub signature_chain_id: Option<u64>,
+    pub anchor: Option<BlockAnchor>,
+    /// Evaluated over every range.
+    pub policy: Option<SigningPolicy>,
+    /// One per range, in journal order, echoed into each attested range's
+    /// `Output.transfer`; empty when the ranges have none.
+    pub transfers: Vec<TransferDetails>,
+}
+
+impl fmt::Debug for BatchInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchInput")
+            .field("signer", &self.signer)
+            .field("signature", &Redacted)
+            .field("typed_data_concat", &BlobLen(self.typed_data_concat.len()))
+            .field("ranges", &self.ranges)
+            .field("require_canonical", &self.require_canonical)
+            .field("assert", &self.assert)
+            .field("extract_paths", &self.extract_paths)
+            .field("disclosure_salt", &self.disclosure_salt.map(|_| Redacted))
+            .field("envelope", &self.envelope)
+            .field("key_signature", &self.key_signature.as_ref().map(KeySignature::scheme))
+            .field("segments", &self.segments)
+            .field("expected_digests", &self.expected_digests)
+            .field("digest_mode", &self.digest_mode)
+            .field("intent_policy", &self.intent_policy)
+            .field("allow_high_s", &self.allow_high_s)
+            .field("signature_chain_id", &self.signature_chain_id)
+            .field("anchor", &self.anchor)
+            .field("policy", &self.policy)
+            .field("transfers", &self.transfers)
+            .finish()
+    }
+}
+
+impl BatchInput {
+    /// This batch without its blob, sent as a frame after it; see `Input::header`.
+    pub fn header(&self) -> Self {
+        Self {
+            typed_data_concat: Bytes::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Validate every range against `typed_data_concat`, failing on the first bad one.
+    /// The ranges, and each segment's, must also form a valid set, as the batch guest
+    /// requires; see `DigestRange::validate_set`.
+    pub fn validate(&self) -> Result<(), ErrorCode> {
+        self.check_segments()?;
+        let blob_len = self.typed_data_concat.len();
+        DigestRange::validate_set(&self.ranges, blob_len, false)?;
+        for segment in &self.segments {
+            DigestRange::validate_set(&segment.ranges, blob_len, false)?;
+        }
+        self.ranges
+            .iter()
+            .chain(self.segments.iter().flat_map(|segment| &segment.ranges))
+            .try_for_each(|range| range.validate(&self.typed_data_concat))
+    }
+
+    /// Check that no segment is empty and that segment spans are pairwise disjoint,
+    /// so every range is attributed to exactly one co-signer.
+    pub fn check_segments(&self) -> Result<(), ErrorCode> {
+        let mut spans = self
+            .segments
+            .iter()
+            .map(SignedSegment::span)
+            .collect::<Result<Vec<_>, _>>()?;
+        spans.sort_by_key(|span| span.start);
+        if spans.windows(2).any(|pair| pair[0].end > pair[1].start) {
+            return Err(ErrorCode::OverlappingSegments);
+        }
+        Ok(())
+    }
+}
+
+/// One co-signer's share of a batch: `signer` signed, in EIP-191 personal mode, the
+/// bytes from the start of its first range to the end of its last. `Debug` redacts
+/// the signature.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SignedSegment {
+    #[serde(with = "serde_hex::address")]
+    pub signer: Address,
+    #[serde(with = "serde_hex::signature")]
+    pub signature: Signature,
+    pub ranges: Vec<DigestRange>,
+}
+
+impl fmt::Debug for SignedSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignedSegment")
+            .field("signer", &self.signer)
+            .field("signature", &Redacted)
+            .field("ranges", &self.ranges)
+            .finish()
+    }
+}
+
+impl SignedSegment {
+    /// The byte span covering all of the segment's ranges, which is what was signed.
+    pub fn span(&self) -> Result<DigestRange, ErrorCode> {
+        let start = self.ranges.iter().map(|range| range.start).min();
+        let end = self.ranges.iter().map(|range| range.end).max();
+        match (start, end) {
+            (Some(start), Some(end)) if start < end => Ok(DigestRange { start, end }),
+            _ => Err(ErrorCode::OverlappingSegments),
+        }
+    }
+
+    /// Verify the segment's signature over its span of `blob`.
+    pub fn verify(&self, blob: &[u8]) -> Result<(), ErrorCode> {
+        let span = self.span()?;
+        let signed = blob
+            .get(span.start..span.end)
+            .ok_or(ErrorCode::RangeOutOfBounds)?;
+        Envelope::Personal
+            .verify(signed, self.signature, self.signer)
+            .map_err(|_| ErrorCode::InvalidSignature)?;
+        Ok(())
+    }
+}
+
+/// Public journal committed by the guests. See `journal` for the versioning policy.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Output {
+    pub version: u16,
+    #[serde(with = "serde_hex::address")]
     pub signer: Address,
+    /// EIP-712 digest of the range, or its `userOpHash` in `DigestMode::UserOperation`;
+    /// zero when `failure` is set.
+    #[serde(with = "serde_hex::b256")]
     pub digest: B256,
+    /// Set when the range could not be attested; the receipt then proves the failure.
+    pub failure: Option<Failure>,
+    /// ABI-encoded message fields requested by `extract_paths`; empty on failure
+    /// and in privacy mode.
+    #[serde(with = "serde_hex::bytes")]
+    pub fields: Bytes,
+    /// Salted field hashes, see `typed_data::field_commitments`; privacy mode only.
+    #[serde(with = "serde_hex::b256_vec")]
+    pub field_commitments: Vec<B256>,
+    pub scheme: SignatureScheme,
+    /// Signer's public key for schemes other than secp256k1, whose `signer` is zero.
+    #[serde(with = "serde_hex::bytes")]
+    pub public_key: Bytes,
+    /// keccak256 of the WebAuthn credential ID; zero for other schemes.
+    #[serde(with = "serde_hex::b256")]
+    pub credential_id_hash: B256,
+    /// Outcome of checking the batch against `BatchInput.expected_digests`; repeated
+    /// in every entry of the batch journal, and `None` when no manifest was given.
+    pub manifest: Option<ManifestCheck>,
+    /// EIP-712 `hashStruct(message)` of the range, e.g. a Seaport order hash; zero
+    /// on failure.
+    #[serde(with = "serde_hex::b256")]
+    pub struct_hash: B256,
+    /// Nonce, deadline and time checked for ranges an intent envelope authorized.
+    pub intent: Option<IntentCommitment>,
+    /// Whether the secp256k1 signature was required to be low-s, so no second
+    /// signature over the same message could have produced this journal. False for
+    /// other schemes and when `allow_high_s` was set.
+    pub low_s_enforced: bool,
+    /// Chain ID embedded in the secp256k1 signature's EIP-155 `v` value, for
+    /// verifiers that require signatures presented for their network.
+    pub signature_chain_id: Option<u64>,
+    /// Block the host anchored the proof to. The guest cannot check it, so it only
+    /// means something to a verifier that compares `hash` with `blockhash(number)`.
+    pub anchor: Option<BlockAnchor>,
+    /// Whether the message complied with `Input.policy`, and that policy's hash;
+    /// `None` on failure and when no policy was given.
+    pub policy: Option<PolicyVerdict>,
+    /// `manifest_hash` of the ranges proven together, in journal order, and of the
+    /// expected digests; repeated in every entry of a batch journal.
+    #[serde(with = "serde_hex::b256")]
+    pub manifest_hash: B256,
+    /// keccak256 of the whole blob the range was proven in, so a blob fetched by its
+    /// hash can be matched to the journal; repeated in every entry of a batch journal.
+    #[serde(with = "serde_hex::b256")]
+    pub blob_hash: B256,
+    /// Which of `Input.message_modes` the signature matched; `None` when none were
+    /// listed, on failure and in the batch guest.
+    pub message_mode: Option<MessageMode>,
+    /// Recipient and amount the host chose for the Permit2 transfer the message
+    /// permits, not checked by the guest; `None` on failure and when none was given.
+    pub transfer: Option<TransferDetails>,
+}
+
+/// A block by number and hash, as `eth_getBlockByNumber` reports them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockAnchor {
+    pub number: u64,
+    #[serde(with = "serde_hex::b256")]
+    pub hash: B256,
+}
+
+/// Attests whether a host-supplied manifest of digests matches the digests the guest
+/// computed, e.g. the list a wallet showed its user before they signed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestCheck {
+    pub all_match: bool,
+    /// Index of the first entry that differs, including a missing or extra entry
+    pub first_mismatch: Option<u32>,
+}
+
+impl ManifestCheck {
+    pub fn compare(expected: &[B256], computed: &[B256]) -> Self {
+        let first_mismatch = expected
+            .iter()
+            .zip(computed)
+            .position(|(expected, computed)| expected != computed)
+            .or((expected.len() != computed.len()).then(|| expected.len().min(computed.len())))
+            .map(|index| index as u32);
+        Self {
+            all_match: first_mismatch.is_none(),
+            first_mismatch,
+        }
+    }
+}
+
+/// keccak256 of `abi.encode((uint64 start, uint64 end)[] ranges,
+/// bytes32[] expectedDigests)`, pinning exactly which manifest a proof was made for.
+pub fn manifest_hash<'a>(
+    ranges: impl IntoIterator<Item = &'a DigestRange>,
+    expected_digests: &[B256],
+) -> B256 {
+    let ranges: Vec<(u64, u64)> = ranges
+        .into_iter()
+        .map(|range| (range.start as u64, range.end as u64))
+        .collect();
+    keccak256((ranges, expected_digests.to_vec()).abi_encode())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Failure {
+    pub code: ErrorCode,
+    pub range: DigestRange,
+}
+
+/// Stands in for secrets in `Debug` output.
+struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Stands in for a potentially large blob in `Debug` output.
+struct BlobLen(usize);
+
+impl fmt::Debug for BlobLen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} bytes>", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical::compact_typed_data;
+    use alloc::string::ToString;
+    use proptest::prelude::*;
+
+    fn digests(bytes: &[u8]) -> Vec<B256> {
+        bytes.iter().copied().map(B256::repeat_byte).collect()
+    }
+
+    fn mismatch_at(expected: &[u8], computed: &[u8]) -> Option<u32> {
+        let check = ManifestCheck::compare(&digests(expected), &digests(computed));
+        assert_eq!(check.all_match, check.first_mismatch.is_none());
+        check.first_mismatch
+    }
+
+    #[test]
+    fn manifest_check_matches_equal_lists() {
+        assert_eq!(mismatch_at(&[1, 2, 3], &[1, 2, 3]), None);
+        assert_eq!(mismatch_at(&[], &[]), None);
+    }
+
+    #[test]
+    fn manifest_check_reports_the_first_differing_entry() {
+        assert_eq!(mismatch_at(&[1, 2, 3], &[1, 9, 8]), Some(1));
+        assert_eq!(mismatch_at(&[1, 2], &[9]), Some(0));
+    }
+
+    #[test]
+    fn manifest_check_counts_missing_and_extra_entries() {
+        assert_eq!(mismatch_at(&[1, 2, 3], &[1, 2]), Some(2));
+        assert_eq!(mismatch_at(&[1, 2], &[1, 2, 3]), Some(2));
+        assert_eq!(mismatch_at(&[], &[1]), Some(0));
+    }
+
+    // A canonical `Mail` typed data whose message holds `contents`
+    fn mail(contents: &str) -> String {
+        let json = serde_json::json!({
+            "types": {
+                "EIP712Domain": [{ "name": "name", "type": "string" }],
+                "Mail": [{ "name": "contents", "type": "string" }],
+            },
+            "primaryType": "Mail",
+            "domain": { "name": "Ünïcödé 🚀" },
+            "message": { "contents": contents },
+        });
+        compact_typed_data(&json.to_string()).unwrap()
+    }
+
+    #[test]
+    fn ranges_splitting_an_emoji_are_invalid_utf8() {
+        let blob = mail("gm 🦊");
+        let fox = blob.find('🦊').unwrap();
+        for end in fox + 1..fox + '🦊'.len_utf8() {
+            let range = DigestRange { start: 0, end };
+            assert_eq!(range.slice_checked(blob.as_bytes()), Err(ErrorCode::InvalidUtf8));
+            assert_eq!(range.digest(blob.as_bytes(), false), Err(ErrorCode::InvalidUtf8));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn slice_checked_agrees_with_str_slicing(
+            text in "\\PC{0,24}",
+            start in 0usize..120,
+            end in 0usize..120,
+        ) {
+            let blob = text.as_bytes();
+            let range = DigestRange { start, end };
+            let expected = if start >= end {
+                Err(ErrorCode::EmptyRange)
+            } else if end > blob.len() {
+                Err(ErrorCode::RangeOutOfBounds)
+            } else {
+                text.get(start..end).ok_or(ErrorCode::InvalidUtf8)
+            };
+            prop_assert_eq!(range.slice_checked(blob), expected);
+        }
+
+        #[test]
+        fn non_ascii_messages_hash_like_alloy(contents in "\\PC{0,32}") {
+            let blob = mail(&contents);
+            let range = DigestRange { start: 0, end: blob.len() };
+            let typed_data = typed_data::parse_typed_data(&blob).unwrap();
+            let digest = range.digest(blob.as_bytes(), true).unwrap();
+            prop_assert_eq!(digest, typed_data.eip712_signing_hash().unwrap());
+            let (fast_digest, _) = range.eip712_hashes(blob.as_bytes(), true).unwrap();
+            prop_assert_eq!(fast_digest, digest);
+        }
+
+        #[test]
+        fn ranges_over_non_ascii_blobs_never_panic(
+            contents in "\\PC{0,16}",
+            start in 0usize..256,
+            end in 0usize..256,
+        ) {
+            let blob = mail(&contents);
+            let range = DigestRange { start, end };
+            let _ = range.digest(blob.as_bytes(), false);
+            let _ = range.eip712_hashes(blob.as_bytes(), false);
+            let _ = DigestRange::validate_set(&[range], blob.len(), true);
+        }
+    }
 }
@@ -0,0 +1,139 @@
+//! Merkle envelopes: the wallet signs only the root of the objects' digests.
+//!
+//! Each object is then proven on its own, with a branch from its EIP-712 digest to
+//! the signed root, so the signature is 32 bytes however large the batch and the
+//! per-object proofs stay small and can run in parallel. Pairs are hashed sorted
+//! (`keccak256(min || max)`), as OpenZeppelin's `MerkleProof` does, and an odd node
+//! is promoted to the next layer unchanged.
+
+use alloc::vec::Vec;
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+
+use crate::{hash::keccak256, serde_hex};
+
+/// The signed root and the branch proving one object's digest belongs to it, as
+/// carried by `signing::Envelope::Merkle`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MerkleBranch {
+    #[serde(with = "serde_hex::b256")]
+    pub root: B256,
+    /// Sibling hashes from the leaf up.
+    #[serde(with = "serde_hex::b256_vec")]
+    pub proof: Vec<B256>,
+}
+
+impl MerkleBranch {
+    /// Whether `leaf` hashes up to `root` along `proof`.
+    pub fn contains(&self, leaf: B256) -> bool {
+        self.proof.iter().fold(leaf, |node, sibling| hash_pair(node, *sibling)) == self.root
+    }
+}
+
+/// Root of the tree over `leaves`; zero when there are none.
+pub fn root(leaves: &[B256]) -> B256 {
+    let mut layer = leaves.to_vec();
+    while layer.len() > 1 {
+        layer = next_layer(&layer);
+    }
+    layer.first().copied().unwrap_or_default()
+}
+
+/// Branch for `leaves[index]`; `None` when `index` is out of bounds.
+pub fn branch(leaves: &[B256], index: usize) -> Option<MerkleBranch> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut proof = Vec::new();
+    let mut layer = leaves.to_vec();
+    let mut index = index;
+    while layer.len() > 1 {
+        if let Some(sibling) = layer.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        layer = next_layer(&layer);
+        index /= 2;
+    }
+    Some(MerkleBranch {
+        root: layer[0],
+        proof,
+    })
+}
+
+fn next_layer(layer: &[B256]) -> Vec<B256> {
+    layer
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(*left, *right),
+            [node] => *node,
+            _ => unreachable!("chunks(2) yields one or two nodes"),
+        })
+        .collect()
+}
+
+fn hash_pair(a: B256, b: B256) -> B256 {
+    if a < b {
+        keccak256([a.as_slice(), b.as_slice()].concat())
+    } else {
+        keccak256([b.as_slice(), a.as_slice()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u8) -> Vec<B256> {
+        (1..=count).map(B256::repeat_byte).collect()
+    }
+
+    #[test]
+    fn every_branch_reaches_the_root() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let root = root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let branch = branch(&leaves, index).unwrap();
+                assert_eq!(branch.root, root, "{count} leaves, #{index}");
+                assert!(branch.contains(*leaf), "{count} leaves, #{index}");
+            }
+        }
+    }
+
+    #[test]
+    fn pairs_hash_sorted_and_odd_nodes_are_promoted() {
+        let [a, b, c] = [B256::repeat_byte(2), B256::repeat_byte(1), B256::repeat_byte(3)];
+        let ab = keccak256([b.as_slice(), a.as_slice()].concat());
+        assert_eq!(root(&[a, b]), ab);
+        assert_eq!(root(&[b, a]), ab);
+        let abc = if ab < c {
+            keccak256([ab.as_slice(), c.as_slice()].concat())
+        } else {
+            keccak256([c.as_slice(), ab.as_slice()].concat())
+        };
+        assert_eq!(root(&[a, b, c]), abc);
+        // The promoted node has no sibling at its first layer
+        assert_eq!(branch(&[a, b, c], 2).unwrap().proof, [ab]);
+    }
+
+    #[test]
+    fn single_leaf_is_its_own_root_and_empty_tree_is_zero() {
+        let leaf = B256::repeat_byte(7);
+        assert_eq!(root(&[leaf]), leaf);
+        assert!(branch(&[leaf], 0).unwrap().proof.is_empty());
+        assert_eq!(root(&[]), B256::ZERO);
+        assert!(branch(&[], 0).is_none());
+    }
+
+    #[test]
+    fn branches_reject_other_leaves_and_tampered_proofs() {
+        let leaves = leaves(5);
+        let branch = branch(&leaves, 1).unwrap();
+        assert!(!branch.contains(leaves[0]));
+        assert!(!branch.contains(B256::repeat_byte(0xff)));
+        let mut tampered = branch.clone();
+        tampered.proof[0] = B256::repeat_byte(0xff);
+        assert!(!tampered.contains(leaves[1]));
+        assert!(super::branch(&leaves, leaves.len()).is_none());
+    }
+}
@@ -0,0 +1,184 @@
+//! Signing policies the guests evaluate over each message, so a proof attests not
+//! only who signed a message but that it complied with a committed policy.
+//!
+//! Policies are declarative and load from JSON, e.g.
+//! `{"allowedPrimaryTypes": ["PermitTransferFrom"], "allowedSpenders": ["0x…"],
+//! "maxAmounts": [{"token": "0x…", "amount": "1000000"}]}`. The guests commit the
+//! policy's hash and whether the message passed, see `PolicyVerdict`; a violation is
+//! attested rather than refused.
+//!
+//! Rules match message fields by name, at any depth:
+//! - a `spender` field must hold an allowed spender;
+//! - an `amount` or `value` field is an amount of the `token` field beside it, or of
+//!   the nearest enclosing one, or else of the domain's verifying contract, as in
+//!   ERC-2612 permits. Amounts of tokens without a limit are not capped.
+
+use alloc::{string::String, vec::Vec};
+use alloy_dyn_abi::TypedData;
+use alloy_primitives::{Address, B256, U256};
+use alloy_sol_types::SolValue;
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{hash::keccak256, serde_hex};
+
+/// What a signer's messages may contain. Empty lists do not restrict anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SigningPolicy {
+    pub allowed_primary_types: Vec<String>,
+    #[serde(with = "serde_hex::address_vec")]
+    pub allowed_spenders: Vec<Address>,
+    pub max_amounts: Vec<TokenLimit>,
+}
+
+/// Most of `token` a single message may move or approve.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenLimit {
+    #[serde(with = "serde_hex::address")]
+    pub token: Address,
+    #[serde(with = "serde_hex::u256")]
+    pub amount: U256,
+}
+
+/// Committed in `Output.policy` when the host supplied a policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyVerdict {
+    /// `SigningPolicy::hash` of the policy evaluated
+    #[serde(with = "serde_hex::b256")]
+    pub policy_hash: B256,
+    pub passed: bool,
+}
+
+impl SigningPolicy {
+    /// keccak256 of `abi.encode(string[] allowedPrimaryTypes, address[] allowedSpenders,
+    /// (address token, uint256 amount)[] maxAmounts)`, for verifiers to compare with
+    /// the policy they require.
+    pub fn hash(&self) -> B256 {
+        let max_amounts: Vec<(Address, U256)> = self
+            .max_amounts
+            .iter()
+            .map(|limit| (limit.token, limit.amount))
+            .collect();
+        let encoded = (
+            self.allowed_primary_types.clone(),
+            self.allowed_spenders.clone(),
+            max_amounts,
+        )
+            .abi_encode();
+        keccak256(encoded)
+    }
+
+    /// Evaluate the policy over a range's typed data. UserOperations, which carry
+    /// none, never pass.
+    pub fn evaluate(&self, typed_data: Option<&TypedData>) -> PolicyVerdict {
+        PolicyVerdict {
+            policy_hash: self.hash(),
+            passed: typed_data.is_some_and(|typed_data| self.allows(typed_data)),
+        }
+    }
+
+    /// Whether `typed_data` complies with every rule.
+    pub fn allows(&self, typed_data: &TypedData) -> bool {
+        if !self.allowed_primary_types.is_empty()
+            && !self.allowed_primary_types.contains(&typed_data.primary_type)
+        {
+            return false;
+        }
+        self.allows_value(&typed_data.message, typed_data.domain.verifying_contract)
+    }
+
+    fn allows_value(&self, value: &Value, token: Option<Address>) -> bool {
+        let fields = match value {
+            Value::Object(fields) => fields,
+            Value::Array(items) => return items.iter().all(|item| self.allows_value(item, token)),
+            _ => return true,
+        };
+        let token = match fields.get("token") {
+            Some(token) => parse_address(token),
+            None => token,
+        };
+        for (name, field) in fields {
+            let allowed = match name.as_str() {
+                "spender" => self.allows_spender(field),
+                "amount" | "value" => self.allows_amount(token, field),
+                _ => self.allows_value(field, token),
+            };
+            if !allowed {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn allows_spender(&self, spender: &Value) -> bool {
+        self.allowed_spenders.is_empty()
+            || parse_address(spender)
+                .is_some_and(|spender| self.allowed_spenders.contains(&spender))
+    }
+
+    fn allows_amount(&self, token: Option<Address>, amount: &Value) -> bool {
+        let Some(limit) = self
+            .max_amounts
+            .iter()
+            .find(|limit| Some(limit.token) == token)
+        else {
+            return true;
+        };
+        parse_uint(amount).is_some_and(|amount| amount <= limit.amount)
+    }
+}
+
+fn parse_address(value: &Value) -> Option<Address> {
+    value.as_str().and_then(|s| Address::from_str(s).ok())
+}
+
+// Typed data carries integers as JSON numbers or as decimal or hex strings
+pub(crate) fn parse_uint(value: &Value) -> Option<U256> {
+    match value {
+        Value::Number(number) => number.as_u64().map(U256::from),
+        Value::String(s) => U256::from_str(s).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+
+    fn policy() -> SigningPolicy {
+        SigningPolicy {
+            allowed_primary_types: vec!["PermitTransferFrom".to_string()],
+            allowed_spenders: vec![Address::repeat_byte(0x11), Address::repeat_byte(0x22)],
+            max_amounts: vec![TokenLimit {
+                token: Address::repeat_byte(0xa0),
+                amount: U256::from(1_000_000),
+            }],
+        }
+    }
+
+    #[test]
+    fn round_trips_binary_in_alloys_layout() {
+        let policy = policy();
+        let encoded = bincode::serialize(&policy).unwrap();
+        assert_eq!(bincode::deserialize::<SigningPolicy>(&encoded).unwrap(), policy);
+        let limits: Vec<(Address, U256)> =
+            policy.max_amounts.iter().map(|limit| (limit.token, limit.amount)).collect();
+        let alloy = (&policy.allowed_primary_types, &policy.allowed_spenders, limits);
+        assert_eq!(encoded, bincode::serialize(&alloy).unwrap());
+    }
+
+    #[test]
+    fn reads_decimal_amounts() {
+        let json = r#"{"allowedPrimaryTypes": ["PermitTransferFrom"],
+            "allowedSpenders": ["0x1111111111111111111111111111111111111111",
+                "0x2222222222222222222222222222222222222222"],
+            "maxAmounts": [{"token": "0xa0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0",
+                "amount": "1000000"}]}"#;
+        assert_eq!(serde_json::from_str::<SigningPolicy>(json).unwrap(), policy());
+        let json = serde_json::to_string(&policy()).unwrap();
+        assert_eq!(serde_json::from_str::<SigningPolicy>(&json).unwrap(), policy());
+    }
+}
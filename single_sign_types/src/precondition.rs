@@ -0,0 +1,106 @@
+//! On-chain preconditions proven alongside a signature, e.g. that the signer holds
+//! enough tokens or that a Permit2 nonce is still unused.
+//!
+//! The `single_sign_steel` guest verifies a single-range receipt by composition and
+//! evaluates each precondition as an EVM view call with RISC Zero Steel, against a
+//! block it commits to. The host and the guest make the same calls: these types
+//! describe them, and `holds` judges the value they return.
+
+use alloc::vec::Vec;
+use alloy_primitives::{Address, Bytes, B256, U256};
+use alloy_sol_types::{sol, SolValue};
+use serde::{Deserialize, Serialize};
+
+sol! {
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+    }
+
+    interface IPermit2 {
+        function nonceBitmap(address owner, uint256 wordPos) external view returns (uint256);
+    }
+}
+
+/// A view call and the condition its result must meet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Precondition {
+    /// `token.balanceOf(owner) >= amount`
+    MinBalance {
+        token: Address,
+        owner: Address,
+        amount: U256,
+    },
+    /// Bit `nonce & 0xff` of `permit2.nonceBitmap(owner, nonce >> 8)` is clear, so a
+    /// `SignatureTransfer` permit with this nonce can still be used.
+    Permit2NonceUnused {
+        permit2: Address,
+        owner: Address,
+        nonce: U256,
+    },
+}
+
+impl Precondition {
+    /// Whether the precondition holds, given what its view call returned.
+    pub fn holds(&self, returned: U256) -> bool {
+        match self {
+            Precondition::MinBalance { amount, .. } => returned >= *amount,
+            Precondition::Permit2NonceUnused { nonce, .. } => {
+                !returned.bit(usize::from(nonce.byte(0)))
+            }
+        }
+    }
+
+    /// ABI-encode as `(uint8 kind, address target, address owner, uint256 value)`,
+    /// with kind 1 for `MinBalance` and 2 for `Permit2NonceUnused`.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        match self {
+            Precondition::MinBalance {
+                token,
+                owner,
+                amount,
+            } => (1u8, *token, *owner, *amount).abi_encode(),
+            Precondition::Permit2NonceUnused {
+                permit2,
+                owner,
+                nonce,
+            } => (2u8, *permit2, *owner, *nonce).abi_encode(),
+        }
+    }
+}
+
+/// Permit2 bitmap word holding `nonce`.
+pub fn permit2_word_pos(nonce: U256) -> U256 {
+    nonce >> 8
+}
+
+/// Journal of the `single_sign_steel` guest. Verifiers check `image_id` against the
+/// single-range guest they trust, decode `journal` like that guest's own journal, and
+/// validate `commitment` (Steel's ABI-encoded block commitment) on-chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreconditionOutput {
+    pub image_id: B256,
+    pub journal: Bytes,
+    pub chain_id: u64,
+    pub commitment: Bytes,
+    pub preconditions: Vec<Precondition>,
+}
+
+impl PreconditionOutput {
+    /// ABI-encode as `(bytes32 imageId, bytes journal, uint64 chainId, bytes commitment,
+    /// bytes[] preconditions)`, each precondition encoded by `Precondition::abi_encode`.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        let preconditions: Vec<Bytes> = self
+            .preconditions
+            .iter()
+            .map(|precondition| precondition.abi_encode().into())
+            .collect();
+        (
+            self.image_id,
+            self.journal.clone(),
+            self.chain_id,
+            self.commitment.clone(),
+            preconditions,
+        )
+            .abi_encode()
+    }
+}
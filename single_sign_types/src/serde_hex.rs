@@ -0,0 +1,544 @@
+//! Serde formats of the signature, address, hash and 256-bit integer fields of `Input`,
+//! `BatchInput`, `Output` and the host's manifests. They are fixed here rather than
+//! inherited from `alloy-primitives`, whose layouts have changed between releases (a
+//! `Signature` was `{r, s, v}`, then `{r, s, yParity}`), so that inputs, receipts and
+//! manifests written today stay readable after a dependency upgrade. Use with
+//! `#[serde(with = "...")]`.
+//!
+//! Format 1, the only one so far:
+//! - Human-readable formats such as JSON: `0x`-prefixed lowercase hex, but addresses
+//!   are EIP-55 checksummed and signatures are the 65 bytes `r || s || v` with `v` 27
+//!   or 28. Readers accept hex in any case, 256-bit integers also in decimal, and
+//!   signatures also as the `{r, s, v}` and `{r, s, yParity}` objects of earlier alloy
+//!   releases.
+//! - Binary formats such as risc0 serde: the raw bytes through `serialize_bytes`,
+//!   which is how alloy encodes addresses, hashes, bytes and `U256` values (32
+//!   big-endian bytes), so journal layouts are unchanged; signatures are their 65 bytes.
+//!
+//! Changing a format changes a layout: it bumps `OUTPUT_VERSION` for journals and
+//! the schema of manifests, and readers keep accepting the old format.
+
+use alloc::{format, string::String, vec::Vec};
+use alloy_primitives::{hex, Address, Bytes, Signature, B256, U256};
+use core::fmt;
+use serde::{
+    de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+fn serialize_raw<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&hex::encode_prefixed(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+fn deserialize_raw<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(RawVisitor)
+    } else {
+        deserializer.deserialize_bytes(RawVisitor)
+    }
+}
+
+fn deserialize_fixed<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    let bytes = deserialize_raw(deserializer)?;
+    <[u8; N]>::try_from(bytes.as_slice())
+        .map_err(|_| de::Error::custom(format!("expected {N} bytes, got {}", bytes.len())))
+}
+
+struct RawVisitor;
+
+impl<'de> Visitor<'de> for RawVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("hex string or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Vec<u8>, E> {
+        hex::decode(s).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+/// `Address`: EIP-55 checksummed hex, or 20 raw bytes.
+pub mod address {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(address: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&address.to_checksum(None))
+        } else {
+            serializer.serialize_bytes(address.as_slice())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        deserialize_fixed(deserializer).map(Address::from)
+    }
+}
+
+/// `Option<Address>` in the format of `address`.
+pub mod address_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        address: &Option<Address>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        address.map(Addr).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Address>, D::Error> {
+        Ok(Option::<Addr>::deserialize(deserializer)?.map(|address| address.0))
+    }
+}
+
+/// `Vec<Address>` in the format of `address`.
+pub mod address_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        addresses: &[Address],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(addresses.iter().copied().map(Addr))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Address>, D::Error> {
+        Ok(Vec::<Addr>::deserialize(deserializer)?
+            .into_iter()
+            .map(|address| address.0)
+            .collect())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct Addr(#[serde(with = "address")] Address);
+
+/// `B256`: hex, or 32 raw bytes.
+pub mod b256 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(hash: &B256, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_raw(hash.as_slice(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<B256, D::Error> {
+        deserialize_fixed(deserializer).map(B256::from)
+    }
+}
+
+/// `Option<B256>` in the format of `b256`.
+pub mod b256_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(hash: &Option<B256>, serializer: S) -> Result<S::Ok, S::Error> {
+        hash.map(Hash).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<B256>, D::Error> {
+        Ok(Option::<Hash>::deserialize(deserializer)?.map(|hash| hash.0))
+    }
+}
+
+/// `Vec<B256>` in the format of `b256`.
+pub mod b256_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(hashes: &[B256], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(hashes.iter().copied().map(Hash))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<B256>, D::Error> {
+        Ok(Vec::<Hash>::deserialize(deserializer)?
+            .into_iter()
+            .map(|hash| hash.0)
+            .collect())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct Hash(#[serde(with = "b256")] B256);
+
+/// `U256`: hex, or its 32 big-endian bytes. Readers accept shorter values, and in
+/// human-readable formats decimal strings and numbers too, as policy files write
+/// amounts.
+pub mod u256 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_raw(&value.to_be_bytes::<32>(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(UintVisitor)
+        } else {
+            deserializer.deserialize_bytes(UintVisitor)
+        }
+    }
+}
+
+struct UintVisitor;
+
+impl UintVisitor {
+    fn parse<E: de::Error>(bytes: &[u8]) -> Result<U256, E> {
+        U256::try_from_be_slice(bytes)
+            .ok_or_else(|| E::custom(format!("expected at most 32 bytes, got {}", bytes.len())))
+    }
+}
+
+impl<'de> Visitor<'de> for UintVisitor {
+    type Value = U256;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("256-bit integer as hex, decimal or big-endian bytes")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<U256, E> {
+        Ok(U256::from(value))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<U256, E> {
+        match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(_) => Self::parse(&RawVisitor.visit_str::<E>(s)?),
+            None => U256::from_str_radix(s, 10).map_err(E::custom),
+        }
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<U256, E> {
+        Self::parse(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<U256, A::Error> {
+        Self::parse(&RawVisitor.visit_seq(seq)?)
+    }
+}
+
+/// `Bytes`: hex, or the raw bytes.
+pub mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &Bytes, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_raw(bytes, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+        deserialize_raw(deserializer).map(Bytes::from)
+    }
+}
+
+/// `Vec<Bytes>` in the format of `bytes`.
+pub mod bytes_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(items: &[Bytes], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(items.iter().cloned().map(ByteString))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Bytes>, D::Error> {
+        Ok(Vec::<ByteString>::deserialize(deserializer)?
+            .into_iter()
+            .map(|bytes| bytes.0)
+            .collect())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+struct ByteString(#[serde(with = "bytes")] Bytes);
+
+/// secp256k1 `Signature`: the 65 bytes `r || s || v`, as hex or raw.
+pub mod signature {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        signature: &Signature,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serialize_raw(&signature.as_bytes(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Signature, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(SignatureVisitor)
+        } else {
+            deserializer.deserialize_bytes(SignatureVisitor)
+        }
+    }
+}
+
+struct SignatureVisitor;
+
+impl SignatureVisitor {
+    fn parse<E: de::Error>(bytes: &[u8]) -> Result<Signature, E> {
+        Signature::try_from(bytes).map_err(E::custom)
+    }
+}
+
+impl<'de> Visitor<'de> for SignatureVisitor {
+    type Value = Signature;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("65-byte signature as hex or bytes, or an {r, s, yParity} object")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Signature, E> {
+        Self::parse(&RawVisitor.visit_str::<E>(s)?)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Signature, E> {
+        Self::parse(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Signature, A::Error> {
+        Self::parse(&RawVisitor.visit_seq(seq)?)
+    }
+
+    // Layouts of alloy releases before format 1: `{r, s, yParity}` and `{r, s, v}`
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Signature, A::Error> {
+        let (mut r, mut s, mut y_parity) = (None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "r" => r = Some(map.next_value::<U256>()?),
+                "s" => s = Some(map.next_value::<U256>()?),
+                "yParity" | "v" => y_parity = Some(map.next_value::<Parity>()?.0),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(Signature::new(
+            r.ok_or_else(|| de::Error::missing_field("r"))?,
+            s.ok_or_else(|| de::Error::missing_field("s"))?,
+            y_parity.ok_or_else(|| de::Error::missing_field("yParity"))?,
+        ))
+    }
+}
+
+/// A y parity written as a bool, `0`/`1`, `27`/`28` or an EIP-155 `v`, in a number or
+/// a decimal or hex string.
+struct Parity(bool);
+
+impl Parity {
+    fn from_v<E: de::Error>(v: u64) -> Result<Self, E> {
+        match v {
+            0 | 1 => Ok(Self(v == 1)),
+            27 | 28 => Ok(Self(v == 28)),
+            v if v >= 35 => Ok(Self((v - 35) % 2 == 1)),
+            _ => Err(E::custom(format!("invalid signature v {v}"))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Parity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ParityVisitor)
+    }
+}
+
+struct ParityVisitor;
+
+impl Visitor<'_> for ParityVisitor {
+    type Value = Parity;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("signature y parity or v")
+    }
+
+    fn visit_bool<E: de::Error>(self, y_parity: bool) -> Result<Parity, E> {
+        Ok(Parity(y_parity))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Parity, E> {
+        Parity::from_v(v)
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Parity, E> {
+        let v = match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => s.parse(),
+        };
+        Parity::from_v(v.map_err(E::custom)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec};
+    use alloy_primitives::address;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Fields {
+        #[serde(with = "address")]
+        address: Address,
+        #[serde(with = "address_option")]
+        address_option: Option<Address>,
+        #[serde(with = "address_vec")]
+        address_vec: Vec<Address>,
+        #[serde(with = "b256")]
+        b256: B256,
+        #[serde(with = "b256_option")]
+        b256_option: Option<B256>,
+        #[serde(with = "b256_vec")]
+        b256_vec: Vec<B256>,
+        #[serde(with = "bytes")]
+        bytes: Bytes,
+        #[serde(with = "bytes_vec")]
+        bytes_vec: Vec<Bytes>,
+        #[serde(with = "signature")]
+        signature: Signature,
+        #[serde(with = "u256")]
+        u256: U256,
+    }
+
+    fn fields() -> Fields {
+        Fields {
+            address: address!("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"),
+            address_option: Some(Address::repeat_byte(0x11)),
+            address_vec: vec![Address::repeat_byte(0x22), Address::repeat_byte(0x33)],
+            b256: B256::repeat_byte(0xaa),
+            b256_option: None,
+            b256_vec: vec![B256::repeat_byte(1), B256::repeat_byte(2)],
+            bytes: Bytes::from_static(&[1, 2, 3, 4, 5]),
+            bytes_vec: vec![Bytes::from_static(&[6]), Bytes::new()],
+            signature: Signature::new(U256::from(1), U256::from(2), true),
+            u256: U256::from(1_700_000_000u64),
+        }
+    }
+
+    #[test]
+    fn round_trips_json() {
+        let json = serde_json::to_string(&fields()).unwrap();
+        assert_eq!(serde_json::from_str::<Fields>(&json).unwrap(), fields());
+    }
+
+    #[test]
+    fn round_trips_binary() {
+        let encoded = bincode::serialize(&fields()).unwrap();
+        assert_eq!(bincode::deserialize::<Fields>(&encoded).unwrap(), fields());
+    }
+
+    #[test]
+    fn json_is_prefixed_hex_with_checksummed_addresses() {
+        let json = serde_json::to_value(fields()).unwrap();
+        assert_eq!(json["address"], "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045");
+        assert_eq!(json["b256"], "0x".to_string() + &"aa".repeat(32));
+        assert_eq!(json["b256_option"], serde_json::Value::Null);
+        assert_eq!(json["bytes"], "0x0102030405");
+        assert_eq!(json["bytes_vec"], serde_json::json!(["0x06", "0x"]));
+        let signature = json["signature"].as_str().unwrap();
+        assert_eq!(signature.len(), 2 + 65 * 2);
+        assert!(signature.ends_with("1c"));
+        assert_eq!(json["u256"], format!("0x{}6553f100", "0".repeat(56)));
+    }
+
+    #[test]
+    fn binary_matches_alloy() {
+        // Journal layouts must not change when a field moves to these formats
+        let fields = fields();
+        let alloy = (
+            fields.address,
+            fields.b256,
+            fields.bytes.clone(),
+            fields.u256,
+            &fields.address_vec,
+            &fields.bytes_vec,
+        );
+        let ours = (
+            Addr(fields.address),
+            Hash(fields.b256),
+            Raw(fields.bytes),
+            Uint(fields.u256),
+            Addresses(fields.address_vec.clone()),
+            Blobs(fields.bytes_vec.clone()),
+        );
+        assert_eq!(bincode::serialize(&ours).unwrap(), bincode::serialize(&alloy).unwrap());
+    }
+
+    #[derive(Serialize)]
+    struct Raw(#[serde(with = "bytes")] Bytes);
+
+    #[derive(Serialize)]
+    struct Uint(#[serde(with = "u256")] U256);
+
+    #[derive(Serialize)]
+    struct Addresses(#[serde(with = "address_vec")] Vec<Address>);
+
+    #[derive(Serialize)]
+    struct Blobs(#[serde(with = "bytes_vec")] Vec<Bytes>);
+
+    #[test]
+    fn u256_readers_accept_decimal() {
+        for json in [r#""1000000""#, "1000000", r#""0xf4240""#] {
+            let mut deserializer = serde_json::Deserializer::from_str(json);
+            let value = u256::deserialize(&mut deserializer).unwrap();
+            assert_eq!(value, U256::from(1_000_000), "{json}");
+        }
+        let mut deserializer = serde_json::Deserializer::from_str(r#""f4240""#);
+        assert!(u256::deserialize(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn readers_accept_any_hex_case_and_legacy_signatures() {
+        let upper = format!("\"0x{}\"", "AA".repeat(32));
+        let hash: Hash = serde_json::from_str(&upper).unwrap();
+        assert_eq!(hash.0, B256::repeat_byte(0xaa));
+
+        let expected = Signature::new(U256::from(1), U256::from(2), true);
+        for legacy in [
+            r#"{"r": "0x1", "s": "0x2", "yParity": "0x1"}"#,
+            r#"{"r": "0x1", "s": "0x2", "v": "0x1c"}"#,
+            r#"{"r": "0x1", "s": "0x2", "v": 28}"#,
+        ] {
+            let mut deserializer = serde_json::Deserializer::from_str(legacy);
+            assert_eq!(signature::deserialize(&mut deserializer).unwrap(), expected, "{legacy}");
+        }
+    }
+
+    #[test]
+    fn readers_reject_wrong_lengths() {
+        let short = format!("\"0x{}\"", "aa".repeat(31));
+        assert!(serde_json::from_str::<Hash>(&short).is_err());
+        let mut deserializer = serde_json::Deserializer::from_str(&short);
+        assert!(address::deserialize(&mut deserializer).is_err());
+        let long = format!("\"0x{}\"", "aa".repeat(33));
+        let mut deserializer = serde_json::Deserializer::from_str(&long);
+        assert!(u256::deserialize(&mut deserializer).is_err());
+        let short = format!("\"0x{}\"", "01".repeat(8));
+        let mut deserializer = serde_json::Deserializer::from_str(&short);
+        let value = u256::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, U256::from(0x0101_0101_0101_0101u64));
+    }
+}
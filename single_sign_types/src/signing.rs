@@ -1,49 +1,534 @@
-use alloy_primitives::{keccak256, Address, Bytes, Signature, B256};
+use alloc::{format, string::String, vec::Vec};
+use alloy_primitives::{Address, Bytes, Signature, B256, U256};
 use anyhow::{anyhow, Result};
+use core::str::FromStr;
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey as Ed25519VerifyingKey};
+use p256::ecdsa::{
+    signature::Verifier, Signature as P256Signature, VerifyingKey as P256VerifyingKey,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bls,
+    chain::ChainLink,
+    erc7739::{self, AccountDomain},
+    hash::{keccak256, Keccak256},
+    intent::SignedIntent,
+    merkle::MerkleBranch,
+    serde_hex, webauthn,
+};
+
+/// How `verify_signature` hashes its message.
+///
+/// Committed in journals as `Output.message_mode`, by variant index, and ABI-encoded by
+/// discriminant, so variants must never be reordered, removed or reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageMode {
+    Raw32 = 0,
+    Keccak = 1,
+    Personal = 2,
+}
+
+impl MessageMode {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+impl FromStr for MessageMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "raw32" => Ok(MessageMode::Raw32),
+            "keccak" => Ok(MessageMode::Keccak),
+            "personal" => Ok(MessageMode::Personal),
+            _ => Err(anyhow!("Unknown message mode {s}, expected raw32, keccak or personal")),
+        }
+    }
+}
 
 /// Verify an Ethereum ECDSA signature against an expected signer address.
 /// - `message`: the message bytes (either already hashed or raw bytes)
-/// - `signature`: 65-byte r||s||v signature (v = 27/28 or 0/1 or 35+ chain-ids are ok)
+/// - `signature`: r||s with its recovery parity; parse 27/28 or EIP-155 `v` values with
+///   `parse_signature_with_chain_id`
 /// - `expected`: the address you expect as the signer
 /// - `mode`: how to interpret `message`
 ///     - Raw32: `message` is a 32-byte prehash (use as-is)
 ///     - Keccak: `message` is arbitrary bytes; hash with keccak256(message)
 ///     - Personal: EIP-191; hash with keccak256("\x19Ethereum Signed Message:\n{len}" || message)
-pub enum MessageMode {
-    Raw32,
-    Keccak,
-    Personal,
-}
-
+/// - `require_low_s`: reject signatures whose `s` is in the upper half of the curve
+///   order, whose low-s twin recovers to the same signer (EIP-2)
 pub fn verify_signature(
-    message: Bytes,
+    message: &[u8],
     signature: Signature,
     expected: Address,
     mode: MessageMode,
+    require_low_s: bool,
 ) -> Result<bool> {
+    let recovered = recover_signer(message, signature, mode, require_low_s)?;
+    if recovered != expected {
+        return Err(anyhow!("Recovered address {:#x} does not match expected address {:#x}", recovered, expected));
+    }
+    Ok(true)
+}
+
+/// The address `signature` over `message` recovers to, with the same `mode` and
+/// `require_low_s` as `verify_signature`, for callers with no expected signer.
+pub fn recover_signer(
+    message: &[u8],
+    signature: Signature,
+    mode: MessageMode,
+    require_low_s: bool,
+) -> Result<Address> {
+    if require_low_s && signature.normalize_s().is_some() {
+        return Err(anyhow!("Signature is malleable: s is in the upper half of the curve order"));
+    }
+
     // 1) Build the pre-hash we’ll recover from.
     let prehash: B256 = match mode {
         MessageMode::Raw32 => {
             if message.len() != 32 {
                 return Err(anyhow!("Raw32 mode requires a 32-byte prehash"));
             }
-            B256::from_slice(&message)
-        }
-        MessageMode::Keccak => keccak256(&message),
-        MessageMode::Personal => {
-            // EIP-191: "\x19Ethereum Signed Message:\n" + len + message
-            let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
-            keccak256([prefix.as_bytes(), message.as_ref()].concat())
+            B256::from_slice(message)
         }
+        MessageMode::Keccak => keccak256(message),
+        MessageMode::Personal => eip191_hash(message),
     };
 
-    // 2) Recover and compare.
-    let recovered = signature
+    // 2) Recover.
+    signature
         .recover_address_from_prehash(&prehash)
-        .map_err(|e| anyhow!("recovery failed: {e}"))?;
+        .map_err(|e| anyhow!("recovery failed: {e}"))
+}
 
-    if recovered != expected {
-        return Err(anyhow!("Recovered address {:#x} does not match expected address {:#x}", recovered, expected));
+/// Find which of `modes`, in order, `expected` signed in, for wallets that differ in
+/// how they sign a blob: `Personal` signs `blob` with the EIP-191 prefix, as
+/// `personal_sign` does, `Keccak` signs its bare keccak256, as raw-hash signers do, and
+/// `Raw32` signs the range's EIP-712 `digest`, as `eth_signTypedData` does. Low-s is
+/// required as by `Envelope::verify`.
+pub fn match_message_mode(
+    blob: &[u8],
+    digest: B256,
+    signature: Signature,
+    expected: Address,
+    modes: &[MessageMode],
+) -> Result<MessageMode> {
+    modes
+        .iter()
+        .copied()
+        .find(|&mode| {
+            let message = match mode {
+                MessageMode::Raw32 => digest.as_slice(),
+                MessageMode::Keccak | MessageMode::Personal => blob,
+            };
+            verify_signature(message, signature, expected, mode, true).is_ok()
+        })
+        .ok_or_else(|| anyhow!("The signature is not by {expected:#x} in any of {modes:?}"))
+}
+
+/// Parse a 65-byte `r || s || v` signature, rejecting `v` values other than the
+/// canonical 27 and 28 so each signature has one byte encoding.
+pub fn parse_signature(bytes: &[u8]) -> Result<Signature> {
+    if bytes.len() != 65 {
+        return Err(anyhow!("Expected a 65-byte signature, got {} bytes", bytes.len()));
+    }
+    let v = bytes[64];
+    if !matches!(v, 27 | 28) {
+        return Err(anyhow!("Non-canonical signature v value {v}, expected 27 or 28"));
+    }
+    Signature::try_from(bytes).map_err(|e| anyhow!("Invalid signature: {e}"))
+}
+
+/// Parse an `r || s || v` signature whose `v` is 27/28 or, as legacy EIP-155
+/// transactions encode it, `chain_id * 2 + 35` or `+ 36`, big-endian in up to eight
+/// bytes. Returns the signature with the recovery parity taken from `v`, and the
+/// embedded chain ID, if any.
+pub fn parse_signature_with_chain_id(bytes: &[u8]) -> Result<(Signature, Option<u64>)> {
+    if !(65..=72).contains(&bytes.len()) {
+        return Err(anyhow!("Expected a 65 to 72-byte signature, got {} bytes", bytes.len()));
+    }
+    let v = bytes[64..]
+        .iter()
+        .fold(0u64, |v, &byte| v << 8 | u64::from(byte));
+    let (y_parity, chain_id) = split_v(v)?;
+    let r = U256::from_be_slice(&bytes[..32]);
+    let s = U256::from_be_slice(&bytes[32..64]);
+    Ok((Signature::new(r, s, y_parity), chain_id))
+}
+
+/// Split a signature `v` value into its recovery parity and EIP-155 chain ID. Chain
+/// ID zero (`v` of 35 or 36) is rejected, as are the raw 0/1 parities.
+pub fn split_v(v: u64) -> Result<(bool, Option<u64>)> {
+    match v {
+        27 | 28 => Ok((v == 28, None)),
+        37.. => Ok(((v - 35) % 2 == 1, Some((v - 35) / 2))),
+        _ => Err(anyhow!("Invalid signature v value {v}, expected 27, 28 or an EIP-155 value")),
+    }
+}
+
+/// EIP-191: keccak256("\x19Ethereum Signed Message:\n" + len + message), hashed
+/// incrementally so large messages are not copied.
+pub fn eip191_hash(message: &[u8]) -> B256 {
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+    let mut hasher = Keccak256::new();
+    hasher.update(prefix.as_bytes());
+    hasher.update(message);
+    hasher.finalize()
+}
+
+/// How the signer's key signed `typed_data_concat`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum Envelope {
+    /// EIP-191 personal signature over the raw bytes, as EOAs produce.
+    #[default]
+    Personal,
+    /// ERC-7739 `PersonalSign` rehash under the smart account's domain.
+    Erc7739PersonalSign(AccountDomain),
+    /// ERC-7739 `TypedDataSign` rehash; the signed bytes must be a single typed data.
+    Erc7739TypedDataSign(AccountDomain),
+    /// EIP-712 signature over an intent envelope listing the digests of the objects
+    /// in the blob, rather than over the blob itself; see `intent`.
+    Intent(SignedIntent),
+    /// EIP-191 personal signature over the 32-byte Merkle root of the objects'
+    /// digests, with the branch for the object being proven; see `merkle`.
+    Merkle(MerkleBranch),
+    /// EIP-191 personal signature over the 32-byte head of the hash chain of the
+    /// objects' digests, with the link for the object being proven; see `chain`.
+    HashChain(ChainLink),
+}
+
+impl Envelope {
+    /// The prehash the signer's key actually signed for `message`.
+    pub fn signing_hash(&self, message: &[u8]) -> Result<B256> {
+        match self {
+            Envelope::Personal => Ok(eip191_hash(message)),
+            Envelope::Erc7739PersonalSign(account) => {
+                Ok(erc7739::personal_sign_hash(message, account))
+            }
+            Envelope::Erc7739TypedDataSign(account) => {
+                let typed_data_json = core::str::from_utf8(message)
+                    .map_err(|e| anyhow!("Signed typed data is not valid UTF-8: {e}"))?;
+                erc7739::typed_data_sign_hash(typed_data_json, account)
+            }
+            Envelope::Intent(intent) => Ok(intent.signing_hash()),
+            Envelope::Merkle(branch) => Ok(eip191_hash(branch.root.as_slice())),
+            Envelope::HashChain(link) => Ok(eip191_hash(link.head.as_slice())),
+        }
+    }
+
+    /// The intent the wallet signed, for envelopes that sign one.
+    pub fn intent(&self) -> Option<&SignedIntent> {
+        match self {
+            Envelope::Intent(intent) => Some(intent),
+            _ => None,
+        }
+    }
+
+    /// Whether the signature authorizes the object with EIP-712 `digest`. Always true
+    /// for envelopes signing the blob itself; intents only authorize listed digests,
+    /// and Merkle and hash-chain envelopes the digest their branch or link leads from.
+    pub fn authorizes(&self, digest: B256) -> bool {
+        match self {
+            Envelope::Intent(intent) => intent.authorizes(digest),
+            Envelope::Merkle(branch) => branch.contains(digest),
+            Envelope::HashChain(link) => link.contains(digest),
+            _ => true,
+        }
+    }
+
+    /// Verify that `signature` over `message` in this envelope recovers to `expected`.
+    /// High-s signatures are rejected; normalize them first where malleability is
+    /// acceptable.
+    pub fn verify(&self, message: &[u8], signature: Signature, expected: Address) -> Result<bool> {
+        let prehash = self.signing_hash(message)?;
+        verify_signature(prehash.as_slice(), signature, expected, MessageMode::Raw32, true)
+    }
+
+    /// The address `signature` over `message` in this envelope recovers to, for inputs
+    /// that name no expected signer. High-s signatures are rejected as by `verify`.
+    pub fn recover(&self, message: &[u8], signature: Signature) -> Result<Address> {
+        let prehash = self.signing_hash(message)?;
+        recover_signer(prehash.as_slice(), signature, MessageMode::Raw32, true)
+    }
+}
+
+/// Key type behind a signature. Committed in journals by variant index and ABI-encoded
+/// by discriminant, so variants must never be reordered, removed or reused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignatureScheme {
+    /// Ethereum accounts; the signer is recovered as an address.
+    #[default]
+    Secp256k1 = 0,
+    /// ECDSA P-256 with SHA-256, e.g. passkeys.
+    Secp256r1 = 1,
+    Ed25519 = 2,
+    /// P-256 WebAuthn assertion over the blob's keccak256 as challenge.
+    WebAuthn = 3,
+    /// BLS12-381 aggregate signature over the blob's keccak256.
+    Bls12381 = 4,
+}
+
+impl SignatureScheme {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Signature over `typed_data_concat` by a key that has no Ethereum address. The
+/// raw bytes are signed; `Envelope` only applies to secp256k1 signatures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum KeySignature {
+    /// SEC1-encoded public key and 64-byte `r || s` signature.
+    Secp256r1 {
+        #[serde(with = "serde_hex::bytes")]
+        public_key: Bytes,
+        #[serde(with = "serde_hex::bytes")]
+        signature: Bytes,
+    },
+    /// 32-byte public key and 64-byte signature.
+    Ed25519 {
+        #[serde(with = "serde_hex::bytes")]
+        public_key: Bytes,
+        #[serde(with = "serde_hex::bytes")]
+        signature: Bytes,
+    },
+    /// WebAuthn assertion from a passkey, whose `clientDataJSON.challenge` must be
+    /// the base64url keccak256 of the signed bytes. See `webauthn`.
+    WebAuthn {
+        /// SEC1-encoded P-256 public key of the credential.
+        #[serde(with = "serde_hex::bytes")]
+        public_key: Bytes,
+        #[serde(with = "serde_hex::bytes")]
+        credential_id: Bytes,
+        #[serde(with = "serde_hex::bytes")]
+        authenticator_data: Bytes,
+        client_data_json: String,
+        /// 64-byte `r || s` signature over `authenticatorData || sha256(clientDataJSON)`.
+        #[serde(with = "serde_hex::bytes")]
+        signature: Bytes,
+    },
+    /// One aggregate signature by a set of approvers. See `bls`.
+    Bls12381Aggregate {
+        /// 48-byte compressed G1 public keys, proofs of possession already checked.
+        #[serde(with = "serde_hex::bytes_vec")]
+        public_keys: Vec<Bytes>,
+        /// 96-byte compressed G2 aggregate signature over `keccak256(message)`.
+        #[serde(with = "serde_hex::bytes")]
+        signature: Bytes,
+    },
+}
+
+impl KeySignature {
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            KeySignature::Secp256r1 { .. } => SignatureScheme::Secp256r1,
+            KeySignature::Ed25519 { .. } => SignatureScheme::Ed25519,
+            KeySignature::WebAuthn { .. } => SignatureScheme::WebAuthn,
+            KeySignature::Bls12381Aggregate { .. } => SignatureScheme::Bls12381,
+        }
+    }
+
+    /// The signer's public key; for BLS, the compressed aggregate of all approvers'
+    /// keys, whose keccak256 identifies the approver set on-chain.
+    pub fn public_key(&self) -> Bytes {
+        match self {
+            KeySignature::Secp256r1 { public_key, .. }
+            | KeySignature::Ed25519 { public_key, .. }
+            | KeySignature::WebAuthn { public_key, .. } => public_key.clone(),
+            KeySignature::Bls12381Aggregate { public_keys, .. } => {
+                bls::aggregate_public_keys(public_keys)
+                    .map(|aggregate| Bytes::copy_from_slice(&aggregate.to_compressed()))
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    /// keccak256 of the WebAuthn credential ID; zero for other schemes.
+    pub fn credential_id_hash(&self) -> B256 {
+        match self {
+            KeySignature::WebAuthn { credential_id, .. } => keccak256(credential_id),
+            _ => B256::ZERO,
+        }
+    }
+
+    /// Verify the signature over `message` against the public key.
+    pub fn verify(&self, message: &[u8]) -> Result<bool> {
+        match self {
+            KeySignature::Secp256r1 { public_key, signature } => {
+                verify_p256(public_key, message, signature)?;
+            }
+            KeySignature::Ed25519 { public_key, signature } => {
+                let public_key: &[u8; 32] = public_key
+                    .as_ref()
+                    .try_into()
+                    .map_err(|_| anyhow!("Ed25519 public key must be 32 bytes"))?;
+                let key = Ed25519VerifyingKey::from_bytes(public_key)
+                    .map_err(|e| anyhow!("Invalid Ed25519 public key: {e}"))?;
+                let signature = Ed25519Signature::from_slice(signature)
+                    .map_err(|e| anyhow!("Invalid Ed25519 signature: {e}"))?;
+                key.verify_strict(message, &signature)
+                    .map_err(|e| anyhow!("Ed25519 verification failed: {e}"))?;
+            }
+            KeySignature::WebAuthn {
+                public_key,
+                authenticator_data,
+                client_data_json,
+                signature,
+                ..
+            } => {
+                let signed =
+                    webauthn::signed_message(message, authenticator_data, client_data_json)?;
+                verify_p256(public_key, &signed, signature)?;
+            }
+            KeySignature::Bls12381Aggregate { public_keys, signature } => {
+                bls::verify_aggregate(public_keys, keccak256(message).as_slice(), signature)?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// ECDSA P-256 with SHA-256 over `message`.
+fn verify_p256(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<()> {
+    let key = P256VerifyingKey::from_sec1_bytes(public_key)
+        .map_err(|e| anyhow!("Invalid P-256 public key: {e}"))?;
+    let signature =
+        P256Signature::from_slice(signature).map_err(|e| anyhow!("Invalid P-256 signature: {e}"))?;
+    key.verify(message, &signature)
+        .map_err(|e| anyhow!("P-256 verification failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn signature_bytes(v: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0x11; 32];
+        bytes.extend([0x22; 32]);
+        bytes.extend_from_slice(v);
+        bytes
+    }
+
+    #[test]
+    fn split_v_reads_canonical_and_eip155_values() {
+        assert_eq!(split_v(27).unwrap(), (false, None));
+        assert_eq!(split_v(28).unwrap(), (true, None));
+        assert_eq!(split_v(37).unwrap(), (false, Some(1)));
+        assert_eq!(split_v(38).unwrap(), (true, Some(1)));
+        assert_eq!(split_v(8453 * 2 + 35).unwrap(), (false, Some(8453)));
+        assert_eq!(split_v(8453 * 2 + 36).unwrap(), (true, Some(8453)));
+    }
+
+    #[test]
+    fn split_v_rejects_raw_parities_and_chain_id_zero() {
+        for v in [0, 1, 26, 29, 34, 35, 36] {
+            assert!(split_v(v).is_err(), "v = {v}");
+        }
+    }
+
+    #[test]
+    fn parse_signature_accepts_only_canonical_v() {
+        let signature = parse_signature(&signature_bytes(&[28])).unwrap();
+        assert_eq!(signature.r(), U256::from_be_slice(&[0x11; 32]));
+        assert_eq!(signature.s(), U256::from_be_slice(&[0x22; 32]));
+        assert!(signature.v());
+        assert!(!parse_signature(&signature_bytes(&[27])).unwrap().v());
+        for v in [0, 1, 29, 37] {
+            assert!(parse_signature(&signature_bytes(&[v])).is_err(), "v = {v}");
+        }
+    }
+
+    #[test]
+    fn parse_signature_rejects_other_lengths() {
+        assert!(parse_signature(&signature_bytes(&[])).is_err());
+        assert!(parse_signature(&signature_bytes(&[0, 27])).is_err());
+    }
+
+    #[test]
+    fn parse_signature_round_trips_as_bytes() {
+        let signature = parse_signature(&signature_bytes(&[27])).unwrap();
+        assert_eq!(parse_signature(&signature.as_bytes()).unwrap(), signature);
+    }
+
+    #[test]
+    fn parse_signature_with_chain_id_reads_multi_byte_v() {
+        let (signature, chain_id) = parse_signature_with_chain_id(&signature_bytes(&[28])).unwrap();
+        assert!(signature.v());
+        assert_eq!(chain_id, None);
+
+        // 8453 * 2 + 35 = 0x422d
+        let (signature, chain_id) =
+            parse_signature_with_chain_id(&signature_bytes(&[0x42, 0x2d])).unwrap();
+        assert!(!signature.v());
+        assert_eq!(chain_id, Some(8453));
+        assert_eq!(signature.r(), U256::from_be_slice(&[0x11; 32]));
+    }
+
+    #[test]
+    fn parse_signature_with_chain_id_rejects_bad_lengths_and_v() {
+        assert!(parse_signature_with_chain_id(&signature_bytes(&[])).is_err());
+        assert!(parse_signature_with_chain_id(&signature_bytes(&[0; 8])).is_err());
+        let too_long = signature_bytes(&[0, 0, 0, 0, 0, 0, 0, 0, 37]);
+        assert!(parse_signature_with_chain_id(&too_long).is_err());
+        assert!(parse_signature_with_chain_id(&signature_bytes(&[1])).is_err());
+    }
+
+    #[test]
+    fn key_signatures_round_trip_in_alloys_binary_layout() {
+        let bytes = |byte: u8, len: usize| Bytes::from(vec![byte; len]);
+        let client_data_json = String::from(r#"{"type":"webauthn.get"}"#);
+        let cases = [
+            (
+                KeySignature::Secp256r1 {
+                    public_key: bytes(4, 65),
+                    signature: bytes(1, 64),
+                },
+                bincode::serialize(&(0u32, bytes(4, 65), bytes(1, 64))),
+            ),
+            (
+                KeySignature::Ed25519 {
+                    public_key: bytes(5, 32),
+                    signature: bytes(2, 64),
+                },
+                bincode::serialize(&(1u32, bytes(5, 32), bytes(2, 64))),
+            ),
+            (
+                KeySignature::WebAuthn {
+                    public_key: bytes(4, 65),
+                    credential_id: bytes(6, 16),
+                    authenticator_data: bytes(7, 37),
+                    client_data_json: client_data_json.clone(),
+                    signature: bytes(3, 64),
+                },
+                bincode::serialize(&(
+                    2u32,
+                    bytes(4, 65),
+                    bytes(6, 16),
+                    bytes(7, 37),
+                    &client_data_json,
+                    bytes(3, 64),
+                )),
+            ),
+            (
+                KeySignature::Bls12381Aggregate {
+                    public_keys: vec![bytes(8, 48), bytes(9, 48)],
+                    signature: bytes(10, 96),
+                },
+                bincode::serialize(&(3u32, vec![bytes(8, 48), bytes(9, 48)], bytes(10, 96))),
+            ),
+        ];
+        for (key_signature, alloy) in cases {
+            let scheme = key_signature.scheme();
+            let encoded = bincode::serialize(&key_signature).unwrap();
+            assert_eq!(encoded, alloy.unwrap(), "{scheme:?}");
+            let decoded: KeySignature = bincode::deserialize(&encoded).unwrap();
+            assert_eq!(bincode::serialize(&decoded).unwrap(), encoded, "{scheme:?}");
+            let json = serde_json::to_value(&key_signature).unwrap();
+            let decoded: KeySignature = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&decoded).unwrap(), json, "{scheme:?}");
+        }
     }
-    Ok(true)
 }
@@ -0,0 +1,255 @@
+//! Safe smart-account signatures checked against Ethereum state proofs, so proving
+//! an ERC-1271 approval does not mean trusting an RPC node's `isValidSignature`.
+//!
+//! The guest hashes the RLP block header to the block hash and takes its state root,
+//! follows the account proof to the Safe's storage root, and reads the singleton,
+//! threshold and `owners` linked-list entries from storage proofs. It then runs
+//! Safe's own check: the compatibility fallback handler's `isValidSignature(bytes32)`
+//! rehashes the hash as a `SafeMessage` under the Safe's domain, and `threshold`
+//! owner signatures, sorted by owner address, must sign that.
+//!
+//! Storage layout of Safe v1.3 and v1.4: slot 0 holds the singleton, slot 2 the
+//! `owners` mapping from each owner to the next, and slot 4 the threshold.
+
+use alloc::vec::Vec;
+use alloy_primitives::{address, Address, Bloom, Bytes, Signature, B256, U256};
+use alloy_rlp::Decodable;
+use alloy_sol_types::SolValue;
+use alloy_trie::{nybbles::Nibbles, proof::verify_proof, TrieAccount};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{hash::keccak256, signing::eip191_hash, DigestRange};
+
+/// Storage slot of the Safe proxy's singleton (implementation) address.
+pub const SINGLETON_SLOT: u64 = 0;
+/// Storage slot of the `owners` mapping.
+pub const OWNERS_SLOT: u64 = 2;
+/// Storage slot of the signature threshold.
+pub const THRESHOLD_SLOT: u64 = 4;
+/// Head and tail of the `owners` linked list; never an owner itself.
+pub const SENTINEL_OWNERS: Address = address!("0x0000000000000000000000000000000000000001");
+
+/// A storage slot's value and its Merkle proof from the account's storage root, as
+/// `eth_getProof` returns them. Zero values are proven absent.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageProof {
+    pub slot: B256,
+    pub value: U256,
+    pub proof: Vec<Bytes>,
+}
+
+impl StorageProof {
+    pub fn verify(&self, storage_root: B256) -> Result<()> {
+        let expected = (!self.value.is_zero()).then(|| alloy_rlp::encode(self.value));
+        verify_proof(storage_root, Nibbles::unpack(keccak256(self.slot)), expected, &self.proof)
+            .map_err(|e| anyhow!("Storage proof of slot {} is invalid: {e}", self.slot))
+    }
+}
+
+/// The account's state and its Merkle proof from the block's state root.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountProof {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: B256,
+    pub code_hash: B256,
+    pub proof: Vec<Bytes>,
+}
+
+impl AccountProof {
+    pub fn verify(&self, state_root: B256, account: Address) -> Result<()> {
+        let trie_account = TrieAccount {
+            nonce: self.nonce,
+            balance: self.balance,
+            storage_root: self.storage_root,
+            code_hash: self.code_hash,
+        };
+        let expected = Some(alloy_rlp::encode(trie_account));
+        verify_proof(state_root, Nibbles::unpack(keccak256(account)), expected, &self.proof)
+            .map_err(|e| anyhow!("Account proof of {account:#x} is invalid: {e}"))
+    }
+}
+
+/// Input of the Safe state-proof guest: a blob the Safe signed in EIP-191 personal
+/// mode, the range to attest, and the state that makes the signature valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafeStateInput {
+    pub account: Address,
+    /// Chain the Safe's domain separator is bound to
+    pub chain_id: u64,
+    /// RLP-encoded header of the block the proofs are against
+    pub header_rlp: Bytes,
+    pub account_proof: AccountProof,
+    pub singleton: StorageProof,
+    pub threshold: StorageProof,
+    /// `owners[owner]` entries of the signing owners, in any order
+    pub owners: Vec<StorageProof>,
+    /// Owner signatures packed as Safe expects them, 65 bytes each
+    pub signatures: Bytes,
+    pub typed_data_concat: Bytes,
+    pub digest_range: DigestRange,
+    pub require_canonical: bool,
+}
+
+/// Journal of the Safe state-proof guest. Verifiers check `block_hash` against
+/// `blockhash(block_number)` (or a trusted header oracle) and `singleton` against the
+/// Safe implementations they accept.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateProofOutput {
+    pub account: Address,
+    pub digest: B256,
+    pub block_hash: B256,
+    pub block_number: u64,
+    pub chain_id: u64,
+    pub singleton: Address,
+}
+
+impl StateProofOutput {
+    /// ABI-encode as `(address account, bytes32 digest, bytes32 blockHash,
+    /// uint64 blockNumber, uint64 chainId, address singleton)`.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        (
+            self.account,
+            self.digest,
+            self.block_hash,
+            self.block_number,
+            self.chain_id,
+            self.singleton,
+        )
+            .abi_encode()
+    }
+}
+
+/// Hash of `header_rlp` and the state root and number it commits to.
+pub fn decode_header(header_rlp: &[u8]) -> Result<(B256, B256, u64)> {
+    let buf = &mut &header_rlp[..];
+    let header = alloy_rlp::Header::decode(buf)?;
+    if !header.list || header.payload_length != buf.len() {
+        bail!("Block header is not a single RLP list");
+    }
+    // parentHash, ommersHash, beneficiary, stateRoot, transactionsRoot, receiptsRoot,
+    // logsBloom, difficulty, number, ...
+    B256::decode(buf)?;
+    B256::decode(buf)?;
+    Address::decode(buf)?;
+    let state_root = B256::decode(buf)?;
+    B256::decode(buf)?;
+    B256::decode(buf)?;
+    Bloom::decode(buf)?;
+    U256::decode(buf)?;
+    let number = u64::decode(buf)?;
+    Ok((keccak256(header_rlp), state_root, number))
+}
+
+// The value of the fixed `slot`, checked against the account's storage root
+fn read_slot(proof: &StorageProof, slot: u64, storage_root: B256) -> Result<U256> {
+    if proof.slot != B256::from(U256::from(slot)) {
+        bail!("Expected a proof of slot {slot}, got {}", proof.slot);
+    }
+    proof.verify(storage_root)?;
+    Ok(proof.value)
+}
+
+/// Storage slot of `owners[owner]`.
+pub fn owner_slot(owner: Address) -> B256 {
+    keccak256((owner, U256::from(OWNERS_SLOT)).abi_encode())
+}
+
+/// The hash Safe owners sign for `isValidSignature(data_hash)`:
+/// `keccak256(0x1901 || domainSeparator || keccak256(abi.encode(SAFE_MSG_TYPEHASH,
+/// keccak256(abi.encode(data_hash)))))`.
+pub fn safe_message_hash(chain_id: u64, safe: Address, data_hash: B256) -> B256 {
+    let domain_typehash = keccak256("EIP712Domain(uint256 chainId,address verifyingContract)");
+    let domain_separator = keccak256((domain_typehash, U256::from(chain_id), safe).abi_encode());
+    let message_typehash = keccak256("SafeMessage(bytes message)");
+    let struct_hash = keccak256((message_typehash, keccak256(data_hash)).abi_encode());
+    let mut preimage = Vec::with_capacity(66);
+    preimage.extend_from_slice(&[0x19, 0x01]);
+    preimage.extend_from_slice(domain_separator.as_slice());
+    preimage.extend_from_slice(struct_hash.as_slice());
+    keccak256(preimage)
+}
+
+impl SafeStateInput {
+    /// Check everything from the header down to the owner signatures over the blob,
+    /// and return the journal attesting `digest` for the Safe.
+    pub fn verify(&self, digest: B256) -> Result<StateProofOutput> {
+        let (block_hash, state_root, block_number) = decode_header(&self.header_rlp)?;
+        self.account_proof.verify(state_root, self.account)?;
+        let storage_root = self.account_proof.storage_root;
+
+        let singleton = read_slot(&self.singleton, SINGLETON_SLOT, storage_root)?;
+        let threshold = read_slot(&self.threshold, THRESHOLD_SLOT, storage_root)?;
+        let threshold = usize::try_from(threshold)
+            .ok()
+            .filter(|threshold| *threshold > 0)
+            .ok_or_else(|| anyhow!("Safe {:#x} has no usable threshold", self.account))?;
+        for owner in &self.owners {
+            owner.verify(storage_root)?;
+        }
+
+        let data_hash = eip191_hash(&self.typed_data_concat);
+        let message_hash = safe_message_hash(self.chain_id, self.account, data_hash);
+        self.check_signatures(message_hash, threshold)?;
+
+        Ok(StateProofOutput {
+            account: self.account,
+            digest,
+            block_hash,
+            block_number,
+            chain_id: self.chain_id,
+            singleton: Address::from_word(B256::from(singleton)),
+        })
+    }
+
+    // Safe's `checkNSignatures` for EOA owners: the first `threshold` signatures must
+    // recover to distinct owners in ascending order
+    fn check_signatures(&self, message_hash: B256, threshold: usize) -> Result<()> {
+        let mut last_owner = Address::ZERO;
+        for owner in recover_owners(message_hash, &self.signatures, threshold)? {
+            if owner <= last_owner || owner == SENTINEL_OWNERS {
+                bail!("Safe signatures must be by distinct owners in ascending order");
+            }
+            let slot = owner_slot(owner);
+            let is_owner = self
+                .owners
+                .iter()
+                .any(|proof| proof.slot == slot && !proof.value.is_zero());
+            if !is_owner {
+                bail!("{owner:#x} is not an owner of Safe {:#x}", self.account);
+            }
+            last_owner = owner;
+        }
+        Ok(())
+    }
+}
+
+/// Recover the signers of the first `count` Safe-packed signatures over
+/// `message_hash`. Contract signatures (v = 0) and pre-approved hashes (v = 1) need
+/// more state and are rejected.
+pub fn recover_owners(message_hash: B256, signatures: &[u8], count: usize) -> Result<Vec<Address>> {
+    if signatures.len() < count * 65 {
+        bail!("Expected {count} signatures, got {} bytes", signatures.len());
+    }
+    signatures
+        .chunks_exact(65)
+        .take(count)
+        .map(|signature| {
+            let v = signature[64];
+            let (prehash, y_parity) = match v {
+                27 | 28 => (message_hash, v == 28),
+                // eth_sign: the owner signed the EIP-191 hash, with v raised by 4
+                31 | 32 => (eip191_hash(message_hash.as_slice()), v == 32),
+                _ => bail!("Unsupported Safe signature type v = {v}"),
+            };
+            let r = U256::from_be_slice(&signature[..32]);
+            let s = U256::from_be_slice(&signature[32..64]);
+            // Safe recovers with `ecrecover`, which accepts high-s signatures
+            Signature::new(r, s, y_parity)
+                .normalized_s()
+                .recover_address_from_prehash(&prehash)
+                .map_err(|e| anyhow!("Safe signature recovery failed: {e}"))
+        })
+        .collect()
+}
@@ -0,0 +1,87 @@
+//! Transfer details of a Permit2 `PermitTransferFrom`: who receives the tokens and
+//! how much of the permitted amount is requested, Permit2's `SignatureTransferDetails`.
+//! The owner does not sign them; the spender picks them when executing the permit. The
+//! host checks them against the message before proving, see `permitted_amount`, and
+//! the guests commit them unchecked in `Output.transfer`, so a consumer executing the
+//! permit moves what the proof names.
+
+use alloy_dyn_abi::TypedData;
+use alloy_primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{policy::parse_uint, serde_hex};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferDetails {
+    #[serde(with = "serde_hex::address")]
+    pub to: Address,
+    #[serde(with = "serde_hex::u256")]
+    pub requested_amount: U256,
+}
+
+/// The `permitted.amount` of a `PermitTransferFrom` message, the most its transfer
+/// may request; `None` for other messages.
+pub fn permitted_amount(typed_data: &TypedData) -> Option<U256> {
+    if typed_data.primary_type != "PermitTransferFrom" {
+        return None;
+    }
+    parse_uint(typed_data.message.get("permitted")?.get("amount")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn permit(amount: Value) -> TypedData {
+        serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [{ "name": "chainId", "type": "uint256" }],
+                "TokenPermissions": [
+                    { "name": "token", "type": "address" },
+                    { "name": "amount", "type": "uint256" },
+                ],
+                "PermitTransferFrom": [
+                    { "name": "permitted", "type": "TokenPermissions" },
+                    { "name": "spender", "type": "address" },
+                    { "name": "nonce", "type": "uint256" },
+                    { "name": "deadline", "type": "uint256" },
+                ],
+            },
+            "primaryType": "PermitTransferFrom",
+            "domain": { "chainId": 1 },
+            "message": {
+                "permitted": {
+                    "token": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                    "amount": amount,
+                },
+                "spender": "0x1111111111111111111111111111111111111111",
+                "nonce": "0",
+                "deadline": "1737072000",
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_the_permitted_amount() {
+        for amount in [json!("300"), json!(300), json!("0x12c")] {
+            assert_eq!(permitted_amount(&permit(amount)), Some(U256::from(300)));
+        }
+        let mut other = permit(json!("300"));
+        other.primary_type = "PermitBatchTransferFrom".into();
+        assert_eq!(permitted_amount(&other), None);
+    }
+
+    #[test]
+    fn round_trips_binary_in_alloys_layout() {
+        let transfer = TransferDetails {
+            to: Address::repeat_byte(0x44),
+            requested_amount: U256::from(250),
+        };
+        let encoded = bincode::serialize(&transfer).unwrap();
+        assert_eq!(bincode::deserialize::<TransferDetails>(&encoded).unwrap(), transfer);
+        let alloy = (transfer.to, transfer.requested_amount);
+        assert_eq!(encoded, bincode::serialize(&alloy).unwrap());
+    }
+}
@@ -1,14 +1,202 @@
-use alloy_primitives::B256;
-use alloy_dyn_abi::TypedData;
+use alloc::{string::String, vec::Vec};
+use alloy_primitives::{Bytes, B256};
+use alloy_dyn_abi::{DynSolValue, Resolver, TypedData};
+use alloy_sol_types::Eip712Domain;
 use anyhow::{anyhow, Result};
 
+use crate::hash::keccak256;
+
+/// Parse an EIP-712 typed-data JSON with `types`, `primaryType`, `domain`, and `message`.
+pub fn parse_typed_data(typed_data_json: &str) -> Result<TypedData> {
+    serde_json::from_str(typed_data_json)
+        .map_err(|e| anyhow!("Invalid EIP-712 typed data JSON: {e}"))
+}
+
 /// Compute a generic EIP-712 digest for any compliant typed-data JSON.
 /// Input is a JSON string with `types`, `primaryType`, `domain`, and `message`.
 /// Returns the bytes32 digest: keccak256("\x19\x01" || domainSeparator || hashStruct(message)).
 pub fn verify_digest(typed_data_json: &str) -> Result<B256> {
-    let typed: TypedData = serde_json::from_str(typed_data_json)
-        .map_err(|e| anyhow!("Invalid EIP-712 typed data JSON: {e}"))?;
+    let typed = parse_typed_data(typed_data_json)?;
     typed
         .eip712_signing_hash()
         .map_err(|e| anyhow!("Failed computing EIP-712 digest: {e}"))
 }
+
+/// The EIP-712 digest from its parts, keccak256("\x19\x01" || domainSeparator ||
+/// structHash), for callers that already hold the struct hash: `eip712_signing_hash`
+/// would hash the message again.
+pub fn signing_hash(domain_separator: B256, struct_hash: B256) -> B256 {
+    let mut preimage = [0u8; 66];
+    preimage[..2].copy_from_slice(&[0x19, 0x01]);
+    preimage[2..34].copy_from_slice(domain_separator.as_slice());
+    preimage[34..].copy_from_slice(struct_hash.as_slice());
+    keccak256(preimage)
+}
+
+/// Domain separators already computed, so a batch whose objects share a domain (the
+/// usual case, e.g. Permit2 on one chain) hashes it once rather than once per object.
+#[derive(Debug, Default)]
+pub struct SeparatorCache {
+    entries: Vec<(Eip712Domain, B256)>,
+}
+
+impl SeparatorCache {
+    /// A batch holds few distinct domains, so a linear scan beats hashing the domain.
+    pub fn separator(&mut self, domain: &Eip712Domain) -> B256 {
+        if let Some((_, separator)) = self.entries.iter().find(|(known, _)| known == domain) {
+            return *separator;
+        }
+        let separator = domain.separator();
+        self.entries.push((domain.clone(), separator));
+        separator
+    }
+}
+
+/// `TypedData::hash_struct` with the type hash of every struct memoized per set of
+/// `types`, so a batch of objects sharing their types runs `encodeType` and hashes it
+/// once per struct rather than once per object and nesting level.
+#[derive(Debug, Default)]
+pub struct TypeHashCache {
+    // Each distinct `types`, with the type hashes of its structs computed so far
+    resolvers: Vec<(Resolver, Vec<(String, B256)>)>,
+}
+
+impl TypeHashCache {
+    pub fn hash_struct(&mut self, typed: &TypedData) -> Result<B256> {
+        let message = typed
+            .coerce()
+            .map_err(|e| anyhow!("Failed resolving typed data message: {e}"))?;
+        let index = match self.resolvers.iter().position(|(known, _)| *known == typed.resolver) {
+            Some(index) => index,
+            None => {
+                self.resolvers.push((typed.resolver.clone(), Vec::new()));
+                self.resolvers.len() - 1
+            }
+        };
+        let (resolver, type_hashes) = &mut self.resolvers[index];
+        hash_struct_value(resolver, type_hashes, &message)
+    }
+}
+
+fn hash_struct_value(
+    resolver: &Resolver,
+    type_hashes: &mut Vec<(String, B256)>,
+    value: &DynSolValue,
+) -> Result<B256> {
+    let DynSolValue::CustomStruct { name, tuple, .. } = value else {
+        return Err(anyhow!("Expected a struct"));
+    };
+    let type_hash = match type_hashes.iter().find(|(known, _)| known == name) {
+        Some((_, type_hash)) => *type_hash,
+        None => {
+            let type_hash = resolver
+                .type_hash(name)
+                .map_err(|e| anyhow!("Failed hashing type {name}: {e}"))?;
+            type_hashes.push((name.clone(), type_hash));
+            type_hash
+        }
+    };
+    let mut encoded = Vec::with_capacity(32 * (tuple.len() + 1));
+    encoded.extend_from_slice(type_hash.as_slice());
+    for field in tuple {
+        encoded.extend_from_slice(data_word(resolver, type_hashes, field)?.as_slice());
+    }
+    Ok(keccak256(encoded))
+}
+
+/// The 32-byte `encodeData` word of one member: structs, arrays, strings and bytes by
+/// their hash, atomic values in place.
+fn data_word(
+    resolver: &Resolver,
+    type_hashes: &mut Vec<(String, B256)>,
+    value: &DynSolValue,
+) -> Result<B256> {
+    match value {
+        DynSolValue::CustomStruct { .. } => hash_struct_value(resolver, type_hashes, value),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            let mut encoded = Vec::with_capacity(32 * values.len());
+            for value in values {
+                encoded.extend_from_slice(data_word(resolver, type_hashes, value)?.as_slice());
+            }
+            Ok(keccak256(encoded))
+        }
+        DynSolValue::String(string) => Ok(keccak256(string.as_bytes())),
+        DynSolValue::Bytes(bytes) => Ok(keccak256(bytes)),
+        value => value
+            .as_word()
+            .ok_or_else(|| anyhow!("Value has no EIP-712 encoding")),
+    }
+}
+
+/// ABI-encode the message fields at `paths` as a parameter tuple, in order, so a
+/// contract can `abi.decode` them with the matching types. Paths name nested struct
+/// members separated by `.` or `/`, e.g. `permitted.token` or `/permitted/token`.
+/// Returns empty bytes when `paths` is empty.
+pub fn extract_fields(typed: &TypedData, paths: &[String]) -> Result<Bytes> {
+    if paths.is_empty() {
+        return Ok(Bytes::new());
+    }
+    let values = extract_values(typed, paths)?;
+    Ok(DynSolValue::Tuple(values).abi_encode_params().into())
+}
+
+/// Commit to the message fields at `paths` without revealing them:
+/// `keccak256(abi.encode(value) || field_salt(salt, path))` per path, in order.
+/// A field is later disclosed by revealing its value and its field salt.
+pub fn field_commitments(typed: &TypedData, paths: &[String], salt: B256) -> Result<Vec<B256>> {
+    Ok(extract_values(typed, paths)?
+        .iter()
+        .zip(paths)
+        .map(|(value, path)| {
+            keccak256([value.abi_encode().as_slice(), field_salt(salt, path).as_slice()].concat())
+        })
+        .collect())
+}
+
+/// Salt for one field, `keccak256(salt || path)`, so revealing one field does not let
+/// the other (often low-entropy) fields be brute-forced.
+pub fn field_salt(salt: B256, path: &str) -> B256 {
+    keccak256([salt.as_slice(), path.as_bytes()].concat())
+}
+
+/// Fields committed for `paths`: their values when `salt` is `None`, otherwise
+/// only their `field_commitments` (privacy mode).
+pub fn disclose(
+    typed: &TypedData,
+    paths: &[String],
+    salt: Option<B256>,
+) -> Result<(Bytes, Vec<B256>)> {
+    match salt {
+        None => Ok((extract_fields(typed, paths)?, Vec::new())),
+        Some(salt) => Ok((Bytes::new(), field_commitments(typed, paths, salt)?)),
+    }
+}
+
+fn extract_values(typed: &TypedData, paths: &[String]) -> Result<Vec<DynSolValue>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    let message = typed
+        .coerce()
+        .map_err(|e| anyhow!("Failed resolving typed data message: {e}"))?;
+    paths
+        .iter()
+        .map(|path| lookup_field(&message, path))
+        .collect()
+}
+
+fn lookup_field(message: &DynSolValue, path: &str) -> Result<DynSolValue> {
+    path.trim_start_matches('/')
+        .split(['.', '/'])
+        .try_fold(message, |value, segment| match value {
+            DynSolValue::CustomStruct {
+                prop_names, tuple, ..
+            } => prop_names
+                .iter()
+                .position(|name| name == segment)
+                .map(|index| &tuple[index])
+                .ok_or_else(|| anyhow!("No field `{segment}` on path `{path}`")),
+            _ => Err(anyhow!("`{segment}` on path `{path}` is not inside a struct")),
+        })
+        .cloned()
+}
@@ -0,0 +1,115 @@
+//! ERC-4337 UserOperations as an alternative to EIP-712 objects in the blob.
+//!
+//! Each range then holds one UserOperation in the EntryPoint v0.7 RPC format, and
+//! its digest is the `userOpHash`:
+//! `keccak256(abi.encode(keccak256(pack(userOp)), entryPoint, chainId))`.
+//! The preimage is 96 bytes, while an EIP-712 digest hashes 66, so a `userOpHash`
+//! can never pass for an EIP-712 digest or vice versa.
+
+use alloy_primitives::{address, Address, Bytes, B256, U256};
+use alloy_sol_types::SolValue;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::hash::keccak256;
+
+/// Canonical EntryPoint v0.7 deployment, at the same address on every chain.
+pub const ENTRY_POINT_V07: Address = address!("0x0000000071727De22E5E9d8BAd0edAC6A8b2fcE7");
+
+/// EntryPoint v0.7 UserOperation as sent to `eth_sendUserOperation`. The account's
+/// own `signature` field is not part of the hash and is ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub factory: Option<Address>,
+    pub factory_data: Option<Bytes>,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster: Option<Address>,
+    pub paymaster_verification_gas_limit: Option<U256>,
+    pub paymaster_post_op_gas_limit: Option<U256>,
+    pub paymaster_data: Option<Bytes>,
+}
+
+impl UserOperation {
+    pub fn from_json(user_op_json: &str) -> Result<Self> {
+        serde_json::from_str(user_op_json).map_err(|e| anyhow!("Invalid UserOperation JSON: {e}"))
+    }
+
+    /// `factory || factoryData`, empty for deployed accounts.
+    pub fn init_code(&self) -> Bytes {
+        match self.factory {
+            None => Bytes::new(),
+            Some(factory) => {
+                let factory_data = or_empty(&self.factory_data);
+                [factory.as_slice(), factory_data].concat().into()
+            }
+        }
+    }
+
+    /// `paymaster || uint128(verificationGasLimit) || uint128(postOpGasLimit) ||
+    /// paymasterData`, empty without a paymaster.
+    pub fn paymaster_and_data(&self) -> Result<Bytes> {
+        let Some(paymaster) = self.paymaster else {
+            return Ok(Bytes::new());
+        };
+        let verification_gas_limit =
+            uint128(self.paymaster_verification_gas_limit.unwrap_or_default())?;
+        let post_op_gas_limit = uint128(self.paymaster_post_op_gas_limit.unwrap_or_default())?;
+        let paymaster_data = or_empty(&self.paymaster_data);
+        Ok([
+            paymaster.as_slice(),
+            verification_gas_limit.to_be_bytes().as_slice(),
+            post_op_gas_limit.to_be_bytes().as_slice(),
+            paymaster_data,
+        ]
+        .concat()
+        .into())
+    }
+
+    /// The `userOpHash` the EntryPoint at `entry_point` on `chain_id` assigns.
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> Result<B256> {
+        let account_gas_limits =
+            pack_uint128s(self.verification_gas_limit, self.call_gas_limit)?;
+        let gas_fees = pack_uint128s(self.max_priority_fee_per_gas, self.max_fee_per_gas)?;
+        let packed = (
+            self.sender,
+            self.nonce,
+            keccak256(self.init_code()),
+            keccak256(&self.call_data),
+            account_gas_limits,
+            self.pre_verification_gas,
+            gas_fees,
+            keccak256(self.paymaster_and_data()?),
+        )
+            .abi_encode();
+        Ok(keccak256(
+            (keccak256(packed), entry_point, U256::from(chain_id)).abi_encode(),
+        ))
+    }
+}
+
+/// `bytes32(high << 128 | low)`, as the v0.7 packed gas fields are laid out.
+fn pack_uint128s(high: U256, low: U256) -> Result<B256> {
+    let mut packed = [0u8; 32];
+    packed[..16].copy_from_slice(&uint128(high)?.to_be_bytes());
+    packed[16..].copy_from_slice(&uint128(low)?.to_be_bytes());
+    Ok(B256::from(packed))
+}
+
+fn or_empty(data: &Option<Bytes>) -> &[u8] {
+    data.as_ref().map_or(&[][..], |data| &data[..])
+}
+
+fn uint128(value: U256) -> Result<u128> {
+    if value > U256::from(u128::MAX) {
+        bail!("Gas value {value} does not fit in uint128");
+    }
+    Ok(value.to::<u128>())
+}
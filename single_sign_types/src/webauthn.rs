@@ -0,0 +1,56 @@
+//! WebAuthn assertions, so a passkey can sign the whole typed-data blob.
+//!
+//! The relying party sets the assertion challenge to `keccak256(blob)`. The
+//! authenticator then signs `authenticatorData || sha256(clientDataJSON)`, where
+//! `clientDataJSON` embeds the challenge base64url-encoded without padding.
+
+use alloc::{string::String, vec::Vec};
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::hash::keccak256;
+
+/// Flag bit in `authenticatorData` set when the user was present.
+const USER_PRESENT: u8 = 0x01;
+
+#[derive(Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+}
+
+/// Check the assertion is bound to `message` and return the bytes the
+/// credential's P-256 key signed.
+pub fn signed_message(
+    message: &[u8],
+    authenticator_data: &[u8],
+    client_data_json: &str,
+) -> Result<Vec<u8>> {
+    // rpIdHash (32 bytes) || flags (1 byte) || signCount (4 bytes) || extensions
+    let flags = *authenticator_data
+        .get(32)
+        .ok_or_else(|| anyhow!("authenticatorData is too short"))?;
+    if flags & USER_PRESENT == 0 {
+        bail!("Assertion was made without user presence");
+    }
+
+    let client_data: ClientData = serde_json::from_str(client_data_json)
+        .map_err(|e| anyhow!("Invalid clientDataJSON: {e}"))?;
+    if client_data.kind != "webauthn.get" {
+        bail!("clientDataJSON type is `{}`, not `webauthn.get`", client_data.kind);
+    }
+    if client_data.challenge != challenge(message) {
+        bail!("clientDataJSON challenge is not keccak256 of the signed bytes");
+    }
+
+    let client_data_hash = Sha256::digest(client_data_json.as_bytes());
+    Ok([authenticator_data, client_data_hash.as_slice()].concat())
+}
+
+/// The challenge a relying party requests for `message`: base64url(keccak256(message)).
+pub fn challenge(message: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(keccak256(message))
+}